@@ -0,0 +1,18 @@
+//! System clipboard access.
+
+/// Copy text to the clipboard in the background. No-op on the server.
+#[cfg(target_arch = "wasm32")]
+pub fn write_text(text: &str) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let promise = window.navigator().clipboard().write_text(text);
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_text(_text: &str) {
+    // No clipboard on the server
+}
@@ -0,0 +1,231 @@
+use leptos::prelude::*;
+use megaweb_types::json_path::{self, PathStep};
+use megaweb_types::query::{QueryColumn, SqlType, TypedValue, ValueFormat};
+use megaweb_types::toast::ToastLevel;
+use serde_json::Value;
+
+use crate::state::toast::{push_toast, use_toast_write};
+
+/// Children listed per JSON object or array before the rest are elided.
+const MAX_CHILDREN: usize = 500;
+/// Nesting levels expanded when the tree is first shown.
+const OPEN_DEPTH: usize = 2;
+
+/// Side panel with the full value of one result cell.
+///
+/// JSON and array values are shown as an expandable tree or as
+/// pretty-printed text. Clicking a tree node selects its path, which can be
+/// copied as a SQL expression or added to the query as a new column through
+/// `on_add_column` (expression, alias).
+#[component]
+pub fn CellPanel(
+    column: QueryColumn,
+    value: TypedValue,
+    format: ValueFormat,
+    #[prop(into)] on_close: Callback<()>,
+    #[prop(into)] on_add_column: Callback<(String, String)>,
+) -> impl IntoView {
+    let toast = use_toast_write();
+    let ty = SqlType::parse(&column.data_type);
+    let nested = matches!(value, TypedValue::Json(_) | TypedValue::Array(_));
+    let json = value.to_json();
+    let text = if nested {
+        serde_json::to_string_pretty(&json).unwrap_or_default()
+    } else {
+        value.render(&format)
+    };
+    let (raw, set_raw) = signal(false);
+    let (selected, set_selected) = signal(Vec::<PathStep>::new());
+
+    // (expression, alias, path label) for the selected node
+    let name = column.name.clone();
+    let tree = json.clone();
+    let extraction = Memo::new(move |_| {
+        let path = selected.get();
+        if path.is_empty() {
+            return None;
+        }
+        let scalar =
+            json_path::value_at(&tree, &path).is_some_and(|v| !v.is_object() && !v.is_array());
+        Some((
+            json_path::extract_expression(&name, &ty, &path, scalar),
+            json_path::path_alias(&name, &path),
+            json_path::path_label(&path),
+        ))
+    });
+
+    let copy_text = text.clone();
+    let raw_text = text.clone();
+
+    view! {
+        <div class="cell-panel">
+            <div class="cell-panel-header">
+                <div class="cell-panel-title">
+                    <h4>{column.name.clone()}</h4>
+                    <span class="col-type">{column.data_type.clone()}</span>
+                </div>
+                <button
+                    class="btn btn-sm"
+                    on:click=move |_| {
+                        crate::clipboard::write_text(&copy_text);
+                        push_toast(toast, ToastLevel::Success, "Copied value");
+                    }
+                >
+                    "Copy"
+                </button>
+                <button class="btn btn-sm" on:click=move |_| on_close.run(())>"Close"</button>
+            </div>
+            {nested.then(|| view! {
+                <div class="result-mode-toggle cell-panel-modes">
+                    <button
+                        class=move || if raw.get() { "btn btn-sm" } else { "btn btn-sm active" }
+                        on:click=move |_| set_raw.set(false)
+                    >
+                        "Tree"
+                    </button>
+                    <button
+                        class=move || if raw.get() { "btn btn-sm active" } else { "btn btn-sm" }
+                        on:click=move |_| set_raw.set(true)
+                    >
+                        "Raw"
+                    </button>
+                </div>
+            })}
+            <div class="cell-panel-body">
+                {move || {
+                    if nested && !raw.get() {
+                        json_node(None, json.clone(), Vec::new(), 0, selected, set_selected)
+                    } else {
+                        view! { <pre class="cell-panel-text">{raw_text.clone()}</pre> }.into_any()
+                    }
+                }}
+            </div>
+            {move || extraction.get().map(|(expression, alias, label)| {
+                let sql = format!("{expression} AS {alias}");
+                let sql2 = sql.clone();
+                view! {
+                    <div class="cell-panel-extract">
+                        <div class="cell-panel-path">{label}</div>
+                        <code>{sql}</code>
+                        <div class="cell-panel-actions">
+                            <button
+                                class="btn btn-sm"
+                                on:click=move |_| {
+                                    crate::clipboard::write_text(&sql2);
+                                    push_toast(toast, ToastLevel::Success, "Copied expression");
+                                }
+                            >
+                                "Copy expression"
+                            </button>
+                            <button
+                                class="btn btn-sm btn-primary"
+                                title="Wrap the query so this path becomes a new column"
+                                on:click=move |_| on_add_column.run((expression.clone(), alias.clone()))
+                            >
+                                "Add as column"
+                            </button>
+                        </div>
+                    </div>
+                }
+            })}
+        </div>
+    }
+}
+
+/// One node of the JSON tree, with its children when expanded.
+fn json_node(
+    label: Option<String>,
+    value: Value,
+    path: Vec<PathStep>,
+    depth: usize,
+    selected: ReadSignal<Vec<PathStep>>,
+    set_selected: WriteSignal<Vec<PathStep>>,
+) -> AnyView {
+    let row_path = path.clone();
+    let row_class = move || {
+        if selected.with(|s| *s == row_path) {
+            "json-row json-row--selected"
+        } else {
+            "json-row"
+        }
+    };
+    let select_path = path.clone();
+    let select = move |_| set_selected.set(select_path.clone());
+    let label = label.map(|l| view! { <span class="json-key">{l}": "</span> });
+
+    let children: Vec<(String, PathStep, Value)> = match &value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| (k.clone(), PathStep::Key(k.clone()), v.clone()))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), PathStep::Index(i), v.clone()))
+            .collect(),
+        _ => {
+            return view! {
+                <div class=row_class on:click=select>
+                    <span class="json-toggle"></span>
+                    {label}
+                    <span class=scalar_class(&value)>{value.to_string()}</span>
+                </div>
+            }
+            .into_any();
+        }
+    };
+
+    let (open, set_open) = signal(depth < OPEN_DEPTH);
+    let summary = match &value {
+        Value::Object(_) => format!("{{\u{2026}}} {} keys", children.len()),
+        _ => format!("[\u{2026}] {} items", children.len()),
+    };
+    let hidden = children.len().saturating_sub(MAX_CHILDREN);
+
+    view! {
+        <div class="json-node">
+            <div class=row_class on:click=select>
+                <span
+                    class="json-toggle"
+                    on:click=move |ev| {
+                        ev.stop_propagation();
+                        set_open.update(|o| *o = !*o);
+                    }
+                >
+                    {move || if open.get() { "\u{25BE}" } else { "\u{25B8}" }}
+                </span>
+                {label}
+                <span class="json-summary">{summary}</span>
+            </div>
+            {move || open.get().then(|| {
+                let nodes = children
+                    .iter()
+                    .take(MAX_CHILDREN)
+                    .map(|(label, step, child)| {
+                        let mut child_path = path.clone();
+                        child_path.push(step.clone());
+                        json_node(Some(label.clone()), child.clone(), child_path, depth + 1, selected, set_selected)
+                    })
+                    .collect::<Vec<_>>();
+                view! {
+                    <div class="json-children">
+                        {nodes}
+                        {(hidden > 0).then(|| view! {
+                            <div class="json-more">{format!("\u{2026} {hidden} more")}</div>
+                        })}
+                    </div>
+                }
+            })}
+        </div>
+    }
+    .into_any()
+}
+
+fn scalar_class(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "json-string",
+        Value::Number(_) => "json-number",
+        Value::Bool(_) => "json-bool",
+        _ => "json-null",
+    }
+}
@@ -66,7 +66,7 @@ pub fn SvgChart(config: ChartConfig) -> impl IntoView {
     let y_ticks: Vec<(f64, String)> = (0..=4)
         .map(|i| {
             let val = min_val + y_range * (i as f64 / 4.0);
-            let label = format_value(val);
+            let label = format_axis_value(val);
             let y = padding_top + chart_height - (chart_height * (i as f64 / 4.0));
             (y, label)
         })
@@ -260,7 +260,8 @@ pub fn SvgChart(config: ChartConfig) -> impl IntoView {
     }
 }
 
-fn format_value(v: f64) -> String {
+/// Compact axis label: `1.2M`, `3.4k`, `12`, `0.5`.
+pub fn format_axis_value(v: f64) -> String {
     if v.abs() >= 1_000_000.0 {
         format!("{:.1}M", v / 1_000_000.0)
     } else if v.abs() >= 1_000.0 {
@@ -8,8 +8,15 @@ use leptos::prelude::*;
 #[component]
 pub fn CodeMirrorEditor(
     #[prop(default = String::new())] initial_content: String,
+    /// Initial cursor position as a UTF-16 offset into `initial_content`.
+    #[prop(default = 0)]
+    initial_cursor: usize,
     #[prop(into)] on_execute: Callback<String>,
     #[prop(into, optional)] on_change: Option<Callback<String>>,
+    #[prop(into, optional)] on_cursor: Option<Callback<usize>>,
+    /// Runs when focus leaves the editor.
+    #[prop(into, optional)]
+    on_blur: Option<Callback<()>>,
 ) -> impl IntoView {
     let (content, set_content) = signal(initial_content.clone());
     let editor_id = format!("cm-{}", uuid::Uuid::new_v4().as_simple());
@@ -55,22 +62,44 @@ pub fn CodeMirrorEditor(
                 }
             });
 
-            let _ = create_fn.call4(
+            let on_cursor_clone = on_cursor;
+            let cursor_closure = Closure::<dyn Fn(u32)>::new(move |pos: u32| {
+                if let Some(cb) = &on_cursor_clone {
+                    cb.run(pos as usize);
+                }
+            });
+
+            let _ = create_fn.call6(
                 &JsValue::NULL,
                 &JsValue::from_str(&editor_id_effect),
                 &JsValue::from_str(&initial),
                 execute_closure.as_ref(),
                 change_closure.as_ref(),
+                &JsValue::from(initial_cursor as u32),
+                cursor_closure.as_ref(),
             );
 
             // Prevent closures from being dropped (they need to live as long as the editor)
             execute_closure.forget();
             change_closure.forget();
+            cursor_closure.forget();
         });
     }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = initial_cursor;
+    }
 
     view! {
-        <div class="codemirror-wrapper" id=editor_id_for_view>
+        <div
+            class="codemirror-wrapper"
+            id=editor_id_for_view
+            on:focusout=move |_| {
+                if let Some(cb) = &on_blur {
+                    cb.run(());
+                }
+            }
+        >
             <textarea
                 class="codemirror-fallback"
                 prop:value=move || content.get()
@@ -81,6 +110,8 @@ pub fn CodeMirrorEditor(
                         cb.run(val);
                     }
                 }
+                on:keyup=move |ev| report_textarea_cursor(&ev, on_cursor)
+                on:click=move |ev| report_textarea_cursor(&ev, on_cursor)
                 on:keydown=move |ev| {
                     if (ev.ctrl_key() || ev.meta_key()) && ev.key() == "Enter" {
                         ev.prevent_default();
@@ -94,3 +125,20 @@ pub fn CodeMirrorEditor(
         </div>
     }
 }
+
+/// Report the fallback textarea's cursor position to `on_cursor`.
+#[cfg(target_arch = "wasm32")]
+fn report_textarea_cursor(ev: &leptos::ev::Event, on_cursor: Option<Callback<usize>>) {
+    use wasm_bindgen::JsCast;
+
+    let Some(cb) = on_cursor else { return };
+    let textarea = ev
+        .target()
+        .and_then(|t| t.dyn_into::<web_sys::HtmlTextAreaElement>().ok());
+    if let Some(pos) = textarea.and_then(|t| t.selection_start().ok().flatten()) {
+        cb.run(pos as usize);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn report_textarea_cursor<E>(_ev: &E, _on_cursor: Option<Callback<usize>>) {}
@@ -0,0 +1,346 @@
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use megaweb_types::edit::{parse_input, transaction, EditSet, EditTarget};
+use megaweb_types::query::{ColumnKind, QueryResult};
+use megaweb_types::result_view::cell_text;
+use megaweb_types::toast::ToastLevel;
+
+use crate::state::toast::{push_toast, use_toast_write, ToastState};
+
+/// Loaded rows shown for editing; narrow the query to reach the others.
+const MAX_EDIT_ROWS: usize = 1000;
+
+/// Editable view of a result from a single OLTP table.
+///
+/// Cells of table columns can be changed (type `NULL` for SQL NULL), rows
+/// marked for deletion and new rows added. Pending changes are previewed as
+/// UPDATE/INSERT/DELETE statements and committed in one transaction through
+/// the MegaDB query proxy, after which `on_committed` re-runs the query.
+#[component]
+pub fn DataEditor(
+    result: Arc<QueryResult>,
+    target: EditTarget,
+    database: Signal<String>,
+    #[prop(into)] on_close: Callback<()>,
+    #[prop(into)] on_committed: Callback<()>,
+) -> impl IntoView {
+    let toast = use_toast_write();
+    let (edits, set_edits) = signal(EditSet::default());
+    let (preview, set_preview) = signal(false);
+    let (committing, set_committing) = signal(false);
+
+    let width = result.columns.len();
+    let kinds: Arc<Vec<ColumnKind>> = Arc::new(
+        result
+            .columns
+            .iter()
+            .map(|c| ColumnKind::from_data_type(&c.data_type))
+            .collect(),
+    );
+    let editable: Arc<Vec<bool>> = Arc::new((0..width).map(|c| target.is_editable(c)).collect());
+    let hidden_rows = result.rows.len().saturating_sub(MAX_EDIT_ROWS);
+    let table_name = target.table.clone();
+
+    let script = {
+        let result = Arc::clone(&result);
+        Memo::new(move |_| edits.with(|e| transaction(&e.statements(&target, &result))))
+    };
+
+    let commit = move || {
+        if committing.get_untracked() || edits.with_untracked(EditSet::is_empty) {
+            return;
+        }
+        set_committing.set(true);
+        let count = edits.with_untracked(EditSet::change_count);
+        submit(
+            script.get_untracked(),
+            database.get_untracked(),
+            count,
+            toast,
+            Callback::new(move |ok: bool| {
+                set_committing.set(false);
+                if ok {
+                    set_preview.set(false);
+                    set_edits.set(EditSet::default());
+                    on_committed.run(());
+                }
+            }),
+        );
+    };
+
+    let header = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(col, c)| {
+            let title = if editable[col] {
+                c.data_type.clone()
+            } else {
+                format!("{} (computed, read-only)", c.data_type)
+            };
+            view! {
+                <th class:edit-readonly=!editable[col] title=title>
+                    <span class="col-name">{c.name.clone()}</span>
+                    <span class="col-type">{c.data_type.clone()}</span>
+                </th>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let rows = (0..result.rows.len().min(MAX_EDIT_ROWS))
+        .map(|row| {
+            let cells = (0..width)
+                .map(|col| {
+                    let r = Arc::clone(&result);
+                    let r2 = Arc::clone(&result);
+                    let kind = kinds[col];
+                    if !editable[col] {
+                        return view! {
+                            <td class="edit-readonly">{cell_text(&result.rows[row][col])}</td>
+                        }
+                        .into_any();
+                    }
+                    view! {
+                        <td class:edit-changed=move || edits.with(|e| e.is_changed(row, col))>
+                            <input
+                                class="edit-input"
+                                type="text"
+                                prop:value=move || edits.with(|e| cell_text(e.cell(&r, row, col)))
+                                on:change=move |ev| {
+                                    let value = parse_input(&event_target_value(&ev), kind);
+                                    set_edits.update(|e| e.set_cell(&r2, row, col, value));
+                                }
+                            />
+                        </td>
+                    }
+                    .into_any()
+                })
+                .collect::<Vec<_>>();
+            let deleted = move || edits.with(|e| e.deletes.contains(&row));
+            view! {
+                <tr class:edit-deleted=deleted>
+                    <td class="row-num">
+                        <button
+                            class="edit-row-btn"
+                            title=move || if deleted() { "Keep row" } else { "Delete row" }
+                            on:click=move |_| set_edits.update(|e| e.toggle_delete(row))
+                        >
+                            {move || if deleted() { "\u{21BA}" } else { "\u{2715}" }}
+                        </button>
+                    </td>
+                    {cells}
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let new_rows = {
+        let kinds = Arc::clone(&kinds);
+        let editable = Arc::clone(&editable);
+        move || {
+            let count = edits.with(|e| e.inserts.len());
+            (0..count)
+                .map(|index| {
+                    let cells = (0..width)
+                        .map(|col| {
+                            let kind = kinds[col];
+                            if !editable[col] {
+                                return view! { <td class="edit-readonly"></td> }.into_any();
+                            }
+                            view! {
+                                <td>
+                                    <input
+                                        class="edit-input"
+                                        type="text"
+                                        placeholder="default"
+                                        prop:value=move || edits.with(|e| {
+                                            e.inserts
+                                                .get(index)
+                                                .and_then(|r| r.get(col))
+                                                .filter(|v| !v.is_null())
+                                                .map(cell_text)
+                                                .unwrap_or_default()
+                                        })
+                                        on:change=move |ev| {
+                                            let text = event_target_value(&ev);
+                                            let value = if text.is_empty() {
+                                                serde_json::Value::Null
+                                            } else {
+                                                parse_input(&text, kind)
+                                            };
+                                            set_edits.update(|e| e.set_new_cell(index, col, value));
+                                        }
+                                    />
+                                </td>
+                            }
+                            .into_any()
+                        })
+                        .collect::<Vec<_>>();
+                    view! {
+                        <tr class="edit-new">
+                            <td class="row-num">
+                                <button
+                                    class="edit-row-btn"
+                                    title="Remove new row"
+                                    on:click=move |_| set_edits.update(|e| e.remove_new_row(index))
+                                >
+                                    "\u{2715}"
+                                </button>
+                            </td>
+                            {cells}
+                        </tr>
+                    }
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let pending = move || {
+        let n = edits.with(EditSet::change_count);
+        match n {
+            0 => "No pending changes".to_string(),
+            1 => "1 pending change".to_string(),
+            n => format!("{n} pending changes"),
+        }
+    };
+    let nothing_pending = move || edits.with(EditSet::is_empty);
+
+    view! {
+        <div class="data-editor">
+            <div class="data-editor-toolbar">
+                <span class="data-editor-title">{format!("Editing {table_name}")}</span>
+                <span class="data-editor-pending">{pending}</span>
+                <button class="btn btn-sm" on:click=move |_| set_edits.update(|e| e.add_row(width))>
+                    "Add row"
+                </button>
+                <button
+                    class="btn btn-sm"
+                    disabled=nothing_pending
+                    on:click=move |_| set_preview.set(true)
+                >
+                    "Preview SQL"
+                </button>
+                <button
+                    class="btn btn-sm btn-primary"
+                    disabled=move || nothing_pending() || committing.get()
+                    on:click=move |_| commit()
+                >
+                    {move || if committing.get() { "Committing..." } else { "Commit" }}
+                </button>
+                <button
+                    class="btn btn-sm"
+                    disabled=nothing_pending
+                    on:click=move |_| set_edits.set(EditSet::default())
+                >
+                    "Discard"
+                </button>
+                <button class="btn btn-sm" on:click=move |_| on_close.run(())>"Done"</button>
+            </div>
+            <div class="result-table-scroll">
+                <table class="result-table data-editor-table">
+                    <thead>
+                        <tr>
+                            <th class="row-num">"#"</th>
+                            {header}
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {rows}
+                        {new_rows}
+                    </tbody>
+                </table>
+            </div>
+            {(hidden_rows > 0).then(|| view! {
+                <div class="result-grid-status">
+                    {format!("{hidden_rows} more rows not shown; narrow the query to edit them")}
+                </div>
+            })}
+            <Show when=move || preview.get()>
+                <div class="modal-overlay">
+                    <div class="modal edit-preview-modal">
+                        <div class="modal-header">
+                            <h3>"Pending changes"</h3>
+                        </div>
+                        <div class="modal-body">
+                            <pre class="edit-preview-sql">{move || script.get()}</pre>
+                        </div>
+                        <div class="modal-footer">
+                            <button
+                                class="btn btn-secondary"
+                                on:click=move |_| {
+                                    crate::clipboard::write_text(&script.get_untracked());
+                                    push_toast(toast, ToastLevel::Success, "Copied SQL");
+                                }
+                            >
+                                "Copy"
+                            </button>
+                            <button class="btn btn-secondary" on:click=move |_| set_preview.set(false)>
+                                "Close"
+                            </button>
+                            <button
+                                class="btn btn-primary"
+                                disabled=move || committing.get()
+                                on:click=move |_| commit()
+                            >
+                                "Commit"
+                            </button>
+                        </div>
+                    </div>
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+/// Run the transaction through `/proxy/megadb/query`, then report whether
+/// it succeeded to `done`.
+#[cfg(target_arch = "wasm32")]
+fn submit(
+    script: String,
+    database: String,
+    count: usize,
+    toast: WriteSignal<ToastState>,
+    done: Callback<bool>,
+) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let req = megaweb_types::query::QueryRequest {
+            sql: script,
+            database,
+            limit: None,
+        };
+        let outcome = crate::http::post_json::<QueryResult>("/proxy/megadb/query", &req)
+            .await
+            .and_then(|result| result.error.map_or(Ok(()), Err));
+        match outcome {
+            Ok(()) => {
+                push_toast(
+                    toast,
+                    ToastLevel::Success,
+                    format!("Committed {count} row changes"),
+                );
+                done.run(true);
+            }
+            Err(e) => {
+                push_toast(
+                    toast,
+                    ToastLevel::Error,
+                    format!("Commit failed, nothing was changed: {e}"),
+                );
+                done.run(false);
+            }
+        }
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn submit(
+    _script: String,
+    _database: String,
+    _count: usize,
+    _toast: WriteSignal<ToastState>,
+    done: Callback<bool>,
+) {
+    // No-op on server
+    done.run(false);
+}
@@ -0,0 +1,252 @@
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use megaweb_types::export::{encode_text, to_sql_insert, ExportFormat, ExportRequest};
+use megaweb_types::pivot::{PivotSpec, PivotTable};
+use megaweb_types::query::QueryResult;
+use megaweb_types::toast::ToastLevel;
+
+use crate::state::query::ResultMode;
+use crate::state::settings::use_settings_state;
+use crate::state::toast::{push_toast, use_toast_write, ToastState};
+
+/// Export dropdown for a query result.
+///
+/// Text formats are encoded in the browser when every row is loaded. Binary
+/// formats, and text formats of truncated results, are generated by the
+/// server, which re-runs `sql` without the row limit. In pivot mode the
+/// pivot table is exported, in text formats only.
+#[component]
+pub fn ExportMenu(
+    result: Arc<QueryResult>,
+    mode: Signal<ResultMode>,
+    pivot: Signal<PivotSpec>,
+    sql: Signal<String>,
+    database: Signal<String>,
+) -> impl IntoView {
+    let (settings, _) = use_settings_state();
+    let toast = use_toast_write();
+    let (open, set_open) = signal(false);
+    let (table, set_table) = signal("query_result".to_string());
+    let truncated = result.is_truncated();
+    let result = StoredValue::new(result);
+
+    let export = move |format: ExportFormat| {
+        set_open.set(false);
+        let pivoted = mode.get_untracked() == ResultMode::Pivot;
+        let csv = settings.get_untracked().csv;
+        let table = table.get_untracked();
+
+        if !format.is_binary() && (pivoted || !truncated) {
+            let source = result.with_value(|r| export_source(r, mode, pivot));
+            if let Some(text) = encode_text(&source, format, &csv, Some(&table)) {
+                trigger_download(&text, &format.filename(), format.mime_type());
+                push_toast(
+                    toast,
+                    ToastLevel::Success,
+                    format!("Exported as {}", format.label()),
+                );
+            }
+            return;
+        }
+        if pivoted {
+            push_toast(
+                toast,
+                ToastLevel::Warning,
+                format!("{} export isn't available for pivot tables", format.label()),
+            );
+            return;
+        }
+
+        let rows = result.with_value(|r| r.row_count);
+        push_toast(
+            toast,
+            ToastLevel::Info,
+            format!("Exporting {rows} rows as {}…", format.label()),
+        );
+        download_from_server(
+            ExportRequest {
+                sql: sql.get_untracked(),
+                database: database.get_untracked(),
+                format,
+                csv,
+                table: Some(table),
+            },
+            toast,
+        );
+    };
+
+    let copy_insert = move |_| {
+        set_open.set(false);
+        let source = result.with_value(|r| export_source(r, mode, pivot));
+        crate::clipboard::write_text(&to_sql_insert(&source, &table.get_untracked()));
+        let note = if truncated && mode.get_untracked() != ResultMode::Pivot {
+            " (loaded rows only)"
+        } else {
+            ""
+        };
+        push_toast(
+            toast,
+            ToastLevel::Success,
+            format!("Copied {} INSERT statements{note}", source.rows.len()),
+        );
+    };
+
+    view! {
+        <div class="export-menu">
+            <button
+                class="btn btn-sm"
+                title="Export results"
+                on:click=move |_| set_open.update(|o| *o = !*o)
+            >
+                "Export ▾"
+            </button>
+            <Show when=move || open.get()>
+                <div class="export-menu-dropdown">
+                    {ExportFormat::ALL.into_iter().map(|format| view! {
+                        <button class="export-menu-item" on:click=move |_| export(format)>
+                            <span>{format.label()}</span>
+                            <span class="export-menu-hint">
+                                {move || {
+                                    let pivoted = mode.get() == ResultMode::Pivot;
+                                    if pivoted && format.is_binary() {
+                                        "n/a"
+                                    } else if !pivoted && (format.is_binary() || truncated) {
+                                        "server"
+                                    } else {
+                                        ""
+                                    }
+                                }}
+                            </span>
+                        </button>
+                    }).collect::<Vec<_>>()}
+                    <div class="export-menu-divider"></div>
+                    <label class="export-menu-table">
+                        "INSERT into"
+                        <input
+                            type="text"
+                            prop:value=move || table.get()
+                            on:input=move |ev| set_table.set(event_target_value(&ev))
+                        />
+                    </label>
+                    <button class="export-menu-item" on:click=copy_insert>
+                        "Copy as INSERT statements"
+                    </button>
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+/// What gets exported: the pivot table in pivot mode, else the result.
+fn export_source(
+    result: &Arc<QueryResult>,
+    mode: Signal<ResultMode>,
+    pivot: Signal<PivotSpec>,
+) -> Arc<QueryResult> {
+    if mode.get_untracked() == ResultMode::Pivot {
+        if let Some(table) = PivotTable::build(result, &pivot.get_untracked()) {
+            return Arc::new(table.to_result());
+        }
+    }
+    Arc::clone(result)
+}
+
+/// POST the request to `/export` and save the response as a file.
+#[cfg(target_arch = "wasm32")]
+fn download_from_server(req: ExportRequest, toast: WriteSignal<ToastState>) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let filename = req.format.filename();
+        let label = req.format.label();
+        match fetch_export(&req).await {
+            Ok(blob) => {
+                download_blob(&blob, &filename);
+                push_toast(toast, ToastLevel::Success, format!("Exported as {label}"));
+            }
+            Err(e) => push_toast(toast, ToastLevel::Error, format!("Export failed: {e}")),
+        }
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn download_from_server(_req: ExportRequest, _toast: WriteSignal<ToastState>) {
+    // No-op on server
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn fetch_export(req: &ExportRequest) -> Result<web_sys::Blob, String> {
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen_futures::JsFuture;
+
+    let js_err = |e: JsValue| e.as_string().unwrap_or_else(|| format!("{e:?}"));
+    let window = web_sys::window().ok_or("no window")?;
+    let body = serde_json::to_string(req).map_err(|e| e.to_string())?;
+
+    let headers = web_sys::Headers::new().map_err(js_err)?;
+    headers
+        .set("Content-Type", "application/json")
+        .map_err(js_err)?;
+    let init = web_sys::RequestInit::new();
+    init.set_method("POST");
+    init.set_headers(&headers);
+    init.set_body(&JsValue::from_str(&body));
+
+    let response: web_sys::Response =
+        JsFuture::from(window.fetch_with_str_and_init("/export", &init))
+            .await
+            .map_err(js_err)?
+            .unchecked_into();
+    if !response.ok() {
+        let text = JsFuture::from(response.text().map_err(js_err)?)
+            .await
+            .map_err(js_err)?
+            .as_string()
+            .unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|v| v.get("error")?.as_str().map(str::to_string))
+            .unwrap_or_else(|| format!("HTTP {}", response.status()));
+        return Err(message);
+    }
+    let blob = JsFuture::from(response.blob().map_err(js_err)?)
+        .await
+        .map_err(js_err)?;
+    Ok(blob.unchecked_into())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn trigger_download(content: &str, filename: &str, mime_type: &str) {
+    use wasm_bindgen::prelude::*;
+
+    let blob_parts = js_sys::Array::new();
+    blob_parts.push(&JsValue::from_str(content));
+    let mut opts = web_sys::BlobPropertyBag::new();
+    opts.type_(mime_type);
+    if let Ok(blob) = web_sys::Blob::new_with_str_sequence_and_options(&blob_parts, &opts) {
+        download_blob(&blob, filename);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn trigger_download(_content: &str, _filename: &str, _mime_type: &str) {
+    // No-op on server
+}
+
+#[cfg(target_arch = "wasm32")]
+fn download_blob(blob: &web_sys::Blob, filename: &str) {
+    use wasm_bindgen::JsCast;
+
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+        return;
+    };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(blob) else {
+        return;
+    };
+    if let Ok(element) = document.create_element("a") {
+        let a: web_sys::HtmlAnchorElement = element.unchecked_into();
+        a.set_href(&url);
+        a.set_download(filename);
+        a.click();
+        let _ = web_sys::Url::revoke_object_url(&url);
+    }
+}
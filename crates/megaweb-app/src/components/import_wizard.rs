@@ -0,0 +1,910 @@
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use megaweb_types::ddl::{create_table, ENGINES};
+use megaweb_types::import::{
+    auto_map, column_names, convert_cell, map_row, sniff_delimiter, ImportFile, ImportFormat,
+    ParquetUpload, RowError,
+};
+use megaweb_types::result_view::cell_text;
+use megaweb_types::schema::{ColumnInfo, DatabaseInfo, PartitionInfo, TableInfo};
+use megaweb_types::toast::ToastLevel;
+
+use crate::state::toast::{push_toast, use_toast_write};
+
+/// File rows shown on the preview step.
+const PREVIEW_ROWS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Source,
+    Target,
+    Preview,
+    Import,
+}
+
+/// A file as read in the browser: CSV text is parsed here, Parquet files
+/// are uploaded to and read by the server.
+pub enum LoadedFile {
+    Csv(String),
+    Parquet(ParquetUpload),
+}
+
+/// Where the rows of an import come from.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+enum ImportSource {
+    /// Rows parsed in the browser, sent in batches.
+    Rows(Arc<ImportFile>),
+    /// A Parquet file on the server, read there batch by batch.
+    Upload { id: uuid::Uuid, total_rows: usize },
+}
+
+impl ImportSource {
+    fn total_rows(&self) -> usize {
+        match self {
+            Self::Rows(file) => file.rows.len(),
+            Self::Upload { total_rows, .. } => *total_rows,
+        }
+    }
+}
+
+/// Progress of a running or finished import.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub inserted: usize,
+    pub failed: usize,
+    /// The first row errors, in file order.
+    pub errors: Vec<RowError>,
+    /// Set when the import stopped early: cancelled, or MegaDB failed.
+    pub stopped: Option<String>,
+    pub running: bool,
+    pub finished: bool,
+}
+
+/// What gets imported where, fixed when the import starts.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+struct ImportJob {
+    database: String,
+    table: String,
+    /// `CREATE TABLE` to run first, for a new table.
+    ddl: Option<String>,
+    columns: Vec<ColumnInfo>,
+    mapping: Vec<Option<usize>>,
+    source: ImportSource,
+}
+
+/// Step-by-step import of a CSV or Parquet file into a MegaDB table.
+///
+/// The file's column types are inferred, then mapped onto an existing
+/// table's columns or turned into a `CREATE TABLE` with engine,
+/// partitioning and sort columns. After a preview of the converted rows,
+/// the server inserts the rows in batches and reports rows it rejects.
+#[component]
+pub fn ImportWizard(
+    databases: Vec<DatabaseInfo>,
+    #[prop(into)] on_close: Callback<()>,
+    #[prop(into)] on_imported: Callback<()>,
+) -> impl IntoView {
+    let toast = use_toast_write();
+    let databases = StoredValue::new(databases);
+    let (step, set_step) = signal(Step::Source);
+
+    // Source
+    let (file_name, set_file_name) = signal(String::new());
+    let (format, set_format) = signal(Option::<ImportFormat>::None);
+    let (file, set_file) = signal(Option::<Arc<ImportFile>>::None);
+    // The server's copy of a Parquet file, with its row count; `file` then
+    // holds only its first rows
+    let (upload, set_upload) = signal(Option::<(uuid::Uuid, usize)>::None);
+    let total_rows = move || {
+        upload
+            .get()
+            .map(|(_, total)| total)
+            .or_else(|| file.with(|f| f.as_ref().map(|f| f.rows.len())))
+            .unwrap_or(0)
+    };
+    let (loading, set_loading) = signal(false);
+    let csv_text = StoredValue::new(String::new());
+    let (delimiter, set_delimiter) = signal(',');
+
+    // Target
+    let first_db = databases.with_value(|dbs| dbs.first().map(|d| d.name.clone()));
+    let (database, set_database) = signal(first_db.unwrap_or_else(|| "megadb".to_string()));
+    let (create_new, set_create_new) = signal(true);
+    let (existing, set_existing) = signal(String::new());
+    let (mapping, set_mapping) = signal(Vec::<Option<usize>>::new());
+    let (draft, set_draft) = signal(empty_table());
+
+    let (progress, set_progress) = signal(ImportProgress::default());
+    let cancel = StoredValue::new(false);
+
+    let tables = Memo::new(move |_| {
+        let db = database.get();
+        databases.with_value(|dbs| {
+            dbs.iter()
+                .filter(|d| d.name == db)
+                .flat_map(|d| d.schemas.iter())
+                .flat_map(|s| s.tables.iter().cloned())
+                .collect::<Vec<TableInfo>>()
+        })
+    });
+    let existing_table = Memo::new(move |_| {
+        let name = existing.get();
+        tables.with(|ts| {
+            ts.iter()
+                .find(|t| format!("{}.{}", t.schema_name, t.name) == name)
+                .cloned()
+        })
+    });
+    // Target columns and the file column feeding each
+    let target = Memo::new(move |_| {
+        if create_new.get() {
+            let columns = draft.with(|d| d.columns.clone());
+            let mapping = (0..columns.len()).map(Some).collect::<Vec<_>>();
+            (columns, mapping)
+        } else {
+            let columns = existing_table.with(|t| t.as_ref().map(|t| t.columns.clone()));
+            (columns.unwrap_or_default(), mapping.get())
+        }
+    });
+
+    let use_file = move |parsed: ImportFile| {
+        let stem = file_name.with_untracked(|n| {
+            n.rsplit_once('.')
+                .map_or(n.as_str(), |(stem, _)| stem)
+                .to_string()
+        });
+        let name = column_names(&[stem]).pop().unwrap_or_default();
+        set_draft.set(TableInfo {
+            name,
+            columns: parsed.columns.clone(),
+            ..empty_table()
+        });
+        if let Some(table) = existing_table.get_untracked() {
+            set_mapping.set(auto_map(&parsed.columns, &table.columns));
+        }
+        set_file.set(Some(Arc::new(parsed)));
+    };
+
+    let on_loaded = Callback::new(
+        move |(name, loaded): (String, Result<LoadedFile, String>)| {
+            set_loading.set(false);
+            set_file_name.set(name);
+            if let Some((id, _)) = upload.get_untracked() {
+                discard_upload(id);
+                set_upload.set(None);
+            }
+            match loaded {
+                Ok(LoadedFile::Csv(text)) => {
+                    let d = sniff_delimiter(&text);
+                    set_delimiter.set(d);
+                    set_format.set(Some(ImportFormat::Csv));
+                    use_file(ImportFile::from_csv(&text, d));
+                    csv_text.set_value(text);
+                }
+                Ok(LoadedFile::Parquet(uploaded)) => {
+                    set_format.set(Some(ImportFormat::Parquet));
+                    set_upload.set(Some((uploaded.id, uploaded.total_rows)));
+                    use_file(uploaded.file);
+                }
+                Err(e) => {
+                    set_file.set(None);
+                    push_toast(
+                        toast,
+                        ToastLevel::Error,
+                        format!("Couldn't read the file: {e}"),
+                    );
+                }
+            }
+        },
+    );
+
+    let choose_table = move |name: String| {
+        set_existing.set(name);
+        if let (Some(table), Some(f)) = (existing_table.get_untracked(), file.get_untracked()) {
+            set_mapping.set(auto_map(&f.columns, &table.columns));
+        }
+    };
+
+    let start = move |_| {
+        let Some(file) = file.get_untracked() else {
+            return;
+        };
+        let (columns, mapping) = target.get_untracked();
+        // Only mapped columns are written, so the others keep their defaults
+        let (columns, mapping): (Vec<ColumnInfo>, Vec<Option<usize>>) = columns
+            .into_iter()
+            .zip(mapping)
+            .filter(|(_, m)| m.is_some())
+            .unzip();
+        let (table, ddl) = if create_new.get_untracked() {
+            let d = draft.get_untracked();
+            (
+                format!("{}.{}", d.schema_name, d.name),
+                Some(create_table(&d)),
+            )
+        } else {
+            (existing.get_untracked(), None)
+        };
+        let source = match upload.get_untracked() {
+            Some((id, total_rows)) => ImportSource::Upload { id, total_rows },
+            None => ImportSource::Rows(file),
+        };
+        cancel.set_value(false);
+        set_progress.set(ImportProgress {
+            total: source.total_rows(),
+            running: true,
+            ..Default::default()
+        });
+        set_step.set(Step::Import);
+        run_import(
+            ImportJob {
+                database: database.get_untracked(),
+                table,
+                ddl,
+                columns,
+                mapping,
+                source,
+            },
+            set_progress,
+            cancel,
+        );
+    };
+
+    let can_continue = move || match step.get() {
+        Step::Source => file.with(Option::is_some),
+        Step::Target => target.with(|(columns, mapping)| {
+            let named =
+                draft.with(|d| !d.name.trim().is_empty() && !d.schema_name.trim().is_empty());
+            !columns.is_empty()
+                && mapping.iter().any(Option::is_some)
+                && (!create_new.get() || named)
+                && (create_new.get() || existing_table.with(Option::is_some))
+        }),
+        Step::Preview | Step::Import => true,
+    };
+
+    let source_step = move || {
+        view! {
+            <div class="import-step">
+                <div class="form-field">
+                    <label>"CSV or Parquet file"</label>
+                    <input
+                        type="file"
+                        accept=".csv,.tsv,.txt,.parquet,.pq"
+                        on:change=move |ev| {
+                            set_loading.set(true);
+                            read_file(ev, on_loaded);
+                        }
+                    />
+                </div>
+                {move || loading.get().then(|| view! { <p class="import-note">"Reading file..."</p> })}
+                {move || (format.get() == Some(ImportFormat::Csv)).then(|| view! {
+                    <div class="form-field">
+                        <label>"Delimiter"</label>
+                        <select on:change=move |ev| {
+                            let d = match event_target_value(&ev).as_str() {
+                                "tab" => '\t',
+                                s => s.chars().next().unwrap_or(','),
+                            };
+                            set_delimiter.set(d);
+                            csv_text.with_value(|text| use_file(ImportFile::from_csv(text, d)));
+                        }>
+                            {[(',', ","), (';', ";"), ('\t', "tab"), ('|', "|")].into_iter().map(|(d, label)| view! {
+                                <option value=label selected=move || delimiter.get() == d>{label}</option>
+                            }).collect::<Vec<_>>()}
+                        </select>
+                    </div>
+                })}
+                {move || file.get().map(|f| view! {
+                    <p class="import-note">
+                        {format!(
+                            "{}: {} rows, {} columns ({})",
+                            file_name.get(),
+                            total_rows(),
+                            f.columns.len(),
+                            format.get().map(ImportFormat::label).unwrap_or_default(),
+                        )}
+                    </p>
+                })}
+            </div>
+        }
+    };
+
+    // Re-render the column and partition lists only when they change, so
+    // typing in the other fields keeps focus
+    let draft_columns = Memo::new(move |_| draft.with(|d| d.columns.clone()));
+    let draft_partitions = Memo::new(move |_| draft.with(|d| d.partitions.clone()));
+
+    let new_table_form = move || {
+        let column_rows = move || {
+            draft_columns
+                .get()
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    let name = c.name.clone();
+                    let sorted = move || {
+                        draft.with(|d| d.sort_columns.as_ref().is_some_and(|s| s.contains(&name)))
+                    };
+                    view! {
+                        <tr>
+                            <td>
+                                <input
+                                    type="text"
+                                    prop:value=c.name.clone()
+                                    on:change=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        set_draft.update(|d| d.rename_column(i, value));
+                                    }
+                                />
+                            </td>
+                            <td>
+                                <input
+                                    type="text"
+                                    prop:value=c.data_type.clone()
+                                    on:change=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        set_draft.update(|d| d.columns[i].data_type = value);
+                                    }
+                                />
+                            </td>
+                            <td class="import-check">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=c.nullable
+                                    on:change=move |ev| {
+                                        let checked = event_target_checked(&ev);
+                                        set_draft.update(|d| d.columns[i].nullable = checked);
+                                    }
+                                />
+                            </td>
+                            <td class="import-check">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=sorted
+                                    on:change=move |ev| {
+                                        let checked = event_target_checked(&ev);
+                                        set_draft.update(|d| d.toggle_sort_column(i, checked));
+                                    }
+                                />
+                            </td>
+                        </tr>
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let partitions = move || {
+            draft_partitions.get().into_iter().enumerate().map(|(i, p)| {
+            let columns = draft_columns.get();
+            view! {
+                <div class="import-partition">
+                    <select on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        set_draft.update(|d| d.partitions[i].column = value);
+                    }>
+                        {columns.into_iter().map(|c| {
+                            let selected = c.name == p.column;
+                            view! { <option value=c.name.clone() selected=selected>{c.name.clone()}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    <input
+                        type="text"
+                        list="import-transforms"
+                        placeholder="identity, month, bucket(16)"
+                        prop:value=p.transform.clone()
+                        on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_draft.update(|d| d.partitions[i].transform = value);
+                        }
+                    />
+                    <button class="btn btn-sm" on:click=move |_| set_draft.update(|d| { d.partitions.remove(i); })>
+                        "Remove"
+                    </button>
+                </div>
+            }
+        }).collect::<Vec<_>>()
+        };
+
+        view! {
+            <div class="form-row">
+                <div class="form-field">
+                    <label>"Schema"</label>
+                    <input
+                        type="text"
+                        prop:value=move || draft.with(|d| d.schema_name.clone())
+                        on:input=move |ev| set_draft.update(|d| d.schema_name = event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-field">
+                    <label>"Table name"</label>
+                    <input
+                        type="text"
+                        prop:value=move || draft.with(|d| d.name.clone())
+                        on:input=move |ev| set_draft.update(|d| d.name = event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-field">
+                    <label>"Engine"</label>
+                    <select on:change=move |ev| set_draft.update(|d| d.engine = event_target_value(&ev))>
+                        {ENGINES.into_iter().map(|e| view! {
+                            <option value=e selected=move || draft.with(|d| d.engine == e)>{e}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </div>
+            </div>
+            <table class="import-columns">
+                <thead>
+                    <tr>
+                        <th>"Column"</th>
+                        <th>"Type"</th>
+                        <th>"Nullable"</th>
+                        <th title="Sort columns, in the order they are checked">"Sort"</th>
+                    </tr>
+                </thead>
+                <tbody>{column_rows}</tbody>
+            </table>
+            <div class="import-partitions">
+                <label>"Partitioning"</label>
+                {partitions}
+                <datalist id="import-transforms">
+                    {["identity", "year", "month", "day", "hour", "bucket(16)", "truncate(10)"]
+                        .into_iter()
+                        .map(|t| view! { <option value=t></option> })
+                        .collect::<Vec<_>>()}
+                </datalist>
+                <button
+                    class="btn btn-sm"
+                    on:click=move |_| set_draft.update(|d| {
+                        let column = d.columns.first().map(|c| c.name.clone()).unwrap_or_default();
+                        d.partitions.push(PartitionInfo { column, transform: "identity".into() });
+                    })
+                >
+                    "Add partition"
+                </button>
+            </div>
+            <pre class="import-ddl">{move || draft.with(create_table)}</pre>
+        }
+    };
+
+    let mapping_form = move || {
+        let file_columns = file.with(|f| f.as_ref().map(|f| f.columns.clone()).unwrap_or_default());
+        let rows = existing_table.get().map(|t| t.columns).unwrap_or_default().into_iter().enumerate().map(|(i, c)| {
+            let options = file_columns.iter().enumerate().map(|(j, f)| {
+                let selected = move || mapping.with(|m| m.get(i).copied().flatten() == Some(j));
+                view! { <option value=j.to_string() selected=selected>{f.name.clone()}</option> }
+            }).collect::<Vec<_>>();
+            view! {
+                <tr>
+                    <td>{c.name.clone()}</td>
+                    <td class="col-type">{c.data_type.clone()}</td>
+                    <td>
+                        <select on:change=move |ev| {
+                            let source = event_target_value(&ev).parse::<usize>().ok();
+                            set_mapping.update(|m| {
+                                m.resize(m.len().max(i + 1), None);
+                                m[i] = source;
+                            });
+                        }>
+                            <option value="" selected=move || mapping.with(|m| m.get(i).copied().flatten().is_none())>
+                                "\u{2014} skip \u{2014}"
+                            </option>
+                            {options}
+                        </select>
+                    </td>
+                </tr>
+            }
+        }).collect::<Vec<_>>();
+        view! {
+            <div class="form-field">
+                <label>"Table"</label>
+                <select on:change=move |ev| choose_table(event_target_value(&ev))>
+                    <option value="">"Select a table"</option>
+                    {move || tables.get().into_iter().map(|t| {
+                        let name = format!("{}.{}", t.schema_name, t.name);
+                        let selected = name == existing.get_untracked();
+                        view! {
+                            <option value=name.clone() selected=selected>
+                                {format!("{name} ({})", t.engine)}
+                            </option>
+                        }
+                    }).collect::<Vec<_>>()}
+                </select>
+            </div>
+            <table class="import-columns">
+                <thead>
+                    <tr>
+                        <th>"Table column"</th>
+                        <th>"Type"</th>
+                        <th>"From file column"</th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        }
+    };
+
+    let target_step = move || {
+        view! {
+            <div class="import-step">
+                <div class="form-row">
+                    <div class="form-field">
+                        <label>"Database"</label>
+                        <select on:change=move |ev| {
+                            set_database.set(event_target_value(&ev));
+                            set_existing.set(String::new());
+                        }>
+                            {databases.with_value(|dbs| dbs.iter().map(|d| {
+                                let name = d.name.clone();
+                                let selected = name == database.get_untracked();
+                                view! { <option value=name.clone() selected=selected>{name.clone()}</option> }
+                            }).collect::<Vec<_>>())}
+                        </select>
+                    </div>
+                    <div class="form-field">
+                        <label>"Target"</label>
+                        <div class="result-mode-toggle">
+                            <button
+                                class=move || if create_new.get() { "btn btn-sm active" } else { "btn btn-sm" }
+                                on:click=move |_| set_create_new.set(true)
+                            >
+                                "New table"
+                            </button>
+                            <button
+                                class=move || if create_new.get() { "btn btn-sm" } else { "btn btn-sm active" }
+                                on:click=move |_| set_create_new.set(false)
+                            >
+                                "Existing table"
+                            </button>
+                        </div>
+                    </div>
+                </div>
+                {move || if create_new.get() {
+                    new_table_form().into_any()
+                } else {
+                    mapping_form().into_any()
+                }}
+            </div>
+        }
+    };
+
+    let preview_step = move || {
+        let f = file.get().unwrap_or_default();
+        let (columns, mapping) = target.get();
+        let header = columns
+            .iter()
+            .map(|c| {
+                view! {
+                    <th>
+                        <span class="col-name">{c.name.clone()}</span>
+                        <span class="col-type">{c.data_type.clone()}</span>
+                    </th>
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut invalid = 0;
+        let rows = f
+            .rows
+            .iter()
+            .take(PREVIEW_ROWS)
+            .map(|row| {
+                let values = map_row(row, &mapping);
+                let cells = values
+                    .iter()
+                    .zip(&columns)
+                    .map(|(v, c)| match convert_cell(v, c) {
+                        Ok(converted) => view! { <td>{cell_text(&converted)}</td> }.into_any(),
+                        Err(e) => {
+                            invalid += 1;
+                            view! { <td class="import-invalid" title=e>{cell_text(v)}</td> }
+                                .into_any()
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                view! { <tr>{cells}</tr> }
+            })
+            .collect::<Vec<_>>();
+        view! {
+            <div class="import-step">
+                <p class="import-note">
+                    {format!(
+                        "First {} of {} rows, converted to the target types.",
+                        f.rows.len().min(PREVIEW_ROWS),
+                        total_rows(),
+                    )}
+                    {(invalid > 0).then(|| format!(
+                        " {invalid} highlighted values don't fit their column and will be reported as row errors."
+                    ))}
+                </p>
+                <div class="result-table-scroll import-preview">
+                    <table class="result-table">
+                        <thead><tr>{header}</tr></thead>
+                        <tbody>{rows}</tbody>
+                    </table>
+                </div>
+            </div>
+        }
+    };
+
+    let import_step = move || {
+        view! {
+            <div class="import-step">
+                {move || {
+                    let p = progress.get();
+                    let percent = if p.total == 0 { 100.0 } else { p.processed as f64 * 100.0 / p.total as f64 };
+                    view! {
+                        <div class="import-progress">
+                            <div class="import-progress-bar" style=format!("width:{percent:.1}%")></div>
+                        </div>
+                        <p class="import-note">
+                            {format!(
+                                "{} of {} rows processed: {} inserted, {} failed",
+                                p.processed, p.total, p.inserted, p.failed,
+                            )}
+                        </p>
+                        {p.stopped.map(|reason| view! { <div class="form-error">{reason}</div> })}
+                        {(!p.errors.is_empty()).then(|| view! {
+                            <div class="import-errors">
+                                {p.errors.iter().map(|e| view! {
+                                    <div class="import-error">
+                                        <span class="import-error-row">{format!("Row {}", e.row + 1)}</span>
+                                        <span>{e.message.clone()}</span>
+                                    </div>
+                                }).collect::<Vec<_>>()}
+                                {(p.failed > p.errors.len()).then(|| view! {
+                                    <div class="import-note">{format!("\u{2026} {} more", p.failed - p.errors.len())}</div>
+                                })}
+                            </div>
+                        })}
+                    }
+                }}
+            </div>
+        }
+    };
+
+    let close = move |_| {
+        if progress.with_untracked(|p| p.running) {
+            cancel.set_value(true);
+        }
+        // An import discards the upload itself once it stops
+        if progress.with_untracked(|p| !p.running && !p.finished) {
+            if let Some((id, _)) = upload.get_untracked() {
+                discard_upload(id);
+            }
+        }
+        if progress.with_untracked(|p| p.finished && p.inserted > 0) {
+            on_imported.run(());
+        }
+        on_close.run(());
+    };
+
+    view! {
+        <div class="modal-overlay">
+            <div class="modal import-wizard">
+                <div class="modal-header">
+                    <h3>"Import data"</h3>
+                    <div class="import-steps">
+                        {[(Step::Source, "1. File"), (Step::Target, "2. Target"), (Step::Preview, "3. Preview"), (Step::Import, "4. Import")]
+                            .into_iter()
+                            .map(|(s, label)| view! {
+                                <span class:active=move || step.get() == s>{label}</span>
+                            })
+                            .collect::<Vec<_>>()}
+                    </div>
+                </div>
+                <div class="modal-body">
+                    {move || match step.get() {
+                        Step::Source => source_step().into_any(),
+                        Step::Target => target_step().into_any(),
+                        Step::Preview => preview_step().into_any(),
+                        Step::Import => import_step().into_any(),
+                    }}
+                </div>
+                <div class="modal-footer">
+                    {move || match step.get() {
+                        Step::Source => None,
+                        Step::Target => Some(view! {
+                            <button class="btn btn-secondary" on:click=move |_| set_step.set(Step::Source)>"Back"</button>
+                        }.into_any()),
+                        Step::Preview => Some(view! {
+                            <button class="btn btn-secondary" on:click=move |_| set_step.set(Step::Target)>"Back"</button>
+                        }.into_any()),
+                        Step::Import => None,
+                    }}
+                    <button class="btn btn-secondary" on:click=close>
+                        {move || {
+                            let p = progress.get();
+                            if p.running { "Cancel" } else if p.finished { "Done" } else { "Close" }
+                        }}
+                    </button>
+                    {move || match step.get() {
+                        Step::Source => Some(view! {
+                            <button class="btn btn-primary" disabled=move || !can_continue() on:click=move |_| set_step.set(Step::Target)>
+                                "Next"
+                            </button>
+                        }.into_any()),
+                        Step::Target => Some(view! {
+                            <button class="btn btn-primary" disabled=move || !can_continue() on:click=move |_| set_step.set(Step::Preview)>
+                                "Next"
+                            </button>
+                        }.into_any()),
+                        Step::Preview => Some(view! {
+                            <button class="btn btn-primary" on:click=start>
+                                {move || if create_new.get() { "Create table and import" } else { "Import" }}
+                            </button>
+                        }.into_any()),
+                        Step::Import => None,
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
+
+fn empty_table() -> TableInfo {
+    TableInfo {
+        schema_name: "public".to_string(),
+        name: String::new(),
+        engine: "OLAP".to_string(),
+        row_count: None,
+        size_bytes: None,
+        columns: vec![],
+        partitions: vec![],
+        compression: None,
+        sort_columns: None,
+        primary_key: vec![],
+    }
+}
+
+/// Read the file chosen in a file input and hand it to `done` with its name.
+#[cfg(target_arch = "wasm32")]
+fn read_file(ev: leptos::ev::Event, done: Callback<(String, Result<LoadedFile, String>)>) {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let Some(file) = ev
+        .target()
+        .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+        .and_then(|input| input.files())
+        .and_then(|files| files.get(0))
+    else {
+        return;
+    };
+    let name = file.name();
+    wasm_bindgen_futures::spawn_local(async move {
+        let loaded = match ImportFormat::from_filename(&name) {
+            Some(ImportFormat::Csv) => JsFuture::from(file.text())
+                .await
+                .map(|text| LoadedFile::Csv(text.as_string().unwrap_or_default()))
+                .map_err(|e| format!("{e:?}")),
+            Some(ImportFormat::Parquet) => match JsFuture::from(file.array_buffer()).await {
+                Ok(buffer) => {
+                    let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                    crate::http::post_bytes::<ParquetUpload>("/import/parquet", &bytes)
+                        .await
+                        .map(LoadedFile::Parquet)
+                }
+                Err(e) => Err(format!("{e:?}")),
+            },
+            None => Err("expected a .csv or .parquet file".to_string()),
+        };
+        done.run((name, loaded));
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(_ev: leptos::ev::Event, _done: Callback<(String, Result<LoadedFile, String>)>) {
+    // No-op on server
+}
+
+/// Delete the server's copy of an uploaded file.
+#[cfg(target_arch = "wasm32")]
+fn discard_upload(id: uuid::Uuid) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = crate::http::post_json::<()>("/import/parquet/discard", &id).await;
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn discard_upload(_id: uuid::Uuid) {
+    // No-op on server
+}
+
+/// Create the table if needed, then import the rows in batches, updating
+/// `progress` after each. Stops at the next batch once `cancel` is set.
+/// An uploaded file is discarded once the import stops.
+#[cfg(target_arch = "wasm32")]
+fn run_import(
+    job: ImportJob,
+    set_progress: WriteSignal<ImportProgress>,
+    cancel: StoredValue<bool>,
+) {
+    use megaweb_types::import::{ImportBatch, ImportBatchResult, ParquetBatchRequest, BATCH_ROWS};
+    use megaweb_types::query::{QueryRequest, QueryResult};
+
+    // Row errors listed while importing; the rest are only counted
+    const MAX_LISTED_ERRORS: usize = 200;
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let imported: Result<(), String> = async {
+            if let Some(ddl) = &job.ddl {
+                let req = QueryRequest {
+                    sql: ddl.clone(),
+                    database: job.database.clone(),
+                    limit: None,
+                };
+                crate::http::post_json::<QueryResult>("/proxy/megadb/query", &req)
+                    .await
+                    .and_then(|result| result.error.map_or(Ok(()), Err))
+                    .map_err(|e| format!("CREATE TABLE failed: {e}"))?;
+            }
+
+            let total = job.source.total_rows();
+            for offset in (0..total).step_by(BATCH_ROWS) {
+                if cancel.get_value() {
+                    return Err("Import cancelled; rows already inserted were kept".to_string());
+                }
+                let rows = (total - offset).min(BATCH_ROWS);
+                let sent = match &job.source {
+                    ImportSource::Rows(file) => {
+                        let batch = ImportBatch {
+                            database: job.database.clone(),
+                            table: job.table.clone(),
+                            columns: job.columns.clone(),
+                            rows: file.rows[offset..offset + rows]
+                                .iter()
+                                .map(|row| map_row(row, &job.mapping))
+                                .collect(),
+                            offset,
+                        };
+                        crate::http::post_json::<ImportBatchResult>("/import/batch", &batch).await
+                    }
+                    ImportSource::Upload { id, .. } => {
+                        let req = ParquetBatchRequest {
+                            upload: *id,
+                            database: job.database.clone(),
+                            table: job.table.clone(),
+                            columns: job.columns.clone(),
+                            mapping: job.mapping.clone(),
+                            offset,
+                        };
+                        crate::http::post_json::<ImportBatchResult>("/import/parquet/batch", &req)
+                            .await
+                    }
+                };
+                let result =
+                    sent.map_err(|e| format!("Import stopped at row {}: {e}", offset + 1))?;
+                set_progress.update(|p| {
+                    p.processed += rows;
+                    p.inserted += result.inserted;
+                    p.failed += result.errors.len();
+                    let room = MAX_LISTED_ERRORS.saturating_sub(p.errors.len());
+                    p.errors.extend(result.errors.into_iter().take(room));
+                });
+            }
+            Ok(())
+        }
+        .await;
+
+        if let ImportSource::Upload { id, .. } = job.source {
+            discard_upload(id);
+        }
+        set_progress.update(|p| {
+            p.stopped = imported.err();
+            p.running = false;
+            p.finished = true;
+        });
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_import(
+    _job: ImportJob,
+    _set_progress: WriteSignal<ImportProgress>,
+    _cancel: StoredValue<bool>,
+) {
+    // No-op on server
+}
@@ -59,6 +59,26 @@ pub const SHORTCUTS: &[Shortcut] = &[
         description: "Close current tab",
         category: "Editor",
     },
+    Shortcut {
+        keys: "Shift+Arrows",
+        description: "Extend grid selection",
+        category: "Results",
+    },
+    Shortcut {
+        keys: "Ctrl+A",
+        description: "Select all result cells",
+        category: "Results",
+    },
+    Shortcut {
+        keys: "Ctrl+C",
+        description: "Copy selection as TSV",
+        category: "Results",
+    },
+    Shortcut {
+        keys: "Ctrl+Shift+C",
+        description: "Copy selection with headers",
+        category: "Results",
+    },
     Shortcut {
         keys: "?",
         description: "Show shortcuts",
@@ -163,7 +183,7 @@ pub fn KeyboardManager() -> impl IntoView {
 
 #[component]
 fn ShortcutsHelp(#[prop(into)] on_close: Callback<()>) -> impl IntoView {
-    let categories = ["General", "Navigation", "Editor"];
+    let categories = ["General", "Navigation", "Editor", "Results"];
 
     view! {
         <div class="modal-overlay" on:click=move |_| on_close.run(())>
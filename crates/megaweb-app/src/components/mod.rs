@@ -1,15 +1,29 @@
 pub mod auto_refresh;
+pub mod cell_panel;
 pub mod chart;
 pub mod codemirror;
+pub mod data_editor;
+pub mod export_menu;
+pub mod import_wizard;
 pub mod keyboard;
 pub mod modal;
+pub mod partition_explorer;
+pub mod pivot_table;
+pub mod plan_compare;
+pub mod plan_diagram;
 pub mod pod_card;
 pub mod query_history;
 pub mod query_plan;
+pub mod query_stats;
+pub mod result_chart;
+pub mod result_diff;
+pub mod result_grid;
 pub mod result_table;
 pub mod scaling_panel;
 pub mod schema_tree;
 pub mod sidebar;
 pub mod storage_panel;
 pub mod tab_bar;
+pub mod table_data;
+pub mod table_designer;
 pub mod toast;
@@ -0,0 +1,302 @@
+use leptos::prelude::*;
+use megaweb_types::partitions::{
+    compact_partition_sql, drop_partition_sql, monthly_sizes, partition_warnings, MonthSize,
+    PartitionIssue, TablePartitionsRequest,
+};
+use megaweb_types::schema::TableInfo;
+use megaweb_types::table_stats::PartitionStats;
+use megaweb_types::toast::ToastLevel;
+
+use crate::components::modal::ConfirmModal;
+use crate::components::table_data::post;
+use crate::components::table_designer::run_statements;
+use crate::pages::schema_browser::{format_number, format_size};
+use crate::state::toast::{push_toast, use_toast_write};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Partition,
+    Size,
+    Rows,
+    Modified,
+}
+
+/// A maintenance statement waiting for confirmation.
+#[derive(Debug, Clone)]
+struct PendingAction {
+    title: String,
+    sql: String,
+}
+
+/// The stored partitions of a table with their rows, files, bytes and
+/// last change, a heatmap of bytes by month, and drop and compact actions.
+/// Skewed, tiny and fragmented partitions are flagged.
+#[component]
+pub fn PartitionExplorer(table: TableInfo, database: String) -> impl IntoView {
+    let toast = use_toast_write();
+    let name = format!("{}.{}", table.schema_name, table.name);
+    let (reloads, set_reloads) = signal(0u32);
+    let (sort_by, set_sort_by) = signal(SortBy::Partition);
+    let (pending, set_pending) = signal(Option::<PendingAction>::None);
+
+    let partitions = LocalResource::new({
+        let req = TablePartitionsRequest {
+            database: database.clone(),
+            table: name.clone(),
+        };
+        move || {
+            reloads.track();
+            let req = req.clone();
+            async move { post::<Vec<PartitionStats>>("/proxy/megadb/table/partitions", &req).await }
+        }
+    });
+
+    let confirm = Callback::new(move |_| {
+        let Some(action) = pending.get_untracked() else {
+            return;
+        };
+        set_pending.set(None);
+        let title = action.title.clone();
+        let done = Callback::new(move |result: Result<usize, String>| match result {
+            Ok(_) => {
+                push_toast(toast, ToastLevel::Success, format!("{title}: done"));
+                set_reloads.update(|n| *n += 1);
+            }
+            Err(e) => push_toast(toast, ToastLevel::Error, e),
+        });
+        run_statements(database.clone(), vec![action.sql], done);
+    });
+
+    let rows = move |partitions: &[PartitionStats]| {
+        let warnings = partition_warnings(partitions);
+        let largest = partitions
+            .iter()
+            .filter_map(|p| p.size_bytes)
+            .max()
+            .unwrap_or(0);
+        let mut sorted = partitions.to_vec();
+        match sort_by.get() {
+            SortBy::Partition => sorted.sort_by(|a, b| a.partition.cmp(&b.partition)),
+            SortBy::Size => sorted.sort_by_key(|p| std::cmp::Reverse(p.size_bytes)),
+            SortBy::Rows => sorted.sort_by_key(|p| std::cmp::Reverse(p.row_count)),
+            SortBy::Modified => sorted.sort_by(|a, b| b.last_modified.cmp(&a.last_modified)),
+        }
+        sorted
+            .into_iter()
+            .map(|p| {
+                let share = match (p.size_bytes, largest) {
+                    (Some(b), l) if l > 0 => b as f64 * 100.0 / l as f64,
+                    _ => 0.0,
+                };
+                let badges = warnings
+                    .iter()
+                    .filter(|w| w.partition == p.partition)
+                    .map(|w| {
+                        let class = match w.issue {
+                            PartitionIssue::Skewed => "badge badge--worse",
+                            PartitionIssue::Tiny | PartitionIssue::SmallFiles => "badge badge--hot",
+                        };
+                        view! { <span class=class title=w.message.clone()>{w.issue.label()}</span> }
+                    })
+                    .collect::<Vec<_>>();
+                let (compact, drop) = (
+                    compact_partition_sql(&name, &p.partition),
+                    drop_partition_sql(&name, &p.partition),
+                );
+                let label = p.partition.clone();
+                view! {
+                    <tr>
+                        <td class="col-name">{p.partition.clone()} " " {badges}</td>
+                        <td class="stats-num">{p.row_count.map(format_number)}</td>
+                        <td class="stats-num">{p.file_count.map(format_number)}</td>
+                        <td>
+                            <div class="stats-bar">
+                                <div class="stats-bar-fill" style=format!("width:{share:.1}%")></div>
+                                <span>{p.size_bytes.map(format_size)}</span>
+                            </div>
+                        </td>
+                        <td class="stats-value">{p.last_modified.clone().unwrap_or_default()}</td>
+                        <td class="partition-actions">
+                            <button
+                                class="btn btn-sm"
+                                title="Merge the partition's files"
+                                on:click={
+                                    let label = label.clone();
+                                    move |_| set_pending.set(Some(PendingAction {
+                                        title: format!("Compact {label}"),
+                                        sql: compact.clone(),
+                                    }))
+                                }
+                            >
+                                "Compact"
+                            </button>
+                            <button
+                                class="btn btn-sm btn-danger"
+                                title="Delete the partition and its rows"
+                                on:click=move |_| set_pending.set(Some(PendingAction {
+                                    title: format!("Drop {label}"),
+                                    sql: drop.clone(),
+                                }))
+                            >
+                                "Drop"
+                            </button>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let body = move || match partitions.get() {
+        None => view! { <p class="import-note">"Loading partitions..."</p> }.into_any(),
+        Some(Err(e)) => view! { <div class="form-error">{e}</div> }.into_any(),
+        Some(Ok(partitions)) if partitions.is_empty() => {
+            view! { <p class="import-note">"This table has no stored partitions."</p> }.into_any()
+        }
+        Some(Ok(partitions)) => {
+            let total_rows: u64 = partitions.iter().filter_map(|p| p.row_count).sum();
+            let total_bytes: u64 = partitions.iter().filter_map(|p| p.size_bytes).sum();
+            let warnings = partition_warnings(&partitions);
+            let count = |issue| warnings.iter().filter(|w| w.issue == issue).count();
+            let hints = [
+                (PartitionIssue::Skewed, "Scans of them dominate; a finer transform or bucketing spreads the data"),
+                (PartitionIssue::Tiny, "A coarser transform means fewer partitions to open"),
+                (PartitionIssue::SmallFiles, "Compact them to merge their files"),
+            ]
+            .into_iter()
+            .filter(|(issue, _)| count(*issue) > 0)
+            .map(|(issue, text)| {
+                view! {
+                    <li class="plan-hint plan-hint--warning">
+                        <div class="plan-hint-header">
+                            <span class="plan-hint-rule">{issue.label()}</span>
+                            <span class="plan-hint-node">{format!("{} partition(s)", count(issue))}</span>
+                        </div>
+                        <div class="plan-hint-message">{text}</div>
+                    </li>
+                }
+            })
+            .collect::<Vec<_>>();
+            view! {
+                <p class="import-note">
+                    {format!(
+                        "{} partitions, {} rows, {}",
+                        format_number(partitions.len() as u64),
+                        format_number(total_rows),
+                        format_size(total_bytes),
+                    )}
+                </p>
+                {(!hints.is_empty()).then(|| view! { <ul class="plan-hints">{hints}</ul> })}
+                {heatmap(monthly_sizes(&partitions))}
+                <table class="columns-table">
+                    <thead>
+                        <tr>
+                            <th>"Partition"</th>
+                            <th>"Rows"</th>
+                            <th>"Files"</th>
+                            <th>"Size"</th>
+                            <th>"Last modified"</th>
+                            <th></th>
+                        </tr>
+                    </thead>
+                    <tbody>{rows(&partitions)}</tbody>
+                </table>
+            }
+            .into_any()
+        }
+    };
+
+    view! {
+        <div class="partition-explorer">
+            <div class="table-data-pager">
+                <label>"Sort by"</label>
+                <select on:change=move |ev| set_sort_by.set(match event_target_value(&ev).as_str() {
+                    "size" => SortBy::Size,
+                    "rows" => SortBy::Rows,
+                    "modified" => SortBy::Modified,
+                    _ => SortBy::Partition,
+                })>
+                    <option value="partition">"Partition"</option>
+                    <option value="size">"Size"</option>
+                    <option value="rows">"Rows"</option>
+                    <option value="modified">"Last modified"</option>
+                </select>
+                <button class="btn btn-sm" on:click=move |_| set_reloads.update(|n| *n += 1)>
+                    "Refresh"
+                </button>
+            </div>
+            {body}
+            {move || pending.get().map(|action| view! {
+                <ConfirmModal
+                    title=action.title
+                    message=format!("Run this statement?\n\n{}", action.sql)
+                    show=Signal::stored(true)
+                    on_confirm=confirm
+                    on_cancel=move |_| set_pending.set(None)
+                />
+            })}
+        </div>
+    }
+}
+
+/// Bytes per month as a grid of years by months, shaded by size.
+fn heatmap(months: Vec<MonthSize>) -> Option<impl IntoView> {
+    let largest = months
+        .iter()
+        .map(|m| m.size_bytes)
+        .max()
+        .filter(|&l| l > 0)?;
+    let (first, last) = (months.first()?.year, months.last()?.year);
+    let header = MONTHS.map(|m| view! { <th>{m}</th> });
+    let years = (first..=last)
+        .map(|year| {
+            let cells = (1..=12u32)
+                .map(|month| {
+                    match months.iter().find(|m| m.year == year && m.month == month) {
+                        Some(m) => {
+                            let level = m.size_bytes as f64 / largest as f64;
+                            let title = format!(
+                                "{year}-{month:02}: {} in {} partition(s)",
+                                format_size(m.size_bytes),
+                                m.partitions
+                            );
+                            view! {
+                                <td
+                                    class="heatmap-cell"
+                                    title=title
+                                    style=format!("background: rgba(122, 162, 247, {:.2})", 0.1 + level * 0.9)
+                                ></td>
+                            }
+                            .into_any()
+                        }
+                        None => view! { <td class="heatmap-cell heatmap-empty"></td> }.into_any(),
+                    }
+                })
+                .collect::<Vec<_>>();
+            view! {
+                <tr>
+                    <th>{year}</th>
+                    {cells}
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    Some(view! {
+        <div class="partition-heatmap">
+            <h3>"Size by month"</h3>
+            <table>
+                <thead>
+                    <tr>
+                        <th></th>
+                        {header}
+                    </tr>
+                </thead>
+                <tbody>{years}</tbody>
+            </table>
+        </div>
+    })
+}
@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use megaweb_types::pivot::{Aggregation, PivotSpec, PivotTable, PivotValue};
+use megaweb_types::query::QueryResult;
+
+/// Pivot rows rendered at most; exports include all of them.
+const MAX_RENDERED_ROWS: usize = 1000;
+
+/// Drop zone a field is dragged into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Rows,
+    Columns,
+    Values,
+}
+
+/// Pivot view of a query result.
+///
+/// Fields are dragged from the field list into the rows, columns and values
+/// zones; value fields pick an aggregation. The pivot is computed over the
+/// loaded rows, and every layout change is reported through `on_change`.
+#[component]
+pub fn PivotView(
+    result: Arc<QueryResult>,
+    spec: Signal<PivotSpec>,
+    #[prop(into)] on_change: Callback<PivotSpec>,
+) -> impl IntoView {
+    let spec_result = Arc::clone(&result);
+    let current = Memo::new(move |_| {
+        let mut spec = spec.get();
+        spec.retain_existing(&spec_result);
+        spec
+    });
+    let update = move |f: &dyn Fn(&mut PivotSpec)| {
+        let mut next = current.get_untracked();
+        f(&mut next);
+        on_change.run(next);
+    };
+    let (dragging, set_dragging) = signal(Option::<String>::None);
+
+    let drop_into = move |zone: Zone| {
+        let Some(field) = dragging.get_untracked() else {
+            return;
+        };
+        set_dragging.set(None);
+        update(&|s| {
+            // A field groups either rows or columns, not both
+            s.rows.retain(|f| f != &field);
+            s.columns.retain(|f| f != &field);
+            match zone {
+                Zone::Rows => s.rows.push(field.clone()),
+                Zone::Columns => s.columns.push(field.clone()),
+                Zone::Values => s.values.push(PivotValue {
+                    column: field.clone(),
+                    agg: Aggregation::Sum,
+                }),
+            }
+        });
+    };
+
+    let fields: Vec<String> = result.columns.iter().map(|c| c.name.clone()).collect();
+    let table_result = Arc::clone(&result);
+
+    let zone_view = move |zone: Zone, label: &'static str| {
+        view! {
+            <div
+                class="pivot-zone"
+                on:dragover=move |ev| ev.prevent_default()
+                on:drop=move |ev| {
+                    ev.prevent_default();
+                    drop_into(zone);
+                }
+            >
+                <span class="pivot-zone-label">{label}</span>
+                {move || {
+                    let spec = current.get();
+                    match zone {
+                        Zone::Rows | Zone::Columns => {
+                            let names = if zone == Zone::Rows { spec.rows } else { spec.columns };
+                            names.into_iter().map(|name| {
+                                let remove = name.clone();
+                                view! {
+                                    <span class="pivot-chip">
+                                        {name}
+                                        <span
+                                            class="pivot-chip-remove"
+                                            on:click=move |_| {
+                                                let remove = remove.clone();
+                                                update(&|s| {
+                                                    s.rows.retain(|f| f != &remove);
+                                                    s.columns.retain(|f| f != &remove);
+                                                });
+                                            }
+                                        >
+                                            "x"
+                                        </span>
+                                    </span>
+                                }.into_any()
+                            }).collect::<Vec<_>>()
+                        }
+                        Zone::Values => spec.values.into_iter().enumerate().map(|(i, v)| {
+                            view! {
+                                <span class="pivot-chip">
+                                    <select on:change=move |ev| {
+                                        let label = event_target_value(&ev);
+                                        if let Some(agg) = Aggregation::ALL.into_iter().find(|a| a.label() == label) {
+                                            update(&|s| s.values[i].agg = agg);
+                                        }
+                                    }>
+                                        {Aggregation::ALL.into_iter().map(|a| view! {
+                                            <option value=a.label() selected=a == v.agg>{a.label()}</option>
+                                        }).collect::<Vec<_>>()}
+                                    </select>
+                                    {v.column.clone()}
+                                    <span
+                                        class="pivot-chip-remove"
+                                        on:click=move |_| update(&|s| {
+                                            s.values.remove(i);
+                                        })
+                                    >
+                                        "x"
+                                    </span>
+                                </span>
+                            }.into_any()
+                        }).collect::<Vec<_>>(),
+                    }
+                }}
+            </div>
+        }
+    };
+
+    view! {
+        <div class="pivot-view">
+            <div class="pivot-config">
+                <div class="pivot-fields">
+                    <span class="pivot-zone-label">"Fields"</span>
+                    {fields.into_iter().map(|name| {
+                        let drag = name.clone();
+                        view! {
+                            <span
+                                class="pivot-chip pivot-field"
+                                draggable="true"
+                                on:dragstart=move |_| set_dragging.set(Some(drag.clone()))
+                                on:dragend=move |_| set_dragging.set(None)
+                            >
+                                {name}
+                            </span>
+                        }
+                    }).collect::<Vec<_>>()}
+                </div>
+                {zone_view(Zone::Rows, "Rows")}
+                {zone_view(Zone::Columns, "Columns")}
+                {zone_view(Zone::Values, "Values")}
+            </div>
+            {move || match PivotTable::build(&table_result, &current.get()) {
+                None => view! {
+                    <div class="result-chart-empty">
+                        "Drag fields into Rows, Columns and Values to build a pivot."
+                    </div>
+                }.into_any(),
+                Some(pivot) => pivot_table_view(pivot),
+            }}
+        </div>
+    }
+}
+
+fn pivot_table_view(pivot: PivotTable) -> AnyView {
+    let data_columns = pivot.col_keys.len() * pivot.value_labels.len();
+    let show_totals = pivot.col_keys.len() > 1;
+    let total_rows = pivot.row_keys.len();
+    let headers: Vec<String> = (0..data_columns).map(|i| pivot.column_label(i)).collect();
+    let total_headers: Vec<String> = if show_totals {
+        pivot
+            .value_labels
+            .iter()
+            .map(|l| format!("Total / {l}"))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let row_fields = pivot.row_fields.len().max(1);
+    let row_headers = if pivot.row_fields.is_empty() {
+        vec![String::new()]
+    } else {
+        pivot.row_fields.clone()
+    };
+
+    let rows = pivot
+        .row_keys
+        .iter()
+        .zip(&pivot.cells)
+        .zip(&pivot.row_totals)
+        .take(MAX_RENDERED_ROWS)
+        .map(|((key, cells), totals)| {
+            let labels = if key.is_empty() {
+                vec!["All".to_string()]
+            } else {
+                key.clone()
+            };
+            let key_cells = labels
+                .into_iter()
+                .map(|k| view! { <th class="pivot-row-key">{k}</th> })
+                .collect::<Vec<_>>();
+            view! {
+                <tr>
+                    {key_cells}
+                    {cells.iter().map(|v| number_cell(*v, "")).collect::<Vec<_>>()}
+                    {show_totals.then(|| totals.iter().map(|v| number_cell(*v, "pivot-total")).collect::<Vec<_>>())}
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        <div class="result-table-scroll pivot-table-scroll">
+            <table class="result-table pivot-table">
+                <thead>
+                    <tr>
+                        {row_headers.into_iter().map(|f| view! { <th>{f}</th> }).collect::<Vec<_>>()}
+                        {headers.into_iter().map(|h| view! { <th class="pivot-num">{h}</th> }).collect::<Vec<_>>()}
+                        {total_headers.into_iter().map(|h| view! { <th class="pivot-num pivot-total">{h}</th> }).collect::<Vec<_>>()}
+                    </tr>
+                </thead>
+                <tbody>
+                    {rows}
+                    <tr class="pivot-totals-row">
+                        <th class="pivot-row-key" colspan=row_fields.to_string()>"Total"</th>
+                        {pivot.column_totals.iter().map(|v| number_cell(*v, "pivot-total")).collect::<Vec<_>>()}
+                        {show_totals.then(|| pivot.grand_totals.iter().map(|v| number_cell(*v, "pivot-total")).collect::<Vec<_>>())}
+                    </tr>
+                </tbody>
+            </table>
+            {(total_rows > MAX_RENDERED_ROWS).then(|| view! {
+                <div class="result-grid-status">
+                    {format!("Showing {MAX_RENDERED_ROWS} of {total_rows} pivot rows. Export to get all of them.")}
+                </div>
+            })}
+        </div>
+    }
+    .into_any()
+}
+
+fn number_cell(value: Option<f64>, class: &'static str) -> impl IntoView {
+    let text = value.map(format_number).unwrap_or_default();
+    view! { <td class=format!("pivot-num {class}")>{text}</td> }
+}
+
+fn format_number(v: f64) -> String {
+    if v.fract() == 0.0 && v.abs() < 1e15 {
+        format!("{v:.0}")
+    } else {
+        format!("{v:.2}")
+    }
+}
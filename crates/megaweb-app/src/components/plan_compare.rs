@@ -0,0 +1,331 @@
+use leptos::prelude::*;
+use megaweb_types::explain::{is_explain, plan_text, PlanNode};
+use megaweb_types::plan_diff::{diff_plans, PlanChange, PlanDiffNode, PlanSide};
+use megaweb_types::query::{QueryHistoryEntry, QueryResult};
+
+use crate::components::result_diff::sql_snippet;
+use crate::state::query::{load_history_snapshot, use_query_state};
+
+/// Plan a history entry's plan is compared with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Other {
+    /// The EXPLAIN result in the active tab.
+    CurrentTab,
+    History(uuid::Uuid),
+}
+
+/// A plan being loaded: `None` while loading, then the plan or why there
+/// is none.
+type LoadedPlan = Option<Result<PlanNode, String>>;
+
+/// Comparison of the plan of an EXPLAIN history entry with the current
+/// tab's plan or another EXPLAIN run. Nodes are matched structurally and
+/// shown with their changed operators and cost, row estimate and time
+/// differences.
+#[component]
+pub fn PlanCompare(
+    before: QueryHistoryEntry,
+    #[prop(into)] on_close: Callback<()>,
+) -> impl IntoView {
+    let (query_state, _) = use_query_state();
+    let before_id = before.id;
+
+    let current_plan = move || {
+        query_state.with(|s| {
+            let tab = s.active_tab();
+            if !is_explain(&tab.sql) {
+                return None;
+            }
+            tab.result.as_ref().filter(|r| r.is_ok()).cloned()
+        })
+    };
+    let candidates = move || {
+        query_state.with(|s| {
+            s.history
+                .iter()
+                .rev()
+                .filter(|e| e.id != before_id && e.has_snapshot && e.success && is_explain(&e.sql))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let initial = if current_plan().is_some() {
+        Some(Other::CurrentTab)
+    } else {
+        candidates().first().map(|e| Other::History(e.id))
+    };
+    let (other, set_other) = signal(initial);
+    let (before_plan, set_before_plan) = signal(LoadedPlan::None);
+    let (after_plan, set_after_plan) = signal(LoadedPlan::None);
+
+    leptos::task::spawn_local(async move {
+        set_before_plan.set(Some(plan_of(load_history_snapshot(before_id).await)));
+    });
+    Effect::new(move |_| match other.get() {
+        None => set_after_plan.set(Some(Err("No other EXPLAIN run to compare with".into()))),
+        Some(Other::CurrentTab) => {
+            set_after_plan.set(Some(plan_of(current_plan().map(|r| (*r).clone()))));
+        }
+        Some(Other::History(id)) => {
+            set_after_plan.set(None);
+            leptos::task::spawn_local(async move {
+                let plan = plan_of(load_history_snapshot(id).await);
+                if other.get_untracked() == Some(Other::History(id)) {
+                    set_after_plan.set(Some(plan));
+                }
+            });
+        }
+    });
+
+    let picker = move || {
+        let selected = other.get();
+        let current = current_plan().is_some().then(|| {
+            view! {
+                <option value="current" selected=selected == Some(Other::CurrentTab)>
+                    "Current tab"
+                </option>
+            }
+        });
+        let entries = candidates()
+            .into_iter()
+            .map(|e| {
+                let label = format!(
+                    "{} \u{00B7} {}",
+                    e.executed_at.format("%Y-%m-%d %H:%M:%S"),
+                    sql_snippet(&e.sql)
+                );
+                view! {
+                    <option value=e.id.to_string() selected=selected == Some(Other::History(e.id))>
+                        {label}
+                    </option>
+                }
+            })
+            .collect::<Vec<_>>();
+        view! {
+            <select
+                class="plan-compare-picker"
+                on:change=move |ev| {
+                    let value = event_target_value(&ev);
+                    set_other.set(if value == "current" {
+                        Some(Other::CurrentTab)
+                    } else {
+                        value.parse().ok().map(Other::History)
+                    });
+                }
+            >
+                {current}
+                {entries}
+            </select>
+        }
+    };
+
+    let body = move || {
+        let (Some(b), Some(a)) = (before_plan.get(), after_plan.get()) else {
+            return view! { <p class="query-plan-empty">"Loading plans..."</p> }.into_any();
+        };
+        match (b, a) {
+            (Ok(b), Ok(a)) => diff_view(&diff_plans(&b, &a)).into_any(),
+            (Err(e), _) | (_, Err(e)) => view! { <p class="query-plan-empty">{e}</p> }.into_any(),
+        }
+    };
+
+    let title = format!(
+        "Compare plan of {} run",
+        before.executed_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    view! {
+        <div class="modal-overlay" on:click=move |_| on_close.run(())>
+            <div class="modal plan-compare-modal" on:click=|ev| ev.stop_propagation()>
+                <div class="modal-header">
+                    <h3>{title}</h3>
+                </div>
+                <div class="modal-body">
+                    <pre class="history-sql">{before.sql.clone()}</pre>
+                    <div class="plan-compare-toolbar">
+                        <span>"Compare with"</span>
+                        {picker}
+                    </div>
+                    {body}
+                </div>
+                <div class="modal-footer">
+                    <button class="btn btn-secondary" on:click=move |_| on_close.run(())>
+                        "Close"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+fn plan_of(result: Option<QueryResult>) -> Result<PlanNode, String> {
+    let result = result.ok_or("The result of this run wasn't stored")?;
+    plan_text(&result)
+        .and_then(|text| PlanNode::parse(&text))
+        .ok_or_else(|| "Couldn't read the plan".to_string())
+}
+
+fn diff_view(diff: &PlanDiffNode) -> impl IntoView {
+    let counts = diff.counts();
+    let summary = if counts.is_identical() {
+        "The plans are the same".to_string()
+    } else {
+        format!(
+            "{} replaced, {} changed, {} added, {} removed",
+            counts.replaced, counts.modified, counts.added, counts.removed
+        )
+    };
+    view! {
+        <div class="plan-compare-summary">
+            <span>{summary}</span>
+            {diff.cost_delta().map(|_| {
+                let (b, a) = (diff.before.as_ref(), diff.after.as_ref());
+                let (b, a) = (b.map_or(0.0, |s| s.cost_total), a.map_or(0.0, |s| s.cost_total));
+                delta_badge("total cost", b, a, "")
+            })}
+            {diff.time_delta().map(|_| {
+                let b = diff.before.as_ref().and_then(|s| s.time_ms).unwrap_or(0.0);
+                let a = diff.after.as_ref().and_then(|s| s.time_ms).unwrap_or(0.0);
+                delta_badge("total time", b, a, " ms")
+            })}
+        </div>
+        <div class="query-plan-tree plan-compare-tree">{render_diff_node(diff, 0)}</div>
+    }
+}
+
+/// Render a compared node and its children. Returns AnyView to break type recursion.
+fn render_diff_node(node: &PlanDiffNode, depth: usize) -> AnyView {
+    let side = node.side();
+    let (class, marker) = match node.change {
+        PlanChange::Unchanged => ("plan-diff-node", ""),
+        PlanChange::Modified => ("plan-diff-node plan-diff-modified", "~"),
+        PlanChange::Replaced => ("plan-diff-node plan-diff-replaced", "\u{21C4}"),
+        PlanChange::Added => ("plan-diff-node plan-diff-added", "+"),
+        PlanChange::Removed => ("plan-diff-node plan-diff-removed", "\u{2212}"),
+    };
+    let operator = match (&node.before, node.change) {
+        (Some(before), PlanChange::Replaced) => {
+            format!("{} \u{2192} {}", before.operator, side.operator)
+        }
+        _ => side.operator.clone(),
+    };
+    let relation = side
+        .relation
+        .clone()
+        .map(|r| view! { <span class="plan-node-relation">{format!("on {r}")}</span> });
+
+    let badges = match (&node.before, &node.after) {
+        (Some(b), Some(a)) => {
+            let mut badges = Vec::new();
+            if b.cost_total > 0.0 || a.cost_total > 0.0 {
+                badges.push(delta_badge("cost", b.cost_total, a.cost_total, "").into_any());
+            }
+            badges.push(
+                delta_badge("rows", b.estimated_rows as f64, a.estimated_rows as f64, "")
+                    .into_any(),
+            );
+            if let (Some(bt), Some(at)) = (b.time_ms, a.time_ms) {
+                badges.push(delta_badge("time", bt, at, " ms").into_any());
+            }
+            badges
+        }
+        _ => vec![view! {
+            <span class="badge badge--actual">{side_summary(side)}</span>
+        }
+        .into_any()],
+    };
+
+    let details = match (&node.before, &node.after) {
+        (Some(b), Some(a)) if b.extra != a.extra => {
+            let removed = b.extra.iter().filter(|e| !a.extra.contains(e)).map(|(k, v)| {
+                view! { <div class="plan-diff-extra-removed">{format!("\u{2212} {k}: {v}")}</div> }
+            });
+            let added = a
+                .extra
+                .iter()
+                .filter(|e| !b.extra.contains(e))
+                .map(|(k, v)| {
+                    view! { <div class="plan-diff-extra-added">{format!("+ {k}: {v}")}</div> }
+                });
+            Some(view! {
+                <div class="plan-node-extra">
+                    {removed.collect::<Vec<_>>()}
+                    {added.collect::<Vec<_>>()}
+                </div>
+            })
+        }
+        _ => None,
+    };
+
+    let children = node
+        .children
+        .iter()
+        .map(|child| render_diff_node(child, depth + 1))
+        .collect::<Vec<_>>();
+    let children_view =
+        (!children.is_empty()).then(|| view! { <div class="plan-node-children">{children}</div> });
+
+    view! {
+        <div class=class style=format!("padding-left: {}px", depth * 20)>
+            <div class="plan-node-header">
+                <span class="plan-diff-marker">{marker}</span>
+                <span class="plan-node-operator">{operator}</span>
+                {relation}
+            </div>
+            <div class="plan-node-badges">{badges}</div>
+            {details}
+            {children_view}
+        </div>
+    }
+    .into_any()
+}
+
+/// Badge with a figure before and after, coloured by whether it went down
+/// (better) or up (worse).
+fn delta_badge(label: &'static str, before: f64, after: f64, unit: &'static str) -> impl IntoView {
+    let (class, text) = if (after - before).abs() < f64::EPSILON {
+        (
+            "badge badge--actual",
+            format!("{label} {}{unit}", fmt(after)),
+        )
+    } else {
+        let pct = if before != 0.0 {
+            format!(" ({:+.0}%)", (after - before) / before * 100.0)
+        } else {
+            String::new()
+        };
+        let class = if after < before {
+            "badge badge--better"
+        } else {
+            "badge badge--worse"
+        };
+        (
+            class,
+            format!(
+                "{label} {}{unit} \u{2192} {}{unit}{pct}",
+                fmt(before),
+                fmt(after)
+            ),
+        )
+    };
+    view! { <span class=class>{text}</span> }
+}
+
+fn side_summary(side: &PlanSide) -> String {
+    let mut parts = vec![format!("rows {}", side.estimated_rows)];
+    if side.cost_total > 0.0 {
+        parts.insert(0, format!("cost {}", fmt(side.cost_total)));
+    }
+    if let Some(t) = side.time_ms {
+        parts.push(format!("time {} ms", fmt(t)));
+    }
+    parts.join(" \u{00B7} ")
+}
+
+fn fmt(value: f64) -> String {
+    if value.fract() == 0.0 || value.abs() >= 100.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use megaweb_types::explain::PlanNode;
+use megaweb_types::plan_layout::{flame_layout, output_rows, tree_layout};
+
+use crate::components::query_plan::HOT_NODES;
+
+const NODE_W: f64 = 180.0;
+const NODE_H: f64 = 46.0;
+const GAP_X: f64 = 24.0;
+const GAP_Y: f64 = 56.0;
+const MARGIN: f64 = 12.0;
+/// Widest edge stroke, for the edge carrying the most rows.
+const MAX_EDGE: f64 = 9.0;
+
+const FLAME_W: f64 = 960.0;
+const FLAME_ROW: f64 = 22.0;
+/// Approximate width of a label character, for clipping flame labels.
+const CHAR_W: f64 = 7.0;
+
+/// Node-link diagram of a plan. Edge thickness follows the rows flowing
+/// from child to parent; subtrees can be collapsed and clicking a node
+/// shows its details.
+#[component]
+pub fn PlanDiagram(plan: Arc<PlanNode>) -> impl IntoView {
+    let (collapsed, set_collapsed) = signal(HashSet::<usize>::new());
+    let (selected, set_selected) = signal(Option::<usize>::None);
+    let (zoom, set_zoom) = signal(1.0_f64);
+
+    let analyzed = plan.is_analyzed();
+    let hot = plan.hot_threshold(HOT_NODES, analyzed);
+    let max_rows = plan
+        .preorder()
+        .iter()
+        .map(|n| output_rows(n))
+        .fold(0.0, f64::max);
+
+    let diagram_plan = Arc::clone(&plan);
+    let diagram = move || {
+        let nodes = diagram_plan.preorder();
+        let placed = collapsed.with(|c| tree_layout(&diagram_plan, c));
+        let columns = placed.iter().map(|p| p.x).fold(0.0, f64::max) + 1.0;
+        let rows = placed.iter().map(|p| p.depth).max().unwrap_or(0) + 1;
+        let width = MARGIN * 2.0 + columns * NODE_W + (columns - 1.0) * GAP_X;
+        let height = MARGIN * 2.0 + rows as f64 * NODE_H + (rows - 1) as f64 * GAP_Y;
+        let left = |x: f64| MARGIN + x * (NODE_W + GAP_X);
+        let top = |depth: usize| MARGIN + depth as f64 * (NODE_H + GAP_Y);
+
+        let edges = placed
+            .iter()
+            .filter_map(|p| {
+                let parent = placed.iter().find(|q| Some(q.id) == p.parent)?;
+                let (x1, y1) = (left(parent.x) + NODE_W / 2.0, top(parent.depth) + NODE_H);
+                let (x2, y2) = (left(p.x) + NODE_W / 2.0, top(p.depth));
+                let mid = (y1 + y2) / 2.0;
+                let rows = output_rows(nodes[p.id]);
+                let title = format!("{} rows", rows.round());
+                Some(view! {
+                    <path
+                        class="plan-edge"
+                        d=format!("M{x1},{y1} C{x1},{mid} {x2},{mid} {x2},{y2}")
+                        stroke-width=edge_width(rows, max_rows)
+                    >
+                        <title>{title}</title>
+                    </path>
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let boxes = placed
+            .iter()
+            .map(|p| {
+                let id = p.id;
+                let node = nodes[id];
+                let (x, y) = (left(p.x), top(p.depth));
+                let weight = node.self_weight(analyzed);
+                let class = box_class(weight > 0.0 && weight >= hot, node.misestimate().is_some());
+                let label = clip(&node.operator, NODE_W - 16.0);
+                let sub = clip(&node_summary(node, analyzed), NODE_W - 16.0);
+                let toggle = (!node.children.is_empty()).then(|| {
+                    let sign = if p.hidden > 0 { format!("+{}", p.hidden) } else { "\u{2212}".to_string() };
+                    view! {
+                        <g
+                            class="plan-toggle"
+                            on:click=move |ev| {
+                                ev.stop_propagation();
+                                set_collapsed.update(|c| {
+                                    if !c.remove(&id) {
+                                        c.insert(id);
+                                    }
+                                });
+                            }
+                        >
+                            <circle cx=x + NODE_W / 2.0 cy=y + NODE_H r="9" />
+                            <text x=x + NODE_W / 2.0 y=y + NODE_H + 4.0 text-anchor="middle">{sign}</text>
+                        </g>
+                    }
+                });
+                view! {
+                    <g class=class class:selected=move || selected.get() == Some(id) on:click=move |_| set_selected.set(Some(id))>
+                        <rect x=x y=y width=NODE_W height=NODE_H rx="4" />
+                        <text class="plan-box-label" x=x + 8.0 y=y + 18.0>{label}</text>
+                        <text class="plan-box-sub" x=x + 8.0 y=y + 35.0>{sub}</text>
+                    </g>
+                    {toggle}
+                }
+            })
+            .collect::<Vec<_>>();
+
+        view! {
+            <svg
+                class="plan-svg"
+                width=move || width * zoom.get()
+                height=move || height * zoom.get()
+                viewBox=format!("0 0 {width} {height}")
+            >
+                {edges}
+                {boxes}
+            </svg>
+        }
+    };
+
+    let details_plan = Arc::clone(&plan);
+    view! {
+        <div class="plan-graphic">
+            <div class="plan-graphic-toolbar">
+                <button class="btn btn-sm" on:click=move |_| set_zoom.update(|z| *z = (*z / 1.25).max(0.25))>"\u{2212}"</button>
+                <span class="plan-zoom">{move || format!("{:.0}%", zoom.get() * 100.0)}</span>
+                <button class="btn btn-sm" on:click=move |_| set_zoom.update(|z| *z = (*z * 1.25).min(4.0))>"+"</button>
+                <button class="btn btn-sm" on:click=move |_| set_zoom.set(1.0)>"Reset"</button>
+                <button
+                    class="btn btn-sm"
+                    disabled=move || collapsed.with(HashSet::is_empty)
+                    on:click=move |_| set_collapsed.set(HashSet::new())
+                >
+                    "Expand all"
+                </button>
+                <span class="plan-graphic-hint">"Edge width: rows passed to the parent"</span>
+            </div>
+            <div class="plan-graphic-body">
+                <div class="plan-graphic-canvas">{diagram}</div>
+                {move || selected.get().map(|id| node_details(&details_plan, id, analyzed, set_selected))}
+            </div>
+        </div>
+    }
+}
+
+/// Flame (icicle) view of a plan: each node is as wide as its share of the
+/// total time, or of the total cost without ANALYZE. Clicking a node zooms
+/// into its subtree and shows its details.
+#[component]
+pub fn PlanFlame(plan: Arc<PlanNode>) -> impl IntoView {
+    let (focus, set_focus) = signal(0_usize);
+    let (selected, set_selected) = signal(Option::<usize>::None);
+
+    let analyzed = plan.is_analyzed();
+    let hot = plan.hot_threshold(HOT_NODES, analyzed);
+
+    let flame_plan = Arc::clone(&plan);
+    let flame = move || {
+        let nodes = flame_plan.preorder();
+        let boxes = flame_layout(&flame_plan, analyzed, focus.get());
+        let depth = boxes.iter().map(|b| b.depth).max().unwrap_or(0) + 1;
+        let height = depth as f64 * FLAME_ROW;
+        let rects = boxes
+            .into_iter()
+            .map(|b| {
+                let id = b.id;
+                let node = nodes[id];
+                let (x, w) = (b.start * FLAME_W, b.width * FLAME_W);
+                let y = b.depth as f64 * FLAME_ROW;
+                let weight = node.self_weight(analyzed);
+                let class = box_class(weight > 0.0 && weight >= hot, node.misestimate().is_some());
+                let title = format!("{} \u{2014} {}", node.operator, node_summary(node, analyzed));
+                let label = (w > 3.0 * CHAR_W).then(|| clip(&node.operator, w - 8.0));
+                view! {
+                    <g
+                        class=class
+                        class:selected=move || selected.get() == Some(id)
+                        on:click=move |_| {
+                            set_selected.set(Some(id));
+                            set_focus.set(id);
+                        }
+                    >
+                        <title>{title}</title>
+                        <rect x=x y=y width=(w - 1.0).max(0.5) height=FLAME_ROW - 1.0 />
+                        {label.map(|l| view! { <text class="plan-box-label" x=x + 4.0 y=y + 15.0>{l}</text> })}
+                    </g>
+                }
+            })
+            .collect::<Vec<_>>();
+        view! {
+            <svg
+                class="plan-svg plan-flame"
+                width=FLAME_W
+                height=height
+                viewBox=format!("0 0 {FLAME_W} {height}")
+            >
+                {rects}
+            </svg>
+        }
+    };
+
+    let crumbs_plan = Arc::clone(&plan);
+    let crumbs = move || {
+        let nodes = crumbs_plan.preorder();
+        nodes
+            .get(focus.get())
+            .map(|n| format!("Zoomed into {}", n.operator))
+    };
+
+    let details_plan = Arc::clone(&plan);
+    view! {
+        <div class="plan-graphic">
+            <div class="plan-graphic-toolbar">
+                <button
+                    class="btn btn-sm"
+                    disabled=move || focus.get() == 0
+                    on:click=move |_| set_focus.set(0)
+                >
+                    "Reset zoom"
+                </button>
+                <span class="plan-graphic-hint">
+                    {move || if focus.get() == 0 { None } else { crumbs() }}
+                </span>
+                <span class="plan-graphic-hint">
+                    {if analyzed { "Width: total time" } else { "Width: total cost" }}
+                </span>
+            </div>
+            <div class="plan-graphic-body">
+                <div class="plan-graphic-canvas">{flame}</div>
+                {move || selected.get().map(|id| node_details(&details_plan, id, analyzed, set_selected))}
+            </div>
+        </div>
+    }
+}
+
+/// Side panel with everything known about node `id`.
+fn node_details(
+    plan: &PlanNode,
+    id: usize,
+    analyzed: bool,
+    set_selected: WriteSignal<Option<usize>>,
+) -> Option<AnyView> {
+    let node = *plan.preorder().get(id)?;
+    let mut fields = vec![
+        ("Operator", node.operator.clone()),
+        ("Estimated rows", node.estimated_rows.to_string()),
+    ];
+    if let Some(relation) = &node.relation {
+        fields.insert(1, ("Relation", relation.clone()));
+    }
+    if node.cost_total > 0.0 {
+        fields.push((
+            "Cost",
+            format!("{:.2}..{:.2}", node.cost_startup, node.cost_total),
+        ));
+        fields.push(("Self cost", format!("{:.2}", node.self_cost())));
+    }
+    if let Some(rows) = node.actual_rows {
+        fields.push(("Actual rows", rows.to_string()));
+    }
+    if let Some(loops) = node.actual_loops {
+        fields.push(("Loops", loops.to_string()));
+    }
+    if analyzed {
+        if let Some(total) = node.total_time_ms() {
+            fields.push(("Total time", format!("{total:.3} ms")));
+        }
+        if let Some(own) = node.self_time_ms() {
+            fields.push(("Self time", format!("{own:.3} ms")));
+        }
+    }
+    if let Some(ratio) = node.misestimate() {
+        fields.push((
+            "Row estimate",
+            format!("off by {:.0}\u{00D7}", ratio.max(1.0 / ratio)),
+        ));
+    }
+    let fields = fields
+        .into_iter()
+        .map(|(label, value)| view! { <tr><th>{label}</th><td>{value}</td></tr> })
+        .collect::<Vec<_>>();
+    let extra = node
+        .extra
+        .iter()
+        .map(|(key, value)| {
+            view! {
+                <div class="plan-details-extra">
+                    {(!key.is_empty()).then(|| view! { <span class="plan-node-extra-key">{format!("{key}: ")}</span> })}
+                    {value.clone()}
+                </div>
+            }
+        })
+        .collect::<Vec<_>>();
+    Some(
+        view! {
+            <div class="plan-details">
+                <div class="plan-details-header">
+                    <span>"Node details"</span>
+                    <button class="btn btn-sm" on:click=move |_| set_selected.set(None)>"\u{2715}"</button>
+                </div>
+                <table class="query-stats-table">
+                    <tbody>{fields}</tbody>
+                </table>
+                {extra}
+            </div>
+        }
+        .into_any(),
+    )
+}
+
+fn box_class(hot: bool, misestimate: bool) -> &'static str {
+    match (hot, misestimate) {
+        (true, true) => "plan-box plan-box--hot plan-box--misestimate",
+        (true, false) => "plan-box plan-box--hot",
+        (false, true) => "plan-box plan-box--misestimate",
+        (false, false) => "plan-box",
+    }
+}
+
+/// Second line of a diagram box: relation, rows and time or cost.
+fn node_summary(node: &PlanNode, analyzed: bool) -> String {
+    let mut parts = Vec::new();
+    if let Some(relation) = &node.relation {
+        parts.push(relation.clone());
+    }
+    parts.push(format!("{} rows", output_rows(node).round()));
+    if analyzed {
+        if let Some(total) = node.total_time_ms() {
+            parts.push(format!("{total:.1} ms"));
+        }
+    } else if node.cost_total > 0.0 {
+        parts.push(format!("cost {:.0}", node.cost_total));
+    }
+    parts.join(" \u{00B7} ")
+}
+
+fn edge_width(rows: f64, max_rows: f64) -> f64 {
+    if max_rows <= 0.0 {
+        return 1.0;
+    }
+    1.0 + (MAX_EDGE - 1.0) * (rows + 1.0).log10() / (max_rows + 1.0).log10()
+}
+
+/// Cut `text` to fit `width` pixels, with an ellipsis.
+fn clip(text: &str, width: f64) -> String {
+    let max = (width / CHAR_W).floor().max(1.0) as usize;
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max.saturating_sub(1)).collect();
+    out.push('\u{2026}');
+    out
+}
@@ -1,15 +1,71 @@
 use leptos::prelude::*;
+use megaweb_types::explain::is_explain;
+use megaweb_types::query::{HistoryFilter, QueryHistoryEntry, SavedQuery};
 
-use crate::state::query::use_query_state;
+use crate::state::query::{load_older_history, use_query_state};
 
-/// Collapsible query history panel.
+/// Matching entries rendered per "Show more" step.
+const PAGE_SIZE: usize = 100;
+
+/// Collapsible query history panel with search, filters and saved queries on top.
 #[component]
 pub fn QueryHistoryPanel(
     show: Signal<bool>,
     #[prop(into)] on_restore: Callback<String>,
+    #[prop(into)] on_rerun: Callback<String>,
+    #[prop(into)] on_open_saved: Callback<SavedQuery>,
+    #[prop(into)] on_show_snapshot: Callback<QueryHistoryEntry>,
+    #[prop(into)] on_compare_plan: Callback<QueryHistoryEntry>,
     #[prop(into)] on_close: Callback<()>,
 ) -> impl IntoView {
-    let (query_state, _) = use_query_state();
+    let (query_state, set_query_state) = use_query_state();
+    let (filter, set_filter) = signal(HistoryFilter::default());
+    let (limit, set_limit) = signal(PAGE_SIZE);
+
+    // Reset paging whenever the filter changes
+    Effect::new(move || {
+        filter.track();
+        set_limit.set(PAGE_SIZE);
+    });
+
+    // Distinct values for the database/connection dropdowns
+    let databases = Memo::new(move |_| {
+        let mut dbs: Vec<String> = query_state
+            .get()
+            .history
+            .iter()
+            .map(|e| e.database.clone())
+            .collect();
+        dbs.sort();
+        dbs.dedup();
+        dbs
+    });
+    let connections = Memo::new(move |_| {
+        let mut conns: Vec<String> = query_state
+            .get()
+            .history
+            .iter()
+            .filter_map(|e| e.connection.clone())
+            .collect();
+        conns.sort();
+        conns.dedup();
+        conns
+    });
+
+    // Pinned first, then newest first
+    let matches = Signal::derive(move || {
+        let f = filter.get();
+        let mut entries: Vec<QueryHistoryEntry> = query_state
+            .get()
+            .history
+            .iter()
+            .rev()
+            .filter(|e| f.matches(e))
+            .cloned()
+            .collect();
+        entries.sort_by_key(|e| !e.pinned);
+        entries
+    });
 
     view! {
         <div
@@ -22,52 +78,299 @@ pub fn QueryHistoryPanel(
                     "Close"
                 </button>
             </div>
+            <div class="query-history-filters">
+                <input
+                    type="text"
+                    class="input history-search"
+                    placeholder="Search SQL..."
+                    prop:value=move || filter.get().text
+                    on:input=move |ev| {
+                        let text = event_target_value(&ev);
+                        set_filter.update(|f| f.text = text);
+                    }
+                />
+                <select on:change=move |ev| {
+                    let val = event_target_value(&ev);
+                    set_filter.update(|f| f.success = match val.as_str() {
+                        "ok" => Some(true),
+                        "err" => Some(false),
+                        _ => None,
+                    });
+                }>
+                    <option value="">"All statuses"</option>
+                    <option value="ok">"Succeeded"</option>
+                    <option value="err">"Failed"</option>
+                </select>
+                <select on:change=move |ev| {
+                    let val = event_target_value(&ev);
+                    set_filter.update(|f| f.database = (!val.is_empty()).then_some(val));
+                }>
+                    <option value="">"All databases"</option>
+                    {move || databases.get().into_iter().map(|db| view! {
+                        <option value=db.clone()>{db.clone()}</option>
+                    }).collect::<Vec<_>>()}
+                </select>
+                <select on:change=move |ev| {
+                    let val = event_target_value(&ev);
+                    set_filter.update(|f| f.connection = (!val.is_empty()).then_some(val));
+                }>
+                    <option value="">"All connections"</option>
+                    {move || connections.get().into_iter().map(|c| view! {
+                        <option value=c.clone()>{c.clone()}</option>
+                    }).collect::<Vec<_>>()}
+                </select>
+                <input
+                    type="number"
+                    min="0"
+                    class="history-duration-input"
+                    placeholder="Min ms"
+                    on:input=move |ev| {
+                        let v = event_target_value(&ev).parse().ok();
+                        set_filter.update(|f| f.min_duration_ms = v);
+                    }
+                />
+                <input
+                    type="number"
+                    min="0"
+                    class="history-duration-input"
+                    placeholder="Max ms"
+                    on:input=move |ev| {
+                        let v = event_target_value(&ev).parse().ok();
+                        set_filter.update(|f| f.max_duration_ms = v);
+                    }
+                />
+                <input
+                    type="date"
+                    title="From date"
+                    on:change=move |ev| {
+                        let v = event_target_value(&ev).parse().ok();
+                        set_filter.update(|f| f.from = v);
+                    }
+                />
+                <input
+                    type="date"
+                    title="To date"
+                    on:change=move |ev| {
+                        let v = event_target_value(&ev).parse().ok();
+                        set_filter.update(|f| f.to = v);
+                    }
+                />
+                <label class="history-pinned-toggle">
+                    <input
+                        type="checkbox"
+                        prop:checked=move || filter.get().pinned_only
+                        on:change=move |_| set_filter.update(|f| f.pinned_only = !f.pinned_only)
+                    />
+                    "Pinned"
+                </label>
+            </div>
+            {move || {
+                let saved = query_state.get().saved_queries;
+                if saved.is_empty() {
+                    return None;
+                }
+                Some(view! {
+                    <div class="saved-queries">
+                        <h4>"Saved"</h4>
+                        {saved.into_iter().map(|q| {
+                            let id = q.id;
+                            let query = q.clone();
+                            view! {
+                                <div
+                                    class="saved-query-entry"
+                                    title=q.sql.clone()
+                                    on:click=move |_| on_open_saved.run(query.clone())
+                                >
+                                    <span class="saved-query-name">{q.name.clone()}</span>
+                                    <span
+                                        class="saved-query-delete"
+                                        title="Delete saved query"
+                                        on:click=move |e| {
+                                            e.stop_propagation();
+                                            set_query_state.update(|s| s.delete_saved_query(id));
+                                        }
+                                    >
+                                        "x"
+                                    </span>
+                                </div>
+                            }
+                        }).collect::<Vec<_>>()}
+                    </div>
+                })
+            }}
             <div class="query-history-list">
                 {move || {
-                    let state = query_state.get();
-                    if state.history.is_empty() {
-                        return vec![view! {
+                    let entries = matches.get();
+                    let load_older = query_state.with(|s| s.older_history).then(|| view! {
+                        <button
+                            class="btn btn-sm history-more"
+                            on:click=move |_| {
+                                let before = query_state
+                                    .with_untracked(|s| s.history.first().map(QueryHistoryEntry::storage_key));
+                                if let Some(before) = before {
+                                    leptos::task::spawn_local(load_older_history(set_query_state, before));
+                                }
+                            }
+                        >
+                            "Load older history"
+                        </button>
+                    }.into_any());
+                    if entries.is_empty() {
+                        let message = if query_state.get().history.is_empty() {
+                            "No queries yet. Run a query to see it here."
+                        } else {
+                            "No queries match the current filters."
+                        };
+                        return [view! {
                             <div class="query-history-empty">
-                                <p>"No queries yet. Run a query to see it here."</p>
+                                <p>{message}</p>
                             </div>
-                        }.into_any()];
+                        }.into_any()].into_iter().chain(load_older).collect();
                     }
-                    state.history.iter().rev().map(|entry| {
-                        let sql = entry.sql.clone();
-                        let sql_for_click = sql.clone();
-                        let sql_preview = if sql.len() > 120 {
-                            format!("{}...", &sql[..120])
-                        } else {
-                            sql.clone()
-                        };
-                        let time_display = entry.executed_at.format("%H:%M:%S").to_string();
-                        let duration = format_duration(entry.execution_time_ms);
-                        let success = entry.success;
-                        let status_class = if success { "history-success" } else { "history-error" };
-                        let row_count = entry.row_count;
+                    let total = entries.len();
+                    let shown = limit.get().min(total);
+                    let mut views: Vec<AnyView> = entries.into_iter().take(shown).map(|entry| {
+                        history_entry_view(
+                            entry,
+                            on_restore,
+                            on_rerun,
+                            on_show_snapshot,
+                            on_compare_plan,
+                            set_query_state,
+                        )
+                    }).collect();
+                    if shown < total {
+                        views.push(view! {
+                            <button
+                                class="btn btn-sm history-more"
+                                on:click=move |_| set_limit.update(|l| *l += PAGE_SIZE)
+                            >
+                                {format!("Show more ({} of {total})", shown)}
+                            </button>
+                        }.into_any());
+                    } else {
+                        views.extend(load_older);
+                    }
+                    views
+                }}
+            </div>
+        </div>
+    }
+}
 
+fn history_entry_view(
+    entry: QueryHistoryEntry,
+    on_restore: Callback<String>,
+    on_rerun: Callback<String>,
+    on_show_snapshot: Callback<QueryHistoryEntry>,
+    on_compare_plan: Callback<QueryHistoryEntry>,
+    set_query_state: WriteSignal<crate::state::query::QueryState>,
+) -> AnyView {
+    let id = entry.id;
+    let sql = entry.sql.clone();
+    let sql_for_click = sql.clone();
+    let sql_for_rerun = sql.clone();
+    let sql_preview = if sql.chars().count() > 120 {
+        format!("{}...", sql.chars().take(120).collect::<String>())
+    } else {
+        sql.clone()
+    };
+    let time_display = entry.executed_at.format("%Y-%m-%d %H:%M:%S").to_string();
+    let duration = format_duration(entry.execution_time_ms);
+    let success = entry.success;
+    let status_class = if success {
+        "history-success"
+    } else {
+        "history-error"
+    };
+    let row_count = entry.row_count;
+    let pinned = entry.pinned;
+    let has_snapshot = entry.has_snapshot;
+    let comparable_plan = has_snapshot && success && is_explain(&sql);
+    let database = entry.database.clone();
+    let error = entry.error.clone();
+
+    view! {
+        <div
+            class=if pinned { "query-history-entry history-pinned" } else { "query-history-entry" }
+            on:click=move |_| on_restore.run(sql_for_click.clone())
+            title=error.unwrap_or_else(|| "Click to restore this query".to_string())
+        >
+            <div class="history-meta">
+                <span class=status_class>
+                    {if success { "OK" } else { "ERR" }}
+                </span>
+                <span class="history-time">{time_display}</span>
+                <span class="history-duration">{duration}</span>
+                <span class="history-rows">{format!("{row_count} rows")}</span>
+                <span class="history-database">{database}</span>
+                <span class="history-actions">
+                    <button
+                        class="btn btn-sm"
+                        title=if pinned { "Unpin" } else { "Pin" }
+                        on:click=move |e| {
+                            e.stop_propagation();
+                            set_query_state.update(|s| s.toggle_history_pin(id));
+                        }
+                    >
+                        {if pinned { "Unpin" } else { "Pin" }}
+                    </button>
+                    <button
+                        class="btn btn-sm"
+                        title="Run this query again"
+                        on:click=move |e| {
+                            e.stop_propagation();
+                            on_rerun.run(sql_for_rerun.clone());
+                        }
+                    >
+                        "Run"
+                    </button>
+                    {has_snapshot.then(|| {
+                        let entry = entry.clone();
                         view! {
-                            <div
-                                class="query-history-entry"
-                                on:click=move |_| on_restore.run(sql_for_click.clone())
-                                title="Click to restore this query"
+                            <button
+                                class="btn btn-sm"
+                                title="Show the result from this run"
+                                on:click=move |e| {
+                                    e.stop_propagation();
+                                    on_show_snapshot.run(entry.clone());
+                                }
                             >
-                                <div class="history-meta">
-                                    <span class=status_class>
-                                        {if success { "OK" } else { "ERR" }}
-                                    </span>
-                                    <span class="history-time">{time_display}</span>
-                                    <span class="history-duration">{duration}</span>
-                                    <span class="history-rows">{format!("{row_count} rows")}</span>
-                                </div>
-                                <pre class="history-sql">{sql_preview}</pre>
-                            </div>
-                        }.into_any()
-                    }).collect::<Vec<_>>()
-                }}
+                                "Result"
+                            </button>
+                        }
+                    })}
+                    {comparable_plan.then(|| {
+                        let entry = entry.clone();
+                        view! {
+                            <button
+                                class="btn btn-sm"
+                                title="Compare this plan with the current or another EXPLAIN run"
+                                on:click=move |e| {
+                                    e.stop_propagation();
+                                    on_compare_plan.run(entry.clone());
+                                }
+                            >
+                                "Compare plan"
+                            </button>
+                        }
+                    })}
+                    <button
+                        class="btn btn-sm"
+                        title="Delete from history"
+                        on:click=move |e| {
+                            e.stop_propagation();
+                            set_query_state.update(|s| s.delete_history_entry(id));
+                        }
+                    >
+                        "x"
+                    </button>
+                </span>
             </div>
+            <pre class="history-sql">{sql_preview}</pre>
         </div>
     }
+    .into_any()
 }
 
 fn format_duration(ms: u64) -> String {
@@ -1,9 +1,70 @@
+use std::sync::Arc;
+
 use leptos::prelude::*;
-use megaweb_types::explain::PlanNode;
+use megaweb_types::explain::{DataFusionExplain, PlanKind, PlanNode};
+use megaweb_types::plan_advisor::{advise, HintSeverity};
+use megaweb_types::schema::TableInfo;
+
+use crate::components::plan_diagram::{PlanDiagram, PlanFlame};
+
+/// Nodes with the most self time (or self cost, without ANALYZE) highlighted.
+pub(crate) const HOT_NODES: usize = 3;
+
+/// How a plan is highlighted: which nodes are hot, and what share of the
+/// plan's work each node's weight is.
+#[derive(Clone, Copy)]
+struct Highlight {
+    analyzed: bool,
+    threshold: f64,
+    total: f64,
+}
 
-/// Interactive EXPLAIN query plan visualization.
+/// How a parsed plan is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlanDisplay {
+    Tree,
+    Diagram,
+    Flame,
+}
+
+/// Interactive EXPLAIN query plan visualization, as an indented tree, a
+/// node-link diagram or a flame view.
+///
+/// DataFusion EXPLAIN output can be switched between its logical and
+/// physical plans. For EXPLAIN ANALYZE plans, nodes whose row estimate is
+/// off by an order of magnitude are flagged, and the nodes taking the most
+/// time are highlighted with their share of the total.
+///
+/// Tuning hints from the plan advisor are listed above the plan; `tables`
+/// gives the table metadata (partitions, sort columns) the rules check
+/// against.
 #[component]
-pub fn QueryPlanViewer(plan_text: Signal<Option<String>>) -> impl IntoView {
+pub fn QueryPlanViewer(
+    plan_text: Signal<Option<String>>,
+    tables: Signal<Vec<TableInfo>>,
+) -> impl IntoView {
+    let (kind, set_kind) = signal(PlanKind::Physical);
+    let (display, set_display) = signal(PlanDisplay::Tree);
+    let display_toggle = move || {
+        [
+            (PlanDisplay::Tree, "Tree"),
+            (PlanDisplay::Diagram, "Diagram"),
+            (PlanDisplay::Flame, "Flame"),
+        ]
+        .into_iter()
+        .map(|(d, label)| {
+            view! {
+                <button
+                    class=move || if display.get() == d { "btn btn-sm active" } else { "btn btn-sm" }
+                    on:click=move |_| set_display.set(d)
+                >
+                    {label}
+                </button>
+            }
+        })
+        .collect::<Vec<_>>()
+    };
+
     view! {
         <div class="query-plan">
             {move || match plan_text.get() {
@@ -11,6 +72,43 @@ pub fn QueryPlanViewer(plan_text: Signal<Option<String>>) -> impl IntoView {
                     <p class="query-plan-empty">"Run EXPLAIN to see query plan"</p>
                 }.into_any(),
                 Some(text) => {
+                    if let Some(explain) = DataFusionExplain::parse(&text) {
+                        let toggle = [(PlanKind::Logical, "Logical"), (PlanKind::Physical, "Physical")]
+                            .into_iter()
+                            .map(|(k, label)| view! {
+                                <button
+                                    class=move || if kind.get() == k { "btn btn-sm active" } else { "btn btn-sm" }
+                                    disabled=!explain.has(k)
+                                    on:click=move |_| set_kind.set(k)
+                                >
+                                    {label}
+                                </button>
+                            })
+                            .collect::<Vec<_>>();
+                        // Fall back to whichever plan the output has
+                        let plan = explain
+                            .plan(kind.get())
+                            .or_else(|| explain.plan(PlanKind::Physical))
+                            .or_else(|| explain.plan(PlanKind::Logical));
+                        return view! {
+                            <div class="query-plan-toolbar">
+                                <div class="result-mode-toggle">{toggle}</div>
+                                <div class="result-mode-toggle">{display_toggle()}</div>
+                            </div>
+                            {match plan {
+                                Some(node) => tables.with(|t| {
+                                    view! {
+                                        {plan_hints(&node, t)}
+                                        {plan_view(node, display.get())}
+                                    }
+                                    .into_any()
+                                }),
+                                None => view! { <pre class="query-plan-text">{text}</pre> }.into_any(),
+                            }}
+                        }
+                        .into_any();
+                    }
+
                     // Try parsing as JSON first, then as text
                     let parsed = serde_json::from_str::<serde_json::Value>(&text)
                         .ok()
@@ -19,9 +117,11 @@ pub fn QueryPlanViewer(plan_text: Signal<Option<String>>) -> impl IntoView {
 
                     match parsed {
                         Some(node) => view! {
-                            <div class="query-plan-tree">
-                                {render_plan_node(&node, 0)}
+                            <div class="query-plan-toolbar">
+                                <div class="result-mode-toggle">{display_toggle()}</div>
                             </div>
+                            {tables.with(|t| plan_hints(&node, t))}
+                            {plan_view(node, display.get())}
                         }.into_any(),
                         None => view! {
                             <pre class="query-plan-text">{text}</pre>
@@ -33,8 +133,73 @@ pub fn QueryPlanViewer(plan_text: Signal<Option<String>>) -> impl IntoView {
     }
 }
 
+/// The plan advisor's hints for `node`, if any.
+fn plan_hints(node: &PlanNode, tables: &[TableInfo]) -> Option<AnyView> {
+    let hints = advise(node, tables);
+    if hints.is_empty() {
+        return None;
+    }
+    let nodes = node.preorder();
+    let items = hints
+        .into_iter()
+        .map(|hint| {
+            let class = match hint.severity {
+                HintSeverity::Warning => "plan-hint plan-hint--warning",
+                HintSeverity::Info => "plan-hint plan-hint--info",
+            };
+            let target = nodes.get(hint.node).map(|n| match &n.relation {
+                Some(relation) => format!("{} on {relation}", n.operator),
+                None => n.operator.clone(),
+            });
+            view! {
+                <li class=class>
+                    <div class="plan-hint-header">
+                        <span class="plan-hint-rule">{hint.rule.label()}</span>
+                        <span class="plan-hint-node">{target}</span>
+                    </div>
+                    <div class="plan-hint-message">{hint.message}</div>
+                    <div class="plan-hint-suggestion">{hint.suggestion}</div>
+                </li>
+            }
+        })
+        .collect::<Vec<_>>();
+    Some(
+        view! {
+            <ul class="plan-hints">{items}</ul>
+        }
+        .into_any(),
+    )
+}
+
+fn plan_view(node: PlanNode, display: PlanDisplay) -> AnyView {
+    match display {
+        PlanDisplay::Tree => plan_tree(&node),
+        PlanDisplay::Diagram => view! { <PlanDiagram plan=Arc::new(node) /> }.into_any(),
+        PlanDisplay::Flame => view! { <PlanFlame plan=Arc::new(node) /> }.into_any(),
+    }
+}
+
+fn plan_tree(node: &PlanNode) -> AnyView {
+    let analyzed = node.is_analyzed();
+    let highlight = Highlight {
+        analyzed,
+        threshold: node.hot_threshold(HOT_NODES, analyzed),
+        total: if analyzed {
+            node.total_time_ms().unwrap_or(0.0)
+        } else {
+            node.cost_total
+        },
+    };
+    view! {
+        <div class="query-plan-tree">
+            {render_plan_node(node, 0, highlight)}
+        </div>
+    }
+    .into_any()
+}
+
 /// Render a plan node tree recursively. Returns AnyView to break type recursion.
-fn render_plan_node(node: &PlanNode, depth: usize) -> AnyView {
+fn render_plan_node(node: &PlanNode, depth: usize, highlight: Highlight) -> AnyView {
     let indent = format!("padding-left: {}px", depth * 20);
 
     let cost_display = if node.cost_total > 0.0 {
@@ -54,10 +219,49 @@ fn render_plan_node(node: &PlanNode, depth: usize) -> AnyView {
         .map(|t| format!("time={:.2}ms", t))
         .unwrap_or_default();
 
-    let actual_rows_display = node
-        .actual_rows
-        .map(|r| format!("actual={}", r))
-        .unwrap_or_default();
+    let actual_rows_display = match (node.actual_rows, node.actual_loops) {
+        (_, Some(0)) => "never executed".to_string(),
+        (Some(r), Some(loops)) if loops > 1 => format!("actual={r} \u{00D7}{loops} loops"),
+        (Some(r), _) => format!("actual={r}"),
+        (None, _) => String::new(),
+    };
+
+    let misestimate_view = node.misestimate().map(|ratio| {
+        let text = if ratio >= 1.0 {
+            format!("rows underestimated {ratio:.0}\u{00D7}")
+        } else {
+            format!("rows overestimated {:.0}\u{00D7}", 1.0 / ratio)
+        };
+        view! { <span class="badge badge--misestimate">{text}</span> }
+    });
+
+    let weight = node.self_weight(highlight.analyzed);
+    let hot = weight > 0.0 && weight >= highlight.threshold;
+    let share_view = (hot && highlight.total > 0.0).then(|| {
+        let share = weight / highlight.total * 100.0;
+        let what = if highlight.analyzed { "time" } else { "cost" };
+        view! { <span class="badge badge--hot">{format!("{share:.0}% of {what}")}</span> }
+    });
+
+    let extra_view = (!node.extra.is_empty()).then(|| {
+        let lines = node
+            .extra
+            .iter()
+            .map(|(key, value)| {
+                let key = (!key.is_empty()).then(|| {
+                    view! { <span class="plan-node-extra-key">{format!("{key}:")}</span> }
+                });
+                view! {
+                    <div class="plan-node-extra-line">
+                        {key}
+                        " "
+                        {value.clone()}
+                    </div>
+                }
+            })
+            .collect::<Vec<_>>();
+        view! { <div class="plan-node-extra">{lines}</div> }
+    });
 
     let relation_display = node.relation.clone().unwrap_or_default();
     let operator = node.operator.clone();
@@ -68,7 +272,7 @@ fn render_plan_node(node: &PlanNode, depth: usize) -> AnyView {
         let children: Vec<AnyView> = node
             .children
             .iter()
-            .map(|child| render_plan_node(child, depth + 1))
+            .map(|child| render_plan_node(child, depth + 1, highlight))
             .collect();
         Some(
             view! {
@@ -99,7 +303,7 @@ fn render_plan_node(node: &PlanNode, depth: usize) -> AnyView {
     };
 
     view! {
-        <div class="plan-node" style=indent>
+        <div class="plan-node" class:plan-node--hot=hot style=indent>
             <div class="plan-node-header">
                 {arrow_view}
                 <span class="plan-node-operator">{operator}</span>
@@ -126,7 +330,10 @@ fn render_plan_node(node: &PlanNode, depth: usize) -> AnyView {
                 } else {
                     None
                 }}
+                {misestimate_view}
+                {share_view}
             </div>
+            {extra_view}
             {children_view}
         </div>
     }
@@ -0,0 +1,104 @@
+use leptos::prelude::*;
+use megaweb_types::query::QueryStats;
+
+/// Execution statistics of a result, shown in the result header.
+///
+/// A badge tells at a glance whether partition pruning kicked in; clicking
+/// it opens the full breakdown.
+#[component]
+pub fn QueryStatsPanel(stats: QueryStats) -> impl IntoView {
+    let (open, set_open) = signal(false);
+
+    let (badge, badge_class) = match (stats.pruning_applied(), stats.partitions_total) {
+        (Some(true), Some(total)) => (
+            format!(
+                "Pruned {}/{total} partitions",
+                stats.partitions_pruned.unwrap_or(0)
+            ),
+            "stats-badge stats-badge-ok",
+        ),
+        (Some(false), Some(total)) => (
+            format!("No pruning ({total} partitions)"),
+            "stats-badge stats-badge-warn",
+        ),
+        _ => ("Stats".to_string(), "stats-badge"),
+    };
+
+    let partitions = stats.partitions_total.map(|total| {
+        format!(
+            "{} of {total} read, {} pruned",
+            stats.partitions_scanned().unwrap_or(total),
+            stats.partitions_pruned.unwrap_or(0)
+        )
+    });
+    let cache = stats.cache_hits.map(|hits| {
+        let misses = stats.cache_misses.unwrap_or(0);
+        match stats.cache_hit_ratio() {
+            Some(ratio) => format!("{hits} hits, {misses} misses ({:.0}%)", ratio * 100.0),
+            None => format!("{hits} hits"),
+        }
+    });
+    let rows = [
+        ("Planning", stats.planning_time_ms.map(format_ms)),
+        ("Execution", stats.execution_time_ms.map(format_ms)),
+        ("Bytes scanned", stats.bytes_scanned.map(format_bytes)),
+        ("Rows scanned", stats.rows_scanned.map(|n| n.to_string())),
+        ("Partitions", partitions),
+        ("Cache", cache),
+        ("Peak memory", stats.peak_memory_bytes.map(format_bytes)),
+    ]
+    .into_iter()
+    .filter_map(|(label, value)| {
+        value.map(|value| {
+            view! {
+                <tr>
+                    <th>{label}</th>
+                    <td>{value}</td>
+                </tr>
+            }
+        })
+    })
+    .collect::<Vec<_>>();
+
+    view! {
+        <div class="query-stats">
+            <button
+                class=badge_class
+                title="Execution statistics"
+                on:click=move |_| set_open.update(|o| *o = !*o)
+            >
+                {badge}
+            </button>
+            <Show when=move || open.get()>
+                <div class="query-stats-dropdown">
+                    <table class="query-stats-table">
+                        <tbody>{rows.clone()}</tbody>
+                    </table>
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+fn format_ms(ms: f64) -> String {
+    if ms < 1000.0 {
+        format!("{ms:.1}ms")
+    } else {
+        format!("{:.2}s", ms / 1000.0)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,531 @@
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use megaweb_types::chart::{
+    CategorySeries, ChartData, PointSeries, ResultChartKind, ResultChartSpec,
+};
+use megaweb_types::query::{ColumnKind, QueryResult};
+
+use crate::components::chart::format_axis_value;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 360.0;
+const PAD_LEFT: f64 = 60.0;
+const PAD_RIGHT: f64 = 20.0;
+const PAD_TOP: f64 = 10.0;
+const PAD_BOTTOM: f64 = 40.0;
+/// X-axis labels shown at most; others are skipped.
+const MAX_X_LABELS: usize = 12;
+
+const PALETTE: [&str; 10] = [
+    "#7aa2f7", "#e0af68", "#9ece6a", "#f7768e", "#bb9af7", "#7dcfff", "#ff9e64", "#73daca",
+    "#c0caf5", "#565f89",
+];
+
+fn color(i: usize) -> &'static str {
+    PALETTE[i % PALETTE.len()]
+}
+
+/// Chart view of a query result with column pickers.
+///
+/// Uses `spec` when it fits the result's columns, otherwise a suggested
+/// chart. Every change is reported through `on_change` so the caller can
+/// persist it with the tab.
+#[component]
+pub fn ResultChart(
+    result: Arc<QueryResult>,
+    spec: Signal<Option<ResultChartSpec>>,
+    #[prop(into)] on_change: Callback<ResultChartSpec>,
+) -> impl IntoView {
+    let spec_result = Arc::clone(&result);
+    let current = Memo::new(move |_| {
+        spec.get()
+            .filter(|s| s.fits(&spec_result))
+            .unwrap_or_else(|| ResultChartSpec::suggest(&spec_result))
+    });
+    let update = move |f: &dyn Fn(&mut ResultChartSpec)| {
+        let mut next = current.get_untracked();
+        f(&mut next);
+        on_change.run(next);
+    };
+
+    let columns: Vec<(String, ColumnKind)> = result
+        .columns
+        .iter()
+        .map(|c| (c.name.clone(), c.kind()))
+        .collect();
+    let numeric: Vec<String> = columns
+        .iter()
+        .filter(|(_, k)| *k == ColumnKind::Numeric)
+        .map(|(n, _)| n.clone())
+        .collect();
+    let categorical: Vec<String> = columns
+        .iter()
+        .filter(|(_, k)| *k != ColumnKind::Numeric)
+        .map(|(n, _)| n.clone())
+        .collect();
+    let all_names: Vec<String> = columns.iter().map(|(n, _)| n.clone()).collect();
+
+    let chart_result = Arc::clone(&result);
+
+    view! {
+        <div class="result-chart">
+            <div class="result-chart-controls">
+                <label>
+                    "Chart"
+                    <select on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        if let Some(kind) = ResultChartKind::ALL.into_iter().find(|k| k.label() == value) {
+                            update(&|s| s.kind = kind);
+                        }
+                    }>
+                        {ResultChartKind::ALL.into_iter().map(|k| view! {
+                            <option value=k.label() selected=move || current.get().kind == k>
+                                {k.label()}
+                            </option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+                <label>
+                    "X"
+                    <select on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        update(&|s| s.x = Some(value.clone()));
+                    }>
+                        {all_names.into_iter().map(|name| {
+                            let name2 = name.clone();
+                            let name3 = name.clone();
+                            view! {
+                                <option value=name selected=move || current.get().x.as_ref() == Some(&name2)>
+                                    {name3}
+                                </option>
+                            }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+                <span class="result-chart-y">
+                    "Y"
+                    {numeric.into_iter().map(|name| {
+                        let name2 = name.clone();
+                        let name3 = name.clone();
+                        view! {
+                            <label class="result-chart-check">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || current.get().y.contains(&name2)
+                                    on:change=move |_| {
+                                        let name = name.clone();
+                                        update(&|s| {
+                                            if s.y.contains(&name) {
+                                                s.y.retain(|y| y != &name);
+                                            } else {
+                                                s.y.push(name.clone());
+                                            }
+                                        });
+                                    }
+                                />
+                                {name3}
+                            </label>
+                        }
+                    }).collect::<Vec<_>>()}
+                </span>
+                <label>
+                    "Series"
+                    <select on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        update(&|s| s.series = (!value.is_empty()).then(|| value.clone()));
+                    }>
+                        <option value="" selected=move || current.get().series.is_none()>"(none)"</option>
+                        {categorical.into_iter().map(|name| {
+                            let name2 = name.clone();
+                            let name3 = name.clone();
+                            view! {
+                                <option value=name selected=move || current.get().series.as_ref() == Some(&name2)>
+                                    {name3}
+                                </option>
+                            }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </div>
+            {move || {
+                let spec = current.get();
+                match ChartData::build(&chart_result, &spec) {
+                    None => view! {
+                        <div class="result-chart-empty">
+                            "Pick an X column and at least one numeric Y column."
+                        </div>
+                    }.into_any(),
+                    Some(ChartData::Categorical { categories, series }) if spec.kind == ResultChartKind::Pie => {
+                        pie_chart(&categories, &series)
+                    }
+                    Some(ChartData::Categorical { categories, series }) => {
+                        categorical_chart(spec.kind, &categories, &series)
+                    }
+                    Some(ChartData::Scatter { series }) => scatter_chart(&series),
+                }
+            }}
+        </div>
+    }
+}
+
+/// Map a value in `lo..hi` to a Y pixel coordinate.
+fn scale_y(v: f64, lo: f64, hi: f64) -> f64 {
+    let plot = HEIGHT - PAD_TOP - PAD_BOTTOM;
+    PAD_TOP + plot - (v - lo) / (hi - lo) * plot
+}
+
+/// Value range including zero, padded so the top isn't flush with the edge.
+fn value_range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (lo, hi) = values.fold((0.0_f64, 0.0_f64), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if (hi - lo).abs() < f64::EPSILON {
+        (lo, lo + 1.0)
+    } else {
+        (lo, hi + (hi - lo) * 0.05)
+    }
+}
+
+fn y_axis(lo: f64, hi: f64) -> AnyView {
+    (0..=4)
+        .map(|i| {
+            let v = lo + (hi - lo) * i as f64 / 4.0;
+            let y = scale_y(v, lo, hi);
+            view! {
+                <g>
+                    <line
+                        x1=format!("{PAD_LEFT}")
+                        y1=format!("{y:.1}")
+                        x2=format!("{}", WIDTH - PAD_RIGHT)
+                        y2=format!("{y:.1}")
+                        class="chart-grid-line"
+                    />
+                    <text
+                        x=format!("{}", PAD_LEFT - 8.0)
+                        y=format!("{y:.1}")
+                        class="chart-y-label"
+                        text-anchor="end"
+                        dominant-baseline="middle"
+                    >
+                        {format_axis_value(v)}
+                    </text>
+                </g>
+            }
+        })
+        .collect::<Vec<_>>()
+        .into_any()
+}
+
+fn legend(names: Vec<String>) -> AnyView {
+    view! {
+        <div class="chart-legend">
+            {names.into_iter().enumerate().map(|(i, name)| view! {
+                <span class="chart-legend-item">
+                    <span class="chart-legend-dot" style=format!("background:{}", color(i)) />
+                    {name}
+                </span>
+            }).collect::<Vec<_>>()}
+        </div>
+    }
+    .into_any()
+}
+
+fn svg(body: AnyView) -> AnyView {
+    view! {
+        <svg
+            viewBox=format!("0 0 {WIDTH} {HEIGHT}")
+            preserveAspectRatio="xMidYMid meet"
+            class="chart-svg result-chart-svg"
+        >
+            {body}
+        </svg>
+    }
+    .into_any()
+}
+
+fn short_label(label: &str) -> String {
+    if label.chars().count() > 14 {
+        format!("{}...", label.chars().take(12).collect::<String>())
+    } else {
+        label.to_string()
+    }
+}
+
+/// Bar, stacked bar, line and area charts.
+fn categorical_chart(
+    kind: ResultChartKind,
+    categories: &[String],
+    series: &[CategorySeries],
+) -> AnyView {
+    let n = categories.len().max(1);
+    let plot_width = WIDTH - PAD_LEFT - PAD_RIGHT;
+    let slot = plot_width / n as f64;
+    let stacked = kind == ResultChartKind::StackedBar;
+
+    let (lo, hi) = if stacked {
+        value_range((0..categories.len()).map(|i| series.iter().map(|s| s.values[i]).sum()))
+    } else {
+        value_range(series.iter().flat_map(|s| s.values.iter().copied()))
+    };
+    let zero = scale_y(0.0_f64.clamp(lo, hi), lo, hi);
+    let center = |i: usize| PAD_LEFT + slot * (i as f64 + 0.5);
+
+    let marks: Vec<AnyView> = match kind {
+        ResultChartKind::Bar => {
+            let bar = slot * 0.8 / series.len().max(1) as f64;
+            series
+                .iter()
+                .enumerate()
+                .flat_map(|(si, s)| {
+                    s.values.iter().enumerate().map(move |(i, v)| {
+                        let x = PAD_LEFT + slot * i as f64 + slot * 0.1 + bar * si as f64;
+                        let y = scale_y(*v, lo, hi);
+                        bar_rect(
+                            x,
+                            y.min(zero),
+                            bar,
+                            (y - zero).abs(),
+                            si,
+                            &categories[i],
+                            *v,
+                        )
+                    })
+                })
+                .collect()
+        }
+        ResultChartKind::StackedBar => {
+            let bar = slot * 0.7;
+            let mut base = vec![0.0_f64; categories.len()];
+            let mut rects = Vec::new();
+            for (si, s) in series.iter().enumerate() {
+                for (i, v) in s.values.iter().enumerate() {
+                    let y0 = scale_y(base[i], lo, hi);
+                    base[i] += v;
+                    let y1 = scale_y(base[i], lo, hi);
+                    let x = PAD_LEFT + slot * i as f64 + slot * 0.15;
+                    rects.push(bar_rect(
+                        x,
+                        y0.min(y1),
+                        bar,
+                        (y0 - y1).abs(),
+                        si,
+                        &categories[i],
+                        *v,
+                    ));
+                }
+            }
+            rects
+        }
+        _ => series
+            .iter()
+            .enumerate()
+            .map(|(si, s)| {
+                let points: Vec<String> = s
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| format!("{:.1},{:.1}", center(i), scale_y(*v, lo, hi)))
+                    .collect();
+                let line = points.join(" ");
+                if kind == ResultChartKind::Area {
+                    let area = format!(
+                        "{:.1},{zero:.1} {line} {:.1},{zero:.1}",
+                        center(0),
+                        center(s.values.len().saturating_sub(1)),
+                    );
+                    view! {
+                        <g>
+                            <polygon points=area fill=color(si) fill-opacity="0.25" />
+                            <polyline points=line fill="none" stroke=color(si) stroke-width="2" />
+                        </g>
+                    }
+                    .into_any()
+                } else {
+                    view! {
+                        <polyline points=line fill="none" stroke=color(si) stroke-width="2" />
+                    }
+                    .into_any()
+                }
+            })
+            .collect(),
+    };
+
+    let step = categories.len().div_ceil(MAX_X_LABELS).max(1);
+    let x_labels: Vec<_> = categories
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| i % step == 0)
+        .map(|(i, label)| {
+            view! {
+                <text
+                    x=format!("{:.1}", center(i))
+                    y=format!("{}", HEIGHT - PAD_BOTTOM + 16.0)
+                    class="chart-x-label"
+                    text-anchor="middle"
+                >
+                    {short_label(label)}
+                </text>
+            }
+        })
+        .collect();
+
+    let names = series.iter().map(|s| s.name.clone()).collect();
+    view! {
+        {svg(view! {
+            {y_axis(lo, hi)}
+            {marks}
+            {x_labels}
+        }.into_any())}
+        {legend(names)}
+    }
+    .into_any()
+}
+
+fn bar_rect(
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    series: usize,
+    category: &str,
+    value: f64,
+) -> AnyView {
+    view! {
+        <rect
+            x=format!("{x:.1}")
+            y=format!("{y:.1}")
+            width=format!("{width:.1}")
+            height=format!("{:.1}", height.max(1.0))
+            fill=color(series)
+        >
+            <title>{format!("{category}: {value}")}</title>
+        </rect>
+    }
+    .into_any()
+}
+
+/// Pie of the first series, with a note when others are left out;
+/// negative values are left out too.
+fn pie_chart(categories: &[String], all: &[CategorySeries]) -> AnyView {
+    let Some(series) = all.first() else {
+        return view! { <div class="result-chart-empty">"No numeric values to chart."</div> }
+            .into_any();
+    };
+    let total: f64 = series.values.iter().filter(|v| **v > 0.0).sum();
+    if total <= 0.0 {
+        return view! { <div class="result-chart-empty">"No positive values to chart."</div> }
+            .into_any();
+    }
+    let (cx, cy, r) = (WIDTH / 2.0, HEIGHT / 2.0, HEIGHT / 2.0 - 20.0);
+    let point = |angle: f64| (cx + r * angle.sin(), cy - r * angle.cos());
+
+    let mut start = 0.0_f64;
+    let slices: Vec<_> = series
+        .values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| **v > 0.0)
+        .map(|(i, v)| {
+            let sweep = v / total * std::f64::consts::TAU;
+            let (x0, y0) = point(start);
+            let (x1, y1) = point(start + sweep);
+            start += sweep;
+            let large = if sweep > std::f64::consts::PI { 1 } else { 0 };
+            // A full circle can't be drawn as a single arc
+            let d = if sweep >= std::f64::consts::TAU - 1e-9 {
+                format!(
+                    "M {cx} {} A {r} {r} 0 1 1 {cx} {} A {r} {r} 0 1 1 {cx} {} Z",
+                    cy - r,
+                    cy + r,
+                    cy - r
+                )
+            } else {
+                format!("M {cx} {cy} L {x0:.2} {y0:.2} A {r} {r} 0 {large} 1 {x1:.2} {y1:.2} Z")
+            };
+            let pct = v / total * 100.0;
+            view! {
+                <path d=d fill=color(i) class="result-chart-slice">
+                    <title>{format!("{}: {v} ({pct:.1}%)", categories[i])}</title>
+                </path>
+            }
+        })
+        .collect();
+
+    let note = (all.len() > 1).then(|| {
+        view! {
+            <div class="result-chart-note">
+                {format!(
+                    "Pie charts draw a single series, so only \"{}\" is shown.",
+                    series.name,
+                )}
+            </div>
+        }
+    });
+    view! {
+        {note}
+        {svg(slices.into_any())}
+        {legend(categories.to_vec())}
+    }
+    .into_any()
+}
+
+fn scatter_chart(series: &[PointSeries]) -> AnyView {
+    let xs = series.iter().flat_map(|s| s.points.iter().map(|p| p.0));
+    let (x_lo, x_hi) = xs.fold((f64::MAX, f64::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+    if x_lo > x_hi {
+        return view! { <div class="result-chart-empty">"Scatter charts need a numeric X column."</div> }
+            .into_any();
+    }
+    let x_span = if (x_hi - x_lo).abs() < f64::EPSILON {
+        1.0
+    } else {
+        x_hi - x_lo
+    };
+    let (lo, hi) = value_range(series.iter().flat_map(|s| s.points.iter().map(|p| p.1)));
+    let scale_x = |x: f64| PAD_LEFT + (x - x_lo) / x_span * (WIDTH - PAD_LEFT - PAD_RIGHT);
+
+    let dots: Vec<_> = series
+        .iter()
+        .enumerate()
+        .flat_map(|(si, s)| {
+            s.points.iter().map(move |(x, y)| {
+                view! {
+                    <circle
+                        cx=format!("{:.1}", scale_x(*x))
+                        cy=format!("{:.1}", scale_y(*y, lo, hi))
+                        r="3"
+                        fill=color(si)
+                        fill-opacity="0.8"
+                    >
+                        <title>{format!("({x}, {y})")}</title>
+                    </circle>
+                }
+            })
+        })
+        .collect();
+    let x_labels: Vec<_> = (0..=4)
+        .map(|i| {
+            let v = x_lo + x_span * i as f64 / 4.0;
+            view! {
+                <text
+                    x=format!("{:.1}", scale_x(v))
+                    y=format!("{}", HEIGHT - PAD_BOTTOM + 16.0)
+                    class="chart-x-label"
+                    text-anchor="middle"
+                >
+                    {format_axis_value(v)}
+                </text>
+            }
+        })
+        .collect();
+
+    let names = series.iter().map(|s| s.name.clone()).collect();
+    view! {
+        {svg(view! {
+            {y_axis(lo, hi)}
+            {dots}
+            {x_labels}
+        }.into_any())}
+        {legend(names)}
+    }
+    .into_any()
+}
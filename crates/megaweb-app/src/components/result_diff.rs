@@ -0,0 +1,371 @@
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use megaweb_types::diff::{suggest_keys, CellChange, ResultDiff, RowStatus};
+use megaweb_types::query::QueryResult;
+use megaweb_types::result_view::cell_text;
+
+use crate::state::query::{load_history_snapshot, use_query_state, CompareBaseline, CompareSpec};
+
+/// Diff rows rendered at most.
+const MAX_RENDERED_ROWS: usize = 1000;
+/// History entries offered as baselines, newest first.
+const MAX_HISTORY_BASELINES: usize = 50;
+
+/// Comparison of a result with an earlier one: another tab's result or a
+/// history snapshot.
+///
+/// Rows are matched on the chosen key columns and shown as added, removed
+/// or changed, with the numeric delta of each changed number. The baseline
+/// and keys are reported through `on_change` so re-runs compare against the
+/// same baseline.
+#[component]
+pub fn ResultDiffView(
+    result: Arc<QueryResult>,
+    spec: Signal<CompareSpec>,
+    #[prop(into)] on_change: Callback<CompareSpec>,
+) -> impl IntoView {
+    let (query_state, _) = use_query_state();
+    let choice = Memo::new(move |_| spec.with(|s| s.baseline));
+    let (baseline, set_baseline) = signal(Option::<Arc<QueryResult>>::None);
+    let (loading, set_loading) = signal(false);
+    // Set when a new baseline is picked, so keys are suggested once it loads
+    let (suggest, set_suggest) = signal(false);
+
+    let update = move |f: &dyn Fn(&mut CompareSpec)| {
+        let mut next = spec.get_untracked();
+        f(&mut next);
+        on_change.run(next);
+    };
+
+    let suggest_result = Arc::clone(&result);
+    let loaded = move |earlier: Option<Arc<QueryResult>>| {
+        if let Some(b) = &earlier {
+            if suggest.get_untracked() {
+                set_suggest.set(false);
+                let keys = suggest_keys(b, &suggest_result);
+                update(&|s| s.keys = keys.clone());
+            }
+        }
+        set_baseline.set(earlier);
+    };
+
+    Effect::new(move |_| match choice.get() {
+        None => set_baseline.set(None),
+        Some(CompareBaseline::Tab(id)) => {
+            let earlier = query_state.with(|s| {
+                s.tabs
+                    .iter()
+                    .find(|t| t.id == id)
+                    .and_then(|t| t.result.clone())
+                    .filter(|r| r.is_ok())
+            });
+            loaded(earlier);
+        }
+        Some(CompareBaseline::History(id)) => {
+            set_loading.set(true);
+            let loaded = loaded.clone();
+            leptos::task::spawn_local(async move {
+                let earlier = load_history_snapshot(id).await.map(Arc::new);
+                // Ignore snapshots that arrive after another baseline was picked
+                if choice.get_untracked() == Some(CompareBaseline::History(id)) {
+                    loaded(earlier);
+                }
+                set_loading.set(false);
+            });
+        }
+    });
+
+    let options = move || {
+        query_state.with(|s| {
+            let current = s.active_tab().id;
+            let tabs = s
+                .tabs
+                .iter()
+                .filter(|t| t.id != current && t.result.as_ref().is_some_and(|r| r.is_ok()))
+                .map(|t| {
+                    (
+                        baseline_value(CompareBaseline::Tab(t.id)),
+                        format!("Tab: {}", t.title),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let history = s
+                .history
+                .iter()
+                .rev()
+                .filter(|e| e.has_snapshot && e.success)
+                .take(MAX_HISTORY_BASELINES)
+                .map(|e| {
+                    let label = format!(
+                        "{} \u{00B7} {} rows \u{00B7} {}",
+                        e.executed_at.format("%Y-%m-%d %H:%M:%S"),
+                        e.row_count,
+                        sql_snippet(&e.sql),
+                    );
+                    (baseline_value(CompareBaseline::History(e.id)), label)
+                })
+                .collect::<Vec<_>>();
+            (tabs, history)
+        })
+    };
+
+    let picker = move || {
+        let (tabs, history) = options();
+        let selected = choice.get().map(baseline_value).unwrap_or_default();
+        let option = |(value, label): (String, String)| {
+            let is_selected = value == selected;
+            view! { <option value=value selected=is_selected>{label}</option> }
+        };
+        view! {
+            <select
+                class="diff-baseline"
+                on:change=move |ev| {
+                    let baseline = parse_baseline(&event_target_value(&ev));
+                    set_suggest.set(baseline.is_some());
+                    update(&|s| {
+                        s.baseline = baseline;
+                        s.keys.clear();
+                    });
+                }
+            >
+                <option value="" selected=selected.is_empty()>"Compare with..."</option>
+                <optgroup label="Open tabs">{tabs.into_iter().map(option).collect::<Vec<_>>()}</optgroup>
+                <optgroup label="History">{history.into_iter().map(option).collect::<Vec<_>>()}</optgroup>
+            </select>
+        }
+    };
+
+    let key_result = Arc::clone(&result);
+    let key_picker = move || {
+        let earlier = baseline.get()?;
+        let keys = spec.with(|s| s.keys.clone());
+        let chips = key_result
+            .columns
+            .iter()
+            .filter(|c| earlier.columns.iter().any(|b| b.name == c.name))
+            .map(|c| {
+                let name = c.name.clone();
+                let checked = keys.contains(&name);
+                view! {
+                    <label class="diff-key">
+                        <input
+                            type="checkbox"
+                            prop:checked=checked
+                            on:change=move |_| {
+                                let name = name.clone();
+                                update(&|s| {
+                                    if s.keys.contains(&name) {
+                                        s.keys.retain(|k| k != &name);
+                                    } else {
+                                        s.keys.push(name.clone());
+                                    }
+                                });
+                            }
+                        />
+                        {c.name.clone()}
+                    </label>
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(view! {
+            <div class="diff-keys">
+                <span class="diff-keys-label">"Match rows on"</span>
+                {chips}
+                {keys.is_empty().then(|| view! {
+                    <span class="diff-keys-hint">"(whole row)"</span>
+                })}
+            </div>
+        })
+    };
+
+    let body_result = Arc::clone(&result);
+    let body = move || {
+        if choice.get().is_none() {
+            return view! {
+                <div class="diff-empty">
+                    "Pick another tab or a history entry with a stored result to compare with."
+                </div>
+            }
+            .into_any();
+        }
+        let Some(earlier) = baseline.get() else {
+            let message = if loading.get() {
+                "Loading baseline..."
+            } else {
+                "The baseline result is no longer available."
+            };
+            return view! { <div class="diff-empty">{message}</div> }.into_any();
+        };
+        let (keys, changed_only) = spec.with(|s| (s.keys.clone(), s.changed_only));
+        let diff = ResultDiff::compute(&earlier, &body_result, &keys);
+        let truncated = earlier.is_truncated() || body_result.is_truncated();
+        diff_view(diff, changed_only, truncated).into_any()
+    };
+
+    view! {
+        <div class="result-diff">
+            <div class="diff-toolbar">
+                {picker}
+                <label class="diff-changed-only">
+                    <input
+                        type="checkbox"
+                        prop:checked=move || spec.with(|s| s.changed_only)
+                        on:change=move |ev| {
+                            let on = event_target_checked(&ev);
+                            update(&|s| s.changed_only = on);
+                        }
+                    />
+                    "Differences only"
+                </label>
+            </div>
+            {key_picker}
+            {body}
+        </div>
+    }
+}
+
+fn diff_view(diff: ResultDiff, changed_only: bool, truncated: bool) -> impl IntoView {
+    let summary = if diff.is_identical() {
+        "No differences".to_string()
+    } else {
+        format!(
+            "{} added, {} removed, {} changed, {} unchanged",
+            diff.added, diff.removed, diff.changed, diff.unchanged
+        )
+    };
+    let column_notes = [
+        ("Only in this result", &diff.added_columns),
+        ("Only in the baseline", &diff.removed_columns),
+    ]
+    .into_iter()
+    .filter(|(_, names)| !names.is_empty())
+    .map(|(label, names)| {
+        view! { <span class="diff-column-note">{format!("{label}: {}", names.join(", "))}</span> }
+    })
+    .collect::<Vec<_>>();
+
+    let header = diff
+        .columns
+        .iter()
+        .map(|c| {
+            let is_key = diff.keys.contains(&c.name);
+            view! {
+                <th class:diff-key-col=is_key>
+                    <span class="col-name">{c.name.clone()}</span>
+                    <span class="col-type">{c.data_type.clone()}</span>
+                </th>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let visible: Vec<_> = diff
+        .rows
+        .into_iter()
+        .filter(|r| !changed_only || r.status != RowStatus::Unchanged)
+        .collect();
+    let hidden = visible.len().saturating_sub(MAX_RENDERED_ROWS);
+    let rows = visible
+        .into_iter()
+        .take(MAX_RENDERED_ROWS)
+        .map(|row| {
+            let (class, marker) = match row.status {
+                RowStatus::Added => ("diff-added", "+"),
+                RowStatus::Removed => ("diff-removed", "\u{2212}"),
+                RowStatus::Changed => ("diff-changed", "~"),
+                RowStatus::Unchanged => ("", ""),
+            };
+            let cells = row
+                .values
+                .iter()
+                .zip(&row.changes)
+                .map(|(value, change)| match change {
+                    Some(change) => changed_cell(change).into_any(),
+                    None => view! { <td>{cell_text(value)}</td> }.into_any(),
+                })
+                .collect::<Vec<_>>();
+            view! {
+                <tr class=class>
+                    <td class="row-num diff-marker">{marker}</td>
+                    {cells}
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        <div class="diff-summary">
+            <span>{summary}</span>
+            {column_notes}
+            {truncated.then(|| view! {
+                <span class="result-truncated">"Compared over the loaded rows only"</span>
+            })}
+        </div>
+        <div class="result-table-scroll">
+            <table class="result-table diff-table">
+                <thead>
+                    <tr>
+                        <th class="row-num"></th>
+                        {header}
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        </div>
+        {(hidden > 0).then(|| view! {
+            <div class="result-grid-status">{format!("{hidden} more rows not shown")}</div>
+        })}
+    }
+}
+
+fn changed_cell(change: &CellChange) -> impl IntoView {
+    let before = cell_text(&change.before);
+    let delta = change.delta.map(|d| {
+        let class = if d < 0.0 {
+            "diff-delta diff-down"
+        } else {
+            "diff-delta diff-up"
+        };
+        view! { <span class=class>{format_delta(d)}</span> }
+    });
+    view! {
+        <td class="diff-cell-changed" title=format!("Was {before}")>
+            <span class="diff-before">{before.clone()}</span>
+            <span class="diff-arrow">"\u{2192}"</span>
+            <span class="diff-after">{cell_text(&change.after)}</span>
+            {delta}
+        </td>
+    }
+}
+
+/// Signed delta with float noise rounded away, e.g. `+2.5` or `-0.3`.
+fn format_delta(delta: f64) -> String {
+    let text = format!("{delta:+.6}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// SQL on one line, cut to 60 characters.
+pub(crate) fn sql_snippet(sql: &str) -> String {
+    let flat = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(60) {
+        Some((end, _)) => format!("{}\u{2026}", &flat[..end]),
+        None => flat,
+    }
+}
+
+fn baseline_value(baseline: CompareBaseline) -> String {
+    match baseline {
+        CompareBaseline::Tab(id) => format!("tab:{id}"),
+        CompareBaseline::History(id) => format!("history:{id}"),
+    }
+}
+
+fn parse_baseline(value: &str) -> Option<CompareBaseline> {
+    let (kind, id) = value.split_once(':')?;
+    let id = id.parse().ok()?;
+    match kind {
+        "tab" => Some(CompareBaseline::Tab(id)),
+        "history" => Some(CompareBaseline::History(id)),
+        _ => None,
+    }
+}
@@ -0,0 +1,734 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use leptos::wasm_bindgen::JsCast;
+use leptos::web_sys;
+use megaweb_types::export::{sql_in_list, CopyFormat};
+use megaweb_types::query::{ColumnKind, QueryResult, SqlType, TypedValue};
+use megaweb_types::result_view::{self, CellFilter, ColumnProfile, GridSelection, SelectionMode};
+use megaweb_types::toast::ToastLevel;
+
+use crate::components::cell_panel::CellPanel;
+use crate::state::settings::use_settings_state;
+use crate::state::toast::{push_toast, use_toast_write};
+
+/// Fixed row height in pixels; virtualization depends on every row having it.
+const ROW_HEIGHT: f64 = 26.0;
+/// Rows rendered above and below the viewport to avoid flicker while scrolling.
+const OVERSCAN: usize = 10;
+const DEFAULT_COL_WIDTH: f64 = 160.0;
+const MIN_COL_WIDTH: f64 = 48.0;
+const ROW_NUM_WIDTH: f64 = 56.0;
+
+/// An in-progress column resize: which column, where the drag
+/// started, and the column width at that point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Resize {
+    col: usize,
+    start_x: f64,
+    start_width: f64,
+}
+
+/// Virtualized result grid.
+///
+/// Only the rows inside the scroll viewport (plus a small overscan) are in
+/// the DOM, so results with hundreds of thousands of rows stay responsive.
+/// Headers are sticky; columns can be resized by dragging their right edge
+/// and reordered by dragging the header. Clicking a header sorts by that
+/// column (ascending, descending, off), the inputs under the headers filter
+/// rows, and the "Σ" button opens a profile of the column. Sorting,
+/// filtering and profiling all run client-side over the loaded rows.
+///
+/// Cells are decoded by column type and rendered with the number style and
+/// time zone from Settings; numeric columns are right-aligned.
+///
+/// Cells, rows (row number), columns (Ctrl+click on the header) and
+/// rectangular ranges can be selected with the mouse or keyboard and copied
+/// as TSV, CSV, JSON or Markdown, or as a SQL `IN` list. Double-click or
+/// Enter opens the focused cell in a side panel, where JSON paths can be
+/// added to the query as columns through `on_add_column`.
+#[component]
+pub fn ResultGrid(
+    result: Arc<QueryResult>,
+    #[prop(into)] on_add_column: Callback<(String, String)>,
+) -> impl IntoView {
+    let (settings, _) = use_settings_state();
+    let toast = use_toast_write();
+    let col_count = result.columns.len();
+
+    let (scroll_top, set_scroll_top) = signal(0.0_f64);
+    let (viewport_height, set_viewport_height) = signal(600.0_f64);
+    let (widths, set_widths) = signal(vec![DEFAULT_COL_WIDTH; col_count]);
+    // Display position -> column index in `result.columns`
+    let (order, set_order) = signal((0..col_count).collect::<Vec<_>>());
+    let (resize, set_resize) = signal(Option::<Resize>::None);
+    let (dragging_col, set_dragging_col) = signal(Option::<usize>::None);
+    // (column index, descending)
+    let (sort, set_sort) = signal(Option::<(usize, bool)>::None);
+    let (filters, set_filters) = signal(vec![String::new(); col_count]);
+    let (profile_col, set_profile_col) = signal(Option::<usize>::None);
+    let (selection, set_selection) = signal(Option::<GridSelection>::None);
+    // Mouse button held down over the cells
+    let (selecting, set_selecting) = signal(false);
+    let (detail_open, set_detail_open) = signal(false);
+    let scroll_ref = NodeRef::<leptos::html::Div>::new();
+
+    // Measure the viewport once mounted
+    Effect::new(move || {
+        if let Some(el) = scroll_ref.get() {
+            set_viewport_height.set(el.client_height() as f64);
+        }
+    });
+
+    let on_scroll = move |_| {
+        if let Some(el) = scroll_ref.get() {
+            set_scroll_top.set(el.scroll_top() as f64);
+            set_viewport_height.set(el.client_height() as f64);
+        }
+    };
+
+    let on_mouse_move = move |ev: leptos::ev::MouseEvent| {
+        if let Some(r) = resize.get_untracked() {
+            let width = (r.start_width + ev.client_x() as f64 - r.start_x).max(MIN_COL_WIDTH);
+            set_widths.update(|w| w[r.col] = width);
+        }
+    };
+    let end_drag = move |_| {
+        set_resize.set(None);
+        set_selecting.set(false);
+    };
+
+    let table_width = move || {
+        let w = widths.get();
+        ROW_NUM_WIDTH + order.get().iter().map(|&c| w[c]).sum::<f64>()
+    };
+
+    // Indices into `result.rows` after filtering and sorting
+    let view_result = Arc::clone(&result);
+    let view_rows = Memo::new(move |_| {
+        let parsed: Vec<(usize, CellFilter)> = filters.with(|f| {
+            f.iter()
+                .enumerate()
+                .filter_map(|(c, text)| {
+                    CellFilter::parse(text, view_result.columns[c].kind()).map(|f| (c, f))
+                })
+                .collect()
+        });
+        let mut rows = result_view::filter_rows(&view_result, &parsed);
+        if let Some((col, descending)) = sort.get() {
+            result_view::sort_rows(&view_result, &mut rows, col, descending);
+        }
+        rows
+    });
+
+    // Back to the top when the visible set changes; selected positions
+    // would now point at different rows.
+    Effect::new(move || {
+        view_rows.track();
+        if let Some(el) = scroll_ref.get_untracked() {
+            el.set_scroll_top(0);
+        }
+        set_scroll_top.set(0.0);
+        set_selection.set(None);
+    });
+
+    // Selected (row index, column index) pairs resolved to `result` indices
+    let resolve = move |sel: GridSelection| {
+        let rows: Vec<usize> = view_rows.with_untracked(|v| {
+            sel.rows(v.len())
+                .filter_map(|i| v.get(i).copied())
+                .collect()
+        });
+        let cols: Vec<usize> = order.with_untracked(|o| {
+            sel.cols(o.len())
+                .filter_map(|i| o.get(i).copied())
+                .collect()
+        });
+        (rows, cols)
+    };
+    let copy_result = StoredValue::new(Arc::clone(&result));
+    let copy = move |sel: GridSelection, format: CopyFormat, header: bool| {
+        let (rows, cols) = resolve(sel);
+        let csv = settings.get_untracked().csv;
+        let text = copy_result
+            .with_value(|r| format.encode(&result_view::subset(r, &rows, &cols), header, &csv));
+        crate::clipboard::write_text(&text);
+        push_toast(
+            toast,
+            ToastLevel::Success,
+            format!(
+                "Copied {} cells as {}",
+                rows.len() * cols.len(),
+                format.label()
+            ),
+        );
+    };
+    // Values of the focused column across the selected rows
+    let copy_in_list = move |sel: GridSelection| {
+        let (rows, _) = resolve(sel);
+        let Some(col) = order.with_untracked(|o| o.get(sel.focus.1).copied()) else {
+            return;
+        };
+        let list = copy_result.with_value(|r| {
+            let values = rows.iter().filter_map(|&i| r.rows[i].get(col));
+            sql_in_list(values, r.columns[col].kind())
+        });
+        match list {
+            Some(list) => {
+                crate::clipboard::write_text(&list);
+                push_toast(toast, ToastLevel::Success, "Copied IN list");
+            }
+            None => push_toast(toast, ToastLevel::Warning, "No non-null values to copy"),
+        }
+    };
+
+    // Scroll so the focused cell is visible below the sticky header
+    let reveal = move |(row, pos): (usize, usize)| {
+        let Some(el) = scroll_ref.get_untracked() else {
+            return;
+        };
+        let header = el
+            .query_selector("thead")
+            .ok()
+            .flatten()
+            .and_then(|h| h.dyn_into::<web_sys::HtmlElement>().ok())
+            .map(|h| h.offset_height() as f64)
+            .unwrap_or(0.0);
+        let view_height = el.client_height() as f64 - header;
+        let top = row as f64 * ROW_HEIGHT;
+        let scroll = el.scroll_top() as f64;
+        if top < scroll {
+            el.set_scroll_top(top as i32);
+        } else if top + ROW_HEIGHT > scroll + view_height {
+            el.set_scroll_top((top + ROW_HEIGHT - view_height).ceil() as i32);
+        }
+
+        let w = widths.get_untracked();
+        let order = order.get_untracked();
+        let left = ROW_NUM_WIDTH + order[..pos].iter().map(|&c| w[c]).sum::<f64>();
+        let right = left + w[order[pos]];
+        let scroll_left = el.scroll_left() as f64;
+        let view_width = el.client_width() as f64;
+        if left - ROW_NUM_WIDTH < scroll_left {
+            el.set_scroll_left((left - ROW_NUM_WIDTH) as i32);
+        } else if right > scroll_left + view_width {
+            el.set_scroll_left((right - view_width).ceil() as i32);
+        }
+    };
+
+    let on_key_down = move |ev: leptos::ev::KeyboardEvent| {
+        // Leave typing and button presses to the controls themselves
+        let tag = event_target::<web_sys::Element>(&ev)
+            .tag_name()
+            .to_uppercase();
+        if matches!(tag.as_str(), "INPUT" | "BUTTON" | "SELECT" | "TEXTAREA") {
+            return;
+        }
+        let rows = view_rows.with_untracked(|v| v.len());
+        if rows == 0 || col_count == 0 {
+            return;
+        }
+        let key = ev.key();
+        let ctrl = ev.ctrl_key() || ev.meta_key();
+        let shift = ev.shift_key();
+        if ctrl && key.eq_ignore_ascii_case("a") {
+            ev.prevent_default();
+            set_selection.set(Some(GridSelection::all(rows, col_count)));
+            return;
+        }
+        if ctrl && key.eq_ignore_ascii_case("c") {
+            if let Some(sel) = selection.get_untracked() {
+                ev.prevent_default();
+                copy(sel, CopyFormat::Tsv, shift);
+            }
+            return;
+        }
+        if key == "Escape" {
+            set_selection.set(None);
+            set_detail_open.set(false);
+            return;
+        }
+        if key == "Enter" && selection.get_untracked().is_some() {
+            ev.prevent_default();
+            set_detail_open.update(|o| *o = !*o);
+            return;
+        }
+
+        let Some(sel) = selection.get_untracked() else {
+            if key.starts_with("Arrow") {
+                ev.prevent_default();
+                set_selection.set(Some(GridSelection::cell(0, 0)));
+                reveal((0, 0));
+            }
+            return;
+        };
+        let all_rows = rows as isize;
+        let all_cols = col_count as isize;
+        let page = ((viewport_height.get_untracked() / ROW_HEIGHT) as isize - 2).max(1);
+        let step = |d_row: isize, d_col: isize| sel.moved(d_row, d_col, shift, rows, col_count);
+        let next = match key.as_str() {
+            "ArrowUp" => step(if ctrl { -all_rows } else { -1 }, 0),
+            "ArrowDown" => step(if ctrl { all_rows } else { 1 }, 0),
+            "ArrowLeft" => step(0, if ctrl { -all_cols } else { -1 }),
+            "ArrowRight" => step(0, if ctrl { all_cols } else { 1 }),
+            "Home" => step(if ctrl { -all_rows } else { 0 }, -all_cols),
+            "End" => step(if ctrl { all_rows } else { 0 }, all_cols),
+            "PageUp" => step(-page, 0),
+            "PageDown" => step(page, 0),
+            "Tab" => sel.moved(0, if shift { -1 } else { 1 }, false, rows, col_count),
+            " " if ctrl => GridSelection {
+                mode: SelectionMode::Columns,
+                ..sel
+            },
+            " " if shift => GridSelection {
+                mode: SelectionMode::Rows,
+                ..sel
+            },
+            _ => return,
+        };
+        ev.prevent_default();
+        set_selection.set(Some(next));
+        reveal(next.focus);
+    };
+
+    let types: Vec<SqlType> = result
+        .columns
+        .iter()
+        .map(|c| SqlType::parse(&c.data_type))
+        .collect();
+    let value_format = Memo::new(move |_| settings.with(|s| s.value_format()));
+    let panel_types = types.clone();
+    let panel_result = Arc::clone(&result);
+
+    let total_rows = result.rows.len();
+    let header_result = Arc::clone(&result);
+    let body_result = Arc::clone(&result);
+    let profile_result = Arc::clone(&result);
+
+    view! {
+        <div
+            class=move || if resize.get().is_some() { "result-grid result-grid--resizing" } else { "result-grid" }
+            on:mousemove=on_mouse_move
+            on:mouseup=end_drag
+            on:mouseleave=end_drag
+            tabindex="0"
+            on:keydown=on_key_down
+        >
+            <div class="result-table-scroll" node_ref=scroll_ref on:scroll=on_scroll>
+                <table class="result-table" style=move || format!("width:{}px", table_width())>
+                    <colgroup>
+                        <col style=format!("width:{ROW_NUM_WIDTH}px") />
+                        {move || {
+                            let w = widths.get();
+                            order.get().iter().map(|&c| view! {
+                                <col style=format!("width:{}px", w[c]) />
+                            }).collect::<Vec<_>>()
+                        }}
+                    </colgroup>
+                    <thead>
+                        <tr>
+                            <th
+                                class="row-num"
+                                title="Select all"
+                                on:click=move |_| {
+                                    let rows = view_rows.with_untracked(|v| v.len());
+                                    set_selection.set(Some(GridSelection::all(rows, col_count)));
+                                }
+                            >
+                                "#"
+                            </th>
+                            {move || order.get().into_iter().enumerate().map(|(pos, c)| {
+                                let col = &header_result.columns[c];
+                                let name = col.name.clone();
+                                let dtype = col.data_type.clone();
+                                let dtype2 = dtype.clone();
+                                let kind = col.kind();
+                                view! {
+                                    <th
+                                        class=move || if dragging_col.get() == Some(pos) { "col-dragging" } else { "" }
+                                        on:dragover=move |ev| ev.prevent_default()
+                                        on:drop=move |ev| {
+                                            ev.prevent_default();
+                                            if let Some(from) = dragging_col.get_untracked() {
+                                                set_order.update(|o| move_item(o, from, pos));
+                                            }
+                                            set_dragging_col.set(None);
+                                        }
+                                    >
+                                        <div
+                                            class="col-header-label"
+                                            title=format!("{dtype} \u{2014} click to sort, Ctrl+click to select")
+                                            draggable="true"
+                                            on:dragstart=move |_| set_dragging_col.set(Some(pos))
+                                            on:dragend=move |_| set_dragging_col.set(None)
+                                            on:click=move |ev| {
+                                                if ev.ctrl_key() || ev.meta_key() {
+                                                    set_selection.update(|s| *s = Some(select_column(*s, pos, ev.shift_key())));
+                                                } else {
+                                                    set_sort.update(|s| *s = next_sort(*s, c));
+                                                }
+                                            }
+                                        >
+                                            <span class="col-name">{name}</span>
+                                            <span class="col-sort">
+                                                {move || match sort.get() {
+                                                    Some((col, false)) if col == c => "\u{25B2}",
+                                                    Some((col, true)) if col == c => "\u{25BC}",
+                                                    _ => "",
+                                                }}
+                                            </span>
+                                            <span class="col-type">{dtype2}</span>
+                                        </div>
+                                        <div class="col-header-tools">
+                                            <input
+                                                type="text"
+                                                class="col-filter"
+                                                placeholder=filter_placeholder(kind)
+                                                prop:value=move || filters.with(|f| f[c].clone())
+                                                on:input=move |ev| {
+                                                    let text = event_target_value(&ev);
+                                                    set_filters.update(|f| f[c] = text);
+                                                }
+                                            />
+                                            <button
+                                                class="col-profile-btn"
+                                                title="Column profile"
+                                                on:click=move |_| set_profile_col.update(|p| {
+                                                    *p = if *p == Some(c) { None } else { Some(c) };
+                                                })
+                                            >
+                                                "\u{03A3}"
+                                            </button>
+                                        </div>
+                                        <span
+                                            class="col-resize-handle"
+                                            on:mousedown=move |ev| {
+                                                ev.prevent_default();
+                                                ev.stop_propagation();
+                                                set_resize.set(Some(Resize {
+                                                    col: c,
+                                                    start_x: ev.client_x() as f64,
+                                                    start_width: widths.get_untracked()[c],
+                                                }));
+                                            }
+                                        />
+                                    </th>
+                                }
+                            }).collect::<Vec<_>>()}
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {move || {
+                            let view_rows = view_rows.get();
+                            let row_count = view_rows.len();
+                            let range = visible_range(
+                                scroll_top.get(),
+                                viewport_height.get(),
+                                row_count,
+                            );
+                            let top = range.start as f64 * ROW_HEIGHT;
+                            let bottom = (row_count - range.end) as f64 * ROW_HEIGHT;
+                            let order = order.get();
+                            let sel = selection.get();
+                            let format = value_format.get();
+                            let rows = view_rows[range.clone()].iter().zip(range).map(|(&r, i)| {
+                                let row = &body_result.rows[r];
+                                let row_selected = sel.is_some_and(|s| {
+                                    s.mode == SelectionMode::Rows && s.rows(row_count).contains(&i)
+                                });
+                                view! {
+                                    <tr class=if i % 2 == 0 { "row-even" } else { "row-odd" }>
+                                        <td
+                                            class=if row_selected { "row-num row-selected" } else { "row-num" }
+                                            on:mousedown=move |ev| {
+                                                if ev.button() == 0 {
+                                                    set_selection.update(|s| *s = Some(select_row(*s, i, ev.shift_key())));
+                                                }
+                                            }
+                                        >
+                                            {r + 1}
+                                        </td>
+                                        {order.iter().enumerate().map(|(pos, &c)| {
+                                            let value = row
+                                                .get(c)
+                                                .map(|v| TypedValue::decode(v, &types[c]))
+                                                .unwrap_or(TypedValue::Null);
+                                            let display = value.render(&format);
+                                            let display2 = display.clone();
+                                            let selected = match sel {
+                                                Some(s) if s.focus == (i, pos) => " cell-focus",
+                                                Some(s) if s.contains(i, pos, row_count, col_count) => " cell-selected",
+                                                _ => "",
+                                            };
+                                            let class = format!("{}{selected}", value_class(&value, &types[c]));
+                                            view! {
+                                                <td
+                                                    class=class
+                                                    title=display
+                                                    on:mousedown=move |ev| {
+                                                        if ev.button() != 0 {
+                                                            return;
+                                                        }
+                                                        set_selecting.set(true);
+                                                        set_selection.update(|s| *s = Some(match *s {
+                                                            Some(sel) if ev.shift_key() => GridSelection { focus: (i, pos), ..sel },
+                                                            _ => GridSelection::cell(i, pos),
+                                                        }));
+                                                    }
+                                                    on:dblclick=move |_| set_detail_open.set(true)
+                                                    on:mouseenter=move |_| {
+                                                        if selecting.get_untracked() {
+                                                            set_selection.update(|s| if let Some(sel) = s {
+                                                                sel.focus = (i, pos);
+                                                            });
+                                                        }
+                                                    }
+                                                >
+                                                    {display2}
+                                                </td>
+                                            }
+                                        }).collect::<Vec<_>>()}
+                                    </tr>
+                                }
+                            }).collect::<Vec<_>>();
+                            view! {
+                                <tr class="grid-spacer" style=format!("height:{top}px")></tr>
+                                {rows}
+                                <tr class="grid-spacer" style=format!("height:{bottom}px")></tr>
+                            }
+                        }}
+                    </tbody>
+                </table>
+            </div>
+            {move || {
+                let shown = view_rows.with(|r| r.len());
+                (shown != total_rows).then(|| view! {
+                    <div class="result-grid-status">
+                        {format!("{shown} of {total_rows} loaded rows match the filters")}
+                    </div>
+                })
+            }}
+            {move || selection.get().map(|sel| {
+                let rows = view_rows.with(|v| v.len());
+                let size = format!(
+                    "{} \u{00D7} {} selected",
+                    sel.rows(rows).count(),
+                    sel.cols(col_count).count(),
+                );
+                view! {
+                    <div class="grid-selection-bar">
+                        <span>{size}</span>
+                        <span class="grid-selection-label">"Copy as"</span>
+                        {CopyFormat::ALL.into_iter().map(|format| view! {
+                            <button class="btn btn-sm" on:click=move |_| copy(sel, format, true)>
+                                {format.label()}
+                            </button>
+                        }).collect::<Vec<_>>()}
+                        <button
+                            class="btn btn-sm"
+                            title="Copy the focused column's values as a SQL IN list"
+                            on:click=move |_| copy_in_list(sel)
+                        >
+                            "IN (\u{2026})"
+                        </button>
+                        <button
+                            class="btn btn-sm grid-selection-details"
+                            title="Show the focused cell (Enter)"
+                            on:click=move |_| set_detail_open.update(|o| *o = !*o)
+                        >
+                            "Details"
+                        </button>
+                    </div>
+                }
+            })}
+            {move || {
+                if !detail_open.get() {
+                    return None;
+                }
+                let focus = selection.get()?.focus;
+                let r = view_rows.with(|v| v.get(focus.0).copied())?;
+                let c = order.with(|o| o.get(focus.1).copied())?;
+                let value = panel_result.rows[r]
+                    .get(c)
+                    .map(|v| TypedValue::decode(v, &panel_types[c]))
+                    .unwrap_or(TypedValue::Null);
+                Some(view! {
+                    <CellPanel
+                        column=panel_result.columns[c].clone()
+                        value=value
+                        format=value_format.get()
+                        on_close=move |_| set_detail_open.set(false)
+                        on_add_column=on_add_column
+                    />
+                })
+            }}
+            {move || profile_col.get().map(|c| {
+                let profile = view_rows.with(|rows| ColumnProfile::compute(&profile_result, c, rows));
+                let name = profile_result.columns[c].name.clone();
+                view! {
+                    <ColumnProfilePanel
+                        name=name
+                        profile=profile
+                        on_close=move |_| set_profile_col.set(None)
+                    />
+                }
+            })}
+        </div>
+    }
+}
+
+/// Popover with statistics for one column.
+#[component]
+fn ColumnProfilePanel(
+    name: String,
+    profile: ColumnProfile,
+    #[prop(into)] on_close: Callback<()>,
+) -> impl IntoView {
+    let non_null = profile.row_count - profile.null_count;
+    let stat = |label: &'static str, value: String| {
+        view! {
+            <div class="profile-stat">
+                <span class="profile-label">{label}</span>
+                <span class="profile-value">{value}</span>
+            </div>
+        }
+    };
+    let histogram = profile.histogram.clone().map(|bins| {
+        let peak = bins.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+        view! {
+            <div class="profile-section">
+                <h5>"Distribution"</h5>
+                <div class="profile-histogram">
+                    {bins.into_iter().map(|b| {
+                        let height = b.count as f64 / peak as f64 * 100.0;
+                        view! {
+                            <div
+                                class="profile-bar"
+                                style=format!("height:{height:.1}%")
+                                title=format!("{} \u{2013} {}: {}", format_number(b.start), format_number(b.end), b.count)
+                            />
+                        }
+                    }).collect::<Vec<_>>()}
+                </div>
+            </div>
+        }
+    });
+
+    view! {
+        <div class="column-profile">
+            <div class="column-profile-header">
+                <h4>{name}</h4>
+                <button class="btn btn-sm" on:click=move |_| on_close.run(())>"Close"</button>
+            </div>
+            {stat("Rows", profile.row_count.to_string())}
+            {stat("Nulls", profile.null_count.to_string())}
+            {stat("Distinct", profile.distinct_count.to_string())}
+            {stat("Min", profile.min.as_ref().map(format_value).unwrap_or_default())}
+            {stat("Max", profile.max.as_ref().map(format_value).unwrap_or_default())}
+            <div class="profile-section">
+                <h5>"Top values"</h5>
+                {profile.top_values.iter().map(|(value, count)| {
+                    let pct = *count as f64 / non_null.max(1) as f64 * 100.0;
+                    view! {
+                        <div class="profile-top-value">
+                            <span class="profile-value">{format_value(value)}</span>
+                            <span class="profile-count">{format!("{count} ({pct:.1}%)")}</span>
+                        </div>
+                    }
+                }).collect::<Vec<_>>()}
+            </div>
+            {histogram}
+        </div>
+    }
+}
+
+/// Header click cycles a column through ascending, descending and unsorted.
+fn next_sort(current: Option<(usize, bool)>, col: usize) -> Option<(usize, bool)> {
+    match current {
+        Some((c, false)) if c == col => Some((col, true)),
+        Some((c, true)) if c == col => None,
+        _ => Some((col, false)),
+    }
+}
+
+/// Cell class by value: numbers align right, nulls and structured values
+/// are styled apart.
+fn value_class(value: &TypedValue, ty: &SqlType) -> &'static str {
+    match value {
+        TypedValue::Null if ty.is_numeric() => "cell-null cell-num",
+        TypedValue::Null => "cell-null",
+        TypedValue::Json(_) | TypedValue::Array(_) => "cell-json",
+        TypedValue::Bool(_) => "cell-bool",
+        _ if ty.is_numeric() => "cell-num",
+        _ => "",
+    }
+}
+
+/// Click on a row number: select that row, or extend a row selection.
+fn select_row(current: Option<GridSelection>, row: usize, extend: bool) -> GridSelection {
+    match current {
+        Some(sel) if extend => GridSelection {
+            mode: SelectionMode::Rows,
+            focus: (row, sel.focus.1),
+            ..sel
+        },
+        _ => GridSelection {
+            mode: SelectionMode::Rows,
+            ..GridSelection::cell(row, 0)
+        },
+    }
+}
+
+/// Ctrl+click on a header: select that column, or extend a column selection.
+fn select_column(current: Option<GridSelection>, col: usize, extend: bool) -> GridSelection {
+    match current {
+        Some(sel) if extend => GridSelection {
+            mode: SelectionMode::Columns,
+            focus: (sel.focus.0, col),
+            ..sel
+        },
+        _ => GridSelection {
+            mode: SelectionMode::Columns,
+            ..GridSelection::cell(0, col)
+        },
+    }
+}
+
+fn filter_placeholder(kind: ColumnKind) -> &'static str {
+    match kind {
+        ColumnKind::Numeric => "Filter (>10, null)",
+        _ => "Filter",
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{n:.0}")
+    } else {
+        format!("{n:.2}")
+    }
+}
+
+/// Row indices to render for the given scroll position.
+fn visible_range(scroll_top: f64, viewport_height: f64, total: usize) -> Range<usize> {
+    let first = (scroll_top / ROW_HEIGHT).floor().max(0.0) as usize;
+    let visible = (viewport_height / ROW_HEIGHT).ceil() as usize + 1;
+    let start = first.saturating_sub(OVERSCAN).min(total);
+    let end = (first + visible + OVERSCAN).min(total);
+    start..end
+}
+
+/// Move the element at `from` to position `to`, shifting the rest.
+fn move_item<T>(items: &mut Vec<T>, from: usize, to: usize) {
+    if from == to || from >= items.len() || to >= items.len() {
+        return;
+    }
+    let item = items.remove(from);
+    items.insert(to, item);
+}
+
+/// Render a JSON cell value for display.
+pub fn format_value(val: &serde_json::Value) -> String {
+    result_view::cell_text(val)
+}
@@ -1,13 +1,50 @@
+use std::sync::Arc;
+
 use leptos::prelude::*;
+use megaweb_types::chart::ResultChartSpec;
+use megaweb_types::edit::EditTarget;
+use megaweb_types::pivot::PivotSpec;
 use megaweb_types::query::QueryResult;
 use megaweb_types::toast::ToastLevel;
 
+use crate::components::data_editor::DataEditor;
+use crate::components::export_menu::ExportMenu;
+use crate::components::pivot_table::PivotView;
+use crate::components::query_stats::QueryStatsPanel;
+use crate::components::result_chart::ResultChart;
+use crate::components::result_diff::ResultDiffView;
+use crate::components::result_grid::ResultGrid;
+use crate::pages::schema_browser::load_connection_schema;
+use crate::state::connection::use_connection_state;
+use crate::state::query::{CompareSpec, ResultMode};
 use crate::state::toast::{push_toast, use_toast_write};
 
-/// Result table for query output with CSV/JSON export.
+/// Result table for query output, shown as a grid, chart, pivot or a
+/// comparison with an earlier result, with an export menu.
+///
+/// `sql` is the query the result came from, not the editor text. With
+/// `database` it lets the server re-run the query for exports of the full
+/// result, and finds the table when editing. Results of a single OLTP table
+/// can be edited in place; `on_edited` runs with `sql` after changes are
+/// committed.
 #[component]
-pub fn ResultTable(result: Signal<Option<QueryResult>>) -> impl IntoView {
+pub fn ResultTable(
+    result: Signal<Option<Arc<QueryResult>>>,
+    mode: Signal<ResultMode>,
+    #[prop(into)] on_mode: Callback<ResultMode>,
+    chart: Signal<Option<ResultChartSpec>>,
+    #[prop(into)] on_chart: Callback<ResultChartSpec>,
+    pivot: Signal<PivotSpec>,
+    #[prop(into)] on_pivot: Callback<PivotSpec>,
+    compare: Signal<CompareSpec>,
+    #[prop(into)] on_compare: Callback<CompareSpec>,
+    sql: Signal<String>,
+    database: Signal<String>,
+    #[prop(into)] on_add_column: Callback<(String, String)>,
+    #[prop(into)] on_edited: Callback<String>,
+) -> impl IntoView {
     let toast = use_toast_write();
+    let (connection_state, _) = use_connection_state();
 
     view! {
         <div class="result-table-container">
@@ -22,9 +59,40 @@ pub fn ResultTable(result: Signal<Option<QueryResult>>) -> impl IntoView {
                         <p class="error-message">{r.error.clone().unwrap_or_default()}</p>
                     </div>
                 }.into_any(),
-                Some(ref r) => {
-                    let r_csv = r.clone();
-                    let r_json = r.clone();
+                Some(r) => {
+                    let (editing, set_editing) = signal(Option::<EditTarget>::None);
+                    let start_editing = {
+                        let r = Arc::clone(&r);
+                        move |_| {
+                            let r = Arc::clone(&r);
+                            let sql = sql.get_untracked();
+                            let database = database.get_untracked();
+                            let connection = connection_state.get_untracked().active;
+                            leptos::task::spawn_local(async move {
+                                let tables = match load_connection_schema(connection).await {
+                                    Ok(dbs) => dbs
+                                        .into_iter()
+                                        .filter(|db| db.name == database)
+                                        .flat_map(|db| db.schemas)
+                                        .flat_map(|s| s.tables)
+                                        .collect::<Vec<_>>(),
+                                    Err(e) => {
+                                        push_toast(toast, ToastLevel::Error, format!("Couldn't load the schema: {e}"));
+                                        return;
+                                    }
+                                };
+                                match EditTarget::resolve(&sql, &r, &tables) {
+                                    Ok(target) => set_editing.set(Some(target)),
+                                    Err(refusal) => push_toast(toast, ToastLevel::Warning, refusal.to_string()),
+                                }
+                            });
+                        }
+                    };
+                    let truncated = r.is_truncated().then(|| format!(
+                        "Showing the first {} of {} rows (row limit). Export or raise the limit in Settings to see more.",
+                        r.rows.len(),
+                        r.row_count,
+                    ));
                     view! {
                         <div class="result-table-wrapper">
                             <div class="result-table-header">
@@ -34,63 +102,71 @@ pub fn ResultTable(result: Signal<Option<QueryResult>>) -> impl IntoView {
                                 <span class="result-time">
                                     {format!("{}ms", r.execution_time_ms)}
                                 </span>
+                                {r.stats.clone().filter(|s| !s.is_empty()).map(|stats| view! {
+                                    <QueryStatsPanel stats=stats />
+                                })}
+                                {truncated.map(|msg| view! {
+                                    <span class="result-truncated" title=msg.clone()>
+                                        {format!("Truncated to {} rows", r.rows.len())}
+                                    </span>
+                                })}
+                                <div class="result-mode-toggle">
+                                    {[
+                                        (ResultMode::Grid, "Grid"),
+                                        (ResultMode::Chart, "Chart"),
+                                        (ResultMode::Pivot, "Pivot"),
+                                        (ResultMode::Compare, "Compare"),
+                                    ].into_iter().map(|(m, label)| view! {
+                                        <button
+                                            class=move || mode_class(mode.get() == m)
+                                            on:click=move |_| on_mode.run(m)
+                                        >
+                                            {label}
+                                        </button>
+                                    }).collect::<Vec<_>>()}
+                                </div>
                                 <div class="result-actions">
-                                    <button
-                                        class="btn btn-sm"
-                                        title="Export CSV"
-                                        on:click=move |_| {
-                                            export_csv(&r_csv);
-                                            push_toast(toast, ToastLevel::Success, "Exported as CSV");
-                                        }
-                                    >
-                                        "CSV"
-                                    </button>
-                                    <button
-                                        class="btn btn-sm"
-                                        title="Export JSON"
-                                        on:click=move |_| {
-                                            export_json(&r_json);
-                                            push_toast(toast, ToastLevel::Success, "Exported as JSON");
-                                        }
-                                    >
-                                        "JSON"
-                                    </button>
+                                    <Show when=move || mode.get() == ResultMode::Grid && editing.with(Option::is_none)>
+                                        <button
+                                            class="btn btn-sm"
+                                            title="Edit rows of an OLTP table"
+                                            on:click=start_editing.clone()
+                                        >
+                                            "Edit"
+                                        </button>
+                                    </Show>
+                                    <ExportMenu
+                                        result=Arc::clone(&r)
+                                        mode=mode
+                                        pivot=pivot
+                                        sql=sql
+                                        database=database
+                                    />
                                 </div>
                             </div>
-                            <div class="result-table-scroll">
-                                <table class="result-table">
-                                    <thead>
-                                        <tr>
-                                            {r.columns.iter().map(|col| {
-                                                let name = col.name.clone();
-                                                let dtype = col.data_type.clone();
-                                                let dtype2 = dtype.clone();
-                                                view! {
-                                                    <th title=dtype>
-                                                        <span class="col-name">{name}</span>
-                                                        <span class="col-type">{dtype2}</span>
-                                                    </th>
-                                                }
-                                            }).collect::<Vec<_>>()}
-                                        </tr>
-                                    </thead>
-                                    <tbody>
-                                        {r.rows.iter().take(1000).enumerate().map(|(i, row)| {
-                                            view! {
-                                                <tr class=if i % 2 == 0 { "row-even" } else { "row-odd" }>
-                                                    {row.iter().map(|val| {
-                                                        let display = format_value(val);
-                                                        let display2 = display.clone();
-                                                        view! {
-                                                            <td title=display>{display2}</td>
-                                                        }
-                                                    }).collect::<Vec<_>>()}
-                                                </tr>
-                                            }
-                                        }).collect::<Vec<_>>()}
-                                    </tbody>
-                                </table>
-                            </div>
+                            {move || match (mode.get(), editing.get()) {
+                                (ResultMode::Grid, Some(target)) => view! {
+                                    <DataEditor
+                                        result=Arc::clone(&r)
+                                        target=target
+                                        database=database
+                                        on_close=move |_| set_editing.set(None)
+                                        on_committed=move |_| on_edited.run(sql.get_untracked())
+                                    />
+                                }.into_any(),
+                                (ResultMode::Grid, None) => view! {
+                                    <ResultGrid result=Arc::clone(&r) on_add_column=on_add_column />
+                                }.into_any(),
+                                (ResultMode::Chart, _) => view! {
+                                    <ResultChart result=Arc::clone(&r) spec=chart on_change=on_chart />
+                                }.into_any(),
+                                (ResultMode::Pivot, _) => view! {
+                                    <PivotView result=Arc::clone(&r) spec=pivot on_change=on_pivot />
+                                }.into_any(),
+                                (ResultMode::Compare, _) => view! {
+                                    <ResultDiffView result=Arc::clone(&r) spec=compare on_change=on_compare />
+                                }.into_any(),
+                            }}
                         </div>
                     }.into_any()
                 },
@@ -99,104 +175,10 @@ pub fn ResultTable(result: Signal<Option<QueryResult>>) -> impl IntoView {
     }
 }
 
-fn format_value(val: &serde_json::Value) -> String {
-    match val {
-        serde_json::Value::Null => "NULL".to_string(),
-        serde_json::Value::String(s) => s.clone(),
-        serde_json::Value::Number(n) => n.to_string(),
-        serde_json::Value::Bool(b) => b.to_string(),
-        other => other.to_string(),
-    }
-}
-
-fn escape_csv(s: &str) -> String {
-    if s.contains(',') || s.contains('"') || s.contains('\n') {
-        format!("\"{}\"", s.replace('"', "\"\""))
+fn mode_class(active: bool) -> &'static str {
+    if active {
+        "btn btn-sm active"
     } else {
-        s.to_string()
+        "btn btn-sm"
     }
 }
-
-fn export_csv(result: &QueryResult) {
-    let mut csv = String::new();
-    // Header
-    csv.push_str(
-        &result
-            .columns
-            .iter()
-            .map(|c| escape_csv(&c.name))
-            .collect::<Vec<_>>()
-            .join(","),
-    );
-    csv.push('\n');
-    // Rows
-    for row in &result.rows {
-        csv.push_str(
-            &row.iter()
-                .map(|v| escape_csv(&format_value(v)))
-                .collect::<Vec<_>>()
-                .join(","),
-        );
-        csv.push('\n');
-    }
-    trigger_download(&csv, "query-result.csv", "text/csv");
-}
-
-fn export_json(result: &QueryResult) {
-    let objects: Vec<serde_json::Value> = result
-        .rows
-        .iter()
-        .map(|row| {
-            let mut obj = serde_json::Map::new();
-            for (i, col) in result.columns.iter().enumerate() {
-                obj.insert(
-                    col.name.clone(),
-                    row.get(i).cloned().unwrap_or(serde_json::Value::Null),
-                );
-            }
-            serde_json::Value::Object(obj)
-        })
-        .collect();
-    let json = serde_json::to_string_pretty(&objects).unwrap_or_default();
-    trigger_download(&json, "query-result.json", "application/json");
-}
-
-#[cfg(target_arch = "wasm32")]
-fn trigger_download(content: &str, filename: &str, mime_type: &str) {
-    use wasm_bindgen::prelude::*;
-
-    let window = match web_sys::window() {
-        Some(w) => w,
-        None => return,
-    };
-    let document = match window.document() {
-        Some(d) => d,
-        None => return,
-    };
-
-    let blob_parts = js_sys::Array::new();
-    blob_parts.push(&JsValue::from_str(content));
-    let mut opts = web_sys::BlobPropertyBag::new();
-    opts.type_(mime_type);
-    let blob = match web_sys::Blob::new_with_str_sequence_and_options(&blob_parts, &opts) {
-        Ok(b) => b,
-        Err(_) => return,
-    };
-    let url = match web_sys::Url::create_object_url_with_blob(&blob) {
-        Ok(u) => u,
-        Err(_) => return,
-    };
-
-    if let Ok(element) = document.create_element("a") {
-        let a: web_sys::HtmlAnchorElement = element.unchecked_into();
-        a.set_href(&url);
-        a.set_download(filename);
-        a.click();
-        let _ = web_sys::Url::revoke_object_url(&url);
-    }
-}
-
-#[cfg(not(target_arch = "wasm32"))]
-fn trigger_download(_content: &str, _filename: &str, _mime_type: &str) {
-    // No-op on server
-}
@@ -9,6 +9,9 @@ pub struct Tab {
 }
 
 /// Multi-tab bar component.
+///
+/// Double-clicking a title edits it in place when `on_rename` is set;
+/// dragging a tab onto another reorders them when `on_reorder` is set.
 #[component]
 pub fn TabBar(
     tabs: Signal<Vec<Tab>>,
@@ -16,7 +19,27 @@ pub fn TabBar(
     #[prop(into)] on_select: Callback<usize>,
     #[prop(into)] on_close: Callback<usize>,
     #[prop(into)] on_add: Callback<()>,
+    /// Called with `(index, new_title)` after an in-place rename.
+    #[prop(into, optional)]
+    on_rename: Option<Callback<(usize, String)>>,
+    /// Called with `(from, to)` when a tab is dropped onto another.
+    #[prop(into, optional)]
+    on_reorder: Option<Callback<(usize, usize)>>,
 ) -> impl IntoView {
+    let (editing, set_editing) = signal(Option::<usize>::None);
+    let (dragging, set_dragging) = signal(Option::<usize>::None);
+
+    let commit_rename = move |i: usize, title: String| {
+        // Enter and blur both commit; only the first one counts.
+        if editing.get_untracked() != Some(i) {
+            return;
+        }
+        set_editing.set(None);
+        if let Some(cb) = &on_rename {
+            cb.run((i, title));
+        }
+    };
+
     view! {
         <div class="tab-bar">
             <div class="tab-bar-tabs">
@@ -25,12 +48,60 @@ pub fn TabBar(
                         let is_active = i == active_index.get();
                         let tab_title = tab.title.clone();
                         let closeable = tab.closeable;
+                        let is_editing = editing.get() == Some(i);
+                        let draggable = on_reorder.is_some() && !is_editing;
                         view! {
-                            <button
-                                class=move || if is_active { "tab tab--active" } else { "tab" }
+                            <div
+                                role="tab"
+                                class=move || {
+                                    let mut class = String::from("tab");
+                                    if is_active {
+                                        class.push_str(" tab--active");
+                                    }
+                                    if dragging.get() == Some(i) {
+                                        class.push_str(" tab--dragging");
+                                    }
+                                    class
+                                }
+                                draggable=if draggable { "true" } else { "false" }
                                 on:click=move |_| on_select.run(i)
+                                on:dblclick=move |_| {
+                                    if on_rename.is_some() {
+                                        set_editing.set(Some(i));
+                                    }
+                                }
+                                on:dragstart=move |_| set_dragging.set(Some(i))
+                                on:dragend=move |_| set_dragging.set(None)
+                                on:dragover=move |ev| ev.prevent_default()
+                                on:drop=move |ev| {
+                                    ev.prevent_default();
+                                    if let (Some(from), Some(cb)) = (dragging.get_untracked(), &on_reorder) {
+                                        cb.run((from, i));
+                                    }
+                                    set_dragging.set(None);
+                                }
                             >
-                                <span class="tab-title">{tab_title.clone()}</span>
+                                {if is_editing {
+                                    view! {
+                                        <input
+                                            class="tab-title-input"
+                                            type="text"
+                                            prop:value=tab_title.clone()
+                                            autofocus=true
+                                            on:click=move |e| e.stop_propagation()
+                                            on:blur=move |ev| commit_rename(i, event_target_value(&ev))
+                                            on:keydown=move |ev| {
+                                                match ev.key().as_str() {
+                                                    "Enter" => commit_rename(i, event_target_value(&ev)),
+                                                    "Escape" => set_editing.set(None),
+                                                    _ => {}
+                                                }
+                                            }
+                                        />
+                                    }.into_any()
+                                } else {
+                                    view! { <span class="tab-title">{tab_title.clone()}</span> }.into_any()
+                                }}
                                 {if closeable {
                                     Some(view! {
                                         <span
@@ -46,7 +117,7 @@ pub fn TabBar(
                                 } else {
                                     None
                                 }}
-                            </button>
+                            </div>
                         }
                     }).collect::<Vec<_>>()
                 }}
@@ -0,0 +1,245 @@
+use leptos::prelude::*;
+use megaweb_types::result_view::cell_text;
+use megaweb_types::schema::TableInfo;
+use megaweb_types::table_stats::{
+    TablePreview, TablePreviewRequest, TableStats, TableStatsRequest, MAX_PREVIEW_ROWS,
+    PREVIEW_PAGE_ROWS,
+};
+
+use crate::pages::schema_browser::{format_number, format_size};
+
+/// Pages through a table's first rows, [`PREVIEW_PAGE_ROWS`] at a time.
+#[component]
+pub fn TableDataPreview(table: TableInfo, database: String) -> impl IntoView {
+    let (offset, set_offset) = signal(0u64);
+    let name = format!("{}.{}", table.schema_name, table.name);
+    let page = LocalResource::new(move || {
+        let req = TablePreviewRequest {
+            database: database.clone(),
+            table: name.clone(),
+            offset: offset.get(),
+            limit: PREVIEW_PAGE_ROWS,
+        };
+        async move { post::<TablePreview>("/proxy/megadb/table/preview", &req).await }
+    });
+
+    let pager = move |preview: &TablePreview| {
+        let first = preview.offset;
+        let shown = preview.result.rows.len() as u64;
+        let has_more = preview.has_more;
+        let range = if shown == 0 {
+            "No rows".to_string()
+        } else {
+            format!(
+                "Rows {}\u{2013}{}",
+                format_number(first + 1),
+                format_number(first + shown)
+            )
+        };
+        let capped = (!has_more && first + shown >= MAX_PREVIEW_ROWS).then(|| {
+            view! {
+                <span class="import-note">
+                    {format!("The preview stops at {} rows; query the table for more", format_number(MAX_PREVIEW_ROWS))}
+                </span>
+            }
+        });
+        view! {
+            <div class="table-data-pager">
+                <button
+                    class="btn btn-sm"
+                    disabled=first == 0
+                    on:click=move |_| set_offset.update(|o| *o = o.saturating_sub(PREVIEW_PAGE_ROWS))
+                >
+                    "Previous"
+                </button>
+                <span>{range}</span>
+                <button
+                    class="btn btn-sm"
+                    disabled=!has_more
+                    on:click=move |_| set_offset.update(|o| *o += PREVIEW_PAGE_ROWS)
+                >
+                    "Next"
+                </button>
+                {capped}
+            </div>
+        }
+    };
+
+    view! {
+        <div class="table-data">
+            {move || match page.get() {
+                None => view! { <p class="import-note">"Loading rows..."</p> }.into_any(),
+                Some(Err(e)) => view! { <div class="form-error">{e}</div> }.into_any(),
+                Some(Ok(preview)) => {
+                    let header = preview.result.columns.iter().map(|c| view! {
+                        <th>
+                            <span class="col-name">{c.name.clone()}</span>
+                            <span class="col-type">{c.data_type.clone()}</span>
+                        </th>
+                    }).collect::<Vec<_>>();
+                    let rows = preview.result.rows.iter().map(|row| {
+                        let cells = row.iter().map(|v| {
+                            view! { <td class:cell-null=v.is_null()>{cell_text(v)}</td> }
+                        }).collect::<Vec<_>>();
+                        view! { <tr>{cells}</tr> }
+                    }).collect::<Vec<_>>();
+                    view! {
+                        {pager(&preview)}
+                        <div class="result-table-scroll table-data-rows">
+                            <table class="result-table">
+                                <thead><tr>{header}</tr></thead>
+                                <tbody>{rows}</tbody>
+                            </table>
+                        </div>
+                    }.into_any()
+                }
+            }}
+        </div>
+    }
+}
+
+/// Per-column null share, distinct estimate and range over a sample, and
+/// rows and bytes per stored partition.
+#[component]
+pub fn TableStatsView(table: TableInfo, database: String) -> impl IntoView {
+    let req = TableStatsRequest {
+        database,
+        table: format!("{}.{}", table.schema_name, table.name),
+        columns: table.columns.clone(),
+    };
+    let stats = LocalResource::new(move || {
+        let req = req.clone();
+        async move { post::<TableStats>("/proxy/megadb/table/stats", &req).await }
+    });
+    let types = StoredValue::new(table.columns);
+
+    let column_rows = move |stats: &TableStats| {
+        stats
+            .columns
+            .iter()
+            .map(|c| {
+                let data_type = types.with_value(|cols| {
+                    cols.iter()
+                        .find(|col| col.name == c.name)
+                        .map(|col| col.data_type.clone())
+                        .unwrap_or_default()
+                });
+                let nulls = c.null_fraction.unwrap_or(0.0) * 100.0;
+                let value = |v: &Option<serde_json::Value>| v.as_ref().map(cell_text).unwrap_or_default();
+                view! {
+                    <tr>
+                        <td class="col-name">{c.name.clone()}</td>
+                        <td class="col-type">{data_type}</td>
+                        <td>
+                            <div class="stats-bar" title=format!("{nulls:.1}% NULL")>
+                                <div class="stats-bar-fill" style=format!("width:{nulls:.1}%")></div>
+                                <span>{c.null_fraction.map(|_| format!("{nulls:.1}%"))}</span>
+                            </div>
+                        </td>
+                        <td class="stats-num">{c.distinct_estimate.map(format_number)}</td>
+                        <td class="stats-value">{value(&c.min)}</td>
+                        <td class="stats-value">{value(&c.max)}</td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let partitions = move |stats: &TableStats| {
+        if let Some(e) = &stats.partitions_error {
+            return view! { <p class="import-note">{format!("Partitions unavailable: {e}")}</p> }
+                .into_any();
+        }
+        if stats.partitions.is_empty() {
+            return view! { <p class="import-note">"No stored partitions."</p> }.into_any();
+        }
+        let largest = stats
+            .partitions
+            .iter()
+            .filter_map(|p| p.size_bytes)
+            .max()
+            .unwrap_or(0);
+        let rows = stats.partitions.iter().map(|p| {
+            let share = match (p.size_bytes, largest) {
+                (Some(b), l) if l > 0 => b as f64 * 100.0 / l as f64,
+                _ => 0.0,
+            };
+            view! {
+                <tr>
+                    <td class="col-name">{p.partition.clone()}</td>
+                    <td class="stats-num">{p.row_count.map(format_number)}</td>
+                    <td>
+                        <div class="stats-bar">
+                            <div class="stats-bar-fill" style=format!("width:{share:.1}%")></div>
+                            <span>{p.size_bytes.map(format_size)}</span>
+                        </div>
+                    </td>
+                </tr>
+            }
+        }).collect::<Vec<_>>();
+        view! {
+            <table class="columns-table">
+                <thead>
+                    <tr>
+                        <th>"Partition"</th>
+                        <th>"Rows"</th>
+                        <th>"Size"</th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        }
+        .into_any()
+    };
+
+    view! {
+        <div class="table-stats">
+            {move || match stats.get() {
+                None => view! { <p class="import-note">"Computing statistics..."</p> }.into_any(),
+                Some(Err(e)) => view! { <div class="form-error">{e}</div> }.into_any(),
+                Some(Ok(stats)) => {
+                    let note = if stats.is_sampled() {
+                        format!("Column statistics over a sample of the first {} rows; distinct counts are estimates.", format_number(stats.sampled_rows))
+                    } else {
+                        format!("Column statistics over all {} rows; distinct counts are estimates.", format_number(stats.sampled_rows))
+                    };
+                    view! {
+                        <p class="import-note">{note}</p>
+                        <table class="columns-table">
+                            <thead>
+                                <tr>
+                                    <th>"Column"</th>
+                                    <th>"Type"</th>
+                                    <th>"Nulls"</th>
+                                    <th>"Distinct"</th>
+                                    <th>"Min"</th>
+                                    <th>"Max"</th>
+                                </tr>
+                            </thead>
+                            <tbody>{column_rows(&stats)}</tbody>
+                        </table>
+                        <h3>"Size per partition"</h3>
+                        {partitions(&stats)}
+                    }.into_any()
+                }
+            }}
+        </div>
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn post<R: serde::de::DeserializeOwned>(
+    url: &str,
+    body: &impl serde::Serialize,
+) -> Result<R, String> {
+    crate::http::post_json(url, body).await
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn post<R: serde::de::DeserializeOwned>(
+    _url: &str,
+    _body: &impl serde::Serialize,
+) -> Result<R, String> {
+    // No-op on server
+    Err("Only available in the browser".to_string())
+}
@@ -0,0 +1,476 @@
+use leptos::prelude::*;
+use megaweb_types::ddl::{
+    alter_table, create_table, design_problems, COMPRESSION_CODECS, ENGINES, PARTITION_TRANSFORMS,
+};
+use megaweb_types::schema::{ColumnInfo, PartitionInfo, TableInfo};
+use megaweb_types::toast::ToastLevel;
+
+use crate::state::toast::{push_toast, use_toast_write};
+
+/// Types suggested in the column type inputs; any MegaDB type can be typed.
+const COLUMN_TYPES: [&str; 12] = [
+    "BIGINT",
+    "INTEGER",
+    "SMALLINT",
+    "DOUBLE",
+    "REAL",
+    "DECIMAL(18,6)",
+    "BOOLEAN",
+    "VARCHAR",
+    "DATE",
+    "TIMESTAMP",
+    "TIMESTAMPTZ",
+    "JSON",
+];
+
+/// Editor for a new table's definition, or for an existing table's columns
+/// and storage settings.
+///
+/// The generated `CREATE TABLE`, or the `ALTER TABLE` statements turning
+/// the table into the draft, are previewed as they are edited and run one
+/// by one through the query proxy.
+#[component]
+pub fn TableDesigner(
+    /// The table to alter; `None` designs a new one.
+    existing: Option<TableInfo>,
+    database: String,
+    #[prop(into)] on_close: Callback<()>,
+    #[prop(into)] on_applied: Callback<()>,
+) -> impl IntoView {
+    let toast = use_toast_write();
+    let altering = existing.is_some();
+    let before = StoredValue::new(existing.clone());
+    let initial = existing.unwrap_or_else(new_table);
+    // Index in the original table of each draft column, None for new ones
+    let (origins, set_origins) = signal((0..initial.columns.len()).map(Some).collect::<Vec<_>>());
+    let (draft, set_draft) = signal(initial);
+    let (running, set_running) = signal(false);
+    let (error, set_error) = signal(Option::<String>::None);
+
+    // Re-render the column and partition lists only when they change, so
+    // typing in the other fields keeps focus
+    let draft_columns = Memo::new(move |_| draft.with(|d| d.columns.clone()));
+    let draft_partitions = Memo::new(move |_| draft.with(|d| d.partitions.clone()));
+    let problems = Memo::new(move |_| draft.with(design_problems));
+    let statements = Memo::new(move |_| {
+        draft.with(|d| match before.get_value() {
+            Some(before) => origins.with(|o| alter_table(&before, d, o)),
+            None => Ok(vec![create_table(d)]),
+        })
+    });
+
+    let add_column = move |_| {
+        set_draft.update(|d| {
+            d.columns.push(ColumnInfo {
+                name: format!("column_{}", d.columns.len() + 1),
+                data_type: "VARCHAR".to_string(),
+                nullable: true,
+                comment: None,
+            })
+        });
+        set_origins.update(|o| o.push(None));
+    };
+    let remove_column = move |i: usize| {
+        set_draft.update(|d| d.remove_column(i));
+        set_origins.update(|o| {
+            o.remove(i);
+        });
+    };
+
+    let column_rows = move || {
+        draft_columns
+            .get()
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let name = c.name.clone();
+                let sorted = {
+                    let name = name.clone();
+                    move || draft.with(|d| d.sort_columns.as_ref().is_some_and(|s| s.contains(&name)))
+                };
+                let keyed = move || draft.with(|d| d.primary_key.contains(&name));
+                view! {
+                    <tr>
+                        <td>
+                            <input
+                                type="text"
+                                prop:value=c.name.clone()
+                                on:change=move |ev| {
+                                    let value = event_target_value(&ev);
+                                    set_draft.update(|d| d.rename_column(i, value));
+                                }
+                            />
+                        </td>
+                        <td>
+                            <input
+                                type="text"
+                                list="designer-types"
+                                prop:value=c.data_type.clone()
+                                on:change=move |ev| {
+                                    let value = event_target_value(&ev).trim().to_uppercase();
+                                    set_draft.update(|d| d.columns[i].data_type = value);
+                                }
+                            />
+                        </td>
+                        <td class="import-check">
+                            <input
+                                type="checkbox"
+                                prop:checked=c.nullable
+                                on:change=move |ev| {
+                                    let checked = event_target_checked(&ev);
+                                    set_draft.update(|d| d.columns[i].nullable = checked);
+                                }
+                            />
+                        </td>
+                        <td class="import-check">
+                            <input
+                                type="checkbox"
+                                prop:checked=sorted
+                                on:change=move |ev| {
+                                    let checked = event_target_checked(&ev);
+                                    set_draft.update(|d| d.toggle_sort_column(i, checked));
+                                }
+                            />
+                        </td>
+                        <td class="import-check">
+                            <input
+                                type="checkbox"
+                                title=if altering { "The primary key can't be changed on an existing table" } else { "" }
+                                disabled=altering
+                                prop:checked=keyed
+                                on:change=move |ev| {
+                                    let checked = event_target_checked(&ev);
+                                    set_draft.update(|d| {
+                                        let name = d.columns[i].name.clone();
+                                        d.primary_key.retain(|c| *c != name);
+                                        if checked {
+                                            d.primary_key.push(name);
+                                        }
+                                    });
+                                }
+                            />
+                        </td>
+                        <td>
+                            <input
+                                type="text"
+                                placeholder="Comment"
+                                prop:value=c.comment.clone().unwrap_or_default()
+                                on:change=move |ev| {
+                                    let value = event_target_value(&ev);
+                                    let comment = (!value.trim().is_empty()).then_some(value);
+                                    set_draft.update(|d| d.columns[i].comment = comment);
+                                }
+                            />
+                        </td>
+                        <td>
+                            <button class="btn btn-sm" title="Remove column" on:click=move |_| remove_column(i)>
+                                "\u{00D7}"
+                            </button>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let partitions = move || {
+        let columns = draft_columns.get();
+        draft_partitions.get().into_iter().enumerate().map(|(i, p)| {
+            let (func, width) = split_transform(&p.transform);
+            let takes_width = func == "bucket" || func == "truncate";
+            view! {
+                <div class="import-partition">
+                    <select on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        set_draft.update(|d| d.partitions[i].column = value);
+                    }>
+                        {columns.iter().map(|c| {
+                            let selected = c.name == p.column;
+                            view! { <option value=c.name.clone() selected=selected>{c.name.clone()}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    <select on:change=move |ev| {
+                        let func = event_target_value(&ev);
+                        set_draft.update(|d| {
+                            d.partitions[i].transform = match func.as_str() {
+                                "bucket" => "bucket(16)".to_string(),
+                                "truncate" => "truncate(10)".to_string(),
+                                _ => func,
+                            }
+                        });
+                    }>
+                        {PARTITION_TRANSFORMS.into_iter().map(|t| view! {
+                            <option value=t selected=t == func>{t}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    {takes_width.then(|| {
+                        let func = func.clone();
+                        view! {
+                            <input
+                                type="number"
+                                min="1"
+                                class="designer-width"
+                                title=if func == "bucket" { "Number of buckets" } else { "Truncation width" }
+                                prop:value=width.clone()
+                                on:change=move |ev| {
+                                    let value = event_target_value(&ev);
+                                    set_draft.update(|d| d.partitions[i].transform = format!("{func}({})", value.trim()));
+                                }
+                            />
+                        }
+                    })}
+                    <button class="btn btn-sm" on:click=move |_| set_draft.update(|d| { d.partitions.remove(i); })>
+                        "Remove"
+                    </button>
+                </div>
+            }
+        }).collect::<Vec<_>>()
+    };
+
+    let preview = move || {
+        let problems = problems.get();
+        let notes = (!problems.is_empty()).then(|| {
+            view! {
+                <ul class="designer-problems">
+                    {problems.into_iter().map(|p| view! { <li>{p}</li> }).collect::<Vec<_>>()}
+                </ul>
+            }
+        });
+        let sql = match statements.get() {
+            Ok(statements) if statements.is_empty() => {
+                view! { <p class="import-note">"No changes to apply."</p> }.into_any()
+            }
+            Ok(statements) => {
+                view! { <pre class="import-ddl">{statements.join("\n\n")}</pre> }.into_any()
+            }
+            Err(e) => view! { <div class="form-error">{e}</div> }.into_any(),
+        };
+        view! {
+            {notes}
+            {sql}
+        }
+    };
+
+    let database = StoredValue::new(database);
+    let apply = move |_| {
+        let Ok(sql) = statements.get_untracked() else {
+            return;
+        };
+        set_running.set(true);
+        set_error.set(None);
+        let done = Callback::new(move |result: Result<usize, String>| {
+            set_running.set(false);
+            match result {
+                Ok(n) => {
+                    let message = if altering {
+                        format!(
+                            "Applied {n} change(s) to {}",
+                            draft.with_untracked(|d| d.name.clone())
+                        )
+                    } else {
+                        format!("Created {}", draft.with_untracked(|d| d.name.clone()))
+                    };
+                    push_toast(toast, ToastLevel::Success, message);
+                    on_applied.run(());
+                    on_close.run(());
+                }
+                Err(e) => {
+                    // Statements before the failing one were applied
+                    if altering {
+                        on_applied.run(());
+                    }
+                    set_error.set(Some(e));
+                }
+            }
+        });
+        run_statements(database.get_value(), sql, done);
+    };
+    let can_apply = move || {
+        !running.get()
+            && problems.with(|p| p.is_empty())
+            && statements.with(|s| s.as_ref().is_ok_and(|s| !s.is_empty()))
+    };
+
+    let title = match before.get_value() {
+        Some(t) => format!("Alter {}.{}", t.schema_name, t.name),
+        None => "New table".to_string(),
+    };
+
+    view! {
+        <div class="modal-overlay">
+            <div class="modal table-designer">
+                <div class="modal-header">
+                    <h3>{title}</h3>
+                </div>
+                <div class="modal-body">
+                    <div class="form-row">
+                        <div class="form-field">
+                            <label>"Schema"</label>
+                            <input
+                                type="text"
+                                disabled=altering
+                                prop:value=move || draft.with(|d| d.schema_name.clone())
+                                on:input=move |ev| set_draft.update(|d| d.schema_name = event_target_value(&ev))
+                            />
+                        </div>
+                        <div class="form-field">
+                            <label>"Table name"</label>
+                            <input
+                                type="text"
+                                disabled=altering
+                                prop:value=move || draft.with(|d| d.name.clone())
+                                on:input=move |ev| set_draft.update(|d| d.name = event_target_value(&ev))
+                            />
+                        </div>
+                        <div class="form-field">
+                            <label>"Engine"</label>
+                            <select
+                                disabled=altering
+                                on:change=move |ev| set_draft.update(|d| d.engine = event_target_value(&ev))
+                            >
+                                {ENGINES.into_iter().map(|e| view! {
+                                    <option value=e selected=move || draft.with(|d| d.engine == e)>{e}</option>
+                                }).collect::<Vec<_>>()}
+                            </select>
+                        </div>
+                        <div class="form-field">
+                            <label>"Compression"</label>
+                            <select on:change=move |ev| {
+                                let value = event_target_value(&ev);
+                                set_draft.update(|d| d.compression = (!value.is_empty()).then_some(value));
+                            }>
+                                <option value="" selected=move || draft.with(|d| d.compression.is_none())>
+                                    "none"
+                                </option>
+                                {COMPRESSION_CODECS.into_iter().map(|c| view! {
+                                    <option value=c selected=move || draft.with(|d| d.compression.as_deref() == Some(c))>
+                                        {c}
+                                    </option>
+                                }).collect::<Vec<_>>()}
+                            </select>
+                        </div>
+                    </div>
+                    <table class="import-columns">
+                        <thead>
+                            <tr>
+                                <th>"Column"</th>
+                                <th>"Type"</th>
+                                <th>"Nullable"</th>
+                                <th title="Sort columns, in the order they are checked">"Sort"</th>
+                                <th>"Key"</th>
+                                <th>"Comment"</th>
+                                <th></th>
+                            </tr>
+                        </thead>
+                        <tbody>{column_rows}</tbody>
+                    </table>
+                    <datalist id="designer-types">
+                        {COLUMN_TYPES.into_iter().map(|t| view! { <option value=t></option> }).collect::<Vec<_>>()}
+                    </datalist>
+                    <button class="btn btn-sm" on:click=add_column>"Add column"</button>
+                    <div class="import-partitions">
+                        <label>"Partitioning"</label>
+                        {partitions}
+                        <button
+                            class="btn btn-sm"
+                            on:click=move |_| set_draft.update(|d| {
+                                let column = d.columns.first().map(|c| c.name.clone()).unwrap_or_default();
+                                d.partitions.push(PartitionInfo { column, transform: "identity".into() });
+                            })
+                        >
+                            "Add partition"
+                        </button>
+                    </div>
+                    {preview}
+                    {move || error.get().map(|e| view! { <div class="form-error">{e}</div> })}
+                </div>
+                <div class="modal-footer">
+                    <button class="btn btn-secondary" on:click=move |_| on_close.run(())>"Cancel"</button>
+                    <button class="btn btn-primary" disabled=move || !can_apply() on:click=apply>
+                        {move || match (running.get(), altering) {
+                            (true, _) => "Running...",
+                            (false, true) => "Apply changes",
+                            (false, false) => "Create table",
+                        }}
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+fn new_table() -> TableInfo {
+    TableInfo {
+        schema_name: "public".to_string(),
+        name: String::new(),
+        engine: "OLAP".to_string(),
+        row_count: None,
+        size_bytes: None,
+        columns: vec![ColumnInfo {
+            name: "id".to_string(),
+            data_type: "BIGINT".to_string(),
+            nullable: false,
+            comment: None,
+        }],
+        partitions: vec![],
+        compression: None,
+        sort_columns: None,
+        primary_key: vec![],
+    }
+}
+
+/// `bucket(16)` into `("bucket", "16")`; transforms without a width get an
+/// empty one.
+fn split_transform(transform: &str) -> (String, String) {
+    match transform.split_once('(') {
+        Some((func, rest)) => (
+            func.trim().to_lowercase(),
+            rest.trim_end_matches(')').trim().to_string(),
+        ),
+        None => (transform.trim().to_lowercase(), String::new()),
+    }
+}
+
+/// Run the statements in order, stopping at the first failure. `done`
+/// gets the number run, or the failing statement's error.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn run_statements(
+    database: String,
+    statements: Vec<String>,
+    done: Callback<Result<usize, String>>,
+) {
+    use megaweb_types::query::{QueryRequest, QueryResult};
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let total = statements.len();
+        for (n, sql) in statements.into_iter().enumerate() {
+            let req = QueryRequest {
+                sql: sql.clone(),
+                database: database.clone(),
+                limit: None,
+            };
+            let result = crate::http::post_json::<QueryResult>("/proxy/megadb/query", &req)
+                .await
+                .and_then(|result| result.error.map_or(Ok(()), Err));
+            if let Err(e) = result {
+                let applied = if n > 0 {
+                    format!(" ({n} of {total} statements were applied)")
+                } else {
+                    String::new()
+                };
+                done.run(Err(format!("{sql} failed: {e}{applied}")));
+                return;
+            }
+        }
+        done.run(Ok(total));
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn run_statements(
+    _database: String,
+    _statements: Vec<String>,
+    _done: Callback<Result<usize, String>>,
+) {
+    // No-op on server
+}
@@ -0,0 +1,64 @@
+//! Requests to the server's HTTP API from the browser.
+//!
+//! Failed requests carry the `{"error": ...}` message the server's handlers
+//! return, or the HTTP status when there is none.
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// POST `body` as JSON and decode the JSON response.
+#[cfg(target_arch = "wasm32")]
+pub async fn post_json<R: serde::de::DeserializeOwned>(
+    url: &str,
+    body: &impl serde::Serialize,
+) -> Result<R, String> {
+    let body = serde_json::to_string(body).map_err(|e| e.to_string())?;
+    send(url, "application/json", &JsValue::from_str(&body)).await
+}
+
+/// POST raw bytes and decode the JSON response.
+#[cfg(target_arch = "wasm32")]
+pub async fn post_bytes<R: serde::de::DeserializeOwned>(
+    url: &str,
+    bytes: &[u8],
+) -> Result<R, String> {
+    let body = js_sys::Uint8Array::from(bytes);
+    send(url, "application/octet-stream", &body).await
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn send<R: serde::de::DeserializeOwned>(
+    url: &str,
+    content_type: &str,
+    body: &JsValue,
+) -> Result<R, String> {
+    use wasm_bindgen_futures::JsFuture;
+
+    let js_err = |e: JsValue| e.as_string().unwrap_or_else(|| format!("{e:?}"));
+    let window = web_sys::window().ok_or("no window")?;
+
+    let headers = web_sys::Headers::new().map_err(js_err)?;
+    headers.set("Content-Type", content_type).map_err(js_err)?;
+    let init = web_sys::RequestInit::new();
+    init.set_method("POST");
+    init.set_headers(&headers);
+    init.set_body(body);
+
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_str_and_init(url, &init))
+        .await
+        .map_err(js_err)?
+        .unchecked_into();
+    let text = JsFuture::from(response.text().map_err(js_err)?)
+        .await
+        .map_err(js_err)?
+        .as_string()
+        .unwrap_or_default();
+    if !response.ok() {
+        let message = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|v| v.get("error")?.as_str().map(str::to_string))
+            .unwrap_or_else(|| format!("HTTP {}", response.status()));
+        return Err(message);
+    }
+    serde_json::from_str(&text).map_err(|e| format!("Invalid response: {e}"))
+}
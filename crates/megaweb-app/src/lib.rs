@@ -1,4 +1,6 @@
+pub mod clipboard;
 pub mod components;
+pub mod http;
 pub mod pages;
 pub mod state;
 pub mod storage;
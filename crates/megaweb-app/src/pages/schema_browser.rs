@@ -1,140 +1,147 @@
 use leptos::prelude::*;
+use leptos_router::hooks::use_navigate;
+use megaweb_types::connection::ConnectionConfig;
+use megaweb_types::ddl::create_table;
 use megaweb_types::schema::*;
+use megaweb_types::toast::ToastLevel;
 
+use crate::components::import_wizard::ImportWizard;
+use crate::components::partition_explorer::PartitionExplorer;
 use crate::components::schema_tree::SchemaTree;
+use crate::components::table_data::{TableDataPreview, TableStatsView};
+use crate::components::table_designer::TableDesigner;
+use crate::state::connection::use_connection_state;
+use crate::state::query::use_query_state;
+use crate::state::toast::{push_toast, use_toast_write};
 
-/// Server function to list databases and schemas.
-/// Returns mock data in Phase 1.
+/// How long a connection's catalog is served from the cache.
+#[cfg(feature = "ssr")]
+const CATALOG_TTL: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Catalogs by (MegaDB URL, database), with when they were loaded.
+#[cfg(feature = "ssr")]
+type CatalogCache = std::collections::HashMap<(String, String), (std::time::Instant, DatabaseInfo)>;
+
+#[cfg(feature = "ssr")]
+static CATALOG_CACHE: std::sync::LazyLock<std::sync::Mutex<CatalogCache>> =
+    std::sync::LazyLock::new(Default::default);
+
+#[cfg(feature = "ssr")]
+static CATALOG_CLIENT: std::sync::LazyLock<reqwest::Client> =
+    std::sync::LazyLock::new(reqwest::Client::new);
+
+/// Read the tables of `database` from MegaDB's `/tables` API, falling back
+/// to `information_schema` on servers that don't have it.
+#[cfg(feature = "ssr")]
+async fn fetch_catalog(base_url: &str, database: &str) -> Result<Vec<TableInfo>, String> {
+    use megaweb_types::catalog::*;
+
+    let unreachable = |e: reqwest::Error| format!("MegaDB unreachable at {base_url}: {e}");
+    let resp = CATALOG_CLIENT
+        .get(format!("{base_url}/tables"))
+        .query(&[("database", database)])
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    if resp.status() != reqwest::StatusCode::NOT_FOUND {
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("MegaDB returned {status}: {body}"));
+        }
+        let body = resp.json().await.map_err(|e| format!("Parse error: {e}"))?;
+        return tables_from_api(body, Some(database));
+    }
+
+    let query = |sql: &str| {
+        let request = megaweb_types::query::QueryRequest {
+            sql: sql.to_string(),
+            database: database.to_string(),
+            limit: None,
+        };
+        async move {
+            let result: megaweb_types::query::QueryResult = CATALOG_CLIENT
+                .post(format!("{base_url}/query"))
+                .json(&request)
+                .timeout(std::time::Duration::from_secs(30))
+                .send()
+                .await
+                .map_err(unreachable)?
+                .json()
+                .await
+                .map_err(|e| format!("Parse error: {e}"))?;
+            match result.error {
+                Some(e) => Err(format!("Catalog query failed: {e}")),
+                None => Ok(result),
+            }
+        }
+    };
+    let tables = query(INFORMATION_SCHEMA_TABLES_SQL).await?;
+    let columns = query(INFORMATION_SCHEMA_COLUMNS_SQL).await?;
+    Ok(tables_from_information_schema(&tables, &columns))
+}
+
+/// Server function listing the database of a connection, read from
+/// MegaDB's catalog. Catalogs are cached per connection for a few minutes;
+/// `refresh` reloads it.
 #[server(ListDatabases, "/api")]
-pub async fn list_databases() -> Result<Vec<DatabaseInfo>, ServerFnError> {
-    Ok(vec![DatabaseInfo {
-        name: "megadb".to_string(),
-        default_engine: "OLAP".to_string(),
-        schemas: vec![SchemaInfo {
-            name: "public".to_string(),
-            tables: vec![
-                TableInfo {
-                    schema_name: "public".to_string(),
-                    name: "cur_data".to_string(),
-                    engine: "OLAP".to_string(),
-                    row_count: Some(1_247_832_456),
-                    size_bytes: Some(12_400_000_000),
-                    columns: vec![
-                        ColumnInfo {
-                            name: "line_item_id".into(),
-                            data_type: "VARCHAR".into(),
-                            nullable: false,
-                            comment: None,
-                        },
-                        ColumnInfo {
-                            name: "account_id".into(),
-                            data_type: "VARCHAR".into(),
-                            nullable: false,
-                            comment: None,
-                        },
-                        ColumnInfo {
-                            name: "service_name".into(),
-                            data_type: "VARCHAR".into(),
-                            nullable: false,
-                            comment: None,
-                        },
-                        ColumnInfo {
-                            name: "cost".into(),
-                            data_type: "DECIMAL(18,6)".into(),
-                            nullable: false,
-                            comment: Some("Unblended cost in USD".into()),
-                        },
-                        ColumnInfo {
-                            name: "region".into(),
-                            data_type: "VARCHAR".into(),
-                            nullable: true,
-                            comment: None,
-                        },
-                        ColumnInfo {
-                            name: "billing_period".into(),
-                            data_type: "DATE".into(),
-                            nullable: false,
-                            comment: None,
-                        },
-                    ],
-                    partitions: vec![PartitionInfo {
-                        column: "billing_period".into(),
-                        transform: "month".into(),
-                    }],
-                    compression: Some("Zstd".into()),
-                    sort_columns: Some(vec!["account_id".into(), "service_name".into()]),
-                },
-                TableInfo {
-                    schema_name: "public".to_string(),
-                    name: "cmdb_resources".to_string(),
-                    engine: "OLTP".to_string(),
-                    row_count: Some(45_230),
-                    size_bytes: Some(8_500_000),
-                    columns: vec![
-                        ColumnInfo {
-                            name: "resource_id".into(),
-                            data_type: "VARCHAR".into(),
-                            nullable: false,
-                            comment: None,
-                        },
-                        ColumnInfo {
-                            name: "resource_type".into(),
-                            data_type: "VARCHAR".into(),
-                            nullable: false,
-                            comment: None,
-                        },
-                        ColumnInfo {
-                            name: "tags".into(),
-                            data_type: "JSONB".into(),
-                            nullable: true,
-                            comment: None,
-                        },
-                    ],
-                    partitions: vec![],
-                    compression: None,
-                    sort_columns: None,
-                },
-                TableInfo {
-                    schema_name: "public".to_string(),
-                    name: "cost_alerts".to_string(),
-                    engine: "MEMORY".to_string(),
-                    row_count: Some(128),
-                    size_bytes: Some(64_000),
-                    columns: vec![
-                        ColumnInfo {
-                            name: "alert_id".into(),
-                            data_type: "UUID".into(),
-                            nullable: false,
-                            comment: None,
-                        },
-                        ColumnInfo {
-                            name: "threshold".into(),
-                            data_type: "DECIMAL(18,2)".into(),
-                            nullable: false,
-                            comment: None,
-                        },
-                        ColumnInfo {
-                            name: "triggered_at".into(),
-                            data_type: "TIMESTAMP".into(),
-                            nullable: true,
-                            comment: None,
-                        },
-                    ],
-                    partitions: vec![],
-                    compression: None,
-                    sort_columns: None,
-                },
-            ],
-        }],
-    }])
+pub async fn list_databases(
+    host: String,
+    http_port: u16,
+    database: String,
+    refresh: bool,
+) -> Result<Vec<DatabaseInfo>, ServerFnError> {
+    let base_url = format!("http://{host}:{http_port}");
+    let key = (base_url.clone(), database.clone());
+    if !refresh {
+        let cache = CATALOG_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((loaded, info)) = cache.get(&key) {
+            if loaded.elapsed() < CATALOG_TTL {
+                return Ok(vec![info.clone()]);
+            }
+        }
+    }
+
+    let tables = fetch_catalog(&base_url, &database)
+        .await
+        .map_err(ServerFnError::new)?;
+    let info = megaweb_types::catalog::database_info(&database, tables);
+    CATALOG_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key, (std::time::Instant::now(), info.clone()));
+    Ok(vec![info])
+}
+
+/// The catalog of `connection`, or of the default local connection.
+pub async fn load_connection_schema(
+    connection: Option<ConnectionConfig>,
+) -> Result<Vec<DatabaseInfo>, ServerFnError> {
+    let c = connection.unwrap_or_default();
+    list_databases(c.host, c.http_port, c.database, false).await
 }
 
 /// Schema Browser page.
 #[component]
 pub fn SchemaBrowserPage() -> impl IntoView {
-    let databases = Resource::new(|| (), |_| list_databases());
+    let (connection_state, _) = use_connection_state();
+    // Bumped by the refresh button; any bump bypasses the server's cache
+    let (refreshes, set_refreshes) = signal(0u32);
+    let databases = Resource::new(
+        move || {
+            let c = connection_state.get().active.unwrap_or_default();
+            (c.host, c.http_port, c.database, refreshes.get())
+        },
+        |(host, http_port, database, refreshes)| {
+            list_databases(host, http_port, database, refreshes > 0)
+        },
+    );
     let (selected_table, set_selected_table) = signal(Option::<TableInfo>::None);
     let (filter, set_filter) = signal(String::new());
+    let (show_import, set_show_import) = signal(false);
+    // Some(None) designs a new table, Some(Some(t)) alters t
+    let (designing, set_designing) = signal(Option::<Option<TableInfo>>::None);
 
     let on_select = Callback::new(move |table: TableInfo| {
         set_selected_table.set(Some(table));
@@ -151,6 +158,27 @@ pub fn SchemaBrowserPage() -> impl IntoView {
                         prop:value=move || filter.get()
                         on:input=move |ev| set_filter.set(event_target_value(&ev))
                     />
+                    <button
+                        class="btn btn-sm"
+                        title="Reload the schema from MegaDB"
+                        on:click=move |_| set_refreshes.update(|n| *n += 1)
+                    >
+                        "Refresh"
+                    </button>
+                    <button
+                        class="btn btn-sm schema-import-btn"
+                        title="Import a CSV or Parquet file into a table"
+                        on:click=move |_| set_show_import.set(true)
+                    >
+                        "Import..."
+                    </button>
+                    <button
+                        class="btn btn-sm schema-import-btn"
+                        title="Design a new table"
+                        on:click=move |_| set_designing.set(Some(None))
+                    >
+                        "New table..."
+                    </button>
                 </div>
                 <Suspense fallback=|| view! { <p>"Loading schemas..."</p> }>
                     {move || {
@@ -176,6 +204,35 @@ pub fn SchemaBrowserPage() -> impl IntoView {
                 </Suspense>
             </div>
 
+            {move || show_import.get().then(|| {
+                let dbs = databases.get().and_then(Result::ok).unwrap_or_default();
+                view! {
+                    <ImportWizard
+                        databases=dbs
+                        on_close=move |_| set_show_import.set(false)
+                        on_imported=move |_| set_refreshes.update(|n| *n += 1)
+                    />
+                }
+            })}
+
+            {move || designing.get().map(|existing| {
+                let database = connection_state
+                    .with(|c| c.active.as_ref().map(|a| a.database.clone()))
+                    .unwrap_or_default();
+                view! {
+                    <TableDesigner
+                        existing=existing
+                        database=database
+                        on_close=move |_| set_designing.set(None)
+                        on_applied=move |_| {
+                            set_selected_table.set(None);
+                            set_refreshes.update(|n| *n += 1);
+                        }
+                    />
+                }
+                .into_any()
+            })}
+
             <div class="schema-detail">
                 {move || match selected_table.get() {
                     None => view! {
@@ -184,7 +241,10 @@ pub fn SchemaBrowserPage() -> impl IntoView {
                         </div>
                     }.into_any(),
                     Some(ref table) => view! {
-                        <TableDetail table=table.clone() />
+                        <TableDetail
+                            table=table.clone()
+                            on_design=move |t| set_designing.set(Some(Some(t)))
+                        />
                     }.into_any(),
                 }}
             </div>
@@ -192,98 +252,208 @@ pub fn SchemaBrowserPage() -> impl IntoView {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DetailTab {
+    Overview,
+    Data,
+    Stats,
+    Partitions,
+}
+
 #[component]
-fn TableDetail(table: TableInfo) -> impl IntoView {
-    let size_display = table.size_bytes.map(|b| {
-        if b >= 1_000_000_000 {
-            format!("{:.1} GB", b as f64 / 1_000_000_000.0)
-        } else if b >= 1_000_000 {
-            format!("{:.1} MB", b as f64 / 1_000_000.0)
-        } else {
-            format!("{} KB", b / 1000)
-        }
+fn TableDetail(table: TableInfo, #[prop(into)] on_design: Callback<TableInfo>) -> impl IntoView {
+    let (_, set_query_state) = use_query_state();
+    let toast = use_toast_write();
+    let navigate = use_navigate();
+    let (connection_state, _) = use_connection_state();
+    let database = connection_state.with_untracked(|c| {
+        c.active
+            .as_ref()
+            .map(|a| a.database.clone())
+            .unwrap_or_default()
     });
+    let (show_ddl, set_show_ddl) = signal(false);
+    let (tab, set_tab) = signal(DetailTab::Overview);
+    let partitioned = !table.partitions.is_empty();
+    let ddl = create_table(&table);
+    let ddl_title = format!("DDL {}", table.name);
 
     view! {
         <div class="table-detail">
-            <h2>{format!("{}.{}", table.schema_name, table.name)}</h2>
+            <div class="table-detail-header">
+                <h2>{format!("{}.{}", table.schema_name, table.name)}</h2>
+                <button class="btn btn-sm" on:click=move |_| set_show_ddl.update(|v| *v = !*v)>
+                    {move || if show_ddl.get() { "Hide CREATE TABLE" } else { "Show CREATE TABLE" }}
+                </button>
+                <button
+                    class="btn btn-sm"
+                    title="Change columns, partitioning, sort columns or compression"
+                    on:click={
+                        let table = table.clone();
+                        move |_| on_design.run(table.clone())
+                    }
+                >
+                    "Alter table..."
+                </button>
+            </div>
 
-            <div class="table-meta">
-                <div class="meta-item">
-                    <span class="meta-label">"Engine"</span>
-                    <span class=format!("meta-value engine-badge engine-{}", table.engine.to_lowercase())>
-                        {table.engine.clone()}
-                    </span>
-                </div>
-                {table.compression.as_ref().map(|c| view! {
-                    <div class="meta-item">
-                        <span class="meta-label">"Compression"</span>
-                        <span class="meta-value">{c.clone()}</span>
-                    </div>
-                })}
-                {table.row_count.map(|c| view! {
-                    <div class="meta-item">
-                        <span class="meta-label">"Rows"</span>
-                        <span class="meta-value">{format_number(c)}</span>
-                    </div>
-                })}
-                {size_display.map(|s| view! {
-                    <div class="meta-item">
-                        <span class="meta-label">"Size"</span>
-                        <span class="meta-value">{s}</span>
-                    </div>
-                })}
-                {table.sort_columns.as_ref().map(|cols| view! {
-                    <div class="meta-item">
-                        <span class="meta-label">"Sort"</span>
-                        <span class="meta-value">{cols.join(", ")}</span>
+            <Show when=move || show_ddl.get()>
+                <div class="table-ddl">
+                    <div class="table-ddl-actions">
+                        <button
+                            class="btn btn-sm"
+                            on:click={
+                                let ddl = ddl.clone();
+                                move |_| {
+                                    crate::clipboard::write_text(&ddl);
+                                    push_toast(toast, ToastLevel::Success, "Copied CREATE TABLE");
+                                }
+                            }
+                        >
+                            "Copy"
+                        </button>
+                        <button
+                            class="btn btn-sm btn-primary"
+                            title="Open the statement in a new SQL editor tab"
+                            on:click={
+                                let (ddl, title, navigate) = (ddl.clone(), ddl_title.clone(), navigate.clone());
+                                move |_| {
+                                    set_query_state.update(|s| s.open_in_new_tab(&title, &ddl));
+                                    navigate("/sql", Default::default());
+                                }
+                            }
+                        >
+                            "Open in editor"
+                        </button>
                     </div>
-                })}
+                    <pre class="table-ddl-sql">{ddl.clone()}</pre>
+                </div>
+            </Show>
+
+            <div class="result-mode-toggle table-detail-tabs">
+                {[(DetailTab::Overview, "Overview"), (DetailTab::Data, "Data"), (DetailTab::Stats, "Stats"), (DetailTab::Partitions, "Partitions")]
+                    .into_iter()
+                    .filter(|(t, _)| *t != DetailTab::Partitions || partitioned)
+                    .map(|(t, label)| view! {
+                        <button
+                            class=move || if tab.get() == t { "btn btn-sm active" } else { "btn btn-sm" }
+                            on:click=move |_| set_tab.set(t)
+                        >
+                            {label}
+                        </button>
+                    })
+                    .collect::<Vec<_>>()}
             </div>
 
-            {if !table.partitions.is_empty() {
-                Some(view! {
-                    <div class="table-partitions">
-                        <h3>"Partitions"</h3>
-                        {table.partitions.iter().map(|p| view! {
-                            <span class="partition-badge">
-                                {format!("{} ({})", p.column, p.transform)}
-                            </span>
-                        }).collect::<Vec<_>>()}
-                    </div>
-                })
-            } else {
-                None
+            {move || match tab.get() {
+                DetailTab::Overview => view! {
+                    <TableOverview table=table.clone() />
+                }.into_any(),
+                DetailTab::Data => view! {
+                    <TableDataPreview table=table.clone() database=database.clone() />
+                }.into_any(),
+                DetailTab::Stats => view! {
+                    <TableStatsView table=table.clone() database=database.clone() />
+                }.into_any(),
+                DetailTab::Partitions => view! {
+                    <PartitionExplorer table=table.clone() database=database.clone() />
+                }.into_any(),
             }}
+        </div>
+    }
+}
+
+/// Engine, storage settings, partitioning and columns of a table.
+#[component]
+fn TableOverview(table: TableInfo) -> impl IntoView {
+    let size_display = table.size_bytes.map(format_size);
+
+    view! {
+        <div class="table-meta">
+            <div class="meta-item">
+                <span class="meta-label">"Engine"</span>
+                <span class=format!("meta-value engine-badge engine-{}", table.engine.to_lowercase())>
+                    {table.engine.clone()}
+                </span>
+            </div>
+            {table.compression.as_ref().map(|c| view! {
+                <div class="meta-item">
+                    <span class="meta-label">"Compression"</span>
+                    <span class="meta-value">{c.clone()}</span>
+                </div>
+            })}
+            {table.row_count.map(|c| view! {
+                <div class="meta-item">
+                    <span class="meta-label">"Rows"</span>
+                    <span class="meta-value">{format_number(c)}</span>
+                </div>
+            })}
+            {size_display.map(|s| view! {
+                <div class="meta-item">
+                    <span class="meta-label">"Size"</span>
+                    <span class="meta-value">{s}</span>
+                </div>
+            })}
+            {table.sort_columns.as_ref().map(|cols| view! {
+                <div class="meta-item">
+                    <span class="meta-label">"Sort"</span>
+                    <span class="meta-value">{cols.join(", ")}</span>
+                </div>
+            })}
+        </div>
 
-            <div class="table-columns">
-                <h3>{format!("Columns ({})", table.columns.len())}</h3>
-                <table class="columns-table">
-                    <thead>
+        {if !table.partitions.is_empty() {
+            Some(view! {
+                <div class="table-partitions">
+                    <h3>"Partitions"</h3>
+                    {table.partitions.iter().map(|p| view! {
+                        <span class="partition-badge">
+                            {format!("{} ({})", p.column, p.transform)}
+                        </span>
+                    }).collect::<Vec<_>>()}
+                </div>
+            })
+        } else {
+            None
+        }}
+
+        <div class="table-columns">
+            <h3>{format!("Columns ({})", table.columns.len())}</h3>
+            <table class="columns-table">
+                <thead>
+                    <tr>
+                        <th>"Name"</th>
+                        <th>"Type"</th>
+                        <th>"Nullable"</th>
+                        <th>"Comment"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {table.columns.iter().map(|col| view! {
                         <tr>
-                            <th>"Name"</th>
-                            <th>"Type"</th>
-                            <th>"Nullable"</th>
-                            <th>"Comment"</th>
+                            <td class="col-name">{col.name.clone()}</td>
+                            <td class="col-type">{col.data_type.clone()}</td>
+                            <td>{if col.nullable { "YES" } else { "NO" }}</td>
+                            <td class="col-comment">{col.comment.clone().unwrap_or_default()}</td>
                         </tr>
-                    </thead>
-                    <tbody>
-                        {table.columns.iter().map(|col| view! {
-                            <tr>
-                                <td class="col-name">{col.name.clone()}</td>
-                                <td class="col-type">{col.data_type.clone()}</td>
-                                <td>{if col.nullable { "YES" } else { "NO" }}</td>
-                                <td class="col-comment">{col.comment.clone().unwrap_or_default()}</td>
-                            </tr>
-                        }).collect::<Vec<_>>()}
-                    </tbody>
-                </table>
-            </div>
+                    }).collect::<Vec<_>>()}
+                </tbody>
+            </table>
         </div>
     }
 }
 
-fn format_number(n: u64) -> String {
+pub(crate) fn format_size(bytes: u64) -> String {
+    if bytes >= 1_000_000_000 {
+        format!("{:.1} GB", bytes as f64 / 1_000_000_000.0)
+    } else if bytes >= 1_000_000 {
+        format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+    } else {
+        format!("{} KB", bytes / 1000)
+    }
+}
+
+pub(crate) fn format_number(n: u64) -> String {
     let s = n.to_string();
     let mut result = String::new();
     for (i, c) in s.chars().rev().enumerate() {
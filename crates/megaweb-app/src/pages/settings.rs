@@ -1,4 +1,6 @@
 use leptos::prelude::*;
+use megaweb_types::export::CsvQuoting;
+use megaweb_types::query::{NumberLocale, TimeZoneDisplay};
 use megaweb_types::toast::ToastLevel;
 
 use crate::state::settings::use_settings_state;
@@ -87,6 +89,105 @@ pub fn SettingsPage() -> impl IntoView {
                 </div>
             </div>
 
+            <div class="settings-section">
+                <h3>"Results"</h3>
+                <div class="setting-item">
+                    <label>"Number Format"</label>
+                    <select on:change=move |ev| {
+                        let label = event_target_value(&ev);
+                        let locale = NumberLocale::ALL.into_iter().find(|l| l.label() == label);
+                        set_settings.update(|s| s.number_locale = locale);
+                    }>
+                        <option value="" selected=move || settings.get().number_locale.is_none()>
+                            "Browser default"
+                        </option>
+                        {NumberLocale::ALL.into_iter().map(|l| view! {
+                            <option
+                                value=l.label()
+                                selected=move || settings.get().number_locale == Some(l)
+                            >
+                                {l.label()}
+                            </option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </div>
+                <div class="setting-item">
+                    <label>"Timestamps With Time Zone"</label>
+                    <select on:change=move |ev| {
+                        let label = event_target_value(&ev);
+                        if let Some(tz) = TimeZoneDisplay::ALL.into_iter().find(|t| t.label() == label) {
+                            set_settings.update(|s| s.time_zone = tz);
+                        }
+                    }>
+                        {TimeZoneDisplay::ALL.into_iter().map(|t| view! {
+                            <option value=t.label() selected=move || settings.get().time_zone == t>
+                                {t.label()}
+                            </option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </div>
+            </div>
+
+            <div class="settings-section">
+                <h3>"CSV Export"</h3>
+                <div class="setting-item">
+                    <label>"Delimiter"</label>
+                    <select
+                        prop:value=move || settings.get().csv.delimiter.to_string()
+                        on:change=move |ev| {
+                            if let Some(c) = event_target_value(&ev).chars().next() {
+                                set_settings.update(|s| s.csv.delimiter = c);
+                            }
+                        }
+                    >
+                        <option value=",">"Comma (,)"</option>
+                        <option value=";">"Semicolon (;)"</option>
+                        <option value="\t">"Tab"</option>
+                        <option value="|">"Pipe (|)"</option>
+                    </select>
+                </div>
+                <div class="setting-item">
+                    <label>"Quote Fields"</label>
+                    <select on:change=move |ev| {
+                        let label = event_target_value(&ev);
+                        if let Some(q) = CsvQuoting::ALL.into_iter().find(|q| q.label() == label) {
+                            set_settings.update(|s| s.csv.quoting = q);
+                        }
+                    }>
+                        {CsvQuoting::ALL.into_iter().map(|q| view! {
+                            <option
+                                value=q.label()
+                                selected=move || settings.get().csv.quoting == q
+                            >
+                                {q.label()}
+                            </option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </div>
+                <div class="setting-item">
+                    <label>"NULL As"</label>
+                    <input
+                        type="text"
+                        placeholder="(empty)"
+                        prop:value=move || settings.get().csv.null_value
+                        on:input=move |ev| {
+                            let val = event_target_value(&ev);
+                            set_settings.update(|s| s.csv.null_value = val);
+                        }
+                    />
+                </div>
+                <div class="setting-item">
+                    <label>"Header Row"</label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || settings.get().csv.header
+                        on:change=move |_| {
+                            set_settings.update(|s| s.csv.header = !s.csv.header);
+                        }
+                    />
+                </div>
+            </div>
+
             <div class="settings-section">
                 <h3>"About"</h3>
                 <p>"MegaDB Web Client v0.1.0"</p>
@@ -1,119 +1,43 @@
+use std::sync::Arc;
+
 use leptos::prelude::*;
-use megaweb_types::query::{QueryHistoryEntry, QueryResult};
+use megaweb_types::chart::ResultChartSpec;
+use megaweb_types::json_path::add_column_to_query;
+use megaweb_types::query::{QueryHistoryEntry, QueryRequest, QueryResult, SavedQuery};
 use megaweb_types::toast::ToastLevel;
 
 use crate::components::codemirror::CodeMirrorEditor;
+use crate::components::plan_compare::PlanCompare;
 use crate::components::query_history::QueryHistoryPanel;
 use crate::components::query_plan::QueryPlanViewer;
 use crate::components::result_table::ResultTable;
 use crate::components::tab_bar::{Tab, TabBar};
-use crate::state::query::use_query_state;
+use crate::components::table_data::post;
+use crate::pages::schema_browser::load_connection_schema;
+use crate::state::connection::use_connection_state;
+use crate::state::query::{load_history_snapshot, use_query_state, QueryState, ResultMode};
+use crate::state::settings::use_settings_state;
 use crate::state::toast::{push_toast, use_toast_write};
 
-/// Server function to execute SQL against MegaDB.
-/// In Phase 1, returns mock data. Will proxy to MegaDB HTTP API later.
-#[cfg(feature = "ssr")]
-async fn mock_execute_query(sql: &str) -> QueryResult {
-    use megaweb_types::query::QueryColumn;
-
-    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
-
-    if sql.trim().is_empty() {
-        return QueryResult {
-            columns: vec![],
-            rows: vec![],
-            row_count: 0,
-            execution_time_ms: 0,
-            error: Some("Empty query".to_string()),
-        };
-    }
-
-    // Mock EXPLAIN output
-    if sql.trim().to_uppercase().starts_with("EXPLAIN") {
-        return QueryResult {
-            columns: vec![QueryColumn {
-                name: "QUERY PLAN".into(),
-                data_type: "TEXT".into(),
-                nullable: false,
-            }],
-            rows: vec![vec![serde_json::json!(
-                "Seq Scan on cur_data  (cost=0.00..1234.56 rows=50000 width=120)\n  Filter: (region = 'us-east-1')"
-            )]],
-            row_count: 1,
-            execution_time_ms: 5,
-            error: None,
-        };
-    }
-
-    QueryResult {
-        columns: vec![
-            QueryColumn {
-                name: "line_item_id".into(),
-                data_type: "VARCHAR".into(),
-                nullable: false,
-            },
-            QueryColumn {
-                name: "account_id".into(),
-                data_type: "VARCHAR".into(),
-                nullable: false,
-            },
-            QueryColumn {
-                name: "service_name".into(),
-                data_type: "VARCHAR".into(),
-                nullable: false,
-            },
-            QueryColumn {
-                name: "usage_type".into(),
-                data_type: "VARCHAR".into(),
-                nullable: true,
-            },
-            QueryColumn {
-                name: "cost".into(),
-                data_type: "DECIMAL(18,6)".into(),
-                nullable: false,
-            },
-            QueryColumn {
-                name: "region".into(),
-                data_type: "VARCHAR".into(),
-                nullable: true,
-            },
-            QueryColumn {
-                name: "billing_period".into(),
-                data_type: "DATE".into(),
-                nullable: false,
-            },
-        ],
-        rows: (0..25)
-            .map(|i| {
-                vec![
-                    serde_json::json!(format!("li-{:06}", i)),
-                    serde_json::json!("123456789012"),
-                    serde_json::json!(["EC2", "S3", "RDS", "Lambda", "CloudFront"][i % 5]),
-                    serde_json::json!(["BoxUsage", "DataTransfer", "Requests", "Storage"][i % 4]),
-                    serde_json::json!(format!("{:.2}", (i as f64 * 12.34) + 5.67)),
-                    serde_json::json!(["us-east-1", "eu-west-1", "ap-northeast-1"][i % 3]),
-                    serde_json::json!("2026-02-01"),
-                ]
-            })
-            .collect(),
-        row_count: 25,
-        execution_time_ms: 23,
-        error: None,
-    }
-}
-
-#[server(ExecuteQuery, "/api")]
-pub async fn execute_query(sql: String, _database: String) -> Result<QueryResult, ServerFnError> {
-    Ok(mock_execute_query(&sql).await)
-}
-
 /// SQL Editor page with multi-tab support, query history, and EXPLAIN visualization.
 #[component]
 pub fn SqlEditorPage() -> impl IntoView {
     let (query_state, set_query_state) = use_query_state();
+    let (connection_state, _) = use_connection_state();
+    let (settings, _) = use_settings_state();
     let toast = use_toast_write();
 
+    // Connection name and database recorded with each history entry
+    let active_connection = move || {
+        connection_state
+            .get_untracked()
+            .active
+            .map(|c| (Some(c.name), c.database))
+            .unwrap_or((None, "megadb".to_string()))
+    };
+
     let (show_history, set_show_history) = signal(false);
+    let (compare_plan, set_compare_plan) = signal(Option::<QueryHistoryEntry>::None);
 
     // Derive tab list from global state
     let tabs_signal = Signal::derive(move || {
@@ -130,48 +54,103 @@ pub fn SqlEditorPage() -> impl IntoView {
     });
 
     let active_signal = Signal::derive(move || query_state.get().active_tab_index);
-    let result_signal = Signal::derive(move || query_state.get().active_tab().result.clone());
+    // The active tab's result, changing only when the tab or the result
+    // itself is replaced, so typing doesn't re-render the result view
+    let active_result = Memo::new_with_compare(
+        move |_| query_state.with(|s| (s.active_tab().id, s.active_tab().result.clone())),
+        |old, new| match (old, new) {
+            (Some((old_id, old)), Some((new_id, new))) => {
+                old_id != new_id
+                    || match (old, new) {
+                        (Some(a), Some(b)) => !Arc::ptr_eq(a, b),
+                        (a, b) => a.is_some() != b.is_some(),
+                    }
+            }
+            _ => true,
+        },
+    );
+    let result_signal = Signal::derive(move || active_result.with(|(_, result)| result.clone()));
     let is_running = Signal::derive(move || query_state.get().active_tab().is_running);
+    let result_mode = Signal::derive(move || query_state.with(|s| s.active_tab().result_mode));
+    let chart_spec = Signal::derive(move || query_state.with(|s| s.active_tab().chart.clone()));
+    let pivot_spec = Signal::derive(move || query_state.with(|s| s.active_tab().pivot.clone()));
+    let compare_spec = Signal::derive(move || query_state.with(|s| s.active_tab().compare.clone()));
     let sql_content = Signal::derive(move || query_state.get().active_tab().sql.clone());
+    let result_sql =
+        Signal::derive(move || query_state.with(|s| s.active_tab().result_sql.clone()));
+    let active_database = Signal::derive(move || {
+        connection_state
+            .get()
+            .active
+            .map(|c| c.database)
+            .unwrap_or_else(|| "megadb".to_string())
+    });
 
-    // Track whether current result is an EXPLAIN plan
-    let is_explain = Signal::derive(move || {
-        query_state
+    // Table metadata of the active connection, for the plan advisor
+    let schema = LocalResource::new(move || load_connection_schema(connection_state.get().active));
+    let plan_tables = Signal::derive(move || {
+        schema
             .get()
-            .active_tab()
-            .sql
-            .trim()
-            .to_uppercase()
-            .starts_with("EXPLAIN")
+            .and_then(Result::ok)
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|db| db.schemas)
+            .flat_map(|s| s.tables)
+            .collect::<Vec<_>>()
     });
 
-    // Extract plan text for EXPLAIN viewer
-    let plan_text = Signal::derive(move || {
-        if !is_explain.get() {
+    // Shown when a tab restored after reload has a summary but no cached rows
+    let summary_note = Signal::derive(move || {
+        let state = query_state.get();
+        let tab = state.active_tab();
+        if tab.result.is_some() || tab.is_running {
             return None;
         }
-        let result = query_state.get().active_tab().result.clone()?;
-        if result.error.is_some() {
+        let summary = tab.result_summary.as_ref()?;
+        Some(match &summary.error {
+            Some(err) => format!("Last run failed: {err}"),
+            None => format!(
+                "Last run returned {} rows in {}ms. Run again to see results.",
+                summary.row_count, summary.execution_time_ms
+            ),
+        })
+    });
+
+    // Plan text when the active result is from an EXPLAIN. Keyed on the
+    // result, so the SQL it came from is read untracked
+    let plan_text = Memo::new(move |_| {
+        let result = active_result.with(|(_, result)| result.clone())?;
+        if result.error.is_some()
+            || !query_state
+                .with_untracked(|s| megaweb_types::explain::is_explain(&s.active_tab().result_sql))
+        {
             return None;
         }
-        // EXPLAIN result is typically a single column with the plan text
-        result
-            .rows
-            .first()
-            .and_then(|row| row.first())
-            .and_then(|v| v.as_str().map(String::from))
+        megaweb_types::explain::plan_text(&result)
     });
+    let is_plan = Memo::new(move |_| plan_text.with(Option::is_some));
 
-    // Execute query action
-    let execute_action = Action::new(move |sql: &String| {
-        let sql = sql.clone();
-        async move { execute_query(sql, "megadb".to_string()).await }
+    // Execute query action, through the same MegaDB proxy edits are committed
+    // with. The tab and SQL travel with the result, so each run lands in the
+    // tab that started it
+    let execute_action = Action::new_local(move |(tab_id, sql): &(uuid::Uuid, String)| {
+        let (tab_id, sql) = (*tab_id, sql.clone());
+        let (_, database) = active_connection();
+        let req = QueryRequest {
+            sql: sql.clone(),
+            database,
+            limit: Some(settings.get_untracked().row_limit),
+        };
+        async move {
+            let result = post::<QueryResult>("/proxy/megadb/query", &req).await;
+            (tab_id, sql, result)
+        }
     });
 
     // Update result and history when action completes
     Effect::new(move || {
-        if let Some(result_value) = execute_action.value().get() {
-            let sql = query_state.get_untracked().active_tab().sql.clone();
+        if let Some((tab_id, sql, result_value)) = execute_action.value().get() {
+            let (connection, database) = active_connection();
             match result_value {
                 Ok(ref r) => {
                     let result = r.clone();
@@ -193,41 +172,52 @@ pub fn SqlEditorPage() -> impl IntoView {
                     }
                     let entry = QueryHistoryEntry {
                         id: uuid::Uuid::new_v4(),
-                        sql,
-                        database: "megadb".to_string(),
+                        sql: sql.clone(),
+                        database,
                         execution_time_ms: result.execution_time_ms,
                         row_count: result.row_count,
                         executed_at: chrono::Utc::now(),
                         success: result.is_ok(),
+                        connection,
+                        error: result.error.clone(),
+                        pinned: false,
+                        has_snapshot: false,
                     };
                     set_query_state.update(|s| {
-                        s.active_tab_mut().result = Some(result);
-                        s.active_tab_mut().is_running = false;
-                        s.push_history(entry);
+                        s.push_history(entry, result.is_ok().then_some(&result));
+                        s.set_tab_result(tab_id, sql, result);
                     });
                 }
                 Err(ref e) => {
-                    let err_msg = e.to_string();
+                    let err_msg = e.clone();
                     push_toast(toast, ToastLevel::Error, &err_msg);
                     let entry = QueryHistoryEntry {
                         id: uuid::Uuid::new_v4(),
-                        sql,
-                        database: "megadb".to_string(),
+                        sql: sql.clone(),
+                        database,
                         execution_time_ms: 0,
                         row_count: 0,
                         executed_at: chrono::Utc::now(),
                         success: false,
+                        connection,
+                        error: Some(err_msg.clone()),
+                        pinned: false,
+                        has_snapshot: false,
                     };
                     set_query_state.update(|s| {
-                        s.active_tab_mut().result = Some(QueryResult {
-                            columns: vec![],
-                            rows: vec![],
-                            row_count: 0,
-                            execution_time_ms: 0,
-                            error: Some(err_msg),
-                        });
-                        s.active_tab_mut().is_running = false;
-                        s.push_history(entry);
+                        s.set_tab_result(
+                            tab_id,
+                            sql,
+                            QueryResult {
+                                columns: vec![],
+                                rows: vec![],
+                                row_count: 0,
+                                execution_time_ms: 0,
+                                error: Some(err_msg),
+                                stats: None,
+                            },
+                        );
+                        s.push_history(entry, None);
                     });
                 }
             }
@@ -236,14 +226,24 @@ pub fn SqlEditorPage() -> impl IntoView {
 
     let on_execute = Callback::new(move |sql: String| {
         set_query_state.update(|s| {
-            s.active_tab_mut().sql = sql.clone();
+            s.set_active_sql(sql.clone());
             s.active_tab_mut().is_running = true;
+            s.persist_tabs();
         });
-        execute_action.dispatch(sql);
+        let tab_id = query_state.with_untracked(|s| s.active_tab().id);
+        execute_action.dispatch((tab_id, sql));
     });
 
     let on_tab_select = Callback::new(move |i: usize| {
-        set_query_state.update(|s| s.active_tab_index = i);
+        set_query_state.update(|s| s.select_tab(i));
+    });
+
+    let on_tab_rename = Callback::new(move |(i, title): (usize, String)| {
+        set_query_state.update(|s| s.rename_tab(i, &title));
+    });
+
+    let on_tab_reorder = Callback::new(move |(from, to): (usize, usize)| {
+        set_query_state.update(|s| s.move_tab(from, to));
     });
 
     let on_tab_close = Callback::new(move |i: usize| {
@@ -255,14 +255,80 @@ pub fn SqlEditorPage() -> impl IntoView {
     });
 
     let on_sql_change = Callback::new(move |s: String| {
-        set_query_state.update(|state| state.active_tab_mut().sql = s);
+        set_query_state.update(|state| state.set_active_sql(s));
+    });
+
+    let on_cursor_change = Callback::new(move |cursor: usize| {
+        set_query_state.update(|state| state.set_active_cursor(cursor));
+    });
+
+    // Typing isn't persisted as it happens; save when the editor loses
+    // focus or the page is left
+    let on_editor_blur = Callback::new(move |_: ()| {
+        query_state.with_untracked(QueryState::persist_tabs);
     });
+    let pagehide = window_event_listener(leptos::ev::pagehide, move |_| on_editor_blur.run(()));
+    on_cleanup(move || pagehide.remove());
+
+    // Re-mount the editor only when the active tab changes or SQL is replaced
+    // from outside the editor, not on every keystroke
+    let active_tab_id = Memo::new(move |_| query_state.get().active_tab().id);
+    let (editor_epoch, set_editor_epoch) = signal(0u32);
 
     let on_history_restore = Callback::new(move |sql: String| {
-        set_query_state.update(|s| s.active_tab_mut().sql = sql);
+        set_query_state.update(|s| {
+            s.set_active_sql(sql);
+            s.persist_tabs();
+        });
+        set_editor_epoch.update(|n| *n += 1);
         set_show_history.set(false);
     });
 
+    let on_open_saved = Callback::new(move |query: SavedQuery| {
+        set_query_state.update(|s| s.open_saved_query(&query));
+        set_editor_epoch.update(|n| *n += 1);
+        set_show_history.set(false);
+    });
+
+    // Extracted JSON paths become a column of a wrapping query
+    let on_add_column = Callback::new(move |(expression, alias): (String, String)| {
+        set_query_state.update(|s| {
+            let sql = add_column_to_query(&s.active_tab().sql, &expression, &alias);
+            s.set_active_sql(sql);
+            s.persist_tabs();
+        });
+        set_editor_epoch.update(|n| *n += 1);
+        push_toast(
+            toast,
+            ToastLevel::Info,
+            format!("Added column {alias}; run the query to see it"),
+        );
+    });
+
+    let on_history_rerun = Callback::new(move |sql: String| {
+        on_history_restore.run(sql.clone());
+        on_execute.run(sql);
+    });
+
+    let on_history_snapshot = Callback::new(move |entry: QueryHistoryEntry| {
+        leptos::task::spawn_local(async move {
+            match load_history_snapshot(entry.id).await {
+                Some(result) => {
+                    // A tab of its own, so the current tab's query and result are kept
+                    let title = format!("Snapshot {}", entry.executed_at.format("%H:%M"));
+                    set_query_state.update(|s| {
+                        s.open_in_new_tab(&title, &entry.sql);
+                        let id = s.active_tab().id;
+                        s.set_tab_result(id, entry.sql.clone(), result);
+                    });
+                    set_editor_epoch.update(|n| *n += 1);
+                    set_show_history.set(false);
+                }
+                None => push_toast(toast, ToastLevel::Warning, "Result snapshot not found"),
+            }
+        });
+    });
+
     view! {
         <div class="sql-editor-page">
             <TabBar
@@ -271,15 +337,27 @@ pub fn SqlEditorPage() -> impl IntoView {
                 on_select=on_tab_select
                 on_close=on_tab_close
                 on_add=on_tab_add
+                on_rename=on_tab_rename
+                on_reorder=on_tab_reorder
             />
 
             <div class="sql-editor-content">
                 <div class="editor-pane">
-                    <CodeMirrorEditor
-                        initial_content=String::new()
-                        on_execute=on_execute
-                        on_change=on_sql_change
-                    />
+                    {move || {
+                        active_tab_id.track();
+                        editor_epoch.track();
+                        let tab = query_state.get_untracked().active_tab().clone();
+                        view! {
+                            <CodeMirrorEditor
+                                initial_content=tab.sql
+                                initial_cursor=tab.cursor
+                                on_execute=on_execute
+                                on_change=on_sql_change
+                                on_cursor=on_cursor_change
+                                on_blur=on_editor_blur
+                            />
+                        }
+                    }}
                     <div class="editor-toolbar">
                         <button
                             class="btn btn-primary"
@@ -288,6 +366,24 @@ pub fn SqlEditorPage() -> impl IntoView {
                         >
                             {move || if is_running.get() { "Running..." } else { "Run (Ctrl+Enter)" }}
                         </button>
+                        <button
+                            class="btn btn-secondary"
+                            title="Save this query under the tab title"
+                            on:click=move |_| {
+                                let tab = query_state.get_untracked().active_tab().clone();
+                                // In chart view, keep the chart even if it's still the suggested one
+                                let chart = tab.chart.clone().or_else(|| {
+                                    let result = tab.result.as_ref()?;
+                                    (tab.result_mode == ResultMode::Chart)
+                                        .then(|| ResultChartSpec::suggest(result))
+                                });
+                                let (_, database) = active_connection();
+                                set_query_state.update(|s| s.save_query(&tab.title, &tab.sql, &database, chart));
+                                push_toast(toast, ToastLevel::Success, format!("Saved \"{}\"", tab.title));
+                            }
+                        >
+                            "Save"
+                        </button>
                         <button
                             class="btn btn-secondary"
                             on:click=move |_| set_show_history.update(|v| *v = !*v)
@@ -300,14 +396,40 @@ pub fn SqlEditorPage() -> impl IntoView {
                 <QueryHistoryPanel
                     show=Signal::from(show_history)
                     on_restore=on_history_restore
+                    on_rerun=on_history_rerun
+                    on_open_saved=on_open_saved
+                    on_show_snapshot=on_history_snapshot
+                    on_compare_plan=move |entry| set_compare_plan.set(Some(entry))
                     on_close=Callback::new(move |_| set_show_history.set(false))
                 />
+                {move || compare_plan.get().map(|entry| view! {
+                    <PlanCompare before=entry on_close=move |_| set_compare_plan.set(None) />
+                }.into_any())}
 
                 <div class="results-pane">
-                    {move || if is_explain.get() && plan_text.get().is_some() {
-                        view! { <QueryPlanViewer plan_text=plan_text /> }.into_any()
+                    {move || summary_note.get().map(|note| view! {
+                        <div class="result-summary-note">{note}</div>
+                    })}
+                    {move || if is_plan.get() {
+                        view! { <QueryPlanViewer plan_text=plan_text.into() tables=plan_tables /> }.into_any()
                     } else {
-                        view! { <ResultTable result=result_signal /> }.into_any()
+                        view! {
+                            <ResultTable
+                                result=result_signal
+                                mode=result_mode
+                                on_mode=move |mode| set_query_state.update(|s| s.set_active_result_mode(mode))
+                                chart=chart_spec
+                                on_chart=move |spec| set_query_state.update(|s| s.set_active_chart(spec))
+                                pivot=pivot_spec
+                                on_pivot=move |spec| set_query_state.update(|s| s.set_active_pivot(spec))
+                                compare=compare_spec
+                                on_compare=move |spec| set_query_state.update(|s| s.set_active_compare(spec))
+                                sql=result_sql
+                                database=active_database
+                                on_add_column=on_add_column
+                                on_edited=on_execute
+                            />
+                        }.into_any()
                     }}
                 </div>
             </div>
@@ -1,7 +1,49 @@
+use std::sync::Arc;
+
 use leptos::prelude::*;
-use megaweb_types::query::{QueryHistoryEntry, QueryResult};
+use megaweb_types::chart::ResultChartSpec;
+use megaweb_types::pivot::PivotSpec;
+use megaweb_types::query::{
+    HistoryOverflow, QueryHistoryEntry, QueryResult, QueryResultSummary, SavedQuery, HISTORY_PAGE,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{kv, stores};
+
+/// How the results pane presents a tab's result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultMode {
+    #[default]
+    Grid,
+    Chart,
+    Pivot,
+    Compare,
+}
+
+/// Earlier result a tab's result is compared against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "id")]
+pub enum CompareBaseline {
+    /// The current result of another tab.
+    Tab(uuid::Uuid),
+    /// The result snapshot of a history entry.
+    History(uuid::Uuid),
+}
 
-const MAX_HISTORY: usize = 100;
+/// Comparison settings of a tab; kept across re-runs so each run can be
+/// compared with the same baseline.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompareSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<CompareBaseline>,
+    /// Columns rows are matched on; empty matches whole rows.
+    #[serde(default)]
+    pub keys: Vec<String>,
+    /// Hide unchanged rows.
+    #[serde(default)]
+    pub changed_only: bool,
+}
 
 /// State for a single query tab.
 #[derive(Debug, Clone)]
@@ -9,8 +51,21 @@ pub struct QueryTab {
     pub id: uuid::Uuid,
     pub title: String,
     pub sql: String,
-    pub result: Option<QueryResult>,
+    /// Editor cursor position as a UTF-16 offset into `sql`, as reported by the editor.
+    pub cursor: usize,
+    /// Shared so reading query state doesn't copy large result sets.
+    pub result: Option<Arc<QueryResult>>,
+    /// Summary of the last result; survives reloads even if `result` is not restored.
+    pub result_summary: Option<QueryResultSummary>,
+    /// The SQL that produced `result`. Edits and exports use it rather than
+    /// the editor text, which may have changed since the run.
+    pub result_sql: String,
     pub is_running: bool,
+    pub result_mode: ResultMode,
+    /// Chart configuration; `None` until the user adjusts the suggested chart.
+    pub chart: Option<ResultChartSpec>,
+    pub pivot: PivotSpec,
+    pub compare: CompareSpec,
 }
 
 impl QueryTab {
@@ -19,8 +74,15 @@ impl QueryTab {
             id: uuid::Uuid::new_v4(),
             title: "New Query".to_string(),
             sql: String::new(),
+            cursor: 0,
             result: None,
+            result_summary: None,
+            result_sql: String::new(),
             is_running: false,
+            result_mode: ResultMode::Grid,
+            chart: None,
+            pivot: PivotSpec::default(),
+            compare: CompareSpec::default(),
         }
     }
 
@@ -36,27 +98,118 @@ impl Default for QueryTab {
     }
 }
 
-/// Global query state: tabs, active tab, history.
+/// Persisted form of a query tab. Full results live in IndexedDB.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedTab {
+    id: uuid::Uuid,
+    title: String,
+    sql: String,
+    cursor: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result_summary: Option<QueryResultSummary>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    result_sql: String,
+    #[serde(default)]
+    result_mode: ResultMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chart: Option<ResultChartSpec>,
+    #[serde(default)]
+    pivot: PivotSpec,
+    #[serde(default)]
+    compare: CompareSpec,
+}
+
+/// Persisted tab layout, stored in localStorage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedTabs {
+    tabs: Vec<PersistedTab>,
+    active_tab_index: usize,
+}
+
+impl From<&QueryTab> for PersistedTab {
+    fn from(tab: &QueryTab) -> Self {
+        Self {
+            id: tab.id,
+            title: tab.title.clone(),
+            sql: tab.sql.clone(),
+            cursor: tab.cursor,
+            result_summary: tab.result_summary.clone(),
+            result_sql: tab.result_sql.clone(),
+            result_mode: tab.result_mode,
+            chart: tab.chart.clone(),
+            pivot: tab.pivot.clone(),
+            compare: tab.compare.clone(),
+        }
+    }
+}
+
+impl From<PersistedTab> for QueryTab {
+    fn from(tab: PersistedTab) -> Self {
+        Self {
+            id: tab.id,
+            title: tab.title,
+            sql: tab.sql,
+            cursor: tab.cursor,
+            result: None,
+            result_summary: tab.result_summary,
+            result_sql: tab.result_sql,
+            is_running: false,
+            result_mode: tab.result_mode,
+            chart: tab.chart,
+            pivot: tab.pivot,
+            compare: tab.compare,
+        }
+    }
+}
+
+/// Global query state: tabs, active tab, history, saved queries.
 #[derive(Debug, Clone)]
 pub struct QueryState {
     pub tabs: Vec<QueryTab>,
     pub active_tab_index: usize,
+    /// Loaded history, oldest first. Every entry stays in the object store;
+    /// older pages are loaded on demand.
     pub history: Vec<QueryHistoryEntry>,
+    /// How many history entries to keep loaded.
+    pub history_loaded: usize,
+    /// Whether the object store holds entries older than `history`.
+    pub older_history: bool,
+    pub saved_queries: Vec<SavedQuery>,
 }
 
 impl QueryState {
-    /// Load history from localStorage, create default tab.
+    /// Load tabs from localStorage, or create a default tab.
+    ///
+    /// History, saved queries and tab results are loaded asynchronously
+    /// from the object store by `provide_query_state`.
     pub fn load() -> Self {
-        let history =
-            crate::storage::get::<Vec<QueryHistoryEntry>>(crate::storage::keys::QUERY_HISTORY)
-                .unwrap_or_default();
+        let (tabs, active_tab_index) =
+            match crate::storage::get::<PersistedTabs>(crate::storage::keys::QUERY_TABS) {
+                Some(saved) if !saved.tabs.is_empty() => {
+                    let active = saved.active_tab_index.min(saved.tabs.len() - 1);
+                    (saved.tabs.into_iter().map(QueryTab::from).collect(), active)
+                }
+                _ => (vec![QueryTab::new().with_title("Query 1")], 0),
+            };
         Self {
-            tabs: vec![QueryTab::new().with_title("Query 1")],
-            active_tab_index: 0,
-            history,
+            tabs,
+            active_tab_index,
+            history: Vec::new(),
+            history_loaded: HISTORY_PAGE,
+            older_history: false,
+            saved_queries: Vec::new(),
         }
     }
 
+    /// Persist tab layout, SQL and result summaries to localStorage.
+    pub fn persist_tabs(&self) {
+        let saved = PersistedTabs {
+            tabs: self.tabs.iter().map(PersistedTab::from).collect(),
+            active_tab_index: self.active_tab_index,
+        };
+        crate::storage::set(crate::storage::keys::QUERY_TABS, &saved);
+    }
+
     pub fn active_tab(&self) -> &QueryTab {
         &self.tabs[self.active_tab_index]
     }
@@ -70,25 +223,234 @@ impl QueryState {
         self.tabs
             .push(QueryTab::new().with_title(format!("Query {num}")));
         self.active_tab_index = self.tabs.len() - 1;
+        self.persist_tabs();
+    }
+
+    /// Open `sql` in a new tab titled `title` and switch to it.
+    pub fn open_in_new_tab(&mut self, title: &str, sql: &str) {
+        let mut tab = QueryTab::new().with_title(title);
+        tab.sql = sql.to_string();
+        self.tabs.push(tab);
+        self.active_tab_index = self.tabs.len() - 1;
+        self.persist_tabs();
     }
 
     pub fn close_tab(&mut self, index: usize) {
-        if self.tabs.len() <= 1 {
+        if self.tabs.len() <= 1 || index >= self.tabs.len() {
+            return;
+        }
+        let closed = self.tabs.remove(index);
+        kv::delete(stores::TAB_RESULTS, &closed.id.to_string());
+        if self.active_tab_index > index || self.active_tab_index >= self.tabs.len() {
+            self.active_tab_index -= 1;
+        }
+        self.persist_tabs();
+    }
+
+    pub fn select_tab(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.active_tab_index = index;
+            self.persist_tabs();
+        }
+    }
+
+    /// Rename a tab. Blank titles are ignored.
+    pub fn rename_tab(&mut self, index: usize, title: &str) {
+        let title = title.trim();
+        if title.is_empty() {
             return;
         }
-        self.tabs.remove(index);
-        if self.active_tab_index >= self.tabs.len() {
-            self.active_tab_index = self.tabs.len() - 1;
+        if let Some(tab) = self.tabs.get_mut(index) {
+            tab.title = title.to_string();
+            self.persist_tabs();
         }
     }
 
-    /// Push a history entry, cap at MAX_HISTORY, and persist.
-    pub fn push_history(&mut self, entry: QueryHistoryEntry) {
+    /// Move a tab from one position to another, keeping the active tab selected.
+    pub fn move_tab(&mut self, from: usize, to: usize) {
+        if from == to || from >= self.tabs.len() || to >= self.tabs.len() {
+            return;
+        }
+        let active_id = self.active_tab().id;
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        self.active_tab_index = self
+            .tabs
+            .iter()
+            .position(|t| t.id == active_id)
+            .unwrap_or(0);
+        self.persist_tabs();
+    }
+
+    /// Update the active tab's SQL. This runs on every keystroke, so it
+    /// isn't persisted until [`Self::persist_tabs`] is called.
+    pub fn set_active_sql(&mut self, sql: String) {
+        let tab = self.active_tab_mut();
+        tab.cursor = tab.cursor.min(sql.encode_utf16().count());
+        tab.sql = sql;
+    }
+
+    /// Update the active tab's cursor position; not persisted, like
+    /// [`Self::set_active_sql`].
+    pub fn set_active_cursor(&mut self, cursor: usize) {
+        let tab = self.active_tab_mut();
+        tab.cursor = cursor.min(tab.sql.encode_utf16().count());
+    }
+
+    /// Switch the active tab between grid, chart, pivot and compare views.
+    pub fn set_active_result_mode(&mut self, mode: ResultMode) {
+        self.active_tab_mut().result_mode = mode;
+        self.persist_tabs();
+    }
+
+    /// Update the active tab's chart configuration.
+    pub fn set_active_chart(&mut self, chart: ResultChartSpec) {
+        self.active_tab_mut().chart = Some(chart);
+        self.persist_tabs();
+    }
+
+    /// Update the active tab's pivot layout.
+    pub fn set_active_pivot(&mut self, pivot: PivotSpec) {
+        self.active_tab_mut().pivot = pivot;
+        self.persist_tabs();
+    }
+
+    /// Update the active tab's comparison settings.
+    pub fn set_active_compare(&mut self, compare: CompareSpec) {
+        self.active_tab_mut().compare = compare;
+        self.persist_tabs();
+    }
+
+    /// Load a saved query, and its chart if it has one, into the active tab.
+    pub fn open_saved_query(&mut self, query: &SavedQuery) {
+        let tab = self.active_tab_mut();
+        tab.sql = query.sql.clone();
+        tab.cursor = tab.cursor.min(tab.sql.encode_utf16().count());
+        if let Some(chart) = &query.chart {
+            tab.chart = Some(chart.clone());
+            tab.result_mode = ResultMode::Chart;
+        }
+        self.persist_tabs();
+    }
+
+    /// Store a tab's result and the SQL it came from: the summary in
+    /// localStorage, the rows in the object store.
+    pub fn set_tab_result(&mut self, id: uuid::Uuid, sql: String, result: QueryResult) {
+        let Some(tab) = self.tabs.iter_mut().find(|t| t.id == id) else {
+            return;
+        };
+        kv::save(stores::TAB_RESULTS, &id.to_string(), &result);
+        tab.result_summary = Some(result.summary());
+        tab.result_sql = sql;
+        tab.result = Some(Arc::new(result));
+        tab.is_running = false;
+        self.persist_tabs();
+    }
+
+    /// Push a history entry and persist it, with an optional result snapshot.
+    /// Results too large for a snapshot are left out, and the loaded history
+    /// is then trimmed to its limits.
+    ///
+    /// Only the changed entries are written, not the whole history.
+    pub fn push_history(&mut self, mut entry: QueryHistoryEntry, snapshot: Option<&QueryResult>) {
+        if let Some(result) = snapshot.filter(|r| r.fits_snapshot()) {
+            kv::save(stores::HISTORY_RESULTS, &entry.id.to_string(), result);
+            entry.has_snapshot = true;
+        }
+        kv::save(stores::HISTORY, &entry.storage_key(), &entry);
         self.history.push(entry);
-        if self.history.len() > MAX_HISTORY {
-            self.history.remove(0);
+        self.prune_history();
+    }
+
+    /// Unload the oldest entries beyond `history_loaded`, and evict
+    /// snapshots beyond the snapshot limit. Entries themselves are kept.
+    fn prune_history(&mut self) {
+        let overflow = HistoryOverflow::of(&self.history, self.history_loaded);
+        if overflow.is_empty() {
+            return;
+        }
+        for entry in self
+            .history
+            .iter_mut()
+            .filter(|e| overflow.evicted.contains(&e.id))
+        {
+            entry.has_snapshot = false;
+            kv::save(stores::HISTORY, &entry.storage_key(), entry);
+            kv::delete(stores::HISTORY_RESULTS, &entry.id.to_string());
+        }
+        if !overflow.unloaded.is_empty() {
+            self.history.retain(|e| !overflow.unloaded.contains(&e.id));
+            self.older_history = true;
+        }
+    }
+
+    /// Add a page of `older` entries loaded from the object store before
+    /// the loaded history.
+    pub fn prepend_history(&mut self, mut older: Vec<QueryHistoryEntry>, more: bool) {
+        older.retain(|e| !self.history.iter().any(|h| h.id == e.id));
+        self.history_loaded += older.len();
+        older.append(&mut self.history);
+        self.history = older;
+        self.older_history = more;
+    }
+
+    /// Pin or unpin a history entry.
+    pub fn toggle_history_pin(&mut self, id: uuid::Uuid) {
+        if let Some(entry) = self.history.iter_mut().find(|e| e.id == id) {
+            entry.pinned = !entry.pinned;
+            kv::save(stores::HISTORY, &entry.storage_key(), entry);
         }
-        crate::storage::set(crate::storage::keys::QUERY_HISTORY, &self.history);
+    }
+
+    /// Delete a history entry and its result snapshot.
+    pub fn delete_history_entry(&mut self, id: uuid::Uuid) {
+        let Some(i) = self.history.iter().position(|e| e.id == id) else {
+            return;
+        };
+        let entry = self.history.remove(i);
+        kv::delete(stores::HISTORY, &entry.storage_key());
+        kv::delete(stores::HISTORY_RESULTS, &id.to_string());
+    }
+
+    /// Save SQL and its chart configuration under a name, replacing any
+    /// saved query with the same name.
+    pub fn save_query(
+        &mut self,
+        name: &str,
+        sql: &str,
+        database: &str,
+        chart: Option<ResultChartSpec>,
+    ) {
+        let now = chrono::Utc::now();
+        let query = match self.saved_queries.iter_mut().find(|q| q.name == name) {
+            Some(existing) => {
+                existing.sql = sql.to_string();
+                existing.database = database.to_string();
+                existing.updated_at = now;
+                existing.chart = chart;
+                existing.clone()
+            }
+            None => {
+                let query = SavedQuery {
+                    id: uuid::Uuid::new_v4(),
+                    name: name.to_string(),
+                    sql: sql.to_string(),
+                    database: database.to_string(),
+                    created_at: now,
+                    updated_at: now,
+                    chart,
+                };
+                self.saved_queries.push(query.clone());
+                query
+            }
+        };
+        kv::save(stores::SAVED_QUERIES, &query.id.to_string(), &query);
+    }
+
+    /// Delete a saved query by ID.
+    pub fn delete_saved_query(&mut self, id: uuid::Uuid) {
+        self.saved_queries.retain(|q| q.id != id);
+        kv::delete(stores::SAVED_QUERIES, &id.to_string());
     }
 }
 
@@ -101,9 +463,93 @@ impl Default for QueryState {
 /// Provide query state as a context.
 pub fn provide_query_state() {
     let state = signal(QueryState::load());
+    restore_from_store(state.0, state.1);
     provide_context(state);
 }
 
+/// Load history, saved queries and tab results in the background.
+/// No-op on the server.
+fn restore_from_store(state: ReadSignal<QueryState>, set_state: WriteSignal<QueryState>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let ids: Vec<uuid::Uuid> = state.get_untracked().tabs.iter().map(|t| t.id).collect();
+        leptos::task::spawn_local(async move {
+            migrate_legacy_history().await;
+            migrate_history_keys().await;
+
+            let (history, older) =
+                kv::load_page::<QueryHistoryEntry>(stores::HISTORY, None, HISTORY_PAGE).await;
+            let mut saved = kv::load_all::<SavedQuery>(stores::SAVED_QUERIES).await;
+            saved.sort_by(|a, b| a.name.cmp(&b.name));
+            set_state.update(|s| {
+                // Keep anything recorded while loading
+                s.prepend_history(history, older);
+                s.history_loaded = HISTORY_PAGE;
+                s.prune_history();
+                s.saved_queries = saved;
+            });
+
+            for id in ids {
+                let key = id.to_string();
+                let Some(result) = kv::load::<QueryResult>(stores::TAB_RESULTS, &key).await else {
+                    continue;
+                };
+                set_state.update(|s| {
+                    if let Some(tab) = s.tabs.iter_mut().find(|t| t.id == id && t.result.is_none())
+                    {
+                        tab.result = Some(Arc::new(result));
+                    }
+                });
+            }
+        });
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = (state, set_state);
+    }
+}
+
+/// Move history from the legacy localStorage array into the object store.
+#[cfg(target_arch = "wasm32")]
+async fn migrate_legacy_history() {
+    use crate::storage::keys;
+
+    let Some(legacy) = crate::storage::get::<Vec<QueryHistoryEntry>>(keys::QUERY_HISTORY) else {
+        return;
+    };
+    for entry in &legacy {
+        kv::put(stores::HISTORY, &entry.storage_key(), entry).await;
+    }
+    crate::storage::remove(keys::QUERY_HISTORY);
+}
+
+/// Re-key history entries stored under their id to
+/// [`QueryHistoryEntry::storage_key`], so the store is ordered by time.
+#[cfg(target_arch = "wasm32")]
+async fn migrate_history_keys() {
+    for key in kv::keys(stores::HISTORY).await {
+        if key.find('-') == Some(13) {
+            continue;
+        }
+        if let Some(entry) = kv::load::<QueryHistoryEntry>(stores::HISTORY, &key).await {
+            kv::put(stores::HISTORY, &entry.storage_key(), &entry).await;
+        }
+        kv::remove(stores::HISTORY, &key).await;
+    }
+}
+
+/// Load the page of history entries before the oldest loaded one.
+pub async fn load_older_history(set_state: WriteSignal<QueryState>, before: String) {
+    let (older, more) =
+        kv::load_page::<QueryHistoryEntry>(stores::HISTORY, Some(&before), HISTORY_PAGE).await;
+    set_state.update(|s| s.prepend_history(older, more));
+}
+
+/// Load the result snapshot stored with a history entry.
+pub async fn load_history_snapshot(id: uuid::Uuid) -> Option<QueryResult> {
+    kv::load(stores::HISTORY_RESULTS, &id.to_string()).await
+}
+
 /// Use query state from context.
 pub fn use_query_state() -> (ReadSignal<QueryState>, WriteSignal<QueryState>) {
     expect_context::<(ReadSignal<QueryState>, WriteSignal<QueryState>)>()
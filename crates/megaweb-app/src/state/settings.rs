@@ -1,4 +1,7 @@
+use chrono::Offset;
 use leptos::prelude::*;
+use megaweb_types::export::CsvOptions;
+use megaweb_types::query::{NumberLocale, TimeZoneDisplay, ValueFormat};
 use serde::{Deserialize, Serialize};
 
 /// Application-wide settings, persisted to localStorage.
@@ -8,6 +11,14 @@ pub struct AppSettings {
     pub font_size: u32,
     pub row_limit: u64,
     pub autocomplete: bool,
+    /// CSV dialect used by result exports.
+    #[serde(default)]
+    pub csv: CsvOptions,
+    /// Number style in the result grid; `None` follows the browser language.
+    #[serde(default)]
+    pub number_locale: Option<NumberLocale>,
+    #[serde(default)]
+    pub time_zone: TimeZoneDisplay,
 }
 
 impl Default for AppSettings {
@@ -17,6 +28,9 @@ impl Default for AppSettings {
             font_size: 14,
             row_limit: 1000,
             autocomplete: true,
+            csv: CsvOptions::default(),
+            number_locale: None,
+            time_zone: TimeZoneDisplay::default(),
         }
     }
 }
@@ -31,6 +45,45 @@ impl AppSettings {
     pub fn save(&self) {
         crate::storage::set(crate::storage::keys::SETTINGS, self);
     }
+
+    /// How result values are displayed, with browser defaults resolved.
+    pub fn value_format(&self) -> ValueFormat {
+        ValueFormat {
+            locale: self
+                .number_locale
+                .unwrap_or_else(|| NumberLocale::from_language_tag(&browser_language())),
+            time_zone: match self.time_zone {
+                TimeZoneDisplay::Local => Some(local_offset()),
+                TimeZoneDisplay::Utc => Some(chrono::Utc.fix()),
+                TimeZoneDisplay::Original => None,
+            },
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn browser_language() -> String {
+    web_sys::window()
+        .and_then(|w| w.navigator().language())
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn browser_language() -> String {
+    String::new()
+}
+
+/// The browser's current UTC offset.
+#[cfg(target_arch = "wasm32")]
+fn local_offset() -> chrono::FixedOffset {
+    // Minutes behind UTC, so east of UTC is negative
+    let minutes = js_sys::Date::new_0().get_timezone_offset() as i32;
+    chrono::FixedOffset::west_opt(minutes * 60).unwrap_or(chrono::Utc.fix())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn local_offset() -> chrono::FixedOffset {
+    chrono::Utc.fix()
 }
 
 /// Provide settings state as a context.
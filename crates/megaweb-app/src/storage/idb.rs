@@ -0,0 +1,137 @@
+//! IndexedDB backend for [`super::kv`].
+
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+use super::kv::KvStore;
+
+const DB_NAME: &str = "megaweb";
+
+/// Database schema version. Bump when adding object stores to
+/// [`super::stores::ALL`]; missing stores are created on upgrade.
+///
+/// 1: tabResults
+/// 2: history, savedQueries
+/// 3: historyResults
+const DB_VERSION: u32 = 3;
+
+thread_local! {
+    static DB: RefCell<Option<IdbDatabase>> = const { RefCell::new(None) };
+}
+
+/// Object stores in the `megaweb` IndexedDB database.
+#[derive(Debug, Clone)]
+pub struct IdbStore {
+    db: IdbDatabase,
+}
+
+impl IdbStore {
+    /// Open (and upgrade if needed) the database, reusing a cached handle.
+    /// Returns `None` where IndexedDB is unavailable, e.g. some private modes.
+    pub async fn open() -> Option<Self> {
+        if let Some(db) = DB.with(|db| db.borrow().clone()) {
+            return Some(Self { db });
+        }
+        let factory = web_sys::window()?.indexed_db().ok().flatten()?;
+        let request: IdbOpenDbRequest = factory.open_with_u32(DB_NAME, DB_VERSION).ok()?;
+
+        let req = request.clone();
+        let on_upgrade = Closure::<dyn Fn(web_sys::Event)>::new(move |_: web_sys::Event| {
+            let Ok(result) = req.result() else { return };
+            let db: IdbDatabase = result.unchecked_into();
+            for store in super::stores::ALL {
+                if !db.object_store_names().contains(store) {
+                    let _ = db.create_object_store(store);
+                }
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+
+        let db: IdbDatabase = await_request(&request).await.ok()?.unchecked_into();
+        drop(on_upgrade);
+        DB.with(|cell| *cell.borrow_mut() = Some(db.clone()));
+        Some(Self { db })
+    }
+
+    fn object_store(&self, name: &str, mode: IdbTransactionMode) -> Option<IdbObjectStore> {
+        let tx = self.db.transaction_with_str_and_mode(name, mode).ok()?;
+        tx.object_store(name).ok()
+    }
+}
+
+impl KvStore for IdbStore {
+    async fn get(&self, store: &str, key: &str) -> Option<String> {
+        let store = self.object_store(store, IdbTransactionMode::Readonly)?;
+        let request = store.get(&JsValue::from_str(key)).ok()?;
+        await_request(&request).await.ok()?.as_string()
+    }
+
+    async fn get_all(&self, store: &str) -> Vec<String> {
+        let Some(store) = self.object_store(store, IdbTransactionMode::Readonly) else {
+            return Vec::new();
+        };
+        let Ok(request) = store.get_all() else {
+            return Vec::new();
+        };
+        match await_request(&request).await {
+            Ok(values) => js_sys::Array::from(&values)
+                .iter()
+                .filter_map(|v| v.as_string())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    async fn keys(&self, store: &str) -> Vec<String> {
+        let Some(store) = self.object_store(store, IdbTransactionMode::Readonly) else {
+            return Vec::new();
+        };
+        let Ok(request) = store.get_all_keys() else {
+            return Vec::new();
+        };
+        match await_request(&request).await {
+            Ok(keys) => js_sys::Array::from(&keys)
+                .iter()
+                .filter_map(|k| k.as_string())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    async fn put(&self, store: &str, key: &str, value: String) {
+        if let Some(store) = self.object_store(store, IdbTransactionMode::Readwrite) {
+            if let Ok(request) =
+                store.put_with_key(&JsValue::from_str(&value), &JsValue::from_str(key))
+            {
+                let _ = await_request(&request).await;
+            }
+        }
+    }
+
+    async fn delete(&self, store: &str, key: &str) {
+        if let Some(store) = self.object_store(store, IdbTransactionMode::Readwrite) {
+            if let Ok(request) = store.delete(&JsValue::from_str(key)) {
+                let _ = await_request(&request).await;
+            }
+        }
+    }
+}
+
+/// Resolve an `IdbRequest` into its result value.
+async fn await_request(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let req = request.clone();
+        let on_success = Closure::once_into_js(move |_: web_sys::Event| {
+            let _ = resolve.call1(&JsValue::NULL, &req.result().unwrap_or(JsValue::UNDEFINED));
+        });
+        let on_error = Closure::once_into_js(move |_: web_sys::Event| {
+            let _ = reject.call0(&JsValue::NULL);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await
+}
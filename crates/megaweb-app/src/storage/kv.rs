@@ -0,0 +1,288 @@
+//! Async key-value object stores with versioned records.
+//!
+//! Records are JSON envelopes `{"v": <version>, "data": <value>}`. When a
+//! stored type changes shape, bump its [`Stored::VERSION`] and upgrade old
+//! records in [`Stored::migrate`]; they are rewritten lazily on next save.
+
+use std::future::Future;
+
+use megaweb_types::query::{QueryHistoryEntry, QueryResult, SavedQuery};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[cfg(target_arch = "wasm32")]
+use super::idb::IdbStore;
+use super::local::LocalStore;
+
+/// A string key-value store partitioned into named object stores.
+pub trait KvStore {
+    fn get(&self, store: &str, key: &str) -> impl Future<Output = Option<String>>;
+    /// All values in a store, in key order.
+    fn get_all(&self, store: &str) -> impl Future<Output = Vec<String>>;
+    /// All keys in a store, in order.
+    fn keys(&self, store: &str) -> impl Future<Output = Vec<String>>;
+    fn put(&self, store: &str, key: &str, value: String) -> impl Future<Output = ()>;
+    fn delete(&self, store: &str, key: &str) -> impl Future<Output = ()>;
+}
+
+/// The storage backend in use: IndexedDB where available, else localStorage.
+#[derive(Debug, Clone)]
+pub enum Backend {
+    #[cfg(target_arch = "wasm32")]
+    IndexedDb(IdbStore),
+    Local(LocalStore),
+}
+
+impl Backend {
+    /// Open IndexedDB, falling back to localStorage if it is unavailable.
+    pub async fn open() -> Self {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(store) = IdbStore::open().await {
+            return Backend::IndexedDb(store);
+        }
+        Backend::Local(LocalStore)
+    }
+}
+
+impl KvStore for Backend {
+    async fn get(&self, store: &str, key: &str) -> Option<String> {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Backend::IndexedDb(s) => s.get(store, key).await,
+            Backend::Local(s) => s.get(store, key).await,
+        }
+    }
+
+    async fn get_all(&self, store: &str) -> Vec<String> {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Backend::IndexedDb(s) => s.get_all(store).await,
+            Backend::Local(s) => s.get_all(store).await,
+        }
+    }
+
+    async fn keys(&self, store: &str) -> Vec<String> {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Backend::IndexedDb(s) => s.keys(store).await,
+            Backend::Local(s) => s.keys(store).await,
+        }
+    }
+
+    async fn put(&self, store: &str, key: &str, value: String) {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Backend::IndexedDb(s) => s.put(store, key, value).await,
+            Backend::Local(s) => s.put(store, key, value).await,
+        }
+    }
+
+    async fn delete(&self, store: &str, key: &str) {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Backend::IndexedDb(s) => s.delete(store, key).await,
+            Backend::Local(s) => s.delete(store, key).await,
+        }
+    }
+}
+
+/// A type persisted through [`KvStore`], with a schema version for migrations.
+pub trait Stored: Serialize + DeserializeOwned {
+    /// Current schema version. Bump whenever the serialized shape changes.
+    const VERSION: u32;
+
+    /// Upgrade a value serialized at an older `version` to the current shape.
+    /// Returning `None` drops the record. Records written before versioning
+    /// was introduced have version 0.
+    fn migrate(version: u32, value: serde_json::Value) -> Option<serde_json::Value> {
+        let _ = version;
+        Some(value)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    v: u32,
+    data: serde_json::Value,
+}
+
+fn encode<T: Stored>(value: &T) -> Option<String> {
+    let data = serde_json::to_value(value).ok()?;
+    serde_json::to_string(&Envelope {
+        v: T::VERSION,
+        data,
+    })
+    .ok()
+}
+
+fn decode<T: Stored>(raw: &str) -> Option<T> {
+    let json: serde_json::Value = serde_json::from_str(raw).ok()?;
+    let (version, data) = match serde_json::from_value::<Envelope>(json.clone()) {
+        Ok(env) => (env.v, env.data),
+        Err(_) => (0, json),
+    };
+    let data = match version {
+        v if v == T::VERSION => data,
+        // Written by a newer client; don't guess at its shape.
+        v if v > T::VERSION => return None,
+        v => T::migrate(v, data)?,
+    };
+    serde_json::from_value(data).ok()
+}
+
+/// Read a record. Returns `None` if missing or not decodable.
+pub async fn load<T: Stored>(store: &str, key: &str) -> Option<T> {
+    let raw = Backend::open().await.get(store, key).await?;
+    decode(&raw)
+}
+
+/// Read every decodable record in a store, in key order.
+pub async fn load_all<T: Stored>(store: &str) -> Vec<T> {
+    Backend::open()
+        .await
+        .get_all(store)
+        .await
+        .iter()
+        .filter_map(|raw| decode(raw))
+        .collect()
+}
+
+/// Read the last `limit` decodable records with keys before `before`, or
+/// of the whole store, in key order. Also returns whether records with
+/// earlier keys remain.
+pub async fn load_page<T: Stored>(
+    store: &str,
+    before: Option<&str>,
+    limit: usize,
+) -> (Vec<T>, bool) {
+    let backend = Backend::open().await;
+    let keys = backend.keys(store).await;
+    let end = before.map_or(keys.len(), |b| keys.partition_point(|k| k.as_str() < b));
+    let start = end.saturating_sub(limit);
+    let mut values = Vec::with_capacity(end - start);
+    for key in &keys[start..end] {
+        if let Some(value) = backend.get(store, key).await.and_then(|raw| decode(&raw)) {
+            values.push(value);
+        }
+    }
+    (values, start > 0)
+}
+
+/// All keys in a store, in order.
+pub async fn keys(store: &str) -> Vec<String> {
+    Backend::open().await.keys(store).await
+}
+
+/// Write a record. Returns once the write has completed.
+pub async fn put<T: Stored>(store: &str, key: &str, value: &T) {
+    if let Some(raw) = encode(value) {
+        Backend::open().await.put(store, key, raw).await;
+    }
+}
+
+/// Delete a record. Returns once the delete has completed.
+pub async fn remove(store: &str, key: &str) {
+    Backend::open().await.delete(store, key).await;
+}
+
+/// Write a record in the background. No-op on the server.
+pub fn save<T: Stored + 'static>(store: &'static str, key: &str, value: &T) {
+    let Some(raw) = encode(value) else { return };
+    let key = key.to_string();
+    spawn(async move { Backend::open().await.put(store, &key, raw).await });
+}
+
+/// Delete a record in the background. No-op on the server.
+pub fn delete(store: &'static str, key: &str) {
+    let key = key.to_string();
+    spawn(async move { Backend::open().await.delete(store, &key).await });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn(fut: impl Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(fut);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn(_fut: impl Future<Output = ()> + 'static) {
+    // Nothing is persisted during SSR.
+}
+
+// --- Stored types ---
+
+impl Stored for QueryResult {
+    const VERSION: u32 = 1;
+}
+
+impl Stored for QueryHistoryEntry {
+    const VERSION: u32 = 1;
+}
+
+impl Stored for SavedQuery {
+    const VERSION: u32 = 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// v0 stored a bare name; v1 renamed it to `label`; v2 added `pinned`.
+    /// Records from v0 are dropped when the name is empty.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Note {
+        label: String,
+        pinned: bool,
+    }
+
+    impl Stored for Note {
+        const VERSION: u32 = 2;
+
+        fn migrate(version: u32, value: serde_json::Value) -> Option<serde_json::Value> {
+            match version {
+                0 => {
+                    let name = value.as_str().filter(|n| !n.is_empty())?;
+                    Some(json!({ "label": name, "pinned": false }))
+                }
+                1 => Some(json!({ "label": value["label"], "pinned": false })),
+                _ => None,
+            }
+        }
+    }
+
+    fn note(label: &str, pinned: bool) -> Note {
+        Note {
+            label: label.to_string(),
+            pinned,
+        }
+    }
+
+    #[test]
+    fn round_trips_the_current_version() {
+        let raw = encode(&note("prod", true)).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(json["v"], json!(2));
+        assert_eq!(decode::<Note>(&raw), Some(note("prod", true)));
+    }
+
+    #[test]
+    fn migrates_older_records() {
+        // Written before records were versioned: no envelope at all
+        assert_eq!(decode::<Note>(r#""prod""#), Some(note("prod", false)));
+        assert_eq!(
+            decode::<Note>(r#"{"v":1,"data":{"label":"staging"}}"#),
+            Some(note("staging", false))
+        );
+    }
+
+    #[test]
+    fn drops_records_it_cannot_read() {
+        // Written by a newer client
+        assert_eq!(
+            decode::<Note>(r#"{"v":3,"data":{"label":"prod","pinned":true}}"#),
+            None
+        );
+        // `migrate` gave up on it
+        assert_eq!(decode::<Note>(r#""""#), None);
+        assert_eq!(decode::<Note>("not json"), None);
+    }
+}
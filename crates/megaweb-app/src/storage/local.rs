@@ -0,0 +1,58 @@
+//! localStorage fallback backend for [`super::kv`].
+//!
+//! Each record lives under `megaweb.<store>.<key>`, and `megaweb.<store>`
+//! holds the list of keys so a store can be listed without scanning.
+
+use super::kv::KvStore;
+
+/// Object stores emulated on top of localStorage. No-op on the server.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalStore;
+
+fn index_key(store: &str) -> String {
+    format!("megaweb.{store}")
+}
+
+fn record_key(store: &str, key: &str) -> String {
+    format!("megaweb.{store}.{key}")
+}
+
+fn read_index(store: &str) -> Vec<String> {
+    super::get::<Vec<String>>(&index_key(store)).unwrap_or_default()
+}
+
+impl KvStore for LocalStore {
+    async fn get(&self, store: &str, key: &str) -> Option<String> {
+        super::get_raw(&record_key(store, key))
+    }
+
+    async fn get_all(&self, store: &str) -> Vec<String> {
+        let mut keys = read_index(store);
+        keys.sort();
+        keys.iter()
+            .filter_map(|key| super::get_raw(&record_key(store, key)))
+            .collect()
+    }
+
+    async fn keys(&self, store: &str) -> Vec<String> {
+        let mut keys = read_index(store);
+        keys.sort();
+        keys
+    }
+
+    async fn put(&self, store: &str, key: &str, value: String) {
+        super::set_raw(&record_key(store, key), &value);
+        let mut keys = read_index(store);
+        if !keys.iter().any(|k| k == key) {
+            keys.push(key.to_string());
+            super::set(&index_key(store), &keys);
+        }
+    }
+
+    async fn delete(&self, store: &str, key: &str) {
+        super::remove_raw(&record_key(store, key));
+        let mut keys = read_index(store);
+        keys.retain(|k| k != key);
+        super::set(&index_key(store), &keys);
+    }
+}
@@ -1,3 +1,15 @@
+//! Client-side persistence.
+//!
+//! Small settings go through the synchronous localStorage helpers below.
+//! Larger or growing data (history, saved queries, cached results) goes
+//! through the async object-store layer in [`kv`], backed by IndexedDB with
+//! a localStorage fallback.
+
+#[cfg(target_arch = "wasm32")]
+mod idb;
+pub mod kv;
+mod local;
+
 use serde::{de::DeserializeOwned, Serialize};
 
 /// Read a JSON-deserialized value from localStorage.
@@ -57,7 +69,23 @@ fn remove_raw(_key: &str) {}
 /// Centralized storage key constants.
 pub mod keys {
     pub const SETTINGS: &str = "megaweb.settings";
+    /// Legacy history array; migrated into [`super::stores::HISTORY`] on load.
     pub const QUERY_HISTORY: &str = "megaweb.queryHistory";
     pub const CONNECTIONS: &str = "megaweb.connections";
     pub const SIDEBAR_COLLAPSED: &str = "megaweb.sidebarCollapsed";
+    pub const QUERY_TABS: &str = "megaweb.queryTabs";
+}
+
+/// Object store names used by [`kv`].
+pub mod stores {
+    /// Last result of each query tab, keyed by tab ID.
+    pub const TAB_RESULTS: &str = "tabResults";
+    /// Query history entries, keyed by entry ID.
+    pub const HISTORY: &str = "history";
+    /// Saved queries, keyed by query ID.
+    pub const SAVED_QUERIES: &str = "savedQueries";
+    /// Result snapshots of history entries, keyed by entry ID.
+    pub const HISTORY_RESULTS: &str = "historyResults";
+
+    pub const ALL: &[&str] = &[TAB_RESULTS, HISTORY, SAVED_QUERIES, HISTORY_RESULTS];
 }
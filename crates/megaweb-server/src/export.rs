@@ -0,0 +1,334 @@
+//! Server-side result exports.
+//!
+//! The query is re-run without the client's row limit, so exports contain
+//! the full result rather than the rows loaded in the browser. Only
+//! read-only queries are re-run, and results are capped at
+//! [`MAX_EXPORT_ROWS`] rows since they are encoded in memory. Text formats
+//! use the shared encoders in `megaweb_types::export`; XLSX, Parquet and
+//! Arrow IPC are encoded here.
+
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    BooleanBuilder, Date32Builder, Decimal128Builder, Float32Builder, Float64Builder, Int16Builder,
+    Int32Builder, Int64Builder, Int8Builder, StringBuilder, TimestampMicrosecondBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use axum::{
+    extract::Extension,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use megaweb_types::edit::is_read_only;
+use megaweb_types::export::{encode_text, ExportFormat, ExportRequest, MAX_EXPORT_ROWS};
+use megaweb_types::query::{QueryColumn, QueryRequest, QueryResult, SqlType, TypedValue};
+use megaweb_types::result_view::{cell_number, cell_text};
+use serde_json::Value;
+
+use crate::config::AppConfig;
+use crate::proxy::megadb::send_query;
+use crate::proxy::{api_error, ApiError};
+
+/// Field metadata key holding the original SQL type of a column.
+pub(crate) const DATA_TYPE_METADATA: &str = "megaweb.data_type";
+/// Rows per worksheet in XLSX, including the header row.
+const XLSX_MAX_ROWS: usize = 1_048_576;
+
+/// POST /export: re-run the query and return the encoded file.
+pub async fn export_handler(
+    Extension(config): Extension<AppConfig>,
+    Json(req): Json<ExportRequest>,
+) -> Result<Response, ApiError> {
+    if !is_read_only(&req.sql) {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "Only a single read-only SELECT can be re-run for an export".to_string(),
+        ));
+    }
+    // One row over the cap tells a result at the cap from a larger one
+    let query = QueryRequest {
+        sql: req.sql.clone(),
+        database: req.database.clone(),
+        limit: Some(MAX_EXPORT_ROWS + 1),
+    };
+    let result = send_query(&config, &query)
+        .await?
+        .map_err(|(_, e)| api_error(StatusCode::BAD_GATEWAY, format!("Query failed: {e}")))?;
+    if let Some(err) = result.error {
+        return Err(api_error(StatusCode::BAD_REQUEST, err));
+    }
+    if result.rows.len() as u64 > MAX_EXPORT_ROWS || result.row_count > MAX_EXPORT_ROWS {
+        return Err(api_error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Exports are limited to {MAX_EXPORT_ROWS} rows; add a filter or a LIMIT"),
+        ));
+    }
+
+    let bytes = match encode_text(&result, req.format, &req.csv, req.table.as_deref()) {
+        Some(text) => text.into_bytes(),
+        None => encode_binary(&result, req.format)
+            .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?,
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, req.format.mime_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", req.format.filename()),
+            ),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+fn encode_binary(result: &QueryResult, format: ExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Xlsx => to_xlsx(result),
+        ExportFormat::Parquet => {
+            let batch = to_record_batch(result).map_err(|e| e.to_string())?;
+            let mut buf = Vec::new();
+            let mut writer = parquet::arrow::ArrowWriter::try_new(&mut buf, batch.schema(), None)
+                .map_err(|e| e.to_string())?;
+            writer.write(&batch).map_err(|e| e.to_string())?;
+            writer.close().map_err(|e| e.to_string())?;
+            Ok(buf)
+        }
+        ExportFormat::Arrow => {
+            let batch = to_record_batch(result).map_err(|e| e.to_string())?;
+            let mut buf = Vec::new();
+            let mut writer = arrow_ipc::writer::FileWriter::try_new(&mut buf, &batch.schema())
+                .map_err(|e| e.to_string())?;
+            writer.write(&batch).map_err(|e| e.to_string())?;
+            writer.finish().map_err(|e| e.to_string())?;
+            drop(writer);
+            Ok(buf)
+        }
+        other => Err(format!("{} is not a binary format", other.label())),
+    }
+}
+
+/// Map a column type to an Arrow type. Types without a direct Arrow
+/// equivalent become strings.
+fn arrow_type(ty: &SqlType) -> DataType {
+    match ty {
+        SqlType::Integer { bits, unsigned } => match (bits, unsigned) {
+            (8, false) => DataType::Int8,
+            (8, true) | (16, false) => DataType::Int16,
+            (16, true) | (32, false) => DataType::Int32,
+            (32, true) | (64, false) => DataType::Int64,
+            _ => DataType::Decimal128(38, 0),
+        },
+        SqlType::Float { bits: 32 } => DataType::Float32,
+        SqlType::Float { .. } => DataType::Float64,
+        SqlType::Decimal { precision, scale } => {
+            // A bare DECIMAL is stored as DECIMAL(38, 10)
+            let precision = precision.unwrap_or(38);
+            let scale = scale.unwrap_or(10);
+            if precision <= 38 && scale <= precision {
+                DataType::Decimal128(precision, scale as i8)
+            } else {
+                DataType::Float64
+            }
+        }
+        SqlType::Boolean => DataType::Boolean,
+        SqlType::Date => DataType::Date32,
+        SqlType::Timestamp { tz: true } => {
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+        }
+        SqlType::Timestamp { tz: false } => DataType::Timestamp(TimeUnit::Microsecond, None),
+        _ => DataType::Utf8,
+    }
+}
+
+/// Convert a result to a single Arrow record batch. Each field keeps the
+/// original SQL type in its metadata. A column with cells that don't parse
+/// as its type is written as strings instead, so no value is lost.
+fn to_record_batch(result: &QueryResult) -> Result<RecordBatch, arrow_schema::ArrowError> {
+    let mut fields = Vec::with_capacity(result.columns.len());
+    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(result.columns.len());
+    for (i, col) in result.columns.iter().enumerate() {
+        let ty = SqlType::parse(&col.data_type);
+        let cells: Vec<&Value> = result
+            .rows
+            .iter()
+            .map(|row| row.get(i).unwrap_or(&Value::Null))
+            .collect();
+        let nulls = cells.iter().filter(|v| v.is_null()).count();
+        let mut data_type = arrow_type(&ty);
+        let mut array = build_array(&data_type, &ty, &cells)?;
+        if array.null_count() > nulls {
+            data_type = DataType::Utf8;
+            array = build_array(&data_type, &ty, &cells)?;
+        }
+        arrays.push(array);
+        fields.push(field(col, data_type));
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+}
+
+fn field(col: &QueryColumn, data_type: DataType) -> Field {
+    Field::new(&col.name, data_type, true).with_metadata(std::collections::HashMap::from([(
+        DATA_TYPE_METADATA.to_string(),
+        col.data_type.clone(),
+    )]))
+}
+
+fn build_array(
+    data_type: &DataType,
+    ty: &SqlType,
+    cells: &[&Value],
+) -> Result<ArrayRef, arrow_schema::ArrowError> {
+    let typed = || cells.iter().map(|v| TypedValue::decode(v, ty));
+    macro_rules! integer {
+        ($builder:ty, $t:ty) => {{
+            let mut b = <$builder>::new();
+            for v in typed() {
+                b.append_option(match v {
+                    TypedValue::Int(n) => <$t>::try_from(n).ok(),
+                    _ => None,
+                });
+            }
+            Arc::new(b.finish()) as ArrayRef
+        }};
+    }
+    macro_rules! float {
+        ($builder:ty, $t:ty) => {{
+            let mut b = <$builder>::new();
+            for v in typed() {
+                b.append_option(match v {
+                    TypedValue::Float(n) => Some(n as $t),
+                    TypedValue::Int(n) => Some(n as $t),
+                    _ => None,
+                });
+            }
+            Arc::new(b.finish()) as ArrayRef
+        }};
+    }
+    Ok(match data_type {
+        DataType::Int8 => integer!(Int8Builder, i8),
+        DataType::Int16 => integer!(Int16Builder, i16),
+        DataType::Int32 => integer!(Int32Builder, i32),
+        DataType::Int64 => integer!(Int64Builder, i64),
+        DataType::Float32 => float!(Float32Builder, f32),
+        DataType::Float64 => float!(Float64Builder, f64),
+        DataType::Decimal128(precision, scale) => {
+            let mut b = Decimal128Builder::new();
+            for v in typed() {
+                b.append_option(v.to_unscaled(*scale as u8));
+            }
+            Arc::new(b.finish().with_precision_and_scale(*precision, *scale)?)
+        }
+        DataType::Boolean => {
+            let mut b = BooleanBuilder::new();
+            for v in typed() {
+                b.append_option(match v {
+                    TypedValue::Bool(x) => Some(x),
+                    _ => None,
+                });
+            }
+            Arc::new(b.finish())
+        }
+        DataType::Date32 => {
+            let epoch = chrono::DateTime::UNIX_EPOCH.date_naive();
+            let mut b = Date32Builder::new();
+            for v in typed() {
+                b.append_option(match v {
+                    TypedValue::Date(d) => i32::try_from((d - epoch).num_days()).ok(),
+                    _ => None,
+                });
+            }
+            Arc::new(b.finish())
+        }
+        DataType::Timestamp(_, tz) => {
+            let mut b = TimestampMicrosecondBuilder::new();
+            for v in typed() {
+                b.append_option(match v {
+                    TypedValue::Timestamp(ts) => Some(ts.and_utc().timestamp_micros()),
+                    TypedValue::TimestampTz(ts) => Some(ts.timestamp_micros()),
+                    _ => None,
+                });
+            }
+            Arc::new(b.finish().with_timezone_opt(tz.clone()))
+        }
+        _ => {
+            let mut b = StringBuilder::new();
+            for v in cells {
+                b.append_option((!v.is_null()).then(|| cell_text(v)));
+            }
+            Arc::new(b.finish())
+        }
+    })
+}
+
+fn to_xlsx(result: &QueryResult) -> Result<Vec<u8>, String> {
+    use megaweb_types::query::ColumnKind;
+    use rust_xlsxwriter::{Format, Workbook};
+
+    if result.rows.len() + 1 > XLSX_MAX_ROWS {
+        return Err(format!(
+            "XLSX is limited to {} rows; export as CSV or Parquet instead",
+            XLSX_MAX_ROWS - 1
+        ));
+    }
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    let bold = Format::new().set_bold();
+    let kinds: Vec<ColumnKind> = result.columns.iter().map(QueryColumn::kind).collect();
+
+    for (c, col) in result.columns.iter().enumerate() {
+        sheet
+            .write_string_with_format(0, c as u16, &col.name, &bold)
+            .map_err(|e| e.to_string())?;
+    }
+    for (r, row) in result.rows.iter().enumerate() {
+        let r = r as u32 + 1;
+        for (c, v) in row.iter().enumerate() {
+            let c = c as u16;
+            let written = match (v, kinds.get(c as usize)) {
+                (Value::Null, _) => continue,
+                (Value::Bool(b), _) => sheet.write_boolean(r, c, *b).map(|_| ()),
+                (v, Some(ColumnKind::Numeric)) if cell_number(v).is_some() => sheet
+                    .write_number(r, c, cell_number(v).unwrap_or_default())
+                    .map(|_| ()),
+                (v, _) => sheet.write_string(r, c, cell_text(v)).map(|_| ()),
+            };
+            written.map_err(|e| e.to_string())?;
+        }
+    }
+    sheet.set_freeze_panes(1, 0).map_err(|e| e.to_string())?;
+    workbook.save_to_buffer().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn keeps_cells_that_dont_parse_as_strings() {
+        let column = |name: &str, data_type: &str| QueryColumn {
+            name: name.into(),
+            data_type: data_type.into(),
+            nullable: true,
+        };
+        let result = QueryResult {
+            columns: vec![column("day", "DATE"), column("cost", "DOUBLE")],
+            rows: vec![
+                vec![json!("2026-01-01"), json!(1.5)],
+                vec![json!("infinity"), Value::Null],
+            ],
+            row_count: 2,
+            execution_time_ms: 1,
+            error: None,
+            stats: None,
+        };
+        let batch = to_record_batch(&result).unwrap();
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Utf8);
+        assert_eq!(batch.schema().field(1).data_type(), &DataType::Float64);
+        assert_eq!(batch.column(0).null_count(), 0);
+        assert_eq!(batch.column(1).null_count(), 1);
+    }
+}
@@ -0,0 +1,409 @@
+//! File imports into MegaDB tables.
+//!
+//! CSV files are parsed in the browser and their rows come back here in
+//! batches. Parquet files are uploaded once and kept in a temporary file;
+//! the client gets their columns and first rows to map and preview, then
+//! asks for the file to be inserted batch by batch, which is read here.
+//! Either way rows are converted to the target column types and inserted
+//! with one multi-row `INSERT` per batch.
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, TimeUnit};
+use axum::{body::Bytes, extract::Extension, http::StatusCode, Json};
+use megaweb_types::import::{
+    ImportBatch, ImportBatchResult, ImportFile, ParquetBatchRequest, ParquetUpload, RowError,
+    BATCH_ROWS, PARQUET_PREVIEW_ROWS,
+};
+use megaweb_types::query::{QueryRequest, TypedValue};
+use megaweb_types::schema::ColumnInfo;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::AppConfig;
+use crate::export::DATA_TYPE_METADATA;
+use crate::proxy::megadb::send_query;
+use crate::proxy::{api_error, ApiError};
+
+/// Largest file accepted by the Parquet upload.
+pub const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
+
+/// Uploads not imported or discarded within this time are deleted.
+const UPLOAD_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Temporary file holding an upload.
+fn upload_path(id: uuid::Uuid) -> PathBuf {
+    std::env::temp_dir().join(format!("megaweb-import-{id}.parquet"))
+}
+
+/// POST /import/parquet: store an uploaded Parquet file and return its
+/// columns, first rows and row count.
+pub async fn upload_parquet(body: Bytes) -> Result<Json<ParquetUpload>, ApiError> {
+    remove_stale_uploads().await;
+    let id = uuid::Uuid::new_v4();
+    let path = upload_path(id);
+    tokio::fs::write(&path, &body).await.map_err(|e| {
+        api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Couldn't store the upload: {e}"),
+        )
+    })?;
+    let inspect_path = path.clone();
+    let inspected = tokio::task::spawn_blocking(move || inspect_parquet(&inspect_path))
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    match inspected {
+        Ok((file, total_rows)) => Ok(Json(ParquetUpload {
+            id,
+            file,
+            total_rows,
+        })),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&path).await;
+            Err(api_error(StatusCode::BAD_REQUEST, e))
+        }
+    }
+}
+
+/// POST /import/parquet/discard: delete an upload that won't be imported
+/// further.
+pub async fn discard_parquet(Json(id): Json<uuid::Uuid>) -> Json<()> {
+    let _ = tokio::fs::remove_file(upload_path(id)).await;
+    Json(())
+}
+
+/// Delete uploads older than [`UPLOAD_TTL`], left by imports that were
+/// abandoned.
+async fn remove_stale_uploads() {
+    let Ok(mut entries) = tokio::fs::read_dir(std::env::temp_dir()).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name();
+        if !name.to_string_lossy().starts_with("megaweb-import-") {
+            continue;
+        }
+        let expired = entry
+            .metadata()
+            .await
+            .and_then(|m| m.modified())
+            .is_ok_and(|t| t.elapsed().is_ok_and(|age| age > UPLOAD_TTL));
+        if expired {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+}
+
+/// Columns, first rows and row count of a Parquet file.
+fn inspect_parquet(path: &Path) -> Result<(ImportFile, usize), String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .map_err(|e| format!("Not a Parquet file: {e}"))?;
+    let columns = builder
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| column_info(f))
+        .collect::<Result<Vec<_>, _>>()?;
+    let total_rows = builder.metadata().file_metadata().num_rows().max(0) as usize;
+    let rows = read_rows(path, 0, PARQUET_PREVIEW_ROWS)?;
+    Ok((ImportFile { columns, rows }, total_rows))
+}
+
+/// Up to `limit` rows of a stored upload, starting at row `offset`.
+fn read_rows(path: &Path, offset: usize, limit: usize) -> Result<Vec<Vec<Value>>, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|_| "The uploaded file is gone; choose it again".to_string())?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .map_err(|e| format!("Not a Parquet file: {e}"))?
+        .with_offset(offset)
+        .with_limit(limit)
+        .with_batch_size(limit.max(1))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut rows = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| e.to_string())?;
+        append_rows(&batch, &mut rows)?;
+    }
+    Ok(rows)
+}
+
+/// SQL column for an Arrow field. Files exported by MegaDB Web carry the
+/// original type in the field metadata.
+fn column_info(field: &Field) -> Result<ColumnInfo, String> {
+    let data_type = match field.metadata().get(DATA_TYPE_METADATA) {
+        Some(original) => original.clone(),
+        None => sql_type(field.data_type()).ok_or_else(|| {
+            format!(
+                "Column {} has unsupported type {}",
+                field.name(),
+                field.data_type()
+            )
+        })?,
+    };
+    Ok(ColumnInfo {
+        name: field.name().clone(),
+        data_type,
+        nullable: field.is_nullable(),
+        comment: None,
+    })
+}
+
+fn sql_type(data_type: &DataType) -> Option<String> {
+    Some(match data_type {
+        DataType::Boolean => "BOOLEAN".into(),
+        DataType::Int8 => "TINYINT".into(),
+        DataType::Int16 | DataType::UInt8 => "SMALLINT".into(),
+        DataType::Int32 | DataType::UInt16 => "INTEGER".into(),
+        DataType::Int64 | DataType::UInt32 => "BIGINT".into(),
+        DataType::UInt64 => "DECIMAL(20,0)".into(),
+        DataType::Float32 => "REAL".into(),
+        DataType::Float64 => "DOUBLE".into(),
+        DataType::Decimal128(p, s) => format!("DECIMAL({p},{s})"),
+        DataType::Date32 | DataType::Date64 => "DATE".into(),
+        DataType::Timestamp(_, None) => "TIMESTAMP".into(),
+        DataType::Timestamp(_, Some(_)) => "TIMESTAMP WITH TIME ZONE".into(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "VARCHAR".into(),
+        DataType::Binary | DataType::LargeBinary => "BYTEA".into(),
+        // Unpacked by `decodable` before reading
+        DataType::Dictionary(_, values) => return sql_type(values),
+        _ => return None,
+    })
+}
+
+fn append_rows(batch: &RecordBatch, rows: &mut Vec<Vec<Value>>) -> Result<(), String> {
+    let columns = batch
+        .columns()
+        .iter()
+        .map(decodable)
+        .collect::<Result<Vec<_>, _>>()?;
+    for row in 0..batch.num_rows() {
+        rows.push(columns.iter().map(|c| cell(c.as_ref(), row)).collect());
+    }
+    Ok(())
+}
+
+/// A column in a type [`cell`] reads: dictionary-encoded columns, as
+/// Parquet writers store low-cardinality strings, are unpacked into their
+/// values.
+fn decodable(array: &ArrayRef) -> Result<ArrayRef, String> {
+    match array.as_any_dictionary_opt() {
+        Some(dict) => arrow_select::take::take(dict.values().as_ref(), dict.keys(), None)
+            .map_err(|e| e.to_string()),
+        None => Ok(Arc::clone(array)),
+    }
+}
+
+/// One Arrow value as JSON, in the text forms the SQL types decode from.
+fn cell(array: &dyn Array, row: usize) -> Value {
+    if array.is_null(row) {
+        return Value::Null;
+    }
+    macro_rules! primitive {
+        ($t:ty) => {
+            Value::from(array.as_primitive::<$t>().value(row))
+        };
+    }
+    match array.data_type() {
+        DataType::Boolean => Value::Bool(array.as_boolean().value(row)),
+        DataType::Int8 => primitive!(Int8Type),
+        DataType::Int16 => primitive!(Int16Type),
+        DataType::Int32 => primitive!(Int32Type),
+        DataType::Int64 => primitive!(Int64Type),
+        DataType::UInt8 => primitive!(UInt8Type),
+        DataType::UInt16 => primitive!(UInt16Type),
+        DataType::UInt32 => primitive!(UInt32Type),
+        DataType::UInt64 => primitive!(UInt64Type),
+        DataType::Float32 => primitive!(Float32Type),
+        DataType::Float64 => primitive!(Float64Type),
+        DataType::Decimal128(_, scale) => TypedValue::Decimal {
+            unscaled: array
+                .as_primitive::<arrow_array::types::Decimal128Type>()
+                .value(row),
+            scale: (*scale).max(0) as u8,
+        }
+        .to_json(),
+        DataType::Date32 => {
+            let days = array
+                .as_primitive::<arrow_array::types::Date32Type>()
+                .value(row);
+            let epoch = chrono::DateTime::UNIX_EPOCH.date_naive();
+            Value::String(
+                (epoch + chrono::TimeDelta::days(i64::from(days)))
+                    .format("%Y-%m-%d")
+                    .to_string(),
+            )
+        }
+        DataType::Date64 => {
+            let millis = array
+                .as_primitive::<arrow_array::types::Date64Type>()
+                .value(row);
+            let date = chrono::DateTime::from_timestamp_millis(millis).unwrap_or_default();
+            Value::String(date.format("%Y-%m-%d").to_string())
+        }
+        DataType::Timestamp(unit, tz) => {
+            let micros = timestamp_micros(array, *unit, row);
+            let ts = chrono::DateTime::from_timestamp_micros(micros).unwrap_or_default();
+            Value::String(if tz.is_some() {
+                ts.to_rfc3339()
+            } else {
+                ts.naive_utc().format("%Y-%m-%d %H:%M:%S%.f").to_string()
+            })
+        }
+        DataType::Utf8 => Value::String(array.as_string::<i32>().value(row).to_string()),
+        DataType::LargeUtf8 => Value::String(array.as_string::<i64>().value(row).to_string()),
+        DataType::Utf8View => Value::String(array.as_string_view().value(row).to_string()),
+        DataType::Binary => hex(array.as_binary::<i32>().value(row)),
+        DataType::LargeBinary => hex(array.as_binary::<i64>().value(row)),
+        _ => Value::Null,
+    }
+}
+
+fn timestamp_micros(array: &dyn Array, unit: TimeUnit, row: usize) -> i64 {
+    use arrow_array::types::{
+        TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+        TimestampSecondType,
+    };
+    match unit {
+        TimeUnit::Second => array
+            .as_primitive::<TimestampSecondType>()
+            .value(row)
+            .saturating_mul(1_000_000),
+        TimeUnit::Millisecond => array
+            .as_primitive::<TimestampMillisecondType>()
+            .value(row)
+            .saturating_mul(1_000),
+        TimeUnit::Microsecond => array.as_primitive::<TimestampMicrosecondType>().value(row),
+        TimeUnit::Nanosecond => array.as_primitive::<TimestampNanosecondType>().value(row) / 1_000,
+    }
+}
+
+fn hex(bytes: &[u8]) -> Value {
+    let digits: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    Value::String(format!("\\x{digits}"))
+}
+
+/// POST /import/batch: convert and insert one batch of rows.
+pub async fn import_batch(
+    Extension(config): Extension<AppConfig>,
+    Json(batch): Json<ImportBatch>,
+) -> Result<Json<ImportBatchResult>, ApiError> {
+    insert_batch(&config, &batch).await.map(Json)
+}
+
+/// POST /import/parquet/batch: read [`BATCH_ROWS`] rows of an upload,
+/// then convert and insert them.
+pub async fn import_parquet_batch(
+    Extension(config): Extension<AppConfig>,
+    Json(req): Json<ParquetBatchRequest>,
+) -> Result<Json<ImportBatchResult>, ApiError> {
+    let path = upload_path(req.upload);
+    let rows = tokio::task::spawn_blocking(move || read_rows(&path, req.offset, BATCH_ROWS))
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    insert_batch(&config, &req.batch(&rows)).await.map(Json)
+}
+
+/// Insert a batch of rows.
+///
+/// Rows that don't fit the column types are reported without being sent.
+/// If MegaDB rejects the batch, it is split in halves and retried, down to
+/// single rows, so each failure is attributed to its row without a round
+/// trip per row.
+async fn insert_batch(
+    config: &AppConfig,
+    batch: &ImportBatch,
+) -> Result<ImportBatchResult, ApiError> {
+    let (valid, mut errors) = batch.prepare();
+    let mut inserted = 0;
+    let mut pending = vec![&valid[..]];
+    while let Some(rows) = pending.pop() {
+        if rows.is_empty() {
+            continue;
+        }
+        let sql = batch.insert_sql(rows.iter().map(|(_, values)| values));
+        match run_sql(config, &batch.database, sql).await? {
+            Ok(()) => inserted += rows.len(),
+            Err(message) if rows.len() == 1 => errors.push(RowError {
+                row: rows[0].0,
+                message,
+            }),
+            Err(_) => {
+                let (first, second) = rows.split_at(rows.len() / 2);
+                // Retry the first half first so rows are inserted in order
+                pending.push(second);
+                pending.push(first);
+            }
+        }
+    }
+    errors.sort_by_key(|e| e.row);
+    Ok(ImportBatchResult { inserted, errors })
+}
+
+/// Run a statement on MegaDB. The outer error means MegaDB couldn't be
+/// reached; the inner one that it rejected the statement.
+async fn run_sql(
+    config: &AppConfig,
+    database: &str,
+    sql: String,
+) -> Result<Result<(), String>, ApiError> {
+    let req = QueryRequest {
+        sql,
+        database: database.to_string(),
+        limit: None,
+    };
+    Ok(match send_query(config, &req).await? {
+        Ok(result) => result.error.map_or(Ok(()), Err),
+        Err((_, message)) => Err(message),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::types::Int32Type;
+    use arrow_array::{Date64Array, DictionaryArray};
+
+    #[test]
+    fn reads_dictionary_strings_and_date64() {
+        let service: DictionaryArray<Int32Type> = vec![Some("ec2"), None, Some("s3"), Some("ec2")]
+            .into_iter()
+            .collect();
+        let day = Date64Array::from(vec![0, 86_400_000, 19_000 * 86_400_000, 0]);
+        let batch = RecordBatch::try_from_iter([
+            ("service", Arc::new(service) as ArrayRef),
+            ("day", Arc::new(day) as ArrayRef),
+        ])
+        .unwrap();
+
+        let types: Vec<String> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| column_info(f).unwrap().data_type)
+            .collect();
+        assert_eq!(types, ["VARCHAR", "DATE"]);
+
+        let mut rows = Vec::new();
+        append_rows(&batch, &mut rows).unwrap();
+        assert_eq!(
+            rows,
+            [
+                vec![Value::from("ec2"), Value::from("1970-01-01")],
+                vec![Value::Null, Value::from("1970-01-02")],
+                vec![Value::from("s3"), Value::from("2022-01-08")],
+                vec![Value::from("ec2"), Value::from("1970-01-01")],
+            ]
+        );
+    }
+}
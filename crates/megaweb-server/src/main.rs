@@ -1,4 +1,8 @@
-use axum::{extract::Extension, routing::get, Router};
+use axum::{
+    extract::{DefaultBodyLimit, Extension},
+    routing::{get, post},
+    Router,
+};
 use leptos::config::LeptosOptions;
 use leptos::prelude::*;
 use leptos_axum::{generate_route_list, LeptosRoutes};
@@ -8,6 +12,8 @@ use tower_http::services::ServeDir;
 use tracing_subscriber::EnvFilter;
 
 mod config;
+mod export;
+mod import;
 mod proxy;
 mod websocket;
 
@@ -81,6 +87,14 @@ async fn main() {
     // Build proxy + WS routes as a standalone Router (no state needed).
     let api_routes: Router<()> = proxy::router()
         .route("/ws", get(websocket::ws_upgrade_handler))
+        .route("/export", post(export::export_handler))
+        .route(
+            "/import/parquet",
+            post(import::upload_parquet).layer(DefaultBodyLimit::max(import::MAX_UPLOAD_BYTES)),
+        )
+        .route("/import/parquet/batch", post(import::import_parquet_batch))
+        .route("/import/parquet/discard", post(import::discard_parquet))
+        .route("/import/batch", post(import::import_batch))
         .layer(Extension(Arc::clone(&ws_hub)))
         .layer(Extension(app_config.clone()));
 
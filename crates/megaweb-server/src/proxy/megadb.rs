@@ -1,11 +1,21 @@
 use axum::{
-    extract::Extension,
+    extract::{Extension, Query},
     http::StatusCode,
     response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
 
+use megaweb_types::catalog::tables_from_api;
+use megaweb_types::partitions::TablePartitionsRequest;
+use megaweb_types::query::{QueryRequest, QueryResult};
+use megaweb_types::schema::TableInfo;
+use megaweb_types::table_stats::{
+    partitions_from_result, partitions_sql, PartitionStats, TablePreview, TablePreviewRequest,
+    TableStats, TableStatsRequest,
+};
+
+use super::{api_error, ApiError};
 use crate::config::AppConfig;
 
 /// Health check: probes MegaDB at config.megadb_url/health with 3s timeout.
@@ -30,84 +40,161 @@ async fn health_check(Extension(config): Extension<AppConfig>) -> impl IntoRespo
 /// Query proxy: forwards SQL to MegaDB POST /query.
 async fn proxy_query(
     Extension(config): Extension<AppConfig>,
-    Json(req): Json<megaweb_types::query::QueryRequest>,
-) -> Result<Json<megaweb_types::query::QueryResult>, (StatusCode, Json<serde_json::Value>)> {
+    Json(req): Json<QueryRequest>,
+) -> Result<Json<QueryResult>, ApiError> {
+    send_query(&config, &req)
+        .await?
+        .map(Json)
+        .map_err(|(status, message)| api_error(status, message))
+}
+
+/// Send a query to MegaDB POST /query. The outer error means MegaDB
+/// couldn't be reached or its response read; the inner one is the status
+/// and message of a request it rejected.
+pub(crate) async fn send_query(
+    config: &AppConfig,
+    req: &QueryRequest,
+) -> Result<Result<QueryResult, (StatusCode, String)>, ApiError> {
     let resp = config
         .client
         .post(format!("{}/query", config.megadb_url))
-        .json(&req)
+        .json(req)
         .timeout(std::time::Duration::from_secs(60))
         .send()
         .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_GATEWAY,
-                Json(serde_json::json!({"error": format!("MegaDB unreachable: {e}")})),
-            )
-        })?;
+        .map_err(|e| api_error(StatusCode::BAD_GATEWAY, format!("MegaDB unreachable: {e}")))?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let body = resp.text().await.unwrap_or_default();
-        return Err((
+        return Ok(Err((
             StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-            Json(serde_json::json!({"error": body})),
-        ));
+            body,
+        )));
     }
 
-    let result = resp.json().await.map_err(|e| {
-        (
+    resp.json().await.map(Ok).map_err(|e| {
+        api_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": format!("Failed to parse response: {e}")})),
+            format!("Failed to parse response: {e}"),
         )
-    })?;
+    })
+}
+
+/// Run a generated statement, treating an error MegaDB reports in the
+/// result as a failed request.
+pub(crate) async fn run_checked(
+    config: &AppConfig,
+    database: &str,
+    sql: String,
+) -> Result<QueryResult, ApiError> {
+    let req = QueryRequest {
+        sql,
+        database: database.to_string(),
+        limit: None,
+    };
+    match send_query(config, &req).await? {
+        Ok(result) => match result.error {
+            Some(e) => Err(api_error(StatusCode::BAD_GATEWAY, e)),
+            None => Ok(result),
+        },
+        Err((status, message)) => Err(api_error(status, message)),
+    }
+}
+
+/// Table preview: POST /proxy/megadb/table/preview, one page of a table's
+/// first rows.
+async fn table_preview(
+    Extension(config): Extension<AppConfig>,
+    Json(req): Json<TablePreviewRequest>,
+) -> Result<Json<TablePreview>, ApiError> {
+    let result = run_checked(&config, &req.database, req.sql()).await?;
+    Ok(Json(req.preview(result)))
+}
+
+/// Table statistics: POST /proxy/megadb/table/stats, column statistics over
+/// a sample and the table's partitions. Partitions are optional since not
+/// every MegaDB lists them.
+async fn table_stats(
+    Extension(config): Extension<AppConfig>,
+    Json(req): Json<TableStatsRequest>,
+) -> Result<Json<TableStats>, ApiError> {
+    let result = run_checked(&config, &req.database, req.column_stats_sql()).await?;
+    let (sampled_rows, columns) = req
+        .column_stats(&result)
+        .map_err(|e| api_error(StatusCode::BAD_GATEWAY, e))?;
+    let (partitions, partitions_error) =
+        match run_checked(&config, &req.database, req.partitions_sql()).await {
+            Ok(result) => (partitions_from_result(&result), None),
+            Err((_, Json(body))) => (Vec::new(), body["error"].as_str().map(str::to_string)),
+        };
+    Ok(Json(TableStats {
+        sampled_rows,
+        columns,
+        partitions,
+        partitions_error,
+    }))
+}
 
-    Ok(Json(result))
+/// Table partitions: POST /proxy/megadb/table/partitions, the stored
+/// partitions of a table with their sizes.
+async fn table_partitions(
+    Extension(config): Extension<AppConfig>,
+    Json(req): Json<TablePartitionsRequest>,
+) -> Result<Json<Vec<PartitionStats>>, ApiError> {
+    let result = run_checked(&config, &req.database, partitions_sql(&req.table)).await?;
+    Ok(Json(partitions_from_result(&result)))
 }
 
-/// Tables proxy: GET /proxy/megadb/tables.
+/// Tables proxy: GET /proxy/megadb/tables[?database=...], with MegaDB's
+/// catalog parsed into typed tables.
 async fn proxy_tables(
     Extension(config): Extension<AppConfig>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    let resp = config
-        .client
-        .get(format!("{}/tables", config.megadb_url))
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<Vec<TableInfo>>, ApiError> {
+    let database = params.get("database");
+    let mut request = config.client.get(format!("{}/tables", config.megadb_url));
+    if let Some(database) = database {
+        request = request.query(&[("database", database)]);
+    }
+    let resp = request
         .timeout(std::time::Duration::from_secs(10))
         .send()
         .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_GATEWAY,
-                Json(serde_json::json!({"error": format!("MegaDB unreachable: {e}")})),
-            )
-        })?;
+        .map_err(|e| api_error(StatusCode::BAD_GATEWAY, format!("MegaDB unreachable: {e}")))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(api_error(
+            StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            body,
+        ));
+    }
 
     let body: serde_json::Value = resp.json().await.map_err(|e| {
-        (
+        api_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": format!("Parse error: {e}")})),
+            format!("Parse error: {e}"),
         )
     })?;
+    let tables = tables_from_api(body, database.map(String::as_str))
+        .map_err(|e| api_error(StatusCode::BAD_GATEWAY, e))?;
 
-    Ok(Json(body))
+    Ok(Json(tables))
 }
 
 /// Metrics proxy: GET /proxy/megadb/metrics.
 async fn proxy_metrics(
     Extension(config): Extension<AppConfig>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+) -> Result<impl IntoResponse, ApiError> {
     let resp = config
         .client
         .get(format!("{}/metrics", config.megadb_url))
         .timeout(std::time::Duration::from_secs(5))
         .send()
         .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_GATEWAY,
-                Json(serde_json::json!({"error": format!("MegaDB unreachable: {e}")})),
-            )
-        })?;
+        .map_err(|e| api_error(StatusCode::BAD_GATEWAY, format!("MegaDB unreachable: {e}")))?;
 
     let text = resp.text().await.unwrap_or_default();
     Ok(text)
@@ -118,5 +205,8 @@ pub fn router() -> Router {
         .route("/health", get(health_check))
         .route("/query", post(proxy_query))
         .route("/tables", get(proxy_tables))
+        .route("/table/preview", post(table_preview))
+        .route("/table/stats", post(table_stats))
+        .route("/table/partitions", post(table_partitions))
         .route("/metrics", get(proxy_metrics))
 }
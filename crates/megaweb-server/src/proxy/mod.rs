@@ -2,7 +2,15 @@ pub mod k8s;
 pub mod megadb;
 pub mod prometheus;
 
-use axum::Router;
+use axum::{http::StatusCode, Json, Router};
+
+/// Error response of the proxy and import handlers: a status and an
+/// `{"error": ...}` body.
+pub type ApiError = (StatusCode, Json<serde_json::Value>);
+
+pub fn api_error(status: StatusCode, message: String) -> ApiError {
+    (status, Json(serde_json::json!({ "error": message })))
+}
 
 /// Combined proxy router for all backend services.
 pub fn router() -> Router {
@@ -0,0 +1,483 @@
+//! Building [`DatabaseInfo`] from MegaDB's catalog.
+//!
+//! MegaDB describes its tables through the `/tables` API, with engine,
+//! partitioning and storage details. Servers without it still answer
+//! `information_schema` queries, read here by column name so that MegaDB's
+//! extra columns (`engine`, `partition_by`, ...) are used when present.
+
+use serde::Deserialize;
+
+use crate::query::QueryResult;
+use crate::schema::{ColumnInfo, DatabaseInfo, PartitionInfo, SchemaInfo, TableInfo};
+
+/// Schemas left out of the schema tree.
+const SYSTEM_SCHEMAS: [&str; 3] = ["information_schema", "pg_catalog", "system"];
+
+/// Tables of the current database, with MegaDB's extra columns when it has
+/// them.
+pub const INFORMATION_SCHEMA_TABLES_SQL: &str = "SELECT * FROM information_schema.tables \
+     WHERE table_schema NOT IN ('information_schema', 'pg_catalog', 'system')";
+
+/// Columns of the current database, in table and ordinal order.
+pub const INFORMATION_SCHEMA_COLUMNS_SQL: &str = "SELECT * FROM information_schema.columns \
+     WHERE table_schema NOT IN ('information_schema', 'pg_catalog', 'system') \
+     ORDER BY table_schema, table_name, ordinal_position";
+
+/// A table as the `/tables` API reports it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogTable {
+    #[serde(default)]
+    pub database: Option<String>,
+    #[serde(default = "default_schema", alias = "schema", alias = "table_schema")]
+    pub schema_name: String,
+    #[serde(alias = "table_name")]
+    pub name: String,
+    #[serde(default = "default_engine")]
+    pub engine: String,
+    #[serde(default, alias = "rows")]
+    pub row_count: Option<u64>,
+    #[serde(default, alias = "size")]
+    pub size_bytes: Option<u64>,
+    #[serde(default)]
+    pub columns: Vec<CatalogColumn>,
+    #[serde(default, alias = "partition_by")]
+    pub partitions: Vec<CatalogPartition>,
+    #[serde(default)]
+    pub compression: Option<String>,
+    #[serde(default, alias = "sort_by", alias = "order_by")]
+    pub sort_columns: Option<Vec<String>>,
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogColumn {
+    #[serde(alias = "column_name")]
+    pub name: String,
+    #[serde(alias = "type")]
+    pub data_type: String,
+    #[serde(default = "default_nullable")]
+    pub nullable: bool,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// A partition field, either spelled out or as a spec like `month(ts)`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum CatalogPartition {
+    Field(PartitionInfo),
+    Spec(String),
+}
+
+/// The `/tables` response: a bare list or `{"tables": [...]}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TablesResponse {
+    List(Vec<CatalogTable>),
+    Wrapped { tables: Vec<CatalogTable> },
+}
+
+fn default_schema() -> String {
+    "public".to_string()
+}
+
+fn default_engine() -> String {
+    "OLAP".to_string()
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+impl From<CatalogTable> for TableInfo {
+    fn from(table: CatalogTable) -> Self {
+        Self {
+            schema_name: table.schema_name,
+            name: table.name,
+            engine: table.engine.to_uppercase(),
+            row_count: table.row_count,
+            size_bytes: table.size_bytes,
+            columns: table
+                .columns
+                .into_iter()
+                .map(|c| ColumnInfo {
+                    name: c.name,
+                    data_type: c.data_type,
+                    nullable: c.nullable,
+                    comment: c.comment.filter(|c| !c.is_empty()),
+                })
+                .collect(),
+            partitions: table
+                .partitions
+                .into_iter()
+                .filter_map(|p| match p {
+                    CatalogPartition::Field(info) => Some(info),
+                    CatalogPartition::Spec(spec) => parse_partition_spec(&spec),
+                })
+                .collect(),
+            compression: table.compression.filter(|c| !c.is_empty()),
+            sort_columns: table.sort_columns.filter(|c| !c.is_empty()),
+            primary_key: table.primary_key,
+        }
+    }
+}
+
+/// Parse a `/tables` response into the tables of `database`, or of every
+/// database when `None`. Entries that don't name their database are kept.
+pub fn tables_from_api(
+    body: serde_json::Value,
+    database: Option<&str>,
+) -> Result<Vec<TableInfo>, String> {
+    let tables = match serde_json::from_value(body) {
+        Ok(TablesResponse::List(tables)) | Ok(TablesResponse::Wrapped { tables }) => tables,
+        Err(e) => return Err(format!("Unexpected /tables response: {e}")),
+    };
+    Ok(tables
+        .into_iter()
+        .filter(|t| match (&t.database, database) {
+            (Some(d), Some(wanted)) => d == wanted,
+            _ => true,
+        })
+        .filter(|t| !SYSTEM_SCHEMAS.contains(&t.schema_name.as_str()))
+        .map(TableInfo::from)
+        .collect())
+}
+
+/// Build tables from the results of [`INFORMATION_SCHEMA_TABLES_SQL`] and
+/// [`INFORMATION_SCHEMA_COLUMNS_SQL`]. Views are left out.
+pub fn tables_from_information_schema(
+    tables: &QueryResult,
+    columns: &QueryResult,
+) -> Vec<TableInfo> {
+    let mut out: Vec<TableInfo> = Vec::new();
+    for row in &tables.rows {
+        let cell = |name: &str| cell(tables, row, name);
+        let text = |name: &str| cell(name).and_then(text_value);
+        if text("table_type").is_some_and(|t| t.to_uppercase().contains("VIEW")) {
+            continue;
+        }
+        let (Some(schema_name), Some(name)) = (text("table_schema"), text("table_name")) else {
+            continue;
+        };
+        out.push(TableInfo {
+            schema_name,
+            name,
+            engine: text("engine").map_or_else(default_engine, |e| e.to_uppercase()),
+            row_count: cell("row_count")
+                .or(cell("table_rows"))
+                .and_then(number_value),
+            size_bytes: cell("size_bytes")
+                .or(cell("data_length"))
+                .and_then(number_value),
+            columns: Vec::new(),
+            partitions: text("partition_by")
+                .map(|spec| {
+                    split_top_level(&spec)
+                        .into_iter()
+                        .filter_map(parse_partition_spec)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            compression: text("compression"),
+            sort_columns: cell("sort_columns").and_then(list_value),
+            primary_key: cell("primary_key").and_then(list_value).unwrap_or_default(),
+        });
+    }
+
+    for row in &columns.rows {
+        let text = |name: &str| cell(columns, row, name).and_then(text_value);
+        let (Some(schema), Some(table), Some(name)) = (
+            text("table_schema"),
+            text("table_name"),
+            text("column_name"),
+        ) else {
+            continue;
+        };
+        let Some(info) = out
+            .iter_mut()
+            .find(|t| t.schema_name == schema && t.name == table)
+        else {
+            continue;
+        };
+        info.columns.push(ColumnInfo {
+            name,
+            data_type: text("data_type").unwrap_or_default().to_uppercase(),
+            nullable: text("is_nullable").is_none_or(|n| !n.eq_ignore_ascii_case("NO")),
+            comment: text("column_comment").or_else(|| text("comment")),
+        });
+    }
+    out
+}
+
+/// Group tables into their schemas, sorted by name. The database's default
+/// engine is the one most of its tables use.
+pub fn database_info(name: &str, tables: Vec<TableInfo>) -> DatabaseInfo {
+    let mut engines: Vec<(&str, usize)> = Vec::new();
+    for table in &tables {
+        match engines.iter_mut().find(|(e, _)| *e == table.engine) {
+            Some((_, n)) => *n += 1,
+            None => engines.push((&table.engine, 1)),
+        }
+    }
+    let default_engine = engines
+        .iter()
+        .max_by_key(|(_, n)| *n)
+        .map_or_else(default_engine, |(e, _)| e.to_string());
+
+    let mut schemas: Vec<SchemaInfo> = Vec::new();
+    for table in tables {
+        match schemas.iter_mut().find(|s| s.name == table.schema_name) {
+            Some(schema) => schema.tables.push(table),
+            None => schemas.push(SchemaInfo {
+                name: table.schema_name.clone(),
+                tables: vec![table],
+            }),
+        }
+    }
+    schemas.sort_by(|a, b| a.name.cmp(&b.name));
+    for schema in &mut schemas {
+        schema.tables.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    DatabaseInfo {
+        name: name.to_string(),
+        default_engine,
+        schemas,
+    }
+}
+
+/// Parse a partition spec such as `billing_period`, `month(billing_period)`
+/// or `bucket(16, account_id)`.
+pub fn parse_partition_spec(spec: &str) -> Option<PartitionInfo> {
+    let spec = spec.trim();
+    let Some((transform, args)) = spec.strip_suffix(')').and_then(|s| s.split_once('(')) else {
+        return (!spec.is_empty()).then(|| PartitionInfo {
+            column: spec.to_string(),
+            transform: "identity".to_string(),
+        });
+    };
+    let transform = transform.trim().to_lowercase();
+    let args: Vec<&str> = args.split(',').map(str::trim).collect();
+    match args.as_slice() {
+        [column] if !column.is_empty() => Some(PartitionInfo {
+            column: column.to_string(),
+            transform,
+        }),
+        // Iceberg order: bucket(N, col), truncate(W, col)
+        [width, column] if width.parse::<u32>().is_ok() => Some(PartitionInfo {
+            column: column.to_string(),
+            transform: format!("{transform}({width})"),
+        }),
+        _ => None,
+    }
+}
+
+pub(crate) fn cell<'a>(
+    result: &QueryResult,
+    row: &'a [serde_json::Value],
+    name: &str,
+) -> Option<&'a serde_json::Value> {
+    let idx = result
+        .columns
+        .iter()
+        .position(|c| c.name.eq_ignore_ascii_case(name))?;
+    row.get(idx).filter(|v| !v.is_null())
+}
+
+pub(crate) fn text_value(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()).filter(|s| !s.is_empty()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+pub(crate) fn number_value(value: &serde_json::Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_str()?.trim().parse().ok())
+}
+
+/// A list given as a JSON array or as text like `a, b` or `[a, b]`.
+fn list_value(value: &serde_json::Value) -> Option<Vec<String>> {
+    let items: Vec<String> = match value {
+        serde_json::Value::Array(items) => items.iter().filter_map(text_value).collect(),
+        serde_json::Value::String(s) => s
+            .trim()
+            .trim_start_matches(['[', '{'])
+            .trim_end_matches([']', '}'])
+            .split(',')
+            .map(|c| c.trim().trim_matches('"').to_string())
+            .filter(|c| !c.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    };
+    (!items.is_empty()).then_some(items)
+}
+
+/// Split on commas outside parentheses.
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::result;
+
+    #[test]
+    fn parses_tables_api() {
+        let body = serde_json::json!({
+            "tables": [
+                {
+                    "database": "megadb",
+                    "schema": "public",
+                    "name": "cur_data",
+                    "engine": "olap",
+                    "row_count": 1200,
+                    "columns": [
+                        {"name": "cost", "type": "DECIMAL(18,6)", "nullable": false, "comment": "USD"},
+                        {"name": "billing_period", "type": "DATE"}
+                    ],
+                    "partition_by": ["month(billing_period)", {"column": "region", "transform": "identity"}],
+                    "compression": "zstd",
+                    "sort_by": ["account_id", "service_name"]
+                },
+                {"database": "other", "name": "elsewhere"},
+                {"schema": "information_schema", "name": "tables"}
+            ]
+        });
+        let tables = tables_from_api(body, Some("megadb")).unwrap();
+        assert_eq!(tables.len(), 1);
+        let t = &tables[0];
+        assert_eq!((t.engine.as_str(), t.row_count), ("OLAP", Some(1200)));
+        assert_eq!(t.columns[0].comment.as_deref(), Some("USD"));
+        assert!(t.columns[1].nullable);
+        assert_eq!(
+            t.partitions,
+            [
+                PartitionInfo {
+                    column: "billing_period".into(),
+                    transform: "month".into()
+                },
+                PartitionInfo {
+                    column: "region".into(),
+                    transform: "identity".into()
+                },
+            ]
+        );
+        assert_eq!(t.sort_columns.as_deref().map(<[_]>::len), Some(2));
+        assert!(tables_from_api(serde_json::json!({"error": "nope"}), None).is_err());
+    }
+
+    #[test]
+    fn builds_tables_from_information_schema() {
+        let tables = result(
+            &[
+                ("TABLE_SCHEMA", "VARCHAR"),
+                ("table_name", "VARCHAR"),
+                ("table_type", "VARCHAR"),
+                ("engine", "VARCHAR"),
+                ("partition_by", "VARCHAR"),
+                ("sort_columns", "VARCHAR"),
+                ("table_rows", "VARCHAR"),
+            ],
+            vec![
+                vec![
+                    "public".into(),
+                    "cur_data".into(),
+                    "BASE TABLE".into(),
+                    "OLAP".into(),
+                    "month(billing_period), bucket(16, account_id)".into(),
+                    "[account_id, service_name]".into(),
+                    "42".into(),
+                ],
+                vec![
+                    "public".into(),
+                    "cost_view".into(),
+                    "VIEW".into(),
+                    serde_json::Value::Null,
+                    serde_json::Value::Null,
+                    serde_json::Value::Null,
+                    serde_json::Value::Null,
+                ],
+            ],
+        );
+        let columns = result(
+            &[
+                ("table_schema", "VARCHAR"),
+                ("table_name", "VARCHAR"),
+                ("column_name", "VARCHAR"),
+                ("data_type", "VARCHAR"),
+                ("is_nullable", "VARCHAR"),
+            ],
+            vec![
+                vec![
+                    "public".into(),
+                    "cur_data".into(),
+                    "account_id".into(),
+                    "varchar".into(),
+                    "NO".into(),
+                ],
+                vec![
+                    "public".into(),
+                    "cur_data".into(),
+                    "cost".into(),
+                    "double".into(),
+                    "YES".into(),
+                ],
+                vec![
+                    "public".into(),
+                    "cost_view".into(),
+                    "total".into(),
+                    "double".into(),
+                    "YES".into(),
+                ],
+            ],
+        );
+        let built = tables_from_information_schema(&tables, &columns);
+        assert_eq!(built.len(), 1);
+        let t = &built[0];
+        assert_eq!(t.row_count, Some(42));
+        assert_eq!(t.partitions[1].transform, "bucket(16)");
+        assert_eq!(t.partitions[1].column, "account_id");
+        assert_eq!(
+            t.sort_columns,
+            Some(vec!["account_id".to_string(), "service_name".to_string()])
+        );
+        assert_eq!(t.columns.len(), 2);
+        assert_eq!(t.columns[0].data_type, "VARCHAR");
+        assert!(!t.columns[0].nullable);
+
+        let db = database_info("megadb", built);
+        assert_eq!(db.default_engine, "OLAP");
+        assert_eq!(db.schemas[0].tables[0].name, "cur_data");
+    }
+
+    #[test]
+    fn parses_partition_specs() {
+        let spec = |s: &str| parse_partition_spec(s).map(|p| (p.column, p.transform));
+        assert_eq!(spec("region"), Some(("region".into(), "identity".into())));
+        assert_eq!(spec(" day(ts) "), Some(("ts".into(), "day".into())));
+        assert_eq!(
+            spec("truncate(4, name)"),
+            Some(("name".into(), "truncate(4)".into()))
+        );
+        assert_eq!(spec("bucket(a, b)"), None);
+        assert_eq!(spec(""), None);
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::query::{ColumnKind, QueryResult};
+use crate::result_view::{cell_number, cell_text, compare_values};
+
+/// Series beyond this many are folded into a single "Other" series.
+const MAX_SERIES: usize = 10;
+
+/// Kind of chart drawn from a query result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultChartKind {
+    #[default]
+    Bar,
+    StackedBar,
+    Line,
+    Area,
+    Pie,
+    Scatter,
+}
+
+impl ResultChartKind {
+    pub const ALL: [ResultChartKind; 6] = [
+        ResultChartKind::Bar,
+        ResultChartKind::StackedBar,
+        ResultChartKind::Line,
+        ResultChartKind::Area,
+        ResultChartKind::Pie,
+        ResultChartKind::Scatter,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ResultChartKind::Bar => "Bar",
+            ResultChartKind::StackedBar => "Stacked bar",
+            ResultChartKind::Line => "Line",
+            ResultChartKind::Area => "Area",
+            ResultChartKind::Pie => "Pie",
+            ResultChartKind::Scatter => "Scatter",
+        }
+    }
+}
+
+/// Which columns of a result to chart, and how. Columns are referenced by
+/// name so the spec still applies when the query is re-run.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ResultChartSpec {
+    pub kind: ResultChartKind,
+    /// Category (or numeric X for scatter) column.
+    pub x: Option<String>,
+    /// Value columns. With a `series` column only the first is used.
+    pub y: Vec<String>,
+    /// Optional column whose distinct values split `y` into series.
+    pub series: Option<String>,
+}
+
+impl ResultChartSpec {
+    /// A reasonable default: the first non-numeric column as X and the
+    /// first numeric column as Y.
+    pub fn suggest(result: &QueryResult) -> Self {
+        let x = result
+            .columns
+            .iter()
+            .find(|c| c.kind() != ColumnKind::Numeric)
+            .or_else(|| result.columns.first())
+            .map(|c| c.name.clone());
+        let y = result
+            .columns
+            .iter()
+            .find(|c| c.kind() == ColumnKind::Numeric && Some(&c.name) != x.as_ref())
+            .map(|c| vec![c.name.clone()])
+            .unwrap_or_default();
+        Self {
+            kind: ResultChartKind::Bar,
+            x,
+            y,
+            series: None,
+        }
+    }
+
+    /// Whether every referenced column exists in `result`. A spec with no
+    /// Y columns still fits: it's the user's choice, not a stale spec.
+    pub fn fits(&self, result: &QueryResult) -> bool {
+        let has = |name: &String| result.columns.iter().any(|c| &c.name == name);
+        self.x.as_ref().is_some_and(has)
+            && self.y.iter().all(has)
+            && self.series.as_ref().is_none_or(has)
+    }
+}
+
+/// One named series of a categorical chart, aligned with the categories.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategorySeries {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+/// One named series of a scatter chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointSeries {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Data ready to draw, built from a result and a [`ResultChartSpec`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChartData {
+    /// Values summed per X category; used by every kind except scatter.
+    Categorical {
+        categories: Vec<String>,
+        series: Vec<CategorySeries>,
+    },
+    Scatter {
+        series: Vec<PointSeries>,
+    },
+}
+
+impl ChartData {
+    /// Build chart data over the loaded rows. Returns `None` if the spec
+    /// doesn't fit the result or has no Y column. Non-numeric Y values are
+    /// skipped.
+    pub fn build(result: &QueryResult, spec: &ResultChartSpec) -> Option<Self> {
+        if !spec.fits(result) || spec.y.is_empty() {
+            return None;
+        }
+        let index = |name: &str| result.columns.iter().position(|c| c.name == name);
+        let x = index(spec.x.as_deref()?)?;
+        let ys: Vec<usize> = spec.y.iter().filter_map(|n| index(n)).collect();
+        let split = spec.series.as_deref().and_then(index);
+        let cell = |row: &Vec<Value>, col: usize| row.get(col).cloned().unwrap_or(Value::Null);
+
+        // (series name, x value, y value) triples
+        let mut triples: Vec<(String, Value, f64)> = Vec::new();
+        for row in &result.rows {
+            match split {
+                Some(s) => {
+                    if let Some(y) = cell_number(&cell(row, ys[0])) {
+                        triples.push((cell_text(&cell(row, s)), cell(row, x), y));
+                    }
+                }
+                None => {
+                    for &yc in &ys {
+                        if let Some(y) = cell_number(&cell(row, yc)) {
+                            triples.push((result.columns[yc].name.clone(), cell(row, x), y));
+                        }
+                    }
+                }
+            }
+        }
+        // Series are keyed by their index in `names`; `None` is the folded
+        // bucket, so a real series called "Other" never merges into it.
+        let names = series_names(&triples);
+        let kept: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.as_str(), i))
+            .collect();
+        let other = other_name(&names);
+        let name_of = |key: Option<usize>| key.map_or_else(|| other.clone(), |i| names[i].clone());
+
+        if spec.kind == ResultChartKind::Scatter {
+            let mut series: Vec<PointSeries> = Vec::new();
+            let mut slot: HashMap<Option<usize>, usize> = HashMap::new();
+            for (name, xv, y) in &triples {
+                let Some(xn) = cell_number(xv) else { continue };
+                let key = kept.get(name.as_str()).copied();
+                let i = *slot.entry(key).or_insert_with(|| {
+                    series.push(PointSeries {
+                        name: name_of(key),
+                        points: Vec::new(),
+                    });
+                    series.len() - 1
+                });
+                series[i].points.push((xn, *y));
+            }
+            return Some(ChartData::Scatter { series });
+        }
+
+        // Categories in first-seen order; numeric and temporal X sort naturally.
+        let x_kind = result.columns[x].kind();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut xs: Vec<Value> = Vec::new();
+        for (_, xv, _) in &triples {
+            if seen.insert(cell_text(xv)) {
+                xs.push(xv.clone());
+            }
+        }
+        if matches!(x_kind, ColumnKind::Numeric | ColumnKind::Temporal) {
+            xs.sort_by(|a, b| compare_values(x_kind, a, b));
+        }
+        let categories: Vec<String> = xs.iter().map(cell_text).collect();
+        let position: HashMap<String, usize> = categories
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.clone(), i))
+            .collect();
+
+        let mut series: Vec<CategorySeries> = Vec::new();
+        let mut slot: HashMap<Option<usize>, usize> = HashMap::new();
+        for (name, xv, y) in &triples {
+            let key = kept.get(name.as_str()).copied();
+            let s = *slot.entry(key).or_insert_with(|| {
+                series.push(CategorySeries {
+                    name: name_of(key),
+                    values: vec![0.0; categories.len()],
+                });
+                series.len() - 1
+            });
+            series[s].values[position[&cell_text(xv)]] += y;
+        }
+        Some(ChartData::Categorical { categories, series })
+    }
+}
+
+/// Name for the folded series: "Other", numbered if a kept series already
+/// uses it.
+fn other_name(names: &[String]) -> String {
+    let taken = |n: &str| names.iter().any(|k| k == n);
+    if !taken("Other") {
+        return "Other".to_string();
+    }
+    (2..)
+        .map(|i| format!("Other ({i})"))
+        .find(|n| !taken(n))
+        .unwrap_or_default()
+}
+
+/// Series to keep, largest total first, capped at [`MAX_SERIES`].
+fn series_names(triples: &[(String, Value, f64)]) -> Vec<String> {
+    // Totals in order of first appearance, indexed by name
+    let mut totals: Vec<(String, f64)> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for (name, _, y) in triples {
+        let i = *index.entry(name).or_insert_with(|| {
+            totals.push((name.clone(), 0.0));
+            totals.len() - 1
+        });
+        totals[i].1 += y.abs();
+    }
+    if totals.len() > MAX_SERIES {
+        totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+        totals.truncate(MAX_SERIES - 1);
+    }
+    totals.into_iter().map(|(n, _)| n).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result() -> QueryResult {
+        crate::query::result(
+            &[
+                ("region", "VARCHAR"),
+                ("service", "VARCHAR"),
+                ("cost", "DECIMAL(18,2)"),
+            ],
+            vec![
+                vec![json!("us-east-1"), json!("EC2"), json!("10.5")],
+                vec![json!("eu-west-1"), json!("EC2"), json!("4")],
+                vec![json!("us-east-1"), json!("S3"), json!(2)],
+                vec![json!("us-east-1"), json!("EC2"), json!(1.5)],
+            ],
+        )
+    }
+
+    #[test]
+    fn suggests_category_and_value_columns() {
+        let spec = ResultChartSpec::suggest(&result());
+        assert_eq!(spec.x.as_deref(), Some("region"));
+        assert_eq!(spec.y, vec!["cost".to_string()]);
+        assert!(spec.fits(&result()));
+    }
+
+    #[test]
+    fn sums_values_per_category_and_series() {
+        let spec = ResultChartSpec {
+            kind: ResultChartKind::StackedBar,
+            x: Some("region".into()),
+            y: vec!["cost".into()],
+            series: Some("service".into()),
+        };
+        let Some(ChartData::Categorical { categories, series }) =
+            ChartData::build(&result(), &spec)
+        else {
+            panic!("expected categorical data");
+        };
+        assert_eq!(categories, vec!["us-east-1", "eu-west-1"]);
+        assert_eq!(series[0].name, "EC2");
+        assert_eq!(series[0].values, vec![12.0, 4.0]);
+        assert_eq!(series[1].name, "S3");
+        assert_eq!(series[1].values, vec![2.0, 0.0]);
+    }
+
+    #[test]
+    fn keeps_a_real_other_series_apart_from_the_folded_one() {
+        let mut rows: Vec<Vec<Value>> = (0..MAX_SERIES)
+            .map(|i| vec![json!("us-east-1"), json!(format!("svc{i}")), json!(100 - i)])
+            .collect();
+        rows.push(vec![json!("us-east-1"), json!("Other"), json!(500)]);
+        let result = crate::query::result(
+            &[
+                ("region", "VARCHAR"),
+                ("service", "VARCHAR"),
+                ("cost", "DOUBLE"),
+            ],
+            rows,
+        );
+        let spec = ResultChartSpec {
+            x: Some("region".into()),
+            y: vec!["cost".into()],
+            series: Some("service".into()),
+            ..Default::default()
+        };
+        let Some(ChartData::Categorical { series, .. }) = ChartData::build(&result, &spec) else {
+            panic!("expected categorical data");
+        };
+        assert_eq!(series.len(), MAX_SERIES);
+        let value = |name: &str| series.iter().find(|s| s.name == name).unwrap().values[0];
+        assert_eq!(value("Other"), 500.0);
+        // svc8 and svc9 are the two smallest and get folded
+        assert_eq!(value("Other (2)"), 92.0 + 91.0);
+    }
+
+    #[test]
+    fn keeps_a_spec_without_y_columns() {
+        let spec = ResultChartSpec {
+            x: Some("region".into()),
+            y: Vec::new(),
+            series: Some("service".into()),
+            ..Default::default()
+        };
+        assert!(spec.fits(&result()));
+        assert_eq!(ChartData::build(&result(), &spec), None);
+    }
+
+    #[test]
+    fn rejects_specs_with_missing_columns() {
+        let spec = ResultChartSpec {
+            x: Some("account".into()),
+            y: vec!["cost".into()],
+            ..Default::default()
+        };
+        assert_eq!(ChartData::build(&result(), &spec), None);
+    }
+}
@@ -0,0 +1,1096 @@
+//! MegaDB DDL generated from table metadata.
+
+use crate::export::quote_ident;
+use crate::query::SqlType;
+use crate::schema::{ColumnInfo, PartitionInfo, TableInfo};
+
+/// `CREATE TABLE` statement for `table`: columns with their comments, the
+/// primary key, engine, partitioning, sort order and compression.
+pub fn create_table(table: &TableInfo) -> String {
+    let name = table_name(table);
+    let mut lines: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| {
+            let not_null = if c.nullable { "" } else { " NOT NULL" };
+            let comment = c
+                .comment
+                .as_ref()
+                .map(|text| format!(" COMMENT {}", quote_literal(text)))
+                .unwrap_or_default();
+            format!(
+                "    {} {}{not_null}{comment}",
+                quote_ident(&c.name),
+                type_sql(&c.data_type)
+            )
+        })
+        .collect();
+    if !table.primary_key.is_empty() {
+        lines.push(format!(
+            "    PRIMARY KEY ({})",
+            ident_list(&table.primary_key)
+        ));
+    }
+
+    let mut sql = format!("CREATE TABLE {name} (\n{}\n)", lines.join(",\n"));
+    sql.push_str(&format!("\nENGINE = {}", keyword_sql(&table.engine)));
+    if !table.partitions.is_empty() {
+        let partitions: Vec<String> = table.partitions.iter().map(partition_expression).collect();
+        sql.push_str(&format!("\nPARTITION BY ({})", partitions.join(", ")));
+    }
+    if let Some(sort) = table.sort_columns.as_ref().filter(|s| !s.is_empty()) {
+        sql.push_str(&format!("\nORDER BY ({})", ident_list(sort)));
+    }
+    if let Some(compression) = &table.compression {
+        sql.push_str(&format!("\nCOMPRESSION = {}", quote_literal(compression)));
+    }
+    sql.push(';');
+    sql
+}
+
+/// Partition transform applied to its column: `month(billing_period)`,
+/// `bucket(16, account_id)`, or the bare column for `identity`.
+pub fn partition_expression(partition: &PartitionInfo) -> String {
+    let column = quote_ident(&partition.column);
+    let transform = partition.transform.trim();
+    match transform.split_once('(') {
+        _ if transform.is_empty() || transform.eq_ignore_ascii_case("identity") => column,
+        Some((func, args)) => {
+            let args = args.trim_end_matches(')').trim();
+            format!("{}({args}, {column})", func.trim().to_lowercase())
+        }
+        None => format!("{}({column})", transform.to_lowercase()),
+    }
+}
+
+/// Partition transforms MegaDB supports; `bucket` and `truncate` take a
+/// width, as in `bucket(16)`.
+pub const PARTITION_TRANSFORMS: [&str; 7] = [
+    "identity", "bucket", "truncate", "year", "month", "day", "hour",
+];
+
+/// Compression codecs offered for OLAP tables.
+pub const COMPRESSION_CODECS: [&str; 4] = ["lz4", "zstd", "snappy", "gzip"];
+
+/// Engines new tables can be created with.
+pub const ENGINES: [&str; 2] = ["OLAP", "OLTP"];
+
+/// Type names `SqlType::parse` reads as text; any other name it doesn't
+/// know also comes back as text, so these are listed to tell them apart.
+const TEXT_TYPES: [&str; 5] = ["VARCHAR", "CHAR", "CHARACTER", "TEXT", "STRING"];
+
+/// What's wrong with a table definition before it's sent to MegaDB. An
+/// empty list means the DDL can be run.
+pub fn design_problems(table: &TableInfo) -> Vec<String> {
+    let mut problems = Vec::new();
+    if table.schema_name.trim().is_empty() || table.name.trim().is_empty() {
+        problems.push("The table needs a schema and a name".to_string());
+    }
+    for (what, name) in [("schema", &table.schema_name), ("table", &table.name)] {
+        if name.contains('.') {
+            problems.push(format!("The {what} name {name} can't contain a dot"));
+        } else if !name.trim().is_empty() && name.trim() != name {
+            problems.push(format!(
+                "The {what} name \"{name}\" has leading or trailing spaces"
+            ));
+        }
+    }
+    if !ENGINES
+        .iter()
+        .any(|e| e.eq_ignore_ascii_case(table.engine.trim()))
+    {
+        problems.push(format!("Unknown engine {}; use OLAP or OLTP", table.engine));
+    }
+    if let Some(codec) = &table.compression {
+        if !COMPRESSION_CODECS
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(codec.trim()))
+        {
+            problems.push(format!("Unknown compression codec {codec}"));
+        }
+    }
+    if table.columns.is_empty() {
+        problems.push("Add at least one column".to_string());
+    }
+    let find = |name: &str| table.columns.iter().find(|c| c.name == name);
+    for (i, column) in table.columns.iter().enumerate() {
+        if column.name.trim().is_empty() {
+            problems.push(format!("Column {} has no name", i + 1));
+        } else if column.name.trim() != column.name {
+            problems.push(format!(
+                "Column \"{}\" has leading or trailing spaces",
+                column.name
+            ));
+        } else if table.columns[..i].iter().any(|c| c.name == column.name) {
+            problems.push(format!("Column {} is defined twice", column.name));
+        }
+        if column.data_type.trim().is_empty() {
+            problems.push(format!("Column {} has no type", column.name));
+        } else if !is_known_type(&column.data_type) {
+            problems.push(format!(
+                "Column {} has unknown type {}",
+                column.name, column.data_type
+            ));
+        }
+    }
+
+    for partition in &table.partitions {
+        let Some(column) = find(&partition.column) else {
+            problems.push(format!(
+                "Partition column {} doesn't exist",
+                partition.column
+            ));
+            continue;
+        };
+        let (func, width) = match partition.transform.split_once('(') {
+            Some((func, rest)) => (func.trim(), Some(rest.trim_end_matches(')').trim())),
+            None => (partition.transform.trim(), None),
+        };
+        let func = func.to_lowercase();
+        if !PARTITION_TRANSFORMS.contains(&func.as_str()) {
+            problems.push(format!(
+                "Unknown partition transform {}",
+                partition.transform
+            ));
+            continue;
+        }
+        let takes_width = func == "bucket" || func == "truncate";
+        match width {
+            _ if !takes_width && width.is_some() => {
+                problems.push(format!("{func} takes no width"));
+            }
+            Some(w) if takes_width && w.parse::<u32>().is_ok_and(|w| w > 0) => {}
+            _ if takes_width => problems.push(format!(
+                "{func} on {} needs a positive width, e.g. {func}(16)",
+                column.name
+            )),
+            _ => {}
+        }
+        let temporal = ["DATE", "TIMESTAMP"]
+            .iter()
+            .any(|t| column.data_type.to_uppercase().starts_with(t));
+        if matches!(func.as_str(), "year" | "month" | "day" | "hour") && !temporal {
+            problems.push(format!(
+                "{func} partitioning needs a DATE or TIMESTAMP column; {} is {}",
+                column.name, column.data_type
+            ));
+        }
+        if func == "hour" && column.data_type.to_uppercase().starts_with("DATE") {
+            problems.push(format!(
+                "hour partitioning needs a TIMESTAMP column; {} is a DATE",
+                column.name
+            ));
+        }
+    }
+
+    for name in table
+        .sort_columns
+        .iter()
+        .flatten()
+        .chain(&table.primary_key)
+    {
+        if find(name).is_none() {
+            problems.push(format!("Column {name} doesn't exist"));
+        }
+    }
+    if table.engine.eq_ignore_ascii_case("OLTP") && table.primary_key.is_empty() {
+        problems.push("OLTP tables need a primary key".to_string());
+    }
+    problems
+}
+
+/// Statements turning `before` into `after`. `origins[i]` is the index in
+/// `before.columns` that `after.columns[i]` was edited from, or `None` for
+/// a new column, so renames aren't mistaken for a drop and an add.
+///
+/// Changes MegaDB can't make in place (engine, primary key, the table's
+/// name) are refused with the reason.
+pub fn alter_table(
+    before: &TableInfo,
+    after: &TableInfo,
+    origins: &[Option<usize>],
+) -> Result<Vec<String>, String> {
+    if before.schema_name != after.schema_name || before.name != after.name {
+        return Err("Renaming a table isn't supported here; use ALTER TABLE ... RENAME TO".into());
+    }
+    if !before.engine.eq_ignore_ascii_case(&after.engine) {
+        return Err(format!(
+            "Changing the engine from {} to {} requires recreating the table",
+            before.engine, after.engine
+        ));
+    }
+    if before.primary_key != after.primary_key {
+        return Err("Changing the primary key requires recreating the table".into());
+    }
+
+    let table = table_name(after);
+    let alter = |change: String| format!("ALTER TABLE {table} {change};");
+    let mut statements = Vec::new();
+
+    for (i, column) in before.columns.iter().enumerate() {
+        if !origins.contains(&Some(i)) {
+            statements.push(alter(format!("DROP COLUMN {}", quote_ident(&column.name))));
+        }
+    }
+    for (column, origin) in after.columns.iter().zip(origins) {
+        let name = quote_ident(&column.name);
+        let Some(old) = origin.and_then(|i| before.columns.get(i)) else {
+            let not_null = if column.nullable { "" } else { " NOT NULL" };
+            let comment = column
+                .comment
+                .as_ref()
+                .map(|text| format!(" COMMENT {}", quote_literal(text)))
+                .unwrap_or_default();
+            statements.push(alter(format!(
+                "ADD COLUMN {name} {}{not_null}{comment}",
+                type_sql(&column.data_type)
+            )));
+            continue;
+        };
+        if old.name != column.name {
+            statements.push(alter(format!(
+                "RENAME COLUMN {} TO {name}",
+                quote_ident(&old.name)
+            )));
+        }
+        if old.data_type != column.data_type {
+            statements.push(alter(format!(
+                "ALTER COLUMN {name} SET DATA TYPE {}",
+                type_sql(&column.data_type)
+            )));
+        }
+        if old.nullable != column.nullable {
+            let change = if column.nullable { "DROP" } else { "SET" };
+            statements.push(alter(format!("ALTER COLUMN {name} {change} NOT NULL")));
+        }
+        if old.comment != column.comment {
+            let comment = column
+                .comment
+                .as_deref()
+                .map_or("NULL".to_string(), quote_literal);
+            statements.push(format!("COMMENT ON COLUMN {table}.{name} IS {comment};"));
+        }
+    }
+
+    if before.partitions != after.partitions {
+        statements.push(alter(if after.partitions.is_empty() {
+            "RESET PARTITION BY".to_string()
+        } else {
+            let partitions: Vec<String> =
+                after.partitions.iter().map(partition_expression).collect();
+            format!("SET PARTITION BY ({})", partitions.join(", "))
+        }));
+    }
+    if before.sort_columns != after.sort_columns {
+        statements.push(alter(
+            match after.sort_columns.as_ref().filter(|s| !s.is_empty()) {
+                Some(sort) => format!("SET ORDER BY ({})", ident_list(sort)),
+                None => "RESET ORDER BY".to_string(),
+            },
+        ));
+    }
+    if before.compression != after.compression {
+        statements.push(alter(match &after.compression {
+            Some(codec) => format!("SET COMPRESSION = {}", quote_literal(codec)),
+            None => "RESET COMPRESSION".to_string(),
+        }));
+    }
+    Ok(statements)
+}
+
+/// The table's schema and name, each quoted as needed, so a dot in
+/// either stays part of it.
+fn table_name(table: &TableInfo) -> String {
+    format!(
+        "{}.{}",
+        quote_ident(&table.schema_name),
+        quote_ident(&table.name)
+    )
+}
+
+/// A column type as written in DDL. Well-formed type names such as
+/// `DECIMAL(18,6)`, `TIMESTAMP WITH TIME ZONE` or `LIST<VARCHAR>` are
+/// written as they are; anything else is quoted, so MegaDB rejects it as
+/// an unknown type rather than reading it as more of the statement.
+fn type_sql(data_type: &str) -> String {
+    let data_type = data_type.trim();
+    if well_formed_type(data_type) {
+        data_type.to_string()
+    } else {
+        format!("\"{}\"", data_type.replace('"', "\"\""))
+    }
+}
+
+/// Words, with commas only inside balanced brackets.
+fn well_formed_type(data_type: &str) -> bool {
+    let mut depth = 0usize;
+    !data_type.is_empty()
+        && data_type.chars().all(|c| match c {
+            '(' | '<' | '[' => {
+                depth += 1;
+                true
+            }
+            ')' | '>' | ']' => match depth.checked_sub(1) {
+                Some(d) => {
+                    depth = d;
+                    true
+                }
+                None => false,
+            },
+            ',' => depth > 0,
+            c => c.is_ascii_alphanumeric() || c == '_' || c == ' ',
+        })
+        && depth == 0
+}
+
+/// Whether MegaDB knows the type: a well-formed name `SqlType::parse`
+/// recognises, or one of the text types.
+fn is_known_type(data_type: &str) -> bool {
+    let data_type = data_type.trim();
+    if !well_formed_type(data_type) {
+        return false;
+    }
+    match SqlType::parse(data_type) {
+        SqlType::Array(_) => {
+            let upper = data_type.to_uppercase();
+            let inner = upper.strip_suffix("[]").or_else(|| {
+                ["ARRAY<", "LIST<"]
+                    .iter()
+                    .find_map(|p| upper.strip_prefix(p).and_then(|r| r.strip_suffix('>')))
+            });
+            inner.is_some_and(is_known_type)
+        }
+        SqlType::Text => {
+            let upper = data_type.to_uppercase();
+            let base = upper.split(['(', ' ']).next().unwrap_or_default();
+            TEXT_TYPES.contains(&base)
+        }
+        _ => true,
+    }
+}
+
+/// A keyword option such as the engine, upper-cased, or quoted if it
+/// isn't a single word.
+fn keyword_sql(word: &str) -> String {
+    let word = word.trim();
+    if !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        word.to_uppercase()
+    } else {
+        format!("\"{}\"", word.replace('"', "\"\""))
+    }
+}
+
+fn ident_list(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|n| quote_ident(n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub(crate) fn quote_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Read back a `CREATE TABLE` statement in the form [`create_table`]
+/// writes. Sizes and row counts aren't part of DDL and come back empty.
+pub fn parse_create_table(sql: &str) -> Result<TableInfo, String> {
+    let tokens = tokenize(sql)?;
+    let mut p = Parser { tokens, pos: 0 };
+
+    p.expect_keyword("CREATE")?;
+    p.expect_keyword("TABLE")?;
+    if p.eat_keyword("IF") {
+        p.expect_keyword("NOT")?;
+        p.expect_keyword("EXISTS")?;
+    }
+    let first = p.ident()?;
+    let (schema_name, name) = if p.eat_symbol('.') {
+        (first, p.ident()?)
+    } else {
+        ("public".to_string(), first)
+    };
+
+    let mut table = TableInfo {
+        schema_name,
+        name,
+        engine: "OLAP".to_string(),
+        row_count: None,
+        size_bytes: None,
+        columns: Vec::new(),
+        partitions: Vec::new(),
+        compression: None,
+        sort_columns: None,
+        primary_key: Vec::new(),
+    };
+
+    p.expect_symbol('(')?;
+    loop {
+        if p.eat_keyword("PRIMARY") {
+            p.expect_keyword("KEY")?;
+            table.primary_key = p.ident_list()?;
+        } else {
+            let (column, primary) = p.column()?;
+            if primary {
+                table.primary_key.push(column.name.clone());
+            }
+            table.columns.push(column);
+        }
+        if !p.eat_symbol(',') {
+            break;
+        }
+    }
+    p.expect_symbol(')')?;
+
+    while let Some(token) = p.next() {
+        match token {
+            Tok::Symbol(';') => break,
+            Tok::Word(w) if w.eq_ignore_ascii_case("ENGINE") => {
+                p.eat_symbol('=');
+                table.engine = p.word()?.to_uppercase();
+            }
+            Tok::Word(w) if w.eq_ignore_ascii_case("PARTITION") => {
+                p.expect_keyword("BY")?;
+                table.partitions = p.partitions()?;
+            }
+            Tok::Word(w) if w.eq_ignore_ascii_case("ORDER") => {
+                p.expect_keyword("BY")?;
+                table.sort_columns = Some(p.ident_list()?);
+            }
+            Tok::Word(w) if w.eq_ignore_ascii_case("COMPRESSION") => {
+                p.eat_symbol('=');
+                table.compression = Some(match p.next() {
+                    Some(Tok::Str(s) | Tok::Word(s)) => s,
+                    _ => return Err("Expected a compression codec".into()),
+                });
+            }
+            other => return Err(format!("Unexpected {other} after the column list")),
+        }
+    }
+    Ok(table)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    /// Keyword, unquoted identifier or number.
+    Word(String),
+    /// Double-quoted identifier, unescaped.
+    Quoted(String),
+    /// String literal, unescaped.
+    Str(String),
+    Symbol(char),
+}
+
+impl std::fmt::Display for Tok {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tok::Word(w) => write!(f, "'{w}'"),
+            Tok::Quoted(q) => write!(f, "\"{q}\""),
+            Tok::Str(s) => write!(f, "string '{s}'"),
+            Tok::Symbol(c) => write!(f, "'{c}'"),
+        }
+    }
+}
+
+/// Read up to the closing `quote`; doubled quotes stand for one.
+fn quoted(chars: &mut std::iter::Peekable<std::str::Chars>, quote: char) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some(c) if c == quote => {
+                if chars.peek() == Some(&quote) {
+                    chars.next();
+                    text.push(quote);
+                } else {
+                    return Ok(text);
+                }
+            }
+            Some(c) => text.push(c),
+            None => return Err(format!("Unterminated {quote}")),
+        }
+    }
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Tok>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '\'' => tokens.push(Tok::Str(quoted(&mut chars, '\'')?)),
+            '"' => tokens.push(Tok::Quoted(quoted(&mut chars, '"')?)),
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '$' {
+                        word.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Tok::Word(word));
+            }
+            c => tokens.push(Tok::Symbol(c)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Tok>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let at = self.at_keyword(keyword);
+        if at {
+            self.pos += 1;
+        }
+        at
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!("Expected {keyword}{}", self.found()))
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: char) -> bool {
+        let at = self.peek() == Some(&Tok::Symbol(symbol));
+        if at {
+            self.pos += 1;
+        }
+        at
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{symbol}'{}", self.found()))
+        }
+    }
+
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(token) => format!(", found {token}"),
+            None => " before the end".to_string(),
+        }
+    }
+
+    fn word(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Tok::Word(w)) => Ok(w),
+            _ => {
+                self.pos -= 1;
+                Err(format!("Expected a word{}", self.found()))
+            }
+        }
+    }
+
+    /// Identifier: unquoted ones are folded to lower case.
+    fn ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Tok::Word(w)) => Ok(w.to_lowercase()),
+            Some(Tok::Quoted(q)) => Ok(q),
+            _ => {
+                self.pos -= 1;
+                Err(format!("Expected a name{}", self.found()))
+            }
+        }
+    }
+
+    /// `(a, b)`, or a single bare name.
+    fn ident_list(&mut self) -> Result<Vec<String>, String> {
+        if !self.eat_symbol('(') {
+            return Ok(vec![self.ident()?]);
+        }
+        let mut names = vec![self.ident()?];
+        while self.eat_symbol(',') {
+            names.push(self.ident()?);
+        }
+        self.expect_symbol(')')?;
+        Ok(names)
+    }
+
+    /// A column definition; the flag tells whether it's marked PRIMARY KEY.
+    fn column(&mut self) -> Result<(ColumnInfo, bool), String> {
+        let name = self.ident()?;
+        let data_type = self.data_type()?;
+        let mut column = ColumnInfo {
+            name,
+            data_type,
+            nullable: true,
+            comment: None,
+        };
+        let mut primary = false;
+        loop {
+            if self.eat_keyword("NOT") {
+                self.expect_keyword("NULL")?;
+                column.nullable = false;
+            } else if self.eat_keyword("NULL") {
+                column.nullable = true;
+            } else if self.eat_keyword("COMMENT") {
+                match self.next() {
+                    Some(Tok::Str(text)) => column.comment = Some(text),
+                    _ => return Err(format!("Expected the comment of {}", column.name)),
+                }
+            } else if self.eat_keyword("PRIMARY") {
+                self.expect_keyword("KEY")?;
+                column.nullable = false;
+                primary = true;
+            } else {
+                return Ok((column, primary));
+            }
+        }
+    }
+
+    /// Type words and their arguments, as in `DECIMAL(18,6)` or
+    /// `TIMESTAMP WITH TIME ZONE`.
+    fn data_type(&mut self) -> Result<String, String> {
+        const ENDS: [&str; 4] = ["NOT", "NULL", "COMMENT", "PRIMARY"];
+        let mut text = String::new();
+        let mut depth = 0;
+        while let Some(token) = self.peek() {
+            let at_end = match token {
+                Tok::Word(w) => depth == 0 && ENDS.iter().any(|e| w.eq_ignore_ascii_case(e)),
+                Tok::Symbol(',' | ')') => depth == 0,
+                _ => false,
+            };
+            if at_end {
+                break;
+            }
+            match self.next() {
+                Some(Tok::Word(w)) => {
+                    if text.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                        text.push(' ');
+                    }
+                    text.push_str(&w);
+                }
+                Some(Tok::Symbol(c)) => {
+                    match c {
+                        '(' | '<' => depth += 1,
+                        ')' | '>' => depth -= 1,
+                        _ => {}
+                    }
+                    text.push(c);
+                }
+                Some(other) => return Err(format!("Unexpected {other} in a column type")),
+                None => break,
+            }
+        }
+        if text.is_empty() {
+            return Err(format!("Expected a column type{}", self.found()));
+        }
+        Ok(text)
+    }
+
+    /// `(month(ts), bucket(16, id), region)`.
+    fn partitions(&mut self) -> Result<Vec<PartitionInfo>, String> {
+        self.expect_symbol('(')?;
+        let mut partitions = Vec::new();
+        loop {
+            let first = self.ident()?;
+            let partition = if self.eat_symbol('(') {
+                let mut width = None;
+                if let Some(Tok::Word(w)) = self.peek() {
+                    if w.chars().all(|c| c.is_ascii_digit()) {
+                        width = Some(w.clone());
+                        self.pos += 1;
+                        self.expect_symbol(',')?;
+                    }
+                }
+                let column = self.ident()?;
+                self.expect_symbol(')')?;
+                PartitionInfo {
+                    column,
+                    transform: match width {
+                        Some(width) => format!("{first}({width})"),
+                        None => first,
+                    },
+                }
+            } else {
+                PartitionInfo {
+                    column: first,
+                    transform: "identity".to_string(),
+                }
+            };
+            partitions.push(partition);
+            if !self.eat_symbol(',') {
+                break;
+            }
+        }
+        self.expect_symbol(')')?;
+        Ok(partitions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_create_table() {
+        let table = TableInfo {
+            schema_name: "public".into(),
+            name: "cost_import".into(),
+            engine: "olap".into(),
+            row_count: None,
+            size_bytes: None,
+            columns: vec![
+                ColumnInfo {
+                    name: "account_id".into(),
+                    data_type: "VARCHAR".into(),
+                    nullable: false,
+                    comment: None,
+                },
+                ColumnInfo {
+                    name: "Cost".into(),
+                    data_type: "DECIMAL(18,6)".into(),
+                    nullable: true,
+                    comment: None,
+                },
+                ColumnInfo {
+                    name: "billing_period".into(),
+                    data_type: "DATE".into(),
+                    nullable: false,
+                    comment: None,
+                },
+            ],
+            partitions: vec![
+                PartitionInfo {
+                    column: "billing_period".into(),
+                    transform: "month".into(),
+                },
+                PartitionInfo {
+                    column: "account_id".into(),
+                    transform: "bucket(16)".into(),
+                },
+            ],
+            compression: None,
+            sort_columns: Some(vec!["account_id".into(), "Cost".into()]),
+            primary_key: vec![],
+        };
+        assert_eq!(
+            create_table(&table),
+            "CREATE TABLE public.cost_import (\n    \
+             account_id VARCHAR NOT NULL,\n    \
+             \"Cost\" DECIMAL(18,6),\n    \
+             billing_period DATE NOT NULL\n)\n\
+             ENGINE = OLAP\n\
+             PARTITION BY (month(billing_period), bucket(16, account_id))\n\
+             ORDER BY (account_id, \"Cost\");"
+        );
+        assert_eq!(
+            partition_expression(&PartitionInfo {
+                column: "region".into(),
+                transform: "identity".into(),
+            }),
+            "region"
+        );
+
+        // Dots stay within the schema or table name; malformed types and
+        // engines are quoted rather than spliced into the statement
+        let odd = TableInfo {
+            schema_name: "billing.v2".into(),
+            name: "cost".into(),
+            engine: "OLAP; DROP TABLE x".into(),
+            columns: vec![
+                column("a", "INT, b INT", true, None),
+                column("c", "LIST<DECIMAL(18,6)>", true, None),
+            ],
+            partitions: vec![],
+            sort_columns: None,
+            ..table
+        };
+        assert_eq!(
+            create_table(&odd),
+            "CREATE TABLE \"billing.v2\".cost (\n    \
+             a \"INT, b INT\",\n    \
+             c LIST<DECIMAL(18,6)>\n)\n\
+             ENGINE = \"OLAP; DROP TABLE x\";"
+        );
+    }
+
+    fn column(name: &str, data_type: &str, nullable: bool, comment: Option<&str>) -> ColumnInfo {
+        ColumnInfo {
+            name: name.into(),
+            data_type: data_type.into(),
+            nullable,
+            comment: comment.map(Into::into),
+        }
+    }
+
+    fn representative_tables() -> Vec<TableInfo> {
+        let base = |name: &str, engine: &str, columns: Vec<ColumnInfo>| TableInfo {
+            schema_name: "public".into(),
+            name: name.into(),
+            engine: engine.into(),
+            row_count: None,
+            size_bytes: None,
+            columns,
+            partitions: vec![],
+            compression: None,
+            sort_columns: None,
+            primary_key: vec![],
+        };
+        let mut cur = base(
+            "cur_data",
+            "OLAP",
+            vec![
+                column("account_id", "VARCHAR", false, None),
+                column(
+                    "cost",
+                    "DECIMAL(18,6)",
+                    false,
+                    Some("Unblended cost in USD"),
+                ),
+                column("billing_period", "DATE", false, None),
+                column(
+                    "usage_start",
+                    "TIMESTAMP WITH TIME ZONE",
+                    true,
+                    Some("It's UTC"),
+                ),
+            ],
+        );
+        cur.partitions = vec![
+            PartitionInfo {
+                column: "billing_period".into(),
+                transform: "month".into(),
+            },
+            PartitionInfo {
+                column: "account_id".into(),
+                transform: "bucket(16)".into(),
+            },
+            PartitionInfo {
+                column: "usage_start".into(),
+                transform: "identity".into(),
+            },
+        ];
+        cur.compression = Some("Zstd".into());
+        cur.sort_columns = Some(vec!["account_id".into(), "billing_period".into()]);
+
+        let mut resources = base(
+            "cmdb_resources",
+            "OLTP",
+            vec![
+                column("resource_id", "VARCHAR(64)", false, None),
+                column("Resource Type", "VARCHAR", false, None),
+                column("tags", "JSONB", true, None),
+            ],
+        );
+        resources.schema_name = "Inventory".into();
+        resources.primary_key = vec!["resource_id".into(), "Resource Type".into()];
+
+        let mut alerts = base(
+            "cost_alerts",
+            "MEMORY",
+            vec![
+                column("alert_id", "UUID", false, None),
+                column("thresholds", "LIST<DECIMAL(18,2)>", true, None),
+            ],
+        );
+        alerts.primary_key = vec!["alert_id".into()];
+        vec![cur, resources, alerts]
+    }
+
+    #[test]
+    fn create_table_round_trips() {
+        for table in representative_tables() {
+            let sql = create_table(&table);
+            assert_eq!(parse_create_table(&sql), Ok(table), "{sql}");
+        }
+        let sql = create_table(&representative_tables()[0]);
+        assert!(sql.contains("cost DECIMAL(18,6) NOT NULL COMMENT 'Unblended cost in USD',"));
+        assert!(sql.contains("COMMENT 'It''s UTC'"));
+        assert!(sql.ends_with("ORDER BY (account_id, billing_period)\nCOMPRESSION = 'Zstd';"));
+        let sql = create_table(&representative_tables()[1]);
+        assert!(sql.starts_with("CREATE TABLE \"Inventory\".cmdb_resources ("));
+        assert!(sql.contains("    PRIMARY KEY (resource_id, \"Resource Type\")\n)"));
+    }
+
+    #[test]
+    fn parses_hand_written_ddl() {
+        let table = parse_create_table(
+            "create table if not exists Events (\n\
+             id BIGINT primary key, -- surrogate\n\
+             Kind varchar(20) null, at timestamp not null\n\
+             ) engine = olap partition by (day(at)) order by at",
+        )
+        .unwrap();
+        assert_eq!(
+            (table.schema_name.as_str(), table.name.as_str()),
+            ("public", "events")
+        );
+        assert_eq!(table.primary_key, ["id"]);
+        assert!(!table.columns[0].nullable);
+        assert_eq!(table.columns[1].data_type, "varchar(20)");
+        assert_eq!(table.partitions[0].transform, "day");
+        assert_eq!(table.sort_columns, Some(vec!["at".to_string()]));
+
+        assert!(parse_create_table("CREATE TABLE t (id INT").is_err());
+        assert!(parse_create_table("CREATE TABLE t (id INT) WITH (x = 1)").is_err());
+    }
+
+    #[test]
+    fn checks_designs() {
+        let mut table = representative_tables().remove(0);
+        assert!(design_problems(&table).is_empty());
+
+        table.partitions.push(PartitionInfo {
+            column: "cost".into(),
+            transform: "month".into(),
+        });
+        table.partitions.push(PartitionInfo {
+            column: "account_id".into(),
+            transform: "truncate".into(),
+        });
+        table.columns.push(column("cost", "DOUBLE", true, None));
+        table.sort_columns = Some(vec!["missing".into()]);
+        assert_eq!(
+            design_problems(&table),
+            [
+                "Column cost is defined twice",
+                "month partitioning needs a DATE or TIMESTAMP column; cost is DECIMAL(18,6)",
+                "truncate on account_id needs a positive width, e.g. truncate(16)",
+                "Column missing doesn't exist",
+            ]
+        );
+        assert_eq!(
+            design_problems(&representative_tables()[1]),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_types_engines_and_names() {
+        let mut table = representative_tables().remove(1);
+        table.columns[0].data_type = "INT; DROP TABLE x".into();
+        table.columns[1].data_type = "VARCHR".into();
+        table
+            .columns
+            .push(column("labels", "LIST<VARCHAR>", true, None));
+        table.columns.push(column("ids", "BIGINT[]", true, None));
+        table
+            .columns
+            .push(column("blobs", "LIST<BLOBBY>", true, None));
+        assert_eq!(
+            design_problems(&table),
+            [
+                format!(
+                    "Column {} has unknown type INT; DROP TABLE x",
+                    table.columns[0].name
+                ),
+                format!("Column {} has unknown type VARCHR", table.columns[1].name),
+                "Column blobs has unknown type LIST<BLOBBY>".to_string(),
+            ]
+        );
+
+        let mut table = representative_tables().remove(1);
+        table.engine = "OLAP ENGINE".into();
+        table.compression = Some("brotli".into());
+        assert_eq!(
+            design_problems(&table),
+            [
+                "Unknown engine OLAP ENGINE; use OLAP or OLTP",
+                "Unknown compression codec brotli",
+            ]
+        );
+        table.engine = "MEMORY".into();
+        table.compression = None;
+        assert_eq!(
+            design_problems(&table),
+            ["Unknown engine MEMORY; use OLAP or OLTP"]
+        );
+
+        let mut table = representative_tables().remove(1);
+        table.schema_name = "billing.v2".into();
+        table.name = " resources".into();
+        table.columns[2].name = "tags ".into();
+        assert_eq!(
+            design_problems(&table),
+            [
+                "The schema name billing.v2 can't contain a dot",
+                "The table name \" resources\" has leading or trailing spaces",
+                "Column \"tags \" has leading or trailing spaces",
+            ]
+        );
+        table.schema_name = "  ".into();
+        table.name = "a.b".into();
+        table.columns[2].name = " ".into();
+        table.columns[2].data_type = "\t".into();
+        assert_eq!(
+            design_problems(&table),
+            [
+                "The table needs a schema and a name",
+                "The table name a.b can't contain a dot",
+                "Column 3 has no name",
+                "Column   has no type",
+            ]
+        );
+    }
+
+    #[test]
+    fn alters_existing_tables() {
+        let before = representative_tables().remove(0);
+        let mut after = before.clone();
+        // Drop usage_start, rename cost, add a column, change partitioning
+        after.remove_column(3);
+        after.rename_column(1, "net_cost".into());
+        after.columns[1].comment = None;
+        after.columns[0].nullable = true;
+        after
+            .columns
+            .push(column("region", "VARCHAR", true, Some("AWS region")));
+        after.partitions.truncate(1);
+        after.compression = None;
+        let origins = [Some(0), Some(1), Some(2), None];
+
+        assert_eq!(
+            alter_table(&before, &after, &origins).unwrap(),
+            [
+                "ALTER TABLE public.cur_data DROP COLUMN usage_start;",
+                "ALTER TABLE public.cur_data ALTER COLUMN account_id DROP NOT NULL;",
+                "ALTER TABLE public.cur_data RENAME COLUMN cost TO net_cost;",
+                "COMMENT ON COLUMN public.cur_data.net_cost IS NULL;",
+                "ALTER TABLE public.cur_data ADD COLUMN region VARCHAR COMMENT 'AWS region';",
+                "ALTER TABLE public.cur_data SET PARTITION BY (month(billing_period));",
+                "ALTER TABLE public.cur_data RESET COMPRESSION;",
+            ]
+        );
+        assert_eq!(
+            alter_table(&before, &before, &[Some(0), Some(1), Some(2), Some(3)]),
+            Ok(vec![])
+        );
+
+        let mut oltp = before.clone();
+        oltp.engine = "OLTP".into();
+        assert!(alter_table(&before, &oltp, &[]).is_err());
+    }
+}
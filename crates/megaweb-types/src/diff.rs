@@ -0,0 +1,327 @@
+//! Row-level comparison of two query results.
+//!
+//! Rows are aligned by key columns chosen by the user; with no keys the
+//! whole row is the key, so only added and removed rows can be reported.
+//! Columns are aligned by name.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde_json::Value;
+
+use crate::query::{ColumnKind, QueryColumn, QueryResult};
+use crate::result_view::{cell_number, cell_text};
+
+/// How a row differs between the two results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowStatus {
+    Added,
+    Removed,
+    Changed,
+    Unchanged,
+}
+
+/// A cell whose value differs between the two results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellChange {
+    pub before: Value,
+    pub after: Value,
+    /// `after - before`, when both values are numbers.
+    pub delta: Option<f64>,
+}
+
+/// One row of a [`ResultDiff`], with values for each of its columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffRow {
+    pub status: RowStatus,
+    /// Current values, or the earlier ones for removed rows.
+    pub values: Vec<Value>,
+    /// Per column; `Some` only for changed cells.
+    pub changes: Vec<Option<CellChange>>,
+}
+
+/// Differences between an earlier (`before`) and a current (`after`) result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultDiff {
+    /// Columns present in both results, in the current result's order.
+    pub columns: Vec<QueryColumn>,
+    /// Columns only in the current result.
+    pub added_columns: Vec<String>,
+    /// Columns only in the earlier result.
+    pub removed_columns: Vec<String>,
+    /// Key columns actually used; requested keys missing from either result
+    /// are dropped.
+    pub keys: Vec<String>,
+    /// Current rows in order, followed by removed rows.
+    pub rows: Vec<DiffRow>,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+impl ResultDiff {
+    /// Compare `before` with `after`, matching rows on `keys`. Rows sharing
+    /// a key are paired in the order they appear.
+    pub fn compute(before: &QueryResult, after: &QueryResult, keys: &[String]) -> Self {
+        let position =
+            |result: &QueryResult, name: &str| result.columns.iter().position(|c| c.name == name);
+        // (index in after, index in before) for each shared column
+        let shared: Vec<(usize, usize)> = after
+            .columns
+            .iter()
+            .enumerate()
+            .filter_map(|(a, c)| position(before, &c.name).map(|b| (a, b)))
+            .collect();
+        let added_columns = after
+            .columns
+            .iter()
+            .filter(|c| position(before, &c.name).is_none())
+            .map(|c| c.name.clone())
+            .collect();
+        let removed_columns = before
+            .columns
+            .iter()
+            .filter(|c| position(after, &c.name).is_none())
+            .map(|c| c.name.clone())
+            .collect();
+        let columns: Vec<QueryColumn> = shared
+            .iter()
+            .map(|&(a, _)| after.columns[a].clone())
+            .collect();
+        let kinds: Vec<ColumnKind> = columns
+            .iter()
+            .map(|c| ColumnKind::from_data_type(&c.data_type))
+            .collect();
+
+        let keys: Vec<String> = keys
+            .iter()
+            .filter(|k| columns.iter().any(|c| &c.name == *k))
+            .cloned()
+            .collect();
+        let key_cols: Vec<usize> = if keys.is_empty() {
+            (0..shared.len()).collect()
+        } else {
+            keys.iter()
+                .filter_map(|k| columns.iter().position(|c| &c.name == k))
+                .collect()
+        };
+        let key_of = |row: &[Value], side: fn(&(usize, usize)) -> usize| -> Vec<String> {
+            key_cols
+                .iter()
+                .map(|&i| {
+                    let kind = kinds[i];
+                    key_text(kind, row.get(side(&shared[i])).unwrap_or(&Value::Null))
+                })
+                .collect()
+        };
+
+        let mut pending: HashMap<Vec<String>, VecDeque<usize>> = HashMap::new();
+        for (i, row) in before.rows.iter().enumerate() {
+            pending
+                .entry(key_of(row, |s| s.1))
+                .or_default()
+                .push_back(i);
+        }
+
+        let mut diff = ResultDiff {
+            columns,
+            added_columns,
+            removed_columns,
+            keys,
+            rows: Vec::with_capacity(after.rows.len()),
+            added: 0,
+            removed: 0,
+            changed: 0,
+            unchanged: 0,
+        };
+        let mut matched = vec![false; before.rows.len()];
+        for row in &after.rows {
+            let values: Vec<Value> = shared
+                .iter()
+                .map(|&(a, _)| row.get(a).cloned().unwrap_or(Value::Null))
+                .collect();
+            let earlier = pending
+                .get_mut(&key_of(row, |s| s.0))
+                .and_then(VecDeque::pop_front);
+            let Some(b) = earlier else {
+                diff.added += 1;
+                diff.rows.push(DiffRow {
+                    status: RowStatus::Added,
+                    changes: vec![None; values.len()],
+                    values,
+                });
+                continue;
+            };
+            matched[b] = true;
+            let changes: Vec<Option<CellChange>> = shared
+                .iter()
+                .enumerate()
+                .map(|(i, &(_, bc))| {
+                    let old = before.rows[b].get(bc).unwrap_or(&Value::Null);
+                    cell_change(kinds[i], old, &values[i])
+                })
+                .collect();
+            let status = if changes.iter().any(Option::is_some) {
+                diff.changed += 1;
+                RowStatus::Changed
+            } else {
+                diff.unchanged += 1;
+                RowStatus::Unchanged
+            };
+            diff.rows.push(DiffRow {
+                status,
+                values,
+                changes,
+            });
+        }
+        for (b, row) in before.rows.iter().enumerate() {
+            if matched[b] {
+                continue;
+            }
+            diff.removed += 1;
+            diff.rows.push(DiffRow {
+                status: RowStatus::Removed,
+                values: shared
+                    .iter()
+                    .map(|&(_, bc)| row.get(bc).cloned().unwrap_or(Value::Null))
+                    .collect(),
+                changes: vec![None; shared.len()],
+            });
+        }
+        diff
+    }
+
+    /// Whether the two results hold the same rows and columns.
+    pub fn is_identical(&self) -> bool {
+        self.added == 0
+            && self.removed == 0
+            && self.changed == 0
+            && self.added_columns.is_empty()
+            && self.removed_columns.is_empty()
+    }
+}
+
+/// Columns of both results likely to identify a row: the non-numeric ones.
+pub fn suggest_keys(before: &QueryResult, after: &QueryResult) -> Vec<String> {
+    after
+        .columns
+        .iter()
+        .filter(|c| before.columns.iter().any(|b| b.name == c.name))
+        .filter(|c| ColumnKind::from_data_type(&c.data_type) != ColumnKind::Numeric)
+        .map(|c| c.name.clone())
+        .collect()
+}
+
+/// Text a key cell is matched on. Numbers are normalized so `"1.50"` and
+/// `1.5` match.
+fn key_text(kind: ColumnKind, value: &Value) -> String {
+    match (kind, cell_number(value)) {
+        (ColumnKind::Numeric, Some(n)) => n.to_string(),
+        _ => cell_text(value),
+    }
+}
+
+fn cell_change(kind: ColumnKind, before: &Value, after: &Value) -> Option<CellChange> {
+    let numbers = match kind {
+        ColumnKind::Numeric => cell_number(before).zip(cell_number(after)),
+        _ => None,
+    };
+    let same = match numbers {
+        Some((b, a)) => a == b,
+        None => before.is_null() == after.is_null() && cell_text(before) == cell_text(after),
+    };
+    (!same).then(|| CellChange {
+        before: before.clone(),
+        after: after.clone(),
+        delta: numbers.map(|(b, a)| a - b),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::result;
+    use serde_json::json;
+
+    #[test]
+    fn aligns_rows_by_key_and_reports_deltas() {
+        let cols = [("service", "VARCHAR"), ("cost", "DECIMAL(18,2)")];
+        let before = result(
+            &cols,
+            vec![
+                vec![json!("EC2"), json!("10.00")],
+                vec![json!("S3"), json!("5.50")],
+                vec![json!("RDS"), json!("7")],
+            ],
+        );
+        let after = result(
+            &cols,
+            vec![
+                vec![json!("S3"), json!("5.5")],
+                vec![json!("EC2"), json!(12.5)],
+                vec![json!("Lambda"), json!("1")],
+            ],
+        );
+        let diff = ResultDiff::compute(&before, &after, &["service".to_string()]);
+
+        assert_eq!(
+            (diff.added, diff.removed, diff.changed, diff.unchanged),
+            (1, 1, 1, 1)
+        );
+        let statuses: Vec<RowStatus> = diff.rows.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                RowStatus::Unchanged,
+                RowStatus::Changed,
+                RowStatus::Added,
+                RowStatus::Removed
+            ]
+        );
+        let change = diff.rows[1].changes[1].as_ref().unwrap();
+        assert_eq!(change.delta, Some(2.5));
+        assert!(diff.rows[1].changes[0].is_none());
+        assert_eq!(diff.rows[3].values[0], json!("RDS"));
+    }
+
+    #[test]
+    fn without_keys_whole_rows_are_matched() {
+        let cols = [("a", "INTEGER"), ("b", "VARCHAR")];
+        let before = result(
+            &cols,
+            vec![vec![json!(1), json!("x")], vec![json!(1), json!("x")]],
+        );
+        let after = result(
+            &cols,
+            vec![vec![json!(1), json!("y")], vec![json!(1), json!("x")]],
+        );
+        let diff = ResultDiff::compute(&before, &after, &[]);
+
+        assert_eq!(
+            (diff.added, diff.removed, diff.changed, diff.unchanged),
+            (1, 1, 0, 1)
+        );
+        assert!(!diff.is_identical());
+    }
+
+    #[test]
+    fn columns_are_aligned_by_name() {
+        let before = result(
+            &[("id", "BIGINT"), ("old", "VARCHAR"), ("n", "BIGINT")],
+            vec![vec![json!(1), json!("gone"), json!(3)]],
+        );
+        let after = result(
+            &[("n", "BIGINT"), ("id", "BIGINT"), ("new", "VARCHAR")],
+            vec![vec![json!(3), json!(1), json!("here")]],
+        );
+        let diff = ResultDiff::compute(&before, &after, &["id".to_string(), "missing".to_string()]);
+
+        let names: Vec<&str> = diff.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["n", "id"]);
+        assert_eq!(diff.keys, ["id"]);
+        assert_eq!(diff.added_columns, ["new"]);
+        assert_eq!(diff.removed_columns, ["old"]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(suggest_keys(&before, &after), Vec::<String>::new());
+    }
+}
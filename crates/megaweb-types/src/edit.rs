@@ -0,0 +1,836 @@
+//! Inline edits of a query result over a single OLTP table, and the
+//! UPDATE/INSERT/DELETE statements that apply them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::export::{quote_ident, quote_table, sql_literal};
+use crate::query::{ColumnKind, QueryResult};
+use crate::schema::TableInfo;
+
+/// Clauses that may follow the table in an editable query.
+const TRAILING_CLAUSES: [&str; 5] = ["where", "order", "limit", "offset", "fetch"];
+
+/// Keywords of statements that change data or schema, wherever they appear.
+/// `into` covers `SELECT ... INTO`, which creates a table.
+const WRITE_KEYWORDS: [&str; 14] = [
+    "insert", "update", "delete", "merge", "upsert", "into", "create", "drop", "alter", "truncate",
+    "grant", "revoke", "copy", "call",
+];
+
+/// Top-level keywords that make a result something other than table rows.
+const AGGREGATING_KEYWORDS: [&str; 8] = [
+    "join",
+    "union",
+    "intersect",
+    "except",
+    "group",
+    "having",
+    "distinct",
+    "window",
+];
+
+/// Why a result can't be edited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditRefusal {
+    /// The query isn't a plain `SELECT` from one table.
+    NotSingleTable,
+    UnknownTable(String),
+    /// Only OLTP tables accept row-level changes.
+    ReadOnlyEngine {
+        table: String,
+        engine: String,
+    },
+    NoPrimaryKey(String),
+    /// A primary key column isn't selected as a bare column reference.
+    KeyNotSelected(String),
+}
+
+impl fmt::Display for EditRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotSingleTable => write!(
+                f,
+                "Only results of a SELECT from a single table, without joins or grouping, can be edited"
+            ),
+            Self::UnknownTable(table) => write!(f, "Table {table} wasn't found in the schema"),
+            Self::ReadOnlyEngine { table, engine } => write!(
+                f,
+                "{table} is an {engine} table. Only OLTP tables can be edited; OLAP and MEMORY tables are read-only"
+            ),
+            Self::NoPrimaryKey(table) => write!(
+                f,
+                "{table} has no primary key, so edited rows can't be identified"
+            ),
+            Self::KeyNotSelected(column) => write!(
+                f,
+                "Select the primary key column {column} by name, without an alias, to edit rows"
+            ),
+        }
+    }
+}
+
+/// Table named in the `FROM` clause of a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRef {
+    pub schema: Option<String>,
+    pub name: String,
+}
+
+impl TableRef {
+    pub fn matches(&self, table: &TableInfo) -> bool {
+        self.name.eq_ignore_ascii_case(&table.name)
+            && self
+                .schema
+                .as_ref()
+                .is_none_or(|s| s.eq_ignore_ascii_case(&table.schema_name))
+    }
+}
+
+impl fmt::Display for TableRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.schema {
+            Some(schema) => write!(f, "{schema}.{}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// The table a query reads from, if it is a single-statement `SELECT` from
+/// one table, optionally aliased and followed only by `WHERE`, `ORDER BY`,
+/// `LIMIT`, `OFFSET` or `FETCH`. Subqueries in parentheses are allowed.
+pub fn single_table(sql: &str) -> Option<TableRef> {
+    let mut tokens = tokenize(sql);
+    while tokens.last() == Some(&Token::Symbol(';')) {
+        tokens.pop();
+    }
+    if !tokens.first().is_some_and(|t| t.is_keyword("select")) {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    let mut from = None;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Symbol('(') => depth += 1,
+            Token::Symbol(')') => depth = depth.saturating_sub(1),
+            Token::Symbol(';') => return None,
+            Token::Word(word) if depth == 0 => {
+                let word = word.to_ascii_lowercase();
+                if AGGREGATING_KEYWORDS.contains(&word.as_str()) {
+                    return None;
+                }
+                if word == "from" {
+                    if from.is_some() {
+                        return None;
+                    }
+                    from = Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let rest = &tokens[from? + 1..];
+    let mut parts = Vec::new();
+    let mut i = 0;
+    loop {
+        match rest.get(i)? {
+            Token::Word(w) | Token::Quoted(w) => parts.push(w.clone()),
+            _ => return None,
+        }
+        i += 1;
+        if rest.get(i) == Some(&Token::Symbol('.')) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+
+    let mut tail = &rest[i..];
+    match tail.first() {
+        Some(t) if t.is_keyword("as") => match tail.get(1) {
+            Some(Token::Word(_) | Token::Quoted(_)) => tail = &tail[2..],
+            _ => return None,
+        },
+        Some(Token::Word(w)) if !is_trailing_clause(w) => tail = &tail[1..],
+        Some(Token::Quoted(_)) => tail = &tail[1..],
+        _ => {}
+    }
+    match tail.first() {
+        None => {}
+        Some(Token::Word(w)) if is_trailing_clause(w) => {}
+        _ => return None,
+    }
+
+    let name = parts.pop()?;
+    Some(TableRef {
+        schema: parts.pop(),
+        name,
+    })
+}
+
+/// Whether `sql` is a single `SELECT` (or `WITH ... SELECT`) that can't
+/// change anything, so it is safe to run again, e.g. for an export.
+pub fn is_read_only(sql: &str) -> bool {
+    let mut tokens = tokenize(sql);
+    while tokens.last() == Some(&Token::Symbol(';')) {
+        tokens.pop();
+    }
+    tokens
+        .first()
+        .is_some_and(|t| t.is_keyword("select") || t.is_keyword("with"))
+        && !tokens.iter().any(|t| match t {
+            Token::Symbol(';') => true,
+            Token::Word(w) => WRITE_KEYWORDS.contains(&w.to_ascii_lowercase().as_str()),
+            _ => false,
+        })
+}
+
+/// An item of a select list, as far as editing cares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SelectItem {
+    /// `*` or `t.*`: every table column, in order.
+    All,
+    /// A bare, unaliased column reference such as `owner` or `r."Owner"`.
+    Column(String),
+    /// Anything else: expressions, literals and aliased columns.
+    Computed,
+}
+
+/// The items of a `SELECT` list, up to the top-level `FROM`.
+fn select_items(sql: &str) -> Vec<SelectItem> {
+    let tokens = tokenize(sql);
+    let mut items = Vec::new();
+    let mut item: Vec<&Token> = Vec::new();
+    let mut depth = 0usize;
+    for token in tokens.iter().skip(1) {
+        match token {
+            Token::Symbol('(') => depth += 1,
+            Token::Symbol(')') => depth = depth.saturating_sub(1),
+            t if depth == 0 && t.is_keyword("from") => break,
+            Token::Symbol(',') if depth == 0 => {
+                items.push(select_item(&item));
+                item.clear();
+                continue;
+            }
+            _ => {}
+        }
+        item.push(token);
+    }
+    items.push(select_item(&item));
+    items
+}
+
+fn select_item(tokens: &[&Token]) -> SelectItem {
+    // Names separated by dots, the last of which may be `*`
+    let mut name = None;
+    for (i, token) in tokens.iter().enumerate() {
+        match (i % 2, token) {
+            (0, Token::Word(w) | Token::Quoted(w)) => name = Some(SelectItem::Column(w.clone())),
+            (0, Token::Symbol('*')) if i + 1 == tokens.len() => return SelectItem::All,
+            (1, Token::Symbol('.')) if i + 1 < tokens.len() => {}
+            _ => return SelectItem::Computed,
+        }
+    }
+    name.unwrap_or(SelectItem::Computed)
+}
+
+/// An editable result: the table it came from and how its columns map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditTarget {
+    /// Schema-qualified table name.
+    pub table: String,
+    /// Table column behind each result column; `None` for computed columns,
+    /// which stay read-only.
+    pub columns: Vec<Option<String>>,
+    /// Result column index of each primary key column.
+    pub key: Vec<usize>,
+}
+
+impl EditTarget {
+    /// Check that `result` of `sql` can be edited, given the known tables.
+    pub fn resolve(
+        sql: &str,
+        result: &QueryResult,
+        tables: &[TableInfo],
+    ) -> Result<Self, EditRefusal> {
+        let table_ref = single_table(sql).ok_or(EditRefusal::NotSingleTable)?;
+        let table = tables
+            .iter()
+            .find(|t| table_ref.matches(t))
+            .ok_or_else(|| EditRefusal::UnknownTable(table_ref.to_string()))?;
+        let qualified = format!("{}.{}", table.schema_name, table.name);
+        if !table.engine.eq_ignore_ascii_case("OLTP") {
+            return Err(EditRefusal::ReadOnlyEngine {
+                table: qualified,
+                engine: table.engine.clone(),
+            });
+        }
+        if table.primary_key.is_empty() {
+            return Err(EditRefusal::NoPrimaryKey(qualified));
+        }
+
+        // Only bare column references are editable; an alias or expression
+        // may show anything under a column's name
+        let mut columns: Vec<Option<String>> = Vec::new();
+        for item in select_items(sql) {
+            match item {
+                SelectItem::All => {
+                    columns.extend(table.columns.iter().map(|tc| Some(tc.name.clone())))
+                }
+                SelectItem::Column(name) => columns.push(
+                    table
+                        .columns
+                        .iter()
+                        .find(|tc| tc.name.eq_ignore_ascii_case(&name))
+                        .map(|tc| tc.name.clone()),
+                ),
+                SelectItem::Computed => columns.push(None),
+            }
+        }
+        // A schema out of date with the table leaves nothing editable
+        let aligned = columns.len() == result.columns.len()
+            && columns.iter().zip(&result.columns).all(|(c, rc)| {
+                c.as_ref()
+                    .is_none_or(|name| name.eq_ignore_ascii_case(&rc.name))
+            });
+        if !aligned {
+            columns = vec![None; result.columns.len()];
+        }
+        let key = table
+            .primary_key
+            .iter()
+            .map(|pk| {
+                columns
+                    .iter()
+                    .position(|c| c.as_deref() == Some(pk.as_str()))
+                    .ok_or_else(|| EditRefusal::KeyNotSelected(pk.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            table: qualified,
+            columns,
+            key,
+        })
+    }
+
+    pub fn is_editable(&self, col: usize) -> bool {
+        self.columns.get(col).is_some_and(Option::is_some)
+    }
+}
+
+/// Pending changes to an editable result. Rows are indexes into
+/// `QueryResult::rows`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditSet {
+    /// New cell values of loaded rows, by row then column.
+    pub updates: BTreeMap<usize, BTreeMap<usize, Value>>,
+    /// Loaded rows to delete.
+    pub deletes: BTreeSet<usize>,
+    /// New rows, one value per result column. `Null` leaves the column to
+    /// its default.
+    pub inserts: Vec<Vec<Value>>,
+}
+
+impl EditSet {
+    /// Set a cell of a loaded row; setting it back to the original value
+    /// drops the change.
+    pub fn set_cell(&mut self, result: &QueryResult, row: usize, col: usize, value: Value) {
+        let original = result.rows.get(row).and_then(|r| r.get(col));
+        if original == Some(&value) {
+            if let Some(cells) = self.updates.get_mut(&row) {
+                cells.remove(&col);
+                if cells.is_empty() {
+                    self.updates.remove(&row);
+                }
+            }
+        } else {
+            self.updates.entry(row).or_default().insert(col, value);
+        }
+    }
+
+    /// Current value of a loaded cell, with any pending change applied.
+    pub fn cell<'a>(&'a self, result: &'a QueryResult, row: usize, col: usize) -> &'a Value {
+        self.updates
+            .get(&row)
+            .and_then(|cells| cells.get(&col))
+            .or_else(|| result.rows.get(row).and_then(|r| r.get(col)))
+            .unwrap_or(&Value::Null)
+    }
+
+    pub fn is_changed(&self, row: usize, col: usize) -> bool {
+        self.updates
+            .get(&row)
+            .is_some_and(|cells| cells.contains_key(&col))
+    }
+
+    pub fn toggle_delete(&mut self, row: usize) {
+        if !self.deletes.remove(&row) {
+            self.deletes.insert(row);
+        }
+    }
+
+    pub fn add_row(&mut self, width: usize) {
+        self.inserts.push(vec![Value::Null; width]);
+    }
+
+    pub fn set_new_cell(&mut self, index: usize, col: usize, value: Value) {
+        if let Some(cell) = self.inserts.get_mut(index).and_then(|r| r.get_mut(col)) {
+            *cell = value;
+        }
+    }
+
+    pub fn remove_new_row(&mut self, index: usize) {
+        if index < self.inserts.len() {
+            self.inserts.remove(index);
+        }
+    }
+
+    /// Number of rows that will be updated, inserted or deleted.
+    pub fn change_count(&self) -> usize {
+        let updated = self
+            .updates
+            .keys()
+            .filter(|row| !self.deletes.contains(row))
+            .count();
+        updated + self.deletes.len() + self.inserts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.change_count() == 0
+    }
+
+    /// DELETE, UPDATE and INSERT statements applying the changes, in that
+    /// order. Rows are matched on their original primary key values.
+    pub fn statements(&self, target: &EditTarget, result: &QueryResult) -> Vec<String> {
+        let table = quote_table(&target.table);
+        let kinds: Vec<ColumnKind> = result
+            .columns
+            .iter()
+            .map(|c| ColumnKind::from_data_type(&c.data_type))
+            .collect();
+        let column = |col: usize| target.columns[col].as_deref().map(quote_ident);
+        let key_filter = |row: &[Value]| {
+            target
+                .key
+                .iter()
+                .filter_map(|&col| {
+                    let name = column(col)?;
+                    Some(match &row[col] {
+                        Value::Null => format!("{name} IS NULL"),
+                        v => format!("{name} = {}", sql_literal(v, kinds[col])),
+                    })
+                })
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        };
+
+        let mut statements = Vec::new();
+        for &row in &self.deletes {
+            if let Some(values) = result.rows.get(row) {
+                statements.push(format!("DELETE FROM {table} WHERE {}", key_filter(values)));
+            }
+        }
+        for (&row, cells) in &self.updates {
+            let Some(values) = result.rows.get(row) else {
+                continue;
+            };
+            if self.deletes.contains(&row) {
+                continue;
+            }
+            let assignments: Vec<String> = cells
+                .iter()
+                .filter_map(|(&col, value)| {
+                    Some(format!(
+                        "{} = {}",
+                        column(col)?,
+                        sql_literal(value, kinds[col])
+                    ))
+                })
+                .collect();
+            if !assignments.is_empty() {
+                statements.push(format!(
+                    "UPDATE {table} SET {} WHERE {}",
+                    assignments.join(", "),
+                    key_filter(values)
+                ));
+            }
+        }
+        for values in &self.inserts {
+            let (names, literals): (Vec<String>, Vec<String>) = values
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| !v.is_null())
+                .filter_map(|(col, v)| Some((column(col)?, sql_literal(v, kinds[col]))))
+                .unzip();
+            statements.push(if names.is_empty() {
+                format!("INSERT INTO {table} DEFAULT VALUES")
+            } else {
+                format!(
+                    "INSERT INTO {table} ({}) VALUES ({})",
+                    names.join(", "),
+                    literals.join(", ")
+                )
+            });
+        }
+        statements
+    }
+}
+
+/// Wrap statements in a single transaction script.
+pub fn transaction(statements: &[String]) -> String {
+    let mut script = String::from("BEGIN;\n");
+    for statement in statements {
+        script.push_str(statement);
+        script.push_str(";\n");
+    }
+    script.push_str("COMMIT;");
+    script
+}
+
+/// Cell value for text typed into an editable cell of the given kind.
+/// `NULL` (upper case, as cells render it) stands for SQL NULL.
+pub fn parse_input(text: &str, kind: ColumnKind) -> Value {
+    if text == "NULL" {
+        return Value::Null;
+    }
+    match kind {
+        ColumnKind::Boolean => match text.trim().to_ascii_lowercase().as_str() {
+            "true" | "t" => Value::Bool(true),
+            "false" | "f" => Value::Bool(false),
+            _ => Value::String(text.to_string()),
+        },
+        ColumnKind::Numeric => Value::String(text.trim().to_string()),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// Unquoted identifier or keyword, as written.
+    Word(String),
+    /// Double-quoted identifier, unescaped.
+    Quoted(String),
+    Symbol(char),
+    /// String or numeric literal.
+    Literal,
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn is_trailing_clause(word: &str) -> bool {
+    TRAILING_CLAUSES.contains(&word.to_ascii_lowercase().as_str())
+}
+
+fn tokenize(sql: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '\'' => {
+                while let Some(c) = chars.next() {
+                    if c == '\'' {
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                tokens.push(Token::Literal);
+            }
+            '"' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    name.push(c);
+                }
+                tokens.push(Token::Quoted(name));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '$' {
+                        word.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(if c.is_ascii_digit() {
+                    Token::Literal
+                } else {
+                    Token::Word(word)
+                });
+            }
+            c => tokens.push(Token::Symbol(c)),
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::result;
+    use crate::schema::ColumnInfo;
+    use serde_json::json;
+
+    fn table(name: &str, engine: &str, primary_key: &[&str]) -> TableInfo {
+        TableInfo {
+            schema_name: "public".into(),
+            name: name.into(),
+            engine: engine.into(),
+            row_count: None,
+            size_bytes: None,
+            columns: ["resource_id", "resource_type", "tags", "Owner"]
+                .into_iter()
+                .map(|c| ColumnInfo {
+                    name: c.into(),
+                    data_type: "VARCHAR".into(),
+                    nullable: true,
+                    comment: None,
+                })
+                .collect(),
+            partitions: vec![],
+            compression: None,
+            sort_columns: None,
+            primary_key: primary_key.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn finds_single_table_queries() {
+        let plain = |name: &str| {
+            Some(TableRef {
+                schema: None,
+                name: name.into(),
+            })
+        };
+        assert_eq!(
+            single_table("SELECT * FROM cmdb_resources;"),
+            plain("cmdb_resources")
+        );
+        assert_eq!(
+            single_table(
+                "-- recent\nselect r.*, upper(resource_type) from public.\"CMDB\" as r\n\
+                 where tags ->> 'from' = 'x' and resource_id in (select id from other)\n\
+                 order by 1 limit 10"
+            ),
+            Some(TableRef {
+                schema: Some("public".into()),
+                name: "CMDB".into(),
+            })
+        );
+        assert_eq!(
+            single_table("SELECT extract(year FROM ts) FROM t r"),
+            plain("t")
+        );
+        for sql in [
+            "SELECT * FROM a JOIN b ON a.id = b.id",
+            "SELECT * FROM a, b",
+            "SELECT DISTINCT region FROM a",
+            "SELECT region, count(*) FROM a GROUP BY region",
+            "SELECT * FROM (SELECT * FROM a) s",
+            "SELECT * FROM a; DELETE FROM a",
+            "SELECT 1",
+            "UPDATE a SET x = 1",
+        ] {
+            assert_eq!(single_table(sql), None, "{sql}");
+        }
+    }
+
+    #[test]
+    fn recognises_read_only_queries() {
+        for sql in [
+            "SELECT * FROM a JOIN b ON a.id = b.id;",
+            "with t as (select region, sum(cost) c from a group by 1) select * from t",
+            "SELECT 'delete' AS \"update\" FROM a",
+        ] {
+            assert!(is_read_only(sql), "{sql}");
+        }
+        for sql in [
+            "DELETE FROM a",
+            "SELECT * INTO b FROM a",
+            "WITH d AS (DELETE FROM a RETURNING *) SELECT * FROM d",
+            "SELECT * FROM a; DROP TABLE a",
+            "EXPLAIN ANALYZE INSERT INTO a VALUES (1)",
+            "",
+        ] {
+            assert!(!is_read_only(sql), "{sql}");
+        }
+    }
+
+    #[test]
+    fn refuses_read_only_results() {
+        let tables = [
+            table("cur_data", "OLAP", &[]),
+            table("cost_alerts", "MEMORY", &["resource_id"]),
+            table("cmdb_resources", "OLTP", &["resource_id"]),
+            table("cmdb_links", "OLTP", &[]),
+        ];
+        let res = result(&[("resource_type", "VARCHAR")], vec![]);
+        let resolve = |sql: &str| EditTarget::resolve(sql, &res, &tables);
+
+        assert_eq!(
+            resolve("SELECT * FROM cur_data"),
+            Err(EditRefusal::ReadOnlyEngine {
+                table: "public.cur_data".into(),
+                engine: "OLAP".into(),
+            })
+        );
+        assert!(resolve("SELECT * FROM cost_alerts")
+            .unwrap_err()
+            .to_string()
+            .contains("MEMORY table"));
+        assert_eq!(
+            resolve("SELECT * FROM cmdb_links"),
+            Err(EditRefusal::NoPrimaryKey("public.cmdb_links".into()))
+        );
+        assert_eq!(
+            resolve("SELECT * FROM cmdb_resources"),
+            Err(EditRefusal::KeyNotSelected("resource_id".into()))
+        );
+        assert_eq!(
+            resolve("SELECT * FROM missing"),
+            Err(EditRefusal::UnknownTable("missing".into()))
+        );
+        assert_eq!(
+            resolve("SELECT count(*) FROM cmdb_resources GROUP BY 1"),
+            Err(EditRefusal::NotSingleTable)
+        );
+    }
+
+    #[test]
+    fn only_bare_columns_are_editable() {
+        let tables = [table("cmdb_resources", "OLTP", &["resource_id"])];
+        let swapped = result(
+            &[("resource_id", "VARCHAR"), ("owner", "VARCHAR")],
+            vec![vec![json!("ann"), json!("r-1")]],
+        );
+        assert_eq!(
+            EditTarget::resolve(
+                "SELECT \"Owner\" AS resource_id, resource_id AS owner FROM cmdb_resources",
+                &swapped,
+                &tables,
+            ),
+            Err(EditRefusal::KeyNotSelected("resource_id".into()))
+        );
+
+        let res = result(
+            &[
+                ("resource_id", "VARCHAR"),
+                ("resource_type", "VARCHAR"),
+                ("tags", "VARCHAR"),
+                ("Owner", "VARCHAR"),
+                ("owner", "VARCHAR"),
+                ("size", "BIGINT"),
+            ],
+            vec![],
+        );
+        let target = EditTarget::resolve(
+            "SELECT r.*, lower(\"Owner\") AS owner, (tags ->> 'size') size FROM cmdb_resources r",
+            &res,
+            &tables,
+        )
+        .expect("editable");
+        assert_eq!(target.key, vec![0]);
+        assert_eq!(
+            target.columns,
+            [
+                Some("resource_id".to_string()),
+                Some("resource_type".into()),
+                Some("tags".into()),
+                Some("Owner".into()),
+                None,
+                None,
+            ]
+        );
+
+        // Columns missing from the known schema leave nothing to match
+        assert_eq!(
+            EditTarget::resolve("SELECT * FROM cmdb_resources", &swapped, &tables),
+            Err(EditRefusal::KeyNotSelected("resource_id".into()))
+        );
+    }
+
+    #[test]
+    fn generates_statements_in_a_transaction() {
+        let tables = [table("cmdb_resources", "OLTP", &["resource_id"])];
+        let res = result(
+            &[
+                ("resource_id", "VARCHAR"),
+                ("owner", "VARCHAR"),
+                ("n", "BIGINT"),
+            ],
+            vec![
+                vec![json!("r-1"), json!("ann"), json!(1)],
+                vec![json!("r'2"), json!("bob"), json!(2)],
+                vec![json!("r-3"), Value::Null, json!(3)],
+            ],
+        );
+        let target = EditTarget::resolve(
+            "SELECT resource_id, owner, 1 AS n FROM cmdb_resources",
+            &res,
+            &tables,
+        )
+        .expect("editable");
+        assert_eq!(target.key, vec![0]);
+        assert!(target.is_editable(1) && !target.is_editable(2));
+
+        let mut edits = EditSet::default();
+        edits.set_cell(&res, 0, 1, parse_input("O'Brien", ColumnKind::Text));
+        edits.set_cell(&res, 0, 2, parse_input("9", ColumnKind::Numeric));
+        edits.set_cell(&res, 2, 1, parse_input("cy", ColumnKind::Text));
+        edits.set_cell(&res, 2, 1, parse_input("NULL", ColumnKind::Text));
+        edits.toggle_delete(1);
+        edits.add_row(3);
+        edits.set_new_cell(0, 0, json!("r-4"));
+        edits.add_row(3);
+        assert_eq!(edits.change_count(), 4);
+        assert_eq!(edits.cell(&res, 0, 1), &json!("O'Brien"));
+        assert!(!edits.is_changed(2, 1));
+
+        assert_eq!(
+            transaction(&edits.statements(&target, &res)),
+            "BEGIN;\n\
+             DELETE FROM public.cmdb_resources WHERE resource_id = 'r''2';\n\
+             UPDATE public.cmdb_resources SET \"Owner\" = 'O''Brien' WHERE resource_id = 'r-1';\n\
+             INSERT INTO public.cmdb_resources (resource_id) VALUES ('r-4');\n\
+             INSERT INTO public.cmdb_resources DEFAULT VALUES;\n\
+             COMMIT;"
+        );
+    }
+}
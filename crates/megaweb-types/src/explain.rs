@@ -1,6 +1,15 @@
 use serde::{Deserialize, Serialize};
 
+use crate::query::QueryResult;
+
+/// Ratio between actual and estimated rows beyond which an estimate counts
+/// as a misestimate, in either direction.
+pub const MISESTIMATE_FACTOR: f64 = 10.0;
+
 /// A node in an EXPLAIN query plan tree.
+///
+/// With EXPLAIN ANALYZE, `actual_rows` and `actual_time_ms` are per loop,
+/// as PostgreSQL reports them; multiply by `actual_loops` for totals.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanNode {
     pub operator: String,
@@ -10,25 +19,70 @@ pub struct PlanNode {
     pub estimated_rows: u64,
     pub actual_rows: Option<u64>,
     pub actual_time_ms: Option<f64>,
+    /// Times the node ran; `Some(0)` for nodes that were never executed.
+    #[serde(default)]
+    pub actual_loops: Option<u64>,
     pub width: u32,
     pub children: Vec<PlanNode>,
+    /// Detail lines such as `Filter`, `Hash Cond`, `Sort Key` or `Buffers`,
+    /// in plan order.
     pub extra: Vec<(String, String)>,
 }
 
+/// Figures parsed from a text plan's operator line.
+#[derive(Default)]
+struct OperatorLine {
+    operator: String,
+    relation: Option<String>,
+    cost_startup: f64,
+    cost_total: f64,
+    rows: u64,
+    width: u32,
+    actual_rows: Option<u64>,
+    actual_time_ms: Option<f64>,
+    actual_loops: Option<u64>,
+}
+
 impl PlanNode {
+    /// Parse EXPLAIN output of any supported format: DataFusion (its
+    /// physical plan, else the logical one), PostgreSQL JSON, or text.
+    pub fn parse(text: &str) -> Option<Self> {
+        if let Some(explain) = DataFusionExplain::parse(text) {
+            return explain
+                .plan(PlanKind::Physical)
+                .or_else(|| explain.plan(PlanKind::Logical));
+        }
+        serde_json::from_str::<serde_json::Value>(text)
+            .ok()
+            .and_then(|json| Self::parse_json(&json))
+            .or_else(|| Self::parse_text(text))
+    }
+
     /// Parse EXPLAIN text format into a plan tree.
     ///
-    /// Recognizes PostgreSQL/DataFusion EXPLAIN output like:
+    /// Recognizes PostgreSQL-style EXPLAIN and EXPLAIN ANALYZE output like:
     /// ```text
-    ///   Seq Scan on cur_data  (cost=0.00..1234.00 rows=50000 width=120)
-    ///     -> Filter: (region = 'us-east-1')
+    ///   Seq Scan on cur_data  (cost=0.00..1234.00 rows=50000 width=120) (actual time=0.01..9.87 rows=48211 loops=1)
+    ///     Filter: (region = 'us-east-1')
+    ///     Rows Removed by Filter: 1789
+    ///     Buffers: shared hit=412
+    ///     ->  Index Scan using ...
     /// ```
+    /// Lines under a node that don't start with `->` are kept in `extra`;
+    /// trailing `Planning Time`/`Execution Time` lines go to the root's.
     pub fn parse_text(explain_text: &str) -> Option<Self> {
-        let lines: Vec<&str> = explain_text.lines().collect();
+        let lines: Vec<&str> = explain_text.lines().filter(|l| !is_decoration(l)).collect();
         if lines.is_empty() {
             return None;
         }
-        Self::parse_lines(&lines, 0).map(|(node, _)| node)
+        let (mut root, mut idx) = Self::parse_lines(&lines, 0)?;
+        while idx < lines.len() {
+            if let Some(detail) = detail_line(lines[idx]) {
+                root.extra.push(detail);
+            }
+            idx += 1;
+        }
+        Some(root)
     }
 
     fn parse_lines(lines: &[&str], start: usize) -> Option<(Self, usize)> {
@@ -37,20 +91,25 @@ impl PlanNode {
         }
         let line = lines[start];
         let indent = line.len() - line.trim_start().len();
-        let trimmed = line.trim().trim_start_matches("-> ");
+        let trimmed = line.trim().trim_start_matches("->").trim_start();
 
-        // Parse operator name and optional cost/rows/width
-        let (operator, relation, cost_startup, cost_total, rows, width) =
-            Self::parse_operator_line(trimmed);
+        let parsed = Self::parse_operator_line(trimmed);
 
         let mut children = Vec::new();
+        let mut extra = Vec::new();
         let mut idx = start + 1;
         while idx < lines.len() {
-            let next_indent = lines[idx].len() - lines[idx].trim_start().len();
+            let next = lines[idx];
+            let next_indent = next.len() - next.trim_start().len();
             if next_indent <= indent {
                 break;
             }
-            if let Some((child, consumed)) = Self::parse_lines(lines, idx) {
+            if !next.trim_start().starts_with("->") {
+                if let Some(detail) = detail_line(next) {
+                    extra.push(detail);
+                }
+                idx += 1;
+            } else if let Some((child, consumed)) = Self::parse_lines(lines, idx) {
                 children.push(child);
                 idx = consumed;
             } else {
@@ -60,64 +119,160 @@ impl PlanNode {
 
         Some((
             PlanNode {
-                operator,
-                relation,
-                cost_startup,
-                cost_total,
-                estimated_rows: rows,
-                actual_rows: None,
-                actual_time_ms: None,
-                width,
+                operator: parsed.operator,
+                relation: parsed.relation,
+                cost_startup: parsed.cost_startup,
+                cost_total: parsed.cost_total,
+                estimated_rows: parsed.rows,
+                actual_rows: parsed.actual_rows,
+                actual_time_ms: parsed.actual_time_ms,
+                actual_loops: parsed.actual_loops,
+                width: parsed.width,
                 children,
-                extra: Vec::new(),
+                extra,
             },
             idx,
         ))
     }
 
-    fn parse_operator_line(line: &str) -> (String, Option<String>, f64, f64, u64, u32) {
-        // Try to match: "Operator Name on table_name (cost=X..Y rows=N width=W)"
-        let mut operator = line.to_string();
-        let mut relation = None;
-        let mut cost_startup = 0.0;
-        let mut cost_total = 0.0;
-        let mut rows = 0u64;
-        let mut width = 0u32;
-
-        if let Some(paren_start) = line.find("(cost=") {
-            let before_paren = line[..paren_start].trim();
-            if let Some(on_idx) = before_paren.find(" on ") {
-                operator = before_paren[..on_idx].to_string();
-                relation = Some(before_paren[on_idx + 4..].trim().to_string());
-            } else {
-                operator = before_paren.to_string();
-            }
+    fn parse_operator_line(line: &str) -> OperatorLine {
+        // "Operator Name on table_name  (cost=X..Y rows=N width=W) (actual time=A..B rows=N loops=L)"
+        let groups_start = ["(cost=", "(actual ", "(never executed)"]
+            .iter()
+            .filter_map(|g| line.find(g))
+            .min();
+        let Some(groups_start) = groups_start else {
+            return OperatorLine {
+                operator: line.to_string(),
+                ..Default::default()
+            };
+        };
 
-            let cost_part = &line[paren_start..];
-            // Parse cost=X..Y
-            if let Some(cost_str) = cost_part.strip_prefix("(cost=") {
-                let parts: Vec<&str> = cost_str.split_whitespace().collect();
-                if let Some(cost_range) = parts.first() {
-                    let cost_range = cost_range.trim_end_matches(')');
-                    let nums: Vec<&str> = cost_range.split("..").collect();
-                    if nums.len() == 2 {
-                        cost_startup = nums[0].parse().unwrap_or(0.0);
-                        cost_total = nums[1].parse().unwrap_or(0.0);
-                    }
-                }
-                // Parse rows=N
-                for part in &parts {
-                    if let Some(r) = part.strip_prefix("rows=") {
-                        rows = r.trim_end_matches(')').parse().unwrap_or(0);
+        let mut parsed = OperatorLine::default();
+        let before_paren = line[..groups_start].trim();
+        if let Some(on_idx) = before_paren.find(" on ") {
+            parsed.operator = before_paren[..on_idx].to_string();
+            parsed.relation = Some(before_paren[on_idx + 4..].trim().to_string());
+        } else {
+            parsed.operator = before_paren.to_string();
+        }
+
+        for group in line[groups_start..]
+            .split('(')
+            .map(|g| g.trim().trim_end_matches(')'))
+        {
+            if group == "never executed" {
+                parsed.actual_loops = Some(0);
+                continue;
+            }
+            let actual = group.starts_with("actual ");
+            for part in group.split_whitespace() {
+                let Some((key, value)) = part.split_once('=') else {
+                    continue;
+                };
+                match (key, actual) {
+                    ("cost", false) => {
+                        if let Some((a, b)) = value.split_once("..") {
+                            parsed.cost_startup = a.parse().unwrap_or(0.0);
+                            parsed.cost_total = b.parse().unwrap_or(0.0);
+                        }
                     }
-                    if let Some(w) = part.strip_prefix("width=") {
-                        width = w.trim_end_matches(')').parse().unwrap_or(0);
+                    ("rows", false) => parsed.rows = value.parse().unwrap_or(0),
+                    ("width", false) => parsed.width = value.parse().unwrap_or(0),
+                    ("time", true) => {
+                        parsed.actual_time_ms = value
+                            .split_once("..")
+                            .map_or(value, |(_, total)| total)
+                            .parse()
+                            .ok();
                     }
+                    ("rows", true) => parsed.actual_rows = value.parse().ok(),
+                    ("loops", true) => parsed.actual_loops = value.parse().ok(),
+                    _ => {}
                 }
             }
         }
+        parsed
+    }
+
+    /// This node and all its descendants, in preorder.
+    pub fn preorder(&self) -> Vec<&PlanNode> {
+        let mut out = vec![self];
+        for child in &self.children {
+            out.extend(child.preorder());
+        }
+        out
+    }
+
+    /// Number of nodes in this subtree.
+    pub fn size(&self) -> usize {
+        1 + self.children.iter().map(Self::size).sum::<usize>()
+    }
+
+    /// Whether the plan carries EXPLAIN ANALYZE timings.
+    pub fn is_analyzed(&self) -> bool {
+        self.actual_time_ms.is_some() || self.children.iter().any(Self::is_analyzed)
+    }
+
+    /// Time spent in this node over all loops, children included.
+    pub fn total_time_ms(&self) -> Option<f64> {
+        match self.actual_loops {
+            Some(0) => Some(0.0),
+            loops => Some(self.actual_time_ms? * loops.unwrap_or(1) as f64),
+        }
+    }
+
+    /// Time spent in this node itself, excluding its children.
+    pub fn self_time_ms(&self) -> Option<f64> {
+        let children: f64 = self.children.iter().filter_map(Self::total_time_ms).sum();
+        Some((self.total_time_ms()? - children).max(0.0))
+    }
+
+    /// Estimated cost of this node itself, excluding its children.
+    pub fn self_cost(&self) -> f64 {
+        let children: f64 = self.children.iter().map(|c| c.cost_total).sum();
+        (self.cost_total - children).max(0.0)
+    }
+
+    /// Work done in this node itself: its own time if `analyzed`, else its
+    /// own estimated cost.
+    pub fn self_weight(&self, analyzed: bool) -> f64 {
+        if analyzed {
+            self.self_time_ms().unwrap_or(0.0)
+        } else {
+            self.self_cost()
+        }
+    }
+
+    /// The `top`-th largest [`self_weight`](Self::self_weight) in the tree;
+    /// nodes at or above it are the most expensive ones.
+    pub fn hot_threshold(&self, top: usize, analyzed: bool) -> f64 {
+        fn collect(node: &PlanNode, analyzed: bool, out: &mut Vec<f64>) {
+            out.push(node.self_weight(analyzed));
+            for child in &node.children {
+                collect(child, analyzed, out);
+            }
+        }
+        let mut weights = Vec::new();
+        collect(self, analyzed, &mut weights);
+        weights.sort_by(|a, b| b.total_cmp(a));
+        weights
+            .get(top.saturating_sub(1))
+            .or(weights.last())
+            .copied()
+            .unwrap_or(0.0)
+    }
 
-        (operator, relation, cost_startup, cost_total, rows, width)
+    /// Actual rows divided by estimated rows, per loop, when they differ by
+    /// at least [`MISESTIMATE_FACTOR`].
+    pub fn misestimate(&self) -> Option<f64> {
+        // Never executed, or no estimate to compare with
+        if self.actual_loops == Some(0) || self.estimated_rows == 0 {
+            return None;
+        }
+        let actual = self.actual_rows?.max(1) as f64;
+        let ratio = actual / self.estimated_rows.max(1) as f64;
+        (ratio >= MISESTIMATE_FACTOR || ratio <= 1.0 / MISESTIMATE_FACTOR).then_some(ratio)
     }
 
     /// Parse EXPLAIN (FORMAT JSON) output.
@@ -143,6 +298,7 @@ impl PlanNode {
         let rows = plan.get("Plan Rows").and_then(|v| v.as_u64()).unwrap_or(0);
         let actual_rows = plan.get("Actual Rows").and_then(|v| v.as_u64());
         let actual_time = plan.get("Actual Total Time").and_then(|v| v.as_f64());
+        let actual_loops = plan.get("Actual Loops").and_then(|v| v.as_u64());
         let width = plan.get("Plan Width").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
 
         let children = plan
@@ -159,11 +315,375 @@ impl PlanNode {
             estimated_rows: rows,
             actual_rows,
             actual_time_ms: actual_time,
+            actual_loops,
             width,
             children,
-            extra: Vec::new(),
+            extra: Self::json_details(plan),
         })
     }
+
+    fn json_details(plan: &serde_json::Value) -> Vec<(String, String)> {
+        const KEYS: [&str; 8] = [
+            "Filter",
+            "Rows Removed by Filter",
+            "Index Cond",
+            "Hash Cond",
+            "Merge Cond",
+            "Join Filter",
+            "Sort Key",
+            "Group Key",
+        ];
+        let mut extra: Vec<(String, String)> = KEYS
+            .iter()
+            .filter_map(|&key| {
+                let value = match plan.get(key)? {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Array(items) => items
+                        .iter()
+                        .map(|i| i.as_str().map_or_else(|| i.to_string(), str::to_string))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    other => other.to_string(),
+                };
+                Some((key.to_string(), value))
+            })
+            .collect();
+        let blocks = ["Hit", "Read", "Dirtied", "Written"]
+            .iter()
+            .filter_map(|kind| {
+                let n = plan.get(format!("Shared {kind} Blocks"))?.as_u64()?;
+                (n > 0).then(|| format!("{}={n}", kind.to_lowercase()))
+            })
+            .collect::<Vec<_>>();
+        if !blocks.is_empty() {
+            extra.push(("Buffers".into(), format!("shared {}", blocks.join(" "))));
+        }
+        extra
+    }
+}
+
+/// Header and separator lines psql prints around a plan.
+fn is_decoration(line: &str) -> bool {
+    let t = line.trim();
+    t.is_empty()
+        || t == "QUERY PLAN"
+        || t.chars().all(|c| c == '-' || c == '+')
+        || (t.starts_with('(') && (t.ends_with(" row)") || t.ends_with(" rows)")))
+}
+
+/// A `Key: value` detail line, or the whole line under an empty key.
+fn detail_line(line: &str) -> Option<(String, String)> {
+    let t = line.trim();
+    if t.is_empty() {
+        return None;
+    }
+    Some(match t.split_once(": ") {
+        Some((key, value)) => (key.to_string(), value.trim().to_string()),
+        None => (String::new(), t.to_string()),
+    })
+}
+
+/// Which plan of a DataFusion EXPLAIN to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanKind {
+    Logical,
+    Physical,
+}
+
+/// Output of a DataFusion `EXPLAIN`: one plan per `plan_type`, in order.
+///
+/// `EXPLAIN VERBOSE` adds the intermediate plans of every optimizer pass;
+/// `EXPLAIN ANALYZE` reports a single `Plan with Metrics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataFusionExplain {
+    pub sections: Vec<(String, String)>,
+}
+
+impl DataFusionExplain {
+    /// Parse the `plan_type | plan` table DataFusion prints, or a bare
+    /// physical plan whose first line is an `...Exec:` operator.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut sections: Vec<(String, String)> = Vec::new();
+        let mut in_table = false;
+        for line in text.lines() {
+            let t = line.trim();
+            let Some(row) = t.strip_prefix('|') else {
+                continue;
+            };
+            in_table = true;
+            let row = row.strip_suffix('|').unwrap_or(row);
+            let Some((plan_type, plan)) = row.split_once(" | ") else {
+                continue;
+            };
+            // Plan lines keep their indentation after the cell's padding
+            let (plan_type, plan) = (plan_type.trim(), plan.trim_end());
+            if plan_type == "plan_type" {
+                continue;
+            }
+            match sections.last_mut() {
+                Some((_, lines)) if plan_type.is_empty() => {
+                    lines.push('\n');
+                    lines.push_str(plan);
+                }
+                _ => sections.push((plan_type.to_string(), plan.to_string())),
+            }
+        }
+        if !in_table {
+            let first = text.lines().find(|l| !l.trim().is_empty())?;
+            let (operator, _) = first.trim().split_once(':')?;
+            if !operator.ends_with("Exec") {
+                return None;
+            }
+            sections.push(("physical_plan".to_string(), text.to_string()));
+        }
+        (!sections.is_empty()).then_some(Self { sections })
+    }
+
+    /// Render a `plan_type`/`plan` result as the table [`parse`](Self::parse)
+    /// reads. `None` unless the result has exactly those columns.
+    pub fn table_text(result: &QueryResult) -> Option<String> {
+        let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        if names != ["plan_type", "plan"] {
+            return None;
+        }
+        let rows: Vec<(String, String)> = result
+            .rows
+            .iter()
+            .map(|row| {
+                let text = |i: usize| {
+                    row.get(i)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                (text(0), text(1))
+            })
+            .collect();
+        let width = rows
+            .iter()
+            .map(|(t, _)| t.len())
+            .max()
+            .unwrap_or(0)
+            .max("plan_type".len());
+        let mut out = format!("| {:width$} | plan |\n", "plan_type");
+        for (plan_type, plan) in rows {
+            for (i, line) in plan.lines().enumerate() {
+                let label = if i == 0 { plan_type.as_str() } else { "" };
+                out.push_str(&format!("| {label:width$} | {line} |\n"));
+            }
+        }
+        Some(out)
+    }
+
+    /// The final plan of the given kind, parsed into a tree. The physical
+    /// plan comes from `Plan with Metrics` when the EXPLAIN was ANALYZE.
+    pub fn plan(&self, kind: PlanKind) -> Option<PlanNode> {
+        let names: &[&str] = match kind {
+            PlanKind::Logical => &["logical_plan"],
+            PlanKind::Physical => &["Plan with Metrics", "physical_plan"],
+        };
+        let text = names.iter().find_map(|name| {
+            self.sections
+                .iter()
+                .rev()
+                .find(|(t, _)| t == name)
+                .map(|(_, plan)| plan)
+        })?;
+        PlanNode::parse_datafusion(text)
+    }
+
+    /// Whether a plan of the given kind is present.
+    pub fn has(&self, kind: PlanKind) -> bool {
+        self.plan(kind).is_some()
+    }
+}
+
+/// Whether `sql` is an EXPLAIN statement.
+pub fn is_explain(sql: &str) -> bool {
+    sql.trim_start()
+        .get(..7)
+        .is_some_and(|word| word.eq_ignore_ascii_case("EXPLAIN"))
+}
+
+/// Plan text of an EXPLAIN result: DataFusion's `plan_type`/`plan` rows as
+/// a table, or the single plan column's rows joined into lines.
+pub fn plan_text(result: &QueryResult) -> Option<String> {
+    if let Some(table) = DataFusionExplain::table_text(result) {
+        return Some(table);
+    }
+    let lines: Vec<&str> = result
+        .rows
+        .iter()
+        .filter_map(|row| row.first()?.as_str())
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+impl PlanNode {
+    /// Parse one DataFusion logical or physical plan, where every line is a
+    /// node and children are indented under their parent:
+    /// ```text
+    /// ProjectionExec: expr=[service@0 as service]
+    ///   FilterExec: region@1 = us-east-1, metrics=[output_rows=812, elapsed_compute=1.2ms]
+    ///     ParquetExec: file_groups={4 groups: [...]}, projection=[service, region], pruning_predicate=...
+    /// ```
+    /// Details become `extra` entries. `output_rows` and `elapsed_compute`
+    /// metrics fill the actual rows and time, and `Rows=` statistics the
+    /// estimate. DataFusion's compute time is per operator, so times are
+    /// summed up the tree to match the inclusive times of other plans.
+    pub fn parse_datafusion(plan: &str) -> Option<Self> {
+        let lines: Vec<&str> = plan.lines().filter(|l| !l.trim().is_empty()).collect();
+        let (mut root, _) = Self::parse_datafusion_lines(&lines, 0)?;
+        root.accumulate_times();
+        Some(root)
+    }
+
+    fn parse_datafusion_lines(lines: &[&str], start: usize) -> Option<(Self, usize)> {
+        let line = lines.get(start)?;
+        let indent = line.len() - line.trim_start().len();
+        let (operator, details) = line.trim().split_once(':').unwrap_or((line.trim(), ""));
+        let mut details = details.trim();
+
+        let mut node = PlanNode {
+            operator: operator.to_string(),
+            relation: None,
+            cost_startup: 0.0,
+            cost_total: 0.0,
+            estimated_rows: 0,
+            actual_rows: None,
+            actual_time_ms: None,
+            actual_loops: None,
+            width: 0,
+            children: Vec::new(),
+            extra: Vec::new(),
+        };
+        if operator == "TableScan" {
+            let (table, rest) = details.split_once(' ').unwrap_or((details, ""));
+            node.relation = Some(table.trim_end_matches(',').to_string());
+            details = rest.trim();
+        }
+        for (key, value) in datafusion_details(details) {
+            match key.as_str() {
+                "metrics" => {
+                    for (name, metric) in datafusion_details(strip_brackets(&value)) {
+                        match name.as_str() {
+                            "output_rows" => node.actual_rows = metric.parse().ok(),
+                            "elapsed_compute" => node.actual_time_ms = parse_duration_ms(&metric),
+                            _ => {}
+                        }
+                    }
+                }
+                "statistics" => {
+                    if let Some(rows) = statistics_rows(&value) {
+                        node.estimated_rows = rows;
+                    }
+                }
+                _ => {}
+            }
+            node.extra.push((key, value));
+        }
+
+        let mut idx = start + 1;
+        while idx < lines.len() {
+            let next_indent = lines[idx].len() - lines[idx].trim_start().len();
+            if next_indent <= indent {
+                break;
+            }
+            match Self::parse_datafusion_lines(lines, idx) {
+                Some((child, consumed)) => {
+                    node.children.push(child);
+                    idx = consumed;
+                }
+                None => idx += 1,
+            }
+        }
+        Some((node, idx))
+    }
+
+    /// Turn per-operator times into times including the children's.
+    fn accumulate_times(&mut self) -> Option<f64> {
+        let children: Vec<Option<f64>> = self
+            .children
+            .iter_mut()
+            .map(Self::accumulate_times)
+            .collect();
+        if self.actual_time_ms.is_none() && children.iter().all(Option::is_none) {
+            return None;
+        }
+        let total =
+            self.actual_time_ms.unwrap_or(0.0) + children.into_iter().flatten().sum::<f64>();
+        self.actual_time_ms = Some(total);
+        Some(total)
+    }
+}
+
+/// Split `key=value, key=value` details at top-level commas. Text that
+/// isn't a `key=value` pair, like a filter expression, is kept under `expr`.
+fn datafusion_details(details: &str) -> Vec<(String, String)> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    let bytes = details.as_bytes();
+    for (i, c) in details.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 && bytes.get(i + 1) == Some(&b' ') => {
+                parts.push(&details[start..i]);
+                start = i + 2;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&details[start..]);
+
+    let mut out: Vec<(String, String)> = Vec::new();
+    for part in parts.into_iter().map(str::trim).filter(|p| !p.is_empty()) {
+        let pair = part.split_once('=').filter(|(key, _)| {
+            !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        match (pair, out.last_mut()) {
+            (Some((key, value)), _) => out.push((key.to_string(), value.to_string())),
+            (None, Some((key, value))) if key == "expr" => {
+                value.push_str(", ");
+                value.push_str(part);
+            }
+            (None, _) => out.push(("expr".to_string(), part.to_string())),
+        }
+    }
+    out
+}
+
+fn strip_brackets(value: &str) -> &str {
+    value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value)
+}
+
+/// Milliseconds of a DataFusion duration such as `1.5ms`, `230.1µs` or `2s`.
+fn parse_duration_ms(text: &str) -> Option<f64> {
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let scale = match unit {
+        "ns" => 1e-6,
+        "µs" | "us" => 1e-3,
+        "ms" => 1.0,
+        "s" => 1e3,
+        _ => return None,
+    };
+    Some(number * scale)
+}
+
+/// Row count of `statistics=[Rows=Inexact(100), ...]`.
+fn statistics_rows(value: &str) -> Option<u64> {
+    let rest = &value[value.find("Rows=")? + "Rows=".len()..];
+    let open = rest.find('(')?;
+    let close = rest.find(')')?;
+    rest.get(open + 1..close)?.parse().ok()
 }
 
 #[cfg(test)]
@@ -180,4 +700,167 @@ mod tests {
         assert_eq!(node.estimated_rows, 50000);
         assert_eq!(node.width, 120);
     }
+
+    #[test]
+    fn parse_explain_analyze_text() {
+        let text = "\
+                                  QUERY PLAN
+-------------------------------------------------------------------------------
+ Hash Join  (cost=10.00..500.00 rows=100 width=64) (actual time=0.500..42.000 rows=9000 loops=1)
+   Hash Cond: (c.account_id = a.account_id)
+   Buffers: shared hit=120 read=30
+   ->  Seq Scan on cur_data c  (cost=0.00..400.00 rows=10000 width=48) (actual time=0.010..30.000 rows=10000 loops=1)
+         Filter: (region = 'us-east-1'::text)
+         Rows Removed by Filter: 500
+   ->  Hash  (cost=5.00..5.00 rows=50 width=16) (actual time=0.200..0.200 rows=50 loops=1)
+         Buckets: 1024  Batches: 1  Memory Usage: 11kB
+         ->  Index Scan using accounts_pkey on accounts a  (cost=0.15..5.00 rows=50 width=16) (actual time=0.005..0.050 rows=5 loops=4)
+   ->  Sort  (cost=1.00..2.00 rows=10 width=8) (never executed)
+         Sort Key: a.name
+ Planning Time: 0.250 ms
+ Execution Time: 42.500 ms
+(16 rows)";
+        let root = PlanNode::parse_text(text).unwrap();
+        assert_eq!(root.operator, "Hash Join");
+        assert_eq!(root.actual_rows, Some(9000));
+        assert_eq!(root.actual_time_ms, Some(42.0));
+        assert_eq!(root.actual_loops, Some(1));
+        assert_eq!(root.children.len(), 3);
+        assert_eq!(
+            root.extra,
+            [
+                ("Hash Cond".into(), "(c.account_id = a.account_id)".into()),
+                ("Buffers".into(), "shared hit=120 read=30".into()),
+                ("Planning Time".into(), "0.250 ms".into()),
+                ("Execution Time".into(), "42.500 ms".into()),
+            ]
+        );
+        assert!(root.is_analyzed());
+        assert_eq!(root.misestimate(), Some(90.0));
+
+        let scan = &root.children[0];
+        assert_eq!(scan.relation.as_deref(), Some("cur_data c"));
+        assert_eq!(
+            scan.extra[1],
+            ("Rows Removed by Filter".into(), "500".into())
+        );
+        assert!(scan.misestimate().is_none());
+
+        let index = &root.children[1].children[0];
+        assert_eq!(index.operator, "Index Scan using accounts_pkey");
+        assert_eq!(index.total_time_ms(), Some(0.2));
+
+        let sort = &root.children[2];
+        assert_eq!(sort.actual_loops, Some(0));
+        assert_eq!(sort.extra, [("Sort Key".into(), "a.name".into())]);
+
+        // Hash Join itself takes 42 - 30 - 0.2 - 0 ms; the scan is hottest
+        assert!((root.self_time_ms().unwrap() - 11.8).abs() < 1e-9);
+        assert_eq!(root.hot_threshold(1, true), 30.0);
+    }
+
+    const DATAFUSION_EXPLAIN: &str = "\
++---------------+-------------------------------------------------------------+
+| plan_type     | plan                                                        |
++---------------+-------------------------------------------------------------+
+| logical_plan  | Projection: cur_data.service, sum(cur_data.cost)            |
+|               |   Aggregate: groupBy=[[cur_data.service]], aggr=[[sum(cur_data.cost)]] |
+|               |     Filter: cur_data.billing_period >= Date32(\"2026-01-01\") |
+|               |       TableScan: cur_data projection=[service, cost, billing_period], partial_filters=[cur_data.billing_period >= Date32(\"2026-01-01\")] |
+| physical_plan | ProjectionExec: expr=[service@0 as service, sum(cur_data.cost)@1 as sum(cur_data.cost)] |
+|               |   AggregateExec: mode=FinalPartitioned, gby=[service@0 as service], aggr=[sum(cur_data.cost)] |
+|               |     RepartitionExec: partitioning=Hash([service@0], 8), input_partitions=8 |
+|               |       FilterExec: billing_period@2 >= 2026-01-01             |
+|               |         ParquetExec: file_groups={8 groups: [[a.parquet], [b.parquet]]}, projection=[service, cost, billing_period], predicate=billing_period@2 >= 2026-01-01, pruning_predicate=billing_period_max@0 >= 2026-01-01 |
+|               |                                                             |
++---------------+-------------------------------------------------------------+";
+
+    #[test]
+    fn parse_datafusion_explain_sections() {
+        let explain = DataFusionExplain::parse(DATAFUSION_EXPLAIN).unwrap();
+        assert_eq!(explain.sections.len(), 2);
+
+        let logical = explain.plan(PlanKind::Logical).unwrap();
+        assert_eq!(logical.operator, "Projection");
+        let scan = &logical.children[0].children[0].children[0];
+        assert_eq!(scan.operator, "TableScan");
+        assert_eq!(scan.relation.as_deref(), Some("cur_data"));
+        assert_eq!(
+            scan.extra[0],
+            (
+                "projection".into(),
+                "[service, cost, billing_period]".into()
+            )
+        );
+
+        let physical = explain.plan(PlanKind::Physical).unwrap();
+        let repartition = &physical.children[0].children[0];
+        assert_eq!(
+            repartition.extra,
+            [
+                ("partitioning".into(), "Hash([service@0], 8)".into()),
+                ("input_partitions".into(), "8".into()),
+            ]
+        );
+        let filter = &repartition.children[0];
+        assert_eq!(
+            filter.extra,
+            [("expr".into(), "billing_period@2 >= 2026-01-01".into())]
+        );
+        let parquet = &filter.children[0];
+        assert_eq!(parquet.operator, "ParquetExec");
+        assert_eq!(
+            parquet.extra[0],
+            (
+                "file_groups".into(),
+                "{8 groups: [[a.parquet], [b.parquet]]}".into()
+            )
+        );
+        assert_eq!(parquet.extra[3].0, "pruning_predicate");
+    }
+
+    #[test]
+    fn parse_datafusion_metrics() {
+        let plan = "\
+CoalesceBatchesExec: target_batch_size=8192, metrics=[output_rows=812, elapsed_compute=250µs]
+  FilterExec: region@1 = us-east-1, metrics=[output_rows=812, elapsed_compute=1.5ms]
+    ParquetExec: file_groups={1 group: [[a.parquet]]}, statistics=[Rows=Inexact(50000), Bytes=Absent], metrics=[output_rows=50000, elapsed_compute=10ms]";
+        let explain = DataFusionExplain::parse(plan).unwrap();
+        assert!(!explain.has(PlanKind::Logical));
+        let root = explain.plan(PlanKind::Physical).unwrap();
+        assert_eq!(root.actual_rows, Some(812));
+        assert!((root.actual_time_ms.unwrap() - 11.75).abs() < 1e-9);
+        assert!((root.self_time_ms().unwrap() - 0.25).abs() < 1e-9);
+
+        let scan = &root.children[0].children[0];
+        assert_eq!(scan.estimated_rows, 50000);
+        assert_eq!(scan.self_time_ms(), Some(10.0));
+        assert!(scan.misestimate().is_none());
+        assert_eq!(root.hot_threshold(1, true), 10.0);
+    }
+
+    #[test]
+    fn plan_text_from_datafusion_rows() {
+        let column = |name: &str| crate::query::QueryColumn {
+            name: name.into(),
+            data_type: "VARCHAR".into(),
+            nullable: false,
+        };
+        let result = QueryResult {
+            columns: vec![column("plan_type"), column("plan")],
+            rows: vec![vec![
+                serde_json::json!("physical_plan"),
+                serde_json::json!("ProjectionExec: expr=[a@0 as a]\n  EmptyExec"),
+            ]],
+            row_count: 1,
+            execution_time_ms: 1,
+            error: None,
+            stats: None,
+        };
+        let text = plan_text(&result).unwrap();
+        let root = DataFusionExplain::parse(&text)
+            .and_then(|e| e.plan(PlanKind::Physical))
+            .unwrap();
+        assert_eq!(root.children[0].operator, "EmptyExec");
+    }
 }
@@ -0,0 +1,595 @@
+//! Result export formats. Text formats are encoded here so the browser and
+//! the server produce identical output; binary formats (XLSX, Parquet,
+//! Arrow IPC) are only produced by the server.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::query::{ColumnKind, QueryColumn, QueryResult};
+use crate::result_view::{cell_number, cell_text};
+
+/// File format of a result export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Markdown,
+    SqlInsert,
+    Xlsx,
+    Parquet,
+    Arrow,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 7] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+        ExportFormat::SqlInsert,
+        ExportFormat::Xlsx,
+        ExportFormat::Parquet,
+        ExportFormat::Arrow,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::SqlInsert => "SQL INSERT",
+            ExportFormat::Xlsx => "Excel (XLSX)",
+            ExportFormat::Parquet => "Parquet",
+            ExportFormat::Arrow => "Arrow IPC",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::SqlInsert => "sql",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Arrow => "arrow",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown",
+            ExportFormat::SqlInsert => "application/sql",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+            ExportFormat::Arrow => "application/vnd.apache.arrow.file",
+        }
+    }
+
+    /// Binary formats are always generated by the server.
+    pub fn is_binary(self) -> bool {
+        matches!(
+            self,
+            ExportFormat::Xlsx | ExportFormat::Parquet | ExportFormat::Arrow
+        )
+    }
+
+    pub fn filename(self) -> String {
+        format!("query-result.{}", self.extension())
+    }
+}
+
+/// When CSV fields are wrapped in quotes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvQuoting {
+    /// Only fields containing the delimiter, quotes or line breaks.
+    #[default]
+    Minimal,
+    All,
+    /// Every field of a non-numeric column.
+    NonNumeric,
+    Never,
+}
+
+impl CsvQuoting {
+    pub const ALL: [CsvQuoting; 4] = [
+        CsvQuoting::Minimal,
+        CsvQuoting::All,
+        CsvQuoting::NonNumeric,
+        CsvQuoting::Never,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CsvQuoting::Minimal => "When needed",
+            CsvQuoting::All => "Always",
+            CsvQuoting::NonNumeric => "Non-numeric",
+            CsvQuoting::Never => "Never",
+        }
+    }
+}
+
+/// CSV dialect for exports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub quoting: CsvQuoting,
+    /// Written for NULL cells, never quoted.
+    pub null_value: String,
+    pub header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quoting: CsvQuoting::Minimal,
+            null_value: String::new(),
+            header: true,
+        }
+    }
+}
+
+/// Rows a server-side export may hold; larger results need a filter or a
+/// `LIMIT`.
+pub const MAX_EXPORT_ROWS: u64 = 1_000_000;
+
+/// Server-side export: the query, which must be read-only, is re-run
+/// without the row limit and the full result encoded in `format`, up to
+/// [`MAX_EXPORT_ROWS`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRequest {
+    pub sql: String,
+    pub database: String,
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub csv: CsvOptions,
+    /// Target table for SQL INSERT exports.
+    #[serde(default)]
+    pub table: Option<String>,
+}
+
+/// Clipboard format for a grid selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// Tab-separated, pastes into spreadsheets.
+    Tsv,
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 4] = [
+        CopyFormat::Tsv,
+        CopyFormat::Csv,
+        CopyFormat::Json,
+        CopyFormat::Markdown,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyFormat::Tsv => "TSV",
+            CopyFormat::Csv => "CSV",
+            CopyFormat::Json => "JSON",
+            CopyFormat::Markdown => "Markdown",
+        }
+    }
+
+    /// Encode `result`. `header` applies to TSV and CSV; CSV otherwise
+    /// follows the export dialect in `csv`.
+    pub fn encode(self, result: &QueryResult, header: bool, csv: &CsvOptions) -> String {
+        match self {
+            CopyFormat::Tsv => to_csv(
+                result,
+                &CsvOptions {
+                    delimiter: '\t',
+                    header,
+                    ..CsvOptions::default()
+                },
+            ),
+            CopyFormat::Csv => to_csv(
+                result,
+                &CsvOptions {
+                    header,
+                    ..csv.clone()
+                },
+            ),
+            CopyFormat::Json => to_json(result),
+            CopyFormat::Markdown => to_markdown(result),
+        }
+    }
+}
+
+/// Encode a text format. Returns `None` for binary formats.
+pub fn encode_text(
+    result: &QueryResult,
+    format: ExportFormat,
+    csv: &CsvOptions,
+    table: Option<&str>,
+) -> Option<String> {
+    match format {
+        ExportFormat::Csv => Some(to_csv(result, csv)),
+        ExportFormat::Json => Some(to_json(result)),
+        ExportFormat::Markdown => Some(to_markdown(result)),
+        ExportFormat::SqlInsert => Some(to_sql_insert(result, table.unwrap_or("query_result"))),
+        ExportFormat::Xlsx | ExportFormat::Parquet | ExportFormat::Arrow => None,
+    }
+}
+
+pub fn to_csv(result: &QueryResult, options: &CsvOptions) -> String {
+    let field = |text: &str, numeric: bool| -> String {
+        let needs_quotes = match options.quoting {
+            CsvQuoting::All => true,
+            CsvQuoting::NonNumeric => !numeric,
+            CsvQuoting::Never => false,
+            CsvQuoting::Minimal => {
+                text.contains(options.delimiter)
+                    || text.contains('"')
+                    || text.contains('\n')
+                    || text.contains('\r')
+            }
+        };
+        if needs_quotes {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
+        }
+    };
+    let delimiter = options.delimiter.to_string();
+    let kinds: Vec<ColumnKind> = result.columns.iter().map(QueryColumn::kind).collect();
+
+    let mut out = String::new();
+    if options.header {
+        let header: Vec<String> = result
+            .columns
+            .iter()
+            .map(|c| field(&c.name, false))
+            .collect();
+        out.push_str(&header.join(&delimiter));
+        out.push('\n');
+    }
+    for row in &result.rows {
+        let fields: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, v)| match v {
+                Value::Null => options.null_value.clone(),
+                v => field(&cell_text(v), kinds.get(i) == Some(&ColumnKind::Numeric)),
+            })
+            .collect();
+        out.push_str(&fields.join(&delimiter));
+        out.push('\n');
+    }
+    out
+}
+
+/// Rows as an array of objects keyed by column name.
+pub fn to_json(result: &QueryResult) -> String {
+    let objects: Vec<Value> = result
+        .rows
+        .iter()
+        .map(|row| {
+            let mut obj = serde_json::Map::new();
+            for (i, col) in result.columns.iter().enumerate() {
+                obj.insert(col.name.clone(), row.get(i).cloned().unwrap_or(Value::Null));
+            }
+            Value::Object(obj)
+        })
+        .collect();
+    serde_json::to_string_pretty(&objects).unwrap_or_default()
+}
+
+/// GitHub-flavored Markdown table; numeric columns are right-aligned.
+pub fn to_markdown(result: &QueryResult) -> String {
+    let escape = |s: &str| s.replace('|', "\\|").replace('\n', "<br>");
+    let mut out = String::new();
+    let header: Vec<String> = result.columns.iter().map(|c| escape(&c.name)).collect();
+    out.push_str(&format!("| {} |\n", header.join(" | ")));
+    let align: Vec<&str> = result
+        .columns
+        .iter()
+        .map(|c| {
+            if c.kind() == ColumnKind::Numeric {
+                "---:"
+            } else {
+                "---"
+            }
+        })
+        .collect();
+    out.push_str(&format!("| {} |\n", align.join(" | ")));
+    for row in &result.rows {
+        let cells: Vec<String> = row.iter().map(|v| escape(&cell_text(v))).collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+/// One `INSERT` statement per row.
+pub fn to_sql_insert(result: &QueryResult, table: &str) -> String {
+    let columns: Vec<String> = result
+        .columns
+        .iter()
+        .map(|c| quote_ident(&c.name))
+        .collect();
+    let prefix = format!(
+        "INSERT INTO {} ({}) VALUES",
+        quote_table(table),
+        columns.join(", ")
+    );
+    let kinds: Vec<ColumnKind> = result.columns.iter().map(QueryColumn::kind).collect();
+
+    let mut out = String::new();
+    for row in &result.rows {
+        let values: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, v)| sql_literal(v, kinds.get(i).copied().unwrap_or(ColumnKind::Text)))
+            .collect();
+        out.push_str(&format!("{prefix} ({});\n", values.join(", ")));
+    }
+    out
+}
+
+/// Render a cell as a SQL literal for a column of the given kind.
+/// Numbers are left unquoted only when finite; `NaN` and infinities are
+/// quoted, as SQL has no bare literal for them.
+pub fn sql_literal(value: &Value, kind: ColumnKind) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        v if kind == ColumnKind::Numeric && cell_number(v).is_some_and(f64::is_finite) => {
+            cell_text(v)
+        }
+        v => format!("'{}'", cell_text(v).replace('\'', "''")),
+    }
+}
+
+/// `IN (...)` list of the distinct non-null `values`, in first-seen order.
+/// Returns `None` if there are none.
+pub fn sql_in_list<'a>(
+    values: impl IntoIterator<Item = &'a Value>,
+    kind: ColumnKind,
+) -> Option<String> {
+    let mut seen = std::collections::HashSet::new();
+    let literals: Vec<String> = values
+        .into_iter()
+        .filter(|v| !v.is_null())
+        .map(|v| sql_literal(v, kind))
+        .filter(|l| seen.insert(l.clone()))
+        .collect();
+    (!literals.is_empty()).then(|| format!("IN ({})", literals.join(", ")))
+}
+
+/// Keywords that can't be used as bare identifiers, sorted. Quoting a
+/// word that didn't need it is harmless, so this errs on the long side.
+const RESERVED_WORDS: [&str; 97] = [
+    "all",
+    "alter",
+    "analyse",
+    "analyze",
+    "and",
+    "any",
+    "array",
+    "as",
+    "asc",
+    "asymmetric",
+    "between",
+    "both",
+    "by",
+    "case",
+    "cast",
+    "check",
+    "collate",
+    "column",
+    "constraint",
+    "create",
+    "cross",
+    "current_catalog",
+    "current_date",
+    "current_role",
+    "current_time",
+    "current_timestamp",
+    "current_user",
+    "default",
+    "deferrable",
+    "delete",
+    "desc",
+    "distinct",
+    "do",
+    "drop",
+    "else",
+    "end",
+    "except",
+    "exists",
+    "false",
+    "fetch",
+    "for",
+    "foreign",
+    "from",
+    "full",
+    "grant",
+    "group",
+    "having",
+    "ilike",
+    "in",
+    "initially",
+    "inner",
+    "insert",
+    "intersect",
+    "interval",
+    "into",
+    "is",
+    "join",
+    "lateral",
+    "leading",
+    "left",
+    "like",
+    "limit",
+    "localtime",
+    "localtimestamp",
+    "natural",
+    "not",
+    "null",
+    "offset",
+    "on",
+    "only",
+    "or",
+    "order",
+    "outer",
+    "placing",
+    "primary",
+    "references",
+    "returning",
+    "right",
+    "select",
+    "session_user",
+    "set",
+    "some",
+    "symmetric",
+    "table",
+    "then",
+    "to",
+    "trailing",
+    "true",
+    "union",
+    "unique",
+    "update",
+    "user",
+    "using",
+    "values",
+    "variadic",
+    "when",
+    "where",
+];
+
+/// Quote an identifier unless it is a plain lower-case name that isn't a
+/// reserved word.
+pub fn quote_ident(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && RESERVED_WORDS.binary_search(&name).is_err();
+    if plain {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// Quote each part of a possibly schema-qualified table name.
+pub fn quote_table(table: &str) -> String {
+    table
+        .split('.')
+        .map(quote_ident)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result() -> QueryResult {
+        crate::query::result(
+            &[("service", "VARCHAR"), ("Cost", "DECIMAL(18,2)")],
+            vec![
+                vec![json!("EC2; spot"), json!("12.50")],
+                vec![json!("O'Reilly | \"S3\""), Value::Null],
+            ],
+        )
+    }
+
+    #[test]
+    fn csv_respects_dialect() {
+        let default = to_csv(&result(), &CsvOptions::default());
+        assert_eq!(
+            default,
+            "service,Cost\nEC2; spot,12.50\n\"O'Reilly | \"\"S3\"\"\",\n"
+        );
+
+        let options = CsvOptions {
+            delimiter: ';',
+            quoting: CsvQuoting::NonNumeric,
+            null_value: "\\N".into(),
+            header: false,
+        };
+        assert_eq!(
+            to_csv(&result(), &options),
+            "\"EC2; spot\";12.50\n\"O'Reilly | \"\"S3\"\"\";\\N\n"
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_pipes_and_aligns_numbers() {
+        let md = to_markdown(&result());
+        let lines: Vec<&str> = md.lines().collect();
+        assert_eq!(lines[0], "| service | Cost |");
+        assert_eq!(lines[1], "| --- | ---: |");
+        assert_eq!(lines[3], "| O'Reilly \\| \"S3\" | NULL |");
+    }
+
+    #[test]
+    fn sql_insert_quotes_identifiers_and_literals() {
+        let sql = to_sql_insert(&result(), "billing.cur_data");
+        let lines: Vec<&str> = sql.lines().collect();
+        assert_eq!(
+            lines[0],
+            "INSERT INTO billing.cur_data (service, \"Cost\") VALUES ('EC2; spot', 12.50);"
+        );
+        assert_eq!(
+            lines[1],
+            "INSERT INTO billing.cur_data (service, \"Cost\") VALUES ('O''Reilly | \"S3\"', NULL);"
+        );
+    }
+
+    #[test]
+    fn quotes_reserved_words() {
+        assert_eq!(quote_ident("order"), "\"order\"");
+        assert_eq!(quote_ident("user"), "\"user\"");
+        assert_eq!(quote_ident("orders"), "orders");
+        assert_eq!(quote_table("sales.order"), "sales.\"order\"");
+        assert_eq!(
+            to_sql_insert(&result(), "order").lines().next(),
+            Some("INSERT INTO \"order\" (service, \"Cost\") VALUES ('EC2; spot', 12.50);")
+        );
+    }
+
+    #[test]
+    fn in_list_dedups_and_skips_nulls() {
+        let values = [json!("EC2"), Value::Null, json!("S3"), json!("EC2")];
+        assert_eq!(
+            sql_in_list(&values, ColumnKind::Text).as_deref(),
+            Some("IN ('EC2', 'S3')")
+        );
+        assert_eq!(
+            sql_in_list(&[json!("1.5"), json!(2)], ColumnKind::Numeric).as_deref(),
+            Some("IN (1.5, 2)")
+        );
+        assert_eq!(sql_in_list(&[Value::Null], ColumnKind::Text), None);
+    }
+
+    #[test]
+    fn quotes_non_finite_numbers() {
+        assert_eq!(sql_literal(&json!("-2.5e3"), ColumnKind::Numeric), "-2.5e3");
+        assert_eq!(sql_literal(&json!("NaN"), ColumnKind::Numeric), "'NaN'");
+        assert_eq!(sql_literal(&json!("inf"), ColumnKind::Numeric), "'inf'");
+        assert_eq!(
+            sql_literal(&json!("-Infinity"), ColumnKind::Numeric),
+            "'-Infinity'"
+        );
+        assert_eq!(sql_literal(&json!("1e999"), ColumnKind::Numeric), "'1e999'");
+    }
+}
@@ -0,0 +1,507 @@
+//! File imports into MegaDB tables: CSV parsing, column type inference,
+//! value conversion and the batches the server inserts.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::export::{quote_ident, quote_table, sql_literal};
+use crate::query::{ColumnKind, SqlType, TypedValue};
+use crate::result_view::cell_text;
+use crate::schema::ColumnInfo;
+
+/// Rows sent to the server per import request.
+pub const BATCH_ROWS: usize = 1000;
+
+/// Delimiters tried when sniffing a CSV file.
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// File formats that can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ImportFormat {
+    /// Format of a file, from its extension.
+    pub fn from_filename(name: &str) -> Option<Self> {
+        let ext = name.rsplit_once('.')?.1.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" | "tsv" | "txt" => Some(Self::Csv),
+            "parquet" | "pq" => Some(Self::Parquet),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Parquet => "Parquet",
+        }
+    }
+}
+
+/// A parsed file: column names and types, and its rows. CSV cells are
+/// strings; Parquet cells are typed JSON values, and only the first rows
+/// of a Parquet file are read into one; see [`ParquetUpload`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportFile {
+    pub columns: Vec<ColumnInfo>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl ImportFile {
+    /// Parse CSV text with a header row. Column types are inferred from the
+    /// values.
+    pub fn from_csv(text: &str, delimiter: char) -> Self {
+        let mut records = parse_csv(text, delimiter).into_iter();
+        let header = records.next().unwrap_or_default();
+        let width = header.len();
+        let rows: Vec<Vec<Value>> = records
+            .filter(|r| !(r.len() == 1 && r[0].is_empty()))
+            .map(|mut r| {
+                r.resize(width, String::new());
+                r.into_iter().map(Value::String).collect()
+            })
+            .collect();
+        let names = column_names(&header);
+        let columns = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let (data_type, nullable) = infer_type(rows.iter().map(|r| &r[i]));
+                ColumnInfo {
+                    name,
+                    data_type,
+                    nullable,
+                    comment: None,
+                }
+            })
+            .collect();
+        Self { columns, rows }
+    }
+}
+
+/// Split CSV text into records. Quoted fields may contain delimiters,
+/// doubled quotes and line breaks.
+pub fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// The delimiter that splits the first line into the most fields.
+pub fn sniff_delimiter(text: &str) -> char {
+    let first = text.lines().next().unwrap_or_default();
+    DELIMITERS
+        .into_iter()
+        .max_by_key(|d| first.matches(*d).count())
+        .filter(|d| first.contains(*d))
+        .unwrap_or(',')
+}
+
+/// SQL column names for header fields: lower snake case, unique, and never
+/// empty.
+pub fn column_names(header: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (i, field) in header.iter().enumerate() {
+        let mut name: String = field
+            .trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        name = name.trim_matches('_').to_string();
+        while name.contains("__") {
+            name = name.replace("__", "_");
+        }
+        if name.is_empty() {
+            name = format!("column_{}", i + 1);
+        } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name = format!("c_{name}");
+        }
+        let base = name.clone();
+        let mut n = 2;
+        while names.contains(&name) {
+            name = format!("{base}_{n}");
+            n += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// SQL type and nullability fitting every value of a text column. Empty
+/// strings count as NULL.
+pub fn infer_type<'a>(values: impl IntoIterator<Item = &'a Value>) -> (String, bool) {
+    let mut nullable = false;
+    let mut texts = Vec::new();
+    for value in values {
+        match value {
+            Value::Null => nullable = true,
+            Value::String(s) if s.trim().is_empty() => nullable = true,
+            v => texts.push(cell_text(v).trim().to_string()),
+        }
+    }
+    if texts.is_empty() {
+        return ("VARCHAR".to_string(), true);
+    }
+
+    let all = |f: &dyn Fn(&str) -> bool| texts.iter().all(|t| f(t));
+    let fits = |ty: SqlType| {
+        move |t: &str| TypedValue::try_decode(&Value::String(t.to_string()), &ty).is_some()
+    };
+    let data_type = if all(&|t| matches!(t.to_lowercase().as_str(), "true" | "false")) {
+        "BOOLEAN".to_string()
+    } else if all(&|t| t.parse::<i64>().is_ok()) {
+        "BIGINT".to_string()
+    } else if let Some((precision, scale)) = decimal_shape(&texts) {
+        format!("DECIMAL({precision},{scale})")
+    } else if all(&|t| t.parse::<f64>().is_ok_and(f64::is_finite)) {
+        "DOUBLE".to_string()
+    } else if all(&fits(SqlType::Date)) {
+        "DATE".to_string()
+    } else if all(&fits(SqlType::Timestamp { tz: false })) {
+        "TIMESTAMP".to_string()
+    } else if all(&fits(SqlType::Timestamp { tz: true })) {
+        "TIMESTAMP WITH TIME ZONE".to_string()
+    } else {
+        "VARCHAR".to_string()
+    };
+    (data_type, nullable)
+}
+
+/// Precision and scale holding every value, if all are plain decimals that
+/// fit in 38 digits.
+fn decimal_shape(texts: &[String]) -> Option<(usize, usize)> {
+    let mut int_digits = 1;
+    let mut scale = 0;
+    for text in texts {
+        let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        if int.is_empty() && frac.is_empty()
+            || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        int_digits = int_digits.max(int.trim_start_matches('0').len());
+        scale = scale.max(frac.len());
+    }
+    (int_digits + scale <= 38).then_some((int_digits + scale, scale))
+}
+
+/// Convert a file cell to a value of `column`. Empty strings are NULL.
+pub fn convert_cell(value: &Value, column: &ColumnInfo) -> Result<Value, String> {
+    let empty = match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        _ => false,
+    };
+    if empty {
+        return if column.nullable {
+            Ok(Value::Null)
+        } else {
+            Err(format!("{} can't be NULL", column.name))
+        };
+    }
+    let ty = SqlType::parse(&column.data_type);
+    if ty == SqlType::Text {
+        return Ok(Value::String(cell_text(value)));
+    }
+    TypedValue::try_decode(value, &ty)
+        .map(|v| v.to_json())
+        .ok_or_else(|| {
+            format!(
+                "{}: '{}' is not a valid {}",
+                column.name,
+                cell_text(value),
+                column.data_type
+            )
+        })
+}
+
+/// For each target column, the index of the file column with the same name.
+pub fn auto_map(file_columns: &[ColumnInfo], target: &[ColumnInfo]) -> Vec<Option<usize>> {
+    target
+        .iter()
+        .map(|t| {
+            file_columns
+                .iter()
+                .position(|f| f.name.eq_ignore_ascii_case(&t.name))
+        })
+        .collect()
+}
+
+/// A row of the file rearranged to the target columns; unmapped columns
+/// are NULL.
+pub fn map_row(row: &[Value], mapping: &[Option<usize>]) -> Vec<Value> {
+    mapping
+        .iter()
+        .map(|m| m.and_then(|i| row.get(i).cloned()).unwrap_or(Value::Null))
+        .collect()
+}
+
+/// Rows inserted by the server in one request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportBatch {
+    pub database: String,
+    /// Schema-qualified target table.
+    pub table: String,
+    /// Target columns, in the order of each row's values.
+    pub columns: Vec<ColumnInfo>,
+    pub rows: Vec<Vec<Value>>,
+    /// Index of the batch's first row in the file.
+    pub offset: usize,
+}
+
+impl ImportBatch {
+    /// Converted rows, each with its index in the file, and the rows that
+    /// don't fit the target columns.
+    pub fn prepare(&self) -> (Vec<(usize, Vec<Value>)>, Vec<RowError>) {
+        let mut valid = Vec::new();
+        let mut errors = Vec::new();
+        for (i, row) in self.rows.iter().enumerate() {
+            let converted: Result<Vec<Value>, String> = self
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(c, column)| convert_cell(row.get(c).unwrap_or(&Value::Null), column))
+                .collect();
+            match converted {
+                Ok(values) => valid.push((self.offset + i, values)),
+                Err(message) => errors.push(RowError {
+                    row: self.offset + i,
+                    message,
+                }),
+            }
+        }
+        (valid, errors)
+    }
+
+    /// Multi-row `INSERT` of converted rows.
+    pub fn insert_sql<'a>(&self, rows: impl IntoIterator<Item = &'a Vec<Value>>) -> String {
+        let kinds: Vec<ColumnKind> = self
+            .columns
+            .iter()
+            .map(|c| ColumnKind::from_data_type(&c.data_type))
+            .collect();
+        let names: Vec<String> = self.columns.iter().map(|c| quote_ident(&c.name)).collect();
+        let values: Vec<String> = rows
+            .into_iter()
+            .map(|row| {
+                let literals: Vec<String> = row
+                    .iter()
+                    .zip(&kinds)
+                    .map(|(v, kind)| sql_literal(v, *kind))
+                    .collect();
+                format!("({})", literals.join(", "))
+            })
+            .collect();
+        format!(
+            "INSERT INTO {} ({}) VALUES\n{}",
+            quote_table(&self.table),
+            names.join(", "),
+            values.join(",\n")
+        )
+    }
+}
+
+/// Rows of an uploaded Parquet file sent back for mapping and preview.
+pub const PARQUET_PREVIEW_ROWS: usize = 100;
+
+/// A Parquet file kept on the server until it is imported: its columns,
+/// first rows and row count.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParquetUpload {
+    pub id: uuid::Uuid,
+    /// Columns and the first [`PARQUET_PREVIEW_ROWS`] rows.
+    pub file: ImportFile,
+    pub total_rows: usize,
+}
+
+/// A batch of an uploaded Parquet file for the server to read, map and
+/// insert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParquetBatchRequest {
+    pub upload: uuid::Uuid,
+    pub database: String,
+    /// Schema-qualified target table.
+    pub table: String,
+    pub columns: Vec<ColumnInfo>,
+    /// For each target column, the file column feeding it.
+    pub mapping: Vec<Option<usize>>,
+    /// Index of the batch's first row in the file; the batch has up to
+    /// [`BATCH_ROWS`] rows.
+    pub offset: usize,
+}
+
+impl ParquetBatchRequest {
+    /// The batch to insert, from the file rows read at `offset`.
+    pub fn batch(&self, rows: &[Vec<Value>]) -> ImportBatch {
+        ImportBatch {
+            database: self.database.clone(),
+            table: self.table.clone(),
+            columns: self.columns.clone(),
+            rows: rows.iter().map(|row| map_row(row, &self.mapping)).collect(),
+            offset: self.offset,
+        }
+    }
+}
+
+/// A file row that couldn't be imported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowError {
+    /// Zero-based index of the data row in the file.
+    pub row: usize,
+    pub message: String,
+}
+
+/// Outcome of one batch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportBatchResult {
+    pub inserted: usize,
+    pub errors: Vec<RowError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_and_sniffs_csv() {
+        let text = "\u{feff}id;\"Line Item\";note\r\n1;\"a;b\";\"say \"\"hi\"\"\nbye\"\r\n2;c;\n";
+        assert_eq!(sniff_delimiter(text), ';');
+        assert_eq!(
+            parse_csv(text, ';'),
+            vec![
+                vec!["id", "Line Item", "note"],
+                vec!["1", "a;b", "say \"hi\"\nbye"],
+                vec!["2", "c", ""],
+            ]
+        );
+        assert_eq!(
+            column_names(&[
+                "Line Item".into(),
+                "".into(),
+                "2nd".into(),
+                "line-item".into()
+            ]),
+            vec!["line_item", "column_2", "c_2nd", "line_item_2"]
+        );
+    }
+
+    #[test]
+    fn infers_column_types() {
+        let file = ImportFile::from_csv(
+            "id,cost,ratio,active,day,at,name\n\
+             1,12.5,1e3,true,2026-02-01,2026-02-01 10:00:00,a\n\
+             -20,0.125,2,FALSE,2026-02-02,2026-02-01T11:30:00.5,\n",
+            ',',
+        );
+        let types: Vec<(&str, bool)> = file
+            .columns
+            .iter()
+            .map(|c| (c.data_type.as_str(), c.nullable))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("BIGINT", false),
+                ("DECIMAL(5,3)", false),
+                ("DOUBLE", false),
+                ("BOOLEAN", false),
+                ("DATE", false),
+                ("TIMESTAMP", false),
+                ("VARCHAR", true),
+            ]
+        );
+        assert_eq!(file.rows.len(), 2);
+    }
+
+    #[test]
+    fn prepares_batches_with_row_errors() {
+        let column = |name: &str, data_type: &str, nullable: bool| ColumnInfo {
+            name: name.into(),
+            data_type: data_type.into(),
+            nullable,
+            comment: None,
+        };
+        let file_columns = vec![
+            column("Cost", "VARCHAR", true),
+            column("id", "VARCHAR", true),
+        ];
+        let target = vec![
+            column("id", "BIGINT", false),
+            column("cost", "DECIMAL(10,2)", true),
+            column("region", "VARCHAR", true),
+        ];
+        let mapping = auto_map(&file_columns, &target);
+        assert_eq!(mapping, vec![Some(1), Some(0), None]);
+
+        let rows = [
+            vec![json!("1.5"), json!("7")],
+            vec![json!("x"), json!("8")],
+            vec![json!(""), json!("")],
+            vec![json!(""), json!("9")],
+        ];
+        let batch = ImportBatch {
+            database: "megadb".into(),
+            table: "public.costs".into(),
+            columns: target,
+            rows: rows.iter().map(|r| map_row(r, &mapping)).collect(),
+            offset: 100,
+        };
+        let (valid, errors) = batch.prepare();
+        assert_eq!(
+            errors,
+            vec![
+                RowError {
+                    row: 101,
+                    message: "cost: 'x' is not a valid DECIMAL(10,2)".into(),
+                },
+                RowError {
+                    row: 102,
+                    message: "id can't be NULL".into(),
+                },
+            ]
+        );
+        assert_eq!(
+            valid.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![100, 103]
+        );
+        assert_eq!(
+            batch.insert_sql(valid.iter().map(|(_, r)| r)),
+            "INSERT INTO public.costs (id, cost, region) VALUES\n(7, 1.50, NULL),\n(9, NULL, NULL)"
+        );
+    }
+}
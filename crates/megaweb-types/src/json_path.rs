@@ -0,0 +1,144 @@
+//! Paths into nested JSON and array cells, and the SQL expressions that
+//! extract them as columns of their own.
+
+use serde_json::Value;
+
+use crate::export::quote_ident;
+use crate::query::SqlType;
+
+/// One step into a nested value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathStep {
+    Key(String),
+    Index(usize),
+}
+
+/// The value at `path`, if present.
+pub fn value_at<'a>(value: &'a Value, path: &[PathStep]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |v, step| match step {
+        PathStep::Key(k) => v.get(k),
+        PathStep::Index(i) => v.get(i),
+    })
+}
+
+/// JSONPath-style label such as `$.tags.env[0]`.
+pub fn path_label(path: &[PathStep]) -> String {
+    let mut out = String::from("$");
+    for step in path {
+        match step {
+            PathStep::Key(k) if is_plain_key(k) => out.push_str(&format!(".{k}")),
+            PathStep::Key(k) => out.push_str(&format!("[{}]", Value::from(k.as_str()))),
+            PathStep::Index(i) => out.push_str(&format!("[{i}]")),
+        }
+    }
+    out
+}
+
+/// SQL expression extracting `path` from `column` of type `ty`.
+///
+/// SQL arrays are indexed with 1-based subscripts. JSON is walked with
+/// `->`; the last step uses `->>` when the target is a scalar so the
+/// column comes back as text rather than JSON.
+pub fn extract_expression(column: &str, ty: &SqlType, path: &[PathStep], scalar: bool) -> String {
+    let mut expr = quote_ident(column);
+    let mut ty = ty;
+    for (i, step) in path.iter().enumerate() {
+        match (ty, step) {
+            (SqlType::Array(inner), PathStep::Index(n)) => {
+                expr = format!("{expr}[{}]", n + 1);
+                ty = inner;
+            }
+            (_, step) => {
+                let last = i + 1 == path.len();
+                let op = if last && scalar { "->>" } else { "->" };
+                let key = match step {
+                    PathStep::Key(k) => format!("'{}'", k.replace('\'', "''")),
+                    PathStep::Index(n) => n.to_string(),
+                };
+                expr = format!("{expr} {op} {key}");
+            }
+        }
+    }
+    expr
+}
+
+/// Column alias for an extracted path, e.g. `tags_env`.
+pub fn path_alias(column: &str, path: &[PathStep]) -> String {
+    let mut parts = vec![column.to_string()];
+    parts.extend(path.iter().map(|step| match step {
+        PathStep::Key(k) => k.clone(),
+        PathStep::Index(i) => i.to_string(),
+    }));
+    let alias: String = parts
+        .join("_")
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    quote_ident(alias.trim_matches('_'))
+}
+
+/// Wrap `sql` so its result gains `expression AS alias` as a last column.
+pub fn add_column_to_query(sql: &str, expression: &str, alias: &str) -> String {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    format!("SELECT q.*, {expression} AS {alias}\nFROM (\n{sql}\n) AS q")
+}
+
+fn is_plain_key(key: &str) -> bool {
+    key.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path() -> Vec<PathStep> {
+        vec![
+            PathStep::Key("owner's team".into()),
+            PathStep::Index(0),
+            PathStep::Key("name".into()),
+        ]
+    }
+
+    #[test]
+    fn walks_and_labels_paths() {
+        let value = json!({"owner's team": [{"name": "infra"}]});
+        assert_eq!(value_at(&value, &path()), Some(&json!("infra")));
+        assert_eq!(value_at(&value, &[PathStep::Key("missing".into())]), None);
+        assert_eq!(path_label(&path()), "$[\"owner's team\"][0].name");
+    }
+
+    #[test]
+    fn builds_extract_expressions() {
+        assert_eq!(
+            extract_expression("tags", &SqlType::Json, &path(), true),
+            "tags -> 'owner''s team' -> 0 ->> 'name'"
+        );
+        assert_eq!(
+            extract_expression("tags", &SqlType::Json, &path()[..2], false),
+            "tags -> 'owner''s team' -> 0"
+        );
+        let array_of_json = SqlType::Array(Box::new(SqlType::Json));
+        assert_eq!(
+            extract_expression("Events", &array_of_json, &path()[1..], true),
+            "\"Events\"[1] ->> 'name'"
+        );
+        assert_eq!(path_alias("tags", &path()), "tags_owner_s_team_0_name");
+    }
+
+    #[test]
+    fn wraps_query_with_new_column() {
+        assert_eq!(
+            add_column_to_query(
+                "SELECT * FROM cmdb_resources;\n",
+                "tags ->> 'env'",
+                "tags_env"
+            ),
+            "SELECT q.*, tags ->> 'env' AS tags_env\nFROM (\nSELECT * FROM cmdb_resources\n) AS q"
+        );
+    }
+}
@@ -1,8 +1,23 @@
+pub mod catalog;
+pub mod chart;
 pub mod connection;
+pub mod ddl;
+pub mod diff;
+pub mod edit;
 pub mod explain;
+pub mod export;
+pub mod import;
+pub mod json_path;
 pub mod k8s;
 pub mod metrics;
+pub mod partitions;
+pub mod pivot;
+pub mod plan_advisor;
+pub mod plan_diff;
+pub mod plan_layout;
 pub mod query;
+pub mod result_view;
 pub mod schema;
+pub mod table_stats;
 pub mod toast;
 pub mod ws;
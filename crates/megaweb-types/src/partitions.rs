@@ -0,0 +1,269 @@
+//! Partition explorer: sizes by month, maintenance statements and
+//! warnings about partitions that hurt scans.
+//!
+//! Partitions come from [`partitions_sql`](crate::table_stats::partitions_sql).
+//! The month of a partition is read from its value, so tables partitioned
+//! by `year`, `month`, `day` or `hour` of a date get a heatmap and others
+//! don't.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ddl::quote_literal;
+use crate::export::quote_table;
+use crate::table_stats::PartitionStats;
+
+/// A partition this many times the median size is skewed.
+pub const SKEW_FACTOR: f64 = 4.0;
+
+/// Partitions smaller than this are tiny, when the table has several.
+pub const TINY_PARTITION_BYTES: u64 = 16 * 1024 * 1024;
+
+/// A partition with at least this many files averaging under
+/// [`SMALL_FILE_BYTES`] should be compacted.
+pub const SMALL_FILES_MIN_COUNT: u64 = 8;
+
+/// Files smaller than this on average make a partition fragmented.
+pub const SMALL_FILE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// The partitions of a table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TablePartitionsRequest {
+    pub database: String,
+    /// `schema.table`.
+    pub table: String,
+}
+
+/// `ALTER TABLE ... DROP PARTITION`, deleting the partition's rows.
+pub fn drop_partition_sql(table: &str, partition: &str) -> String {
+    format!(
+        "ALTER TABLE {} DROP PARTITION {};",
+        quote_table(table),
+        quote_literal(partition)
+    )
+}
+
+/// `ALTER TABLE ... COMPACT PARTITION`, merging the partition's files.
+pub fn compact_partition_sql(table: &str, partition: &str) -> String {
+    format!(
+        "ALTER TABLE {} COMPACT PARTITION {};",
+        quote_table(table),
+        quote_literal(partition)
+    )
+}
+
+/// Year and month in a partition value such as `2024-06`,
+/// `billing_period_month=2024-06` or `ts_day=2024-06-30`.
+pub fn partition_month(partition: &str) -> Option<(i32, u32)> {
+    let bytes = partition.as_bytes();
+    (0..bytes.len().saturating_sub(6)).find_map(|i| {
+        let candidate = &bytes[i..i + 7];
+        let digits = |r: std::ops::Range<usize>| candidate[r].iter().all(u8::is_ascii_digit);
+        let starts_number = i > 0 && bytes[i - 1].is_ascii_digit();
+        if starts_number || !digits(0..4) || candidate[4] != b'-' || !digits(5..7) {
+            return None;
+        }
+        let text = &partition[i..i + 7];
+        let year = text[..4].parse().ok()?;
+        let month = text[5..].parse().ok().filter(|m| (1..=12).contains(m))?;
+        Some((year, month))
+    })
+}
+
+/// Bytes and partitions of one month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonthSize {
+    pub year: i32,
+    pub month: u32,
+    pub size_bytes: u64,
+    pub partitions: usize,
+}
+
+/// Partition sizes summed by month, oldest first. Partitions without a
+/// month in their value are left out.
+pub fn monthly_sizes(partitions: &[PartitionStats]) -> Vec<MonthSize> {
+    let mut months: Vec<MonthSize> = Vec::new();
+    for p in partitions {
+        let Some((year, month)) = partition_month(&p.partition) else {
+            continue;
+        };
+        let size = p.size_bytes.unwrap_or(0);
+        match months
+            .iter_mut()
+            .find(|m| (m.year, m.month) == (year, month))
+        {
+            Some(m) => {
+                m.size_bytes += size;
+                m.partitions += 1;
+            }
+            None => months.push(MonthSize {
+                year,
+                month,
+                size_bytes: size,
+                partitions: 1,
+            }),
+        }
+    }
+    months.sort_by_key(|m| (m.year, m.month));
+    months
+}
+
+/// What's wrong with a partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionIssue {
+    /// Much larger than the others, so scans of it dominate.
+    Skewed,
+    /// So small that per-partition overhead outweighs its data.
+    Tiny,
+    /// Spread over many small files; compaction helps.
+    SmallFiles,
+}
+
+impl PartitionIssue {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Skewed => "Skewed",
+            Self::Tiny => "Tiny",
+            Self::SmallFiles => "Small files",
+        }
+    }
+}
+
+/// An issue found in a partition, with what to do about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartitionWarning {
+    pub partition: String,
+    pub issue: PartitionIssue,
+    pub message: String,
+}
+
+/// Warnings for skewed, tiny and fragmented partitions. Size comparisons
+/// need at least three partitions with a known size.
+pub fn partition_warnings(partitions: &[PartitionStats]) -> Vec<PartitionWarning> {
+    let mut sizes: Vec<u64> = partitions.iter().filter_map(|p| p.size_bytes).collect();
+    sizes.sort_unstable();
+    let median = (sizes.len() >= 3).then(|| sizes[sizes.len() / 2]);
+
+    let mut warnings = Vec::new();
+    for p in partitions {
+        let warn = |issue, message| PartitionWarning {
+            partition: p.partition.clone(),
+            issue,
+            message,
+        };
+        if let (Some(size), Some(median)) = (p.size_bytes, median) {
+            if median > 0 && size as f64 > median as f64 * SKEW_FACTOR {
+                warnings.push(warn(
+                    PartitionIssue::Skewed,
+                    format!(
+                        "{:.1}\u{00D7} the median partition size; a finer transform or bucketing spreads it out",
+                        size as f64 / median as f64
+                    ),
+                ));
+            } else if size < TINY_PARTITION_BYTES {
+                warnings.push(warn(
+                    PartitionIssue::Tiny,
+                    "Under 16 MiB; a coarser transform, e.g. month instead of day, means fewer partitions to open".to_string(),
+                ));
+            }
+        }
+        if let (Some(files), Some(size)) = (p.file_count, p.size_bytes) {
+            if files >= SMALL_FILES_MIN_COUNT && size / files < SMALL_FILE_BYTES {
+                warnings.push(warn(
+                    PartitionIssue::SmallFiles,
+                    format!("{files} files averaging under 32 MiB; compact the partition"),
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn partition(name: &str, size_bytes: u64, file_count: u64) -> PartitionStats {
+        PartitionStats {
+            partition: name.into(),
+            row_count: None,
+            file_count: Some(file_count),
+            size_bytes: Some(size_bytes),
+            last_modified: None,
+        }
+    }
+
+    #[test]
+    fn reads_months_from_partition_values() {
+        assert_eq!(partition_month("2024-06"), Some((2024, 6)));
+        assert_eq!(
+            partition_month("billing_period_month=2024-06"),
+            Some((2024, 6))
+        );
+        assert_eq!(partition_month("ts_hour=2023-12-31-23"), Some((2023, 12)));
+        assert_eq!(partition_month("region=eu-west-1"), None);
+        assert_eq!(partition_month("bucket=12024-06"), None);
+        assert_eq!(partition_month("2024-13"), None);
+
+        let months = monthly_sizes(&[
+            partition("day=2024-07-01", 10, 1),
+            partition("day=2024-06-02", 20, 1),
+            partition("day=2024-06-01", 5, 1),
+            partition("region=eu", 100, 1),
+        ]);
+        assert_eq!(
+            months,
+            [
+                MonthSize {
+                    year: 2024,
+                    month: 6,
+                    size_bytes: 25,
+                    partitions: 2
+                },
+                MonthSize {
+                    year: 2024,
+                    month: 7,
+                    size_bytes: 10,
+                    partitions: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn warns_about_skewed_tiny_and_fragmented_partitions() {
+        let partitions = [
+            partition("2024-01", 100 * MIB, 2),
+            partition("2024-02", 120 * MIB, 2),
+            partition("2024-03", 110 * MIB, 40),
+            partition("2024-04", 900 * MIB, 4),
+            partition("2024-05", MIB, 1),
+        ];
+        let warnings: Vec<(String, PartitionIssue)> = partition_warnings(&partitions)
+            .into_iter()
+            .map(|w| (w.partition, w.issue))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                ("2024-03".to_string(), PartitionIssue::SmallFiles),
+                ("2024-04".to_string(), PartitionIssue::Skewed),
+                ("2024-05".to_string(), PartitionIssue::Tiny),
+            ]
+        );
+        assert!(partition_warnings(&partitions[..2]).is_empty());
+    }
+
+    #[test]
+    fn builds_maintenance_statements() {
+        assert_eq!(
+            drop_partition_sql("public.cur_data", "billing_period_month=2024-06"),
+            "ALTER TABLE public.cur_data DROP PARTITION 'billing_period_month=2024-06';"
+        );
+        assert_eq!(
+            compact_partition_sql("public.cur_data", "o'clock"),
+            "ALTER TABLE public.cur_data COMPACT PARTITION 'o''clock';"
+        );
+    }
+}
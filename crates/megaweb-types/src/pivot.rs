@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::query::{ColumnKind, QueryColumn, QueryResult};
+use crate::result_view::{cell_number, cell_text, compare_values};
+
+/// How values are combined in a pivot cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    #[default]
+    Sum,
+    Avg,
+    Count,
+    Min,
+    Max,
+}
+
+impl Aggregation {
+    pub const ALL: [Aggregation; 5] = [
+        Aggregation::Sum,
+        Aggregation::Avg,
+        Aggregation::Count,
+        Aggregation::Min,
+        Aggregation::Max,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Aggregation::Sum => "sum",
+            Aggregation::Avg => "avg",
+            Aggregation::Count => "count",
+            Aggregation::Min => "min",
+            Aggregation::Max => "max",
+        }
+    }
+}
+
+/// An aggregated value field of a pivot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PivotValue {
+    pub column: String,
+    pub agg: Aggregation,
+}
+
+impl PivotValue {
+    pub fn label(&self) -> String {
+        format!("{}({})", self.agg.label(), self.column)
+    }
+}
+
+/// Pivot layout: which columns group rows, which spread across columns,
+/// and which are aggregated. Columns are referenced by name.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PivotSpec {
+    pub rows: Vec<String>,
+    pub columns: Vec<String>,
+    pub values: Vec<PivotValue>,
+}
+
+impl PivotSpec {
+    /// Drop fields that don't exist in `result`.
+    pub fn retain_existing(&mut self, result: &QueryResult) {
+        let has = |name: &String| result.columns.iter().any(|c| &c.name == name);
+        self.rows.retain(has);
+        self.columns.retain(has);
+        self.values.retain(|v| has(&v.column));
+    }
+}
+
+/// Running aggregate of one cell.
+#[derive(Debug, Clone, Copy, Default)]
+struct Acc {
+    count: usize,
+    numeric: usize,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl Acc {
+    fn add(&mut self, value: &Value) {
+        if value.is_null() {
+            return;
+        }
+        self.count += 1;
+        if let Some(n) = cell_number(value) {
+            self.numeric += 1;
+            self.sum += n;
+            self.min = Some(self.min.map_or(n, |m| m.min(n)));
+            self.max = Some(self.max.map_or(n, |m| m.max(n)));
+        }
+    }
+
+    fn finish(&self, agg: Aggregation) -> Option<f64> {
+        match agg {
+            Aggregation::Count => Some(self.count as f64),
+            Aggregation::Sum => (self.numeric > 0).then_some(self.sum),
+            Aggregation::Avg => (self.numeric > 0).then(|| self.sum / self.numeric as f64),
+            Aggregation::Min => self.min,
+            Aggregation::Max => self.max,
+        }
+    }
+}
+
+/// A computed pivot table. Cells hold one value per `(column key, value
+/// field)` pair, laid out column key major.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotTable {
+    pub row_fields: Vec<String>,
+    pub value_labels: Vec<String>,
+    pub row_keys: Vec<Vec<String>>,
+    pub col_keys: Vec<Vec<String>>,
+    /// `cells[row][col_key * values + value]`
+    pub cells: Vec<Vec<Option<f64>>>,
+    /// Per row, one total per value field across all column keys.
+    pub row_totals: Vec<Vec<Option<f64>>>,
+    /// Per column key and value field, the total across all rows.
+    pub column_totals: Vec<Option<f64>>,
+    pub grand_totals: Vec<Option<f64>>,
+}
+
+impl PivotTable {
+    /// Pivot the loaded rows of `result`. Returns `None` without any value
+    /// field or if a referenced column is missing.
+    pub fn build(result: &QueryResult, spec: &PivotSpec) -> Option<Self> {
+        if spec.values.is_empty() {
+            return None;
+        }
+        let index = |name: &String| result.columns.iter().position(|c| &c.name == name);
+        let row_cols: Vec<usize> = spec.rows.iter().map(index).collect::<Option<_>>()?;
+        let col_cols: Vec<usize> = spec.columns.iter().map(index).collect::<Option<_>>()?;
+        let val_cols: Vec<usize> = spec
+            .values
+            .iter()
+            .map(|v| index(&v.column))
+            .collect::<Option<_>>()?;
+        let nv = val_cols.len();
+
+        let key_of = |row: &[Value], cols: &[usize]| -> Vec<Value> {
+            cols.iter()
+                .map(|&c| row.get(c).cloned().unwrap_or(Value::Null))
+                .collect()
+        };
+
+        // Group rows by (row key, column key)
+        let mut row_keys: Vec<Vec<Value>> = Vec::new();
+        let mut col_keys: Vec<Vec<Value>> = Vec::new();
+        let mut row_pos: HashMap<String, usize> = HashMap::new();
+        let mut col_pos: HashMap<String, usize> = HashMap::new();
+        let mut groups: HashMap<(usize, usize), Vec<Acc>> = HashMap::new();
+        let mut row_accs: HashMap<usize, Vec<Acc>> = HashMap::new();
+        let mut col_accs: HashMap<usize, Vec<Acc>> = HashMap::new();
+        let mut grand = vec![Acc::default(); nv];
+
+        for row in &result.rows {
+            let rk = key_of(row, &row_cols);
+            let ck = key_of(row, &col_cols);
+            let r = *row_pos
+                .entry(serde_json::to_string(&rk).unwrap_or_default())
+                .or_insert_with(|| {
+                    row_keys.push(rk);
+                    row_keys.len() - 1
+                });
+            let c = *col_pos
+                .entry(serde_json::to_string(&ck).unwrap_or_default())
+                .or_insert_with(|| {
+                    col_keys.push(ck);
+                    col_keys.len() - 1
+                });
+            let cell = groups
+                .entry((r, c))
+                .or_insert_with(|| vec![Acc::default(); nv]);
+            let row_total = row_accs
+                .entry(r)
+                .or_insert_with(|| vec![Acc::default(); nv]);
+            let col_total = col_accs
+                .entry(c)
+                .or_insert_with(|| vec![Acc::default(); nv]);
+            for (i, &vc) in val_cols.iter().enumerate() {
+                let v = row.get(vc).unwrap_or(&Value::Null);
+                cell[i].add(v);
+                row_total[i].add(v);
+                col_total[i].add(v);
+                grand[i].add(v);
+            }
+        }
+
+        let kinds = |cols: &[usize]| -> Vec<ColumnKind> {
+            cols.iter().map(|&c| result.columns[c].kind()).collect()
+        };
+        let row_order = sorted_keys(&row_keys, &kinds(&row_cols));
+        let col_order = sorted_keys(&col_keys, &kinds(&col_cols));
+        let aggs: Vec<Aggregation> = spec.values.iter().map(|v| v.agg).collect();
+        let finish = |accs: Option<&Vec<Acc>>, i: usize| accs.and_then(|a| a[i].finish(aggs[i]));
+
+        let cells = row_order
+            .iter()
+            .map(|&r| {
+                col_order
+                    .iter()
+                    .flat_map(|&c| (0..nv).map(move |i| (c, i)))
+                    .map(|(c, i)| finish(groups.get(&(r, c)), i))
+                    .collect()
+            })
+            .collect();
+        let row_totals = row_order
+            .iter()
+            .map(|r| (0..nv).map(|i| finish(row_accs.get(r), i)).collect())
+            .collect();
+        let column_totals = col_order
+            .iter()
+            .flat_map(|&c| (0..nv).map(move |i| (c, i)))
+            .map(|(c, i)| finish(col_accs.get(&c), i))
+            .collect();
+        let grand_totals = (0..nv).map(|i| grand[i].finish(aggs[i])).collect();
+
+        let texts = |key: &Vec<Value>| key.iter().map(cell_text).collect::<Vec<_>>();
+        Some(Self {
+            row_fields: spec.rows.clone(),
+            value_labels: spec.values.iter().map(PivotValue::label).collect(),
+            row_keys: row_order.iter().map(|&r| texts(&row_keys[r])).collect(),
+            col_keys: col_order.iter().map(|&c| texts(&col_keys[c])).collect(),
+            cells,
+            row_totals,
+            column_totals,
+            grand_totals,
+        })
+    }
+
+    /// Header for data column `i` of a row: column key and value label.
+    pub fn column_label(&self, i: usize) -> String {
+        let nv = self.value_labels.len();
+        let key = self.col_keys[i / nv].join(" / ");
+        match (key.is_empty(), nv) {
+            (true, _) => self.value_labels[i % nv].clone(),
+            (false, 1) => key,
+            (false, _) => format!("{key} / {}", self.value_labels[i % nv]),
+        }
+    }
+
+    /// Flatten into a plain result (row fields, one column per data cell,
+    /// then totals, and a closing totals row) so it can go through the
+    /// regular exporters.
+    pub fn to_result(&self) -> QueryResult {
+        let column = |name: String, data_type: &str| QueryColumn {
+            name,
+            data_type: data_type.to_string(),
+            nullable: true,
+        };
+        let mut columns: Vec<QueryColumn> = self
+            .row_fields
+            .iter()
+            .map(|f| column(f.clone(), "VARCHAR"))
+            .collect();
+        let data_columns = self.col_keys.len() * self.value_labels.len();
+        columns.extend((0..data_columns).map(|i| column(self.column_label(i), "DOUBLE")));
+        if self.col_keys.len() > 1 {
+            columns.extend(
+                self.value_labels
+                    .iter()
+                    .map(|l| column(format!("Total / {l}"), "DOUBLE")),
+            );
+        }
+
+        let number = |v: &Option<f64>| v.map(Value::from).unwrap_or(Value::Null);
+        let mut rows: Vec<Vec<Value>> = self
+            .row_keys
+            .iter()
+            .zip(&self.cells)
+            .zip(&self.row_totals)
+            .map(|((key, cells), totals)| {
+                let mut row: Vec<Value> = key.iter().map(|k| Value::from(k.as_str())).collect();
+                row.extend(cells.iter().map(number));
+                if self.col_keys.len() > 1 {
+                    row.extend(totals.iter().map(number));
+                }
+                row
+            })
+            .collect();
+
+        // Same totals row as the on-screen table: labelled in the first row
+        // field, column totals, then the grand totals.
+        let mut totals: Vec<Value> = (0..self.row_fields.len())
+            .map(|i| Value::from(if i == 0 { "Total" } else { "" }))
+            .collect();
+        totals.extend(self.column_totals.iter().map(number));
+        if self.col_keys.len() > 1 {
+            totals.extend(self.grand_totals.iter().map(number));
+        }
+        rows.push(totals);
+
+        QueryResult {
+            columns,
+            row_count: rows.len() as u64,
+            rows,
+            execution_time_ms: 0,
+            error: None,
+            stats: None,
+        }
+    }
+}
+
+/// Indices of `keys` in display order, comparing field by field.
+fn sorted_keys(keys: &[Vec<Value>], kinds: &[ColumnKind]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by(|&a, &b| {
+        keys[a]
+            .iter()
+            .zip(&keys[b])
+            .zip(kinds)
+            .map(|((x, y), &kind)| match (x.is_null(), y.is_null()) {
+                (true, true) => std::cmp::Ordering::Equal,
+                (true, false) => std::cmp::Ordering::Greater,
+                (false, true) => std::cmp::Ordering::Less,
+                (false, false) => compare_values(kind, x, y),
+            })
+            .find(|o| o.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result() -> QueryResult {
+        crate::query::result(
+            &[
+                ("service", "VARCHAR"),
+                ("month", "DATE"),
+                ("cost", "DECIMAL(18,2)"),
+            ],
+            vec![
+                vec![json!("S3"), json!("2026-02-01"), json!("5")],
+                vec![json!("EC2"), json!("2026-01-01"), json!("10")],
+                vec![json!("EC2"), json!("2026-02-01"), json!("20")],
+                vec![json!("EC2"), json!("2026-02-01"), json!(30)],
+                vec![json!("S3"), json!("2026-01-01"), Value::Null],
+            ],
+        )
+    }
+
+    fn spec(agg: Aggregation) -> PivotSpec {
+        PivotSpec {
+            rows: vec!["service".into()],
+            columns: vec!["month".into()],
+            values: vec![PivotValue {
+                column: "cost".into(),
+                agg,
+            }],
+        }
+    }
+
+    #[test]
+    fn cross_tabulates_with_totals() {
+        let p = PivotTable::build(&result(), &spec(Aggregation::Sum)).unwrap();
+        assert_eq!(p.row_keys, vec![vec!["EC2"], vec!["S3"]]);
+        assert_eq!(p.col_keys, vec![vec!["2026-01-01"], vec!["2026-02-01"]]);
+        assert_eq!(p.cells[0], vec![Some(10.0), Some(50.0)]);
+        // S3 in January only has a NULL cost
+        assert_eq!(p.cells[1], vec![None, Some(5.0)]);
+        assert_eq!(p.row_totals, vec![vec![Some(60.0)], vec![Some(5.0)]]);
+        assert_eq!(p.column_totals, vec![Some(10.0), Some(55.0)]);
+        assert_eq!(p.grand_totals, vec![Some(65.0)]);
+    }
+
+    #[test]
+    fn aggregations_ignore_nulls() {
+        let avg = PivotTable::build(&result(), &spec(Aggregation::Avg)).unwrap();
+        assert_eq!(avg.cells[0], vec![Some(10.0), Some(25.0)]);
+        let count = PivotTable::build(&result(), &spec(Aggregation::Count)).unwrap();
+        assert_eq!(count.cells[1], vec![Some(0.0), Some(1.0)]);
+        let max = PivotTable::build(&result(), &spec(Aggregation::Max)).unwrap();
+        assert_eq!(max.grand_totals, vec![Some(30.0)]);
+    }
+
+    #[test]
+    fn flattens_for_export() {
+        let p = PivotTable::build(&result(), &spec(Aggregation::Sum)).unwrap();
+        let flat = p.to_result();
+        let names: Vec<&str> = flat.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["service", "2026-01-01", "2026-02-01", "Total / sum(cost)"]
+        );
+        assert_eq!(
+            flat.rows[1],
+            vec![json!("S3"), Value::Null, json!(5.0), json!(5.0)]
+        );
+        assert_eq!(
+            flat.rows.last().unwrap(),
+            &vec![json!("Total"), json!(10.0), json!(55.0), json!(65.0)]
+        );
+        assert_eq!(flat.row_count, p.row_keys.len() as u64 + 1);
+    }
+}
@@ -0,0 +1,566 @@
+//! Rule-based tuning hints for a [`PlanNode`] tree.
+//!
+//! Each rule looks at the plan together with the known tables' metadata and
+//! reports problems it can explain with a concrete next step. Table-aware
+//! rules need the scan's relation, so they apply to PostgreSQL-style and
+//! DataFusion logical plans but not to DataFusion physical scans, which
+//! only name their files.
+
+use crate::explain::PlanNode;
+use crate::schema::TableInfo;
+
+/// Input size from which a nested loop or a sort is worth a hint.
+pub const LARGE_INPUT_ROWS: f64 = 10_000.0;
+
+/// The check that produced a hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanRule {
+    /// A partitioned table scanned without any filter on its partition columns.
+    UnfilteredPartitionScan,
+    /// A partition column filtered in a form that can't prune partitions.
+    PartitionPruningDefeated,
+    /// Actual rows far from the estimate.
+    RowMisestimate,
+    /// A nested loop over two large inputs.
+    LargeNestedLoop,
+    /// A large sort that doesn't follow the table's sort columns.
+    SortMismatch,
+}
+
+impl PlanRule {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::UnfilteredPartitionScan => "Full partition scan",
+            Self::PartitionPruningDefeated => "No partition pruning",
+            Self::RowMisestimate => "Row misestimate",
+            Self::LargeNestedLoop => "Large nested loop",
+            Self::SortMismatch => "Sort against table order",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HintSeverity {
+    Info,
+    Warning,
+}
+
+/// A problem found in a plan, with what to do about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanHint {
+    pub rule: PlanRule,
+    pub severity: HintSeverity,
+    /// Preorder index of the node, as in [`PlanNode::preorder`].
+    pub node: usize,
+    pub message: String,
+    pub suggestion: String,
+}
+
+/// Run all rules over `plan`. Hints come warnings first, then in plan order.
+pub fn advise(plan: &PlanNode, tables: &[TableInfo]) -> Vec<PlanHint> {
+    let mut hints = Vec::new();
+    let mut filters = Vec::new();
+    walk(plan, 0, tables, &mut filters, &mut hints);
+    hints.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.node.cmp(&b.node)));
+    hints
+}
+
+fn walk(
+    node: &PlanNode,
+    id: usize,
+    tables: &[TableInfo],
+    filters: &mut Vec<String>,
+    hints: &mut Vec<PlanHint>,
+) {
+    if node.operator.contains("Scan") {
+        if let Some(table) = node.relation.as_deref().and_then(|r| find_table(r, tables)) {
+            let mut predicates = filters.clone();
+            predicates.extend(predicates_of(node));
+            check_partition_scan(id, table, &predicates, hints);
+        }
+    }
+    check_misestimate(node, id, hints);
+    check_nested_loop(node, id, hints);
+    check_sort(node, id, tables, hints);
+
+    // Filters above a scan still restrict what it reads
+    let own = if node.operator.contains("Filter") {
+        predicates_of(node)
+    } else {
+        Vec::new()
+    };
+    let pushed = own.len();
+    filters.extend(own);
+    let mut child_id = id + 1;
+    for child in &node.children {
+        walk(child, child_id, tables, filters, hints);
+        child_id += child.size();
+    }
+    filters.truncate(filters.len() - pushed);
+}
+
+fn check_partition_scan(
+    id: usize,
+    table: &TableInfo,
+    predicates: &[String],
+    hints: &mut Vec<PlanHint>,
+) {
+    if table.partitions.is_empty() {
+        return;
+    }
+    let columns: Vec<&str> = table.partitions.iter().map(|p| p.column.as_str()).collect();
+    let usage: Vec<(&str, Option<bool>)> = columns
+        .iter()
+        .map(|&column| {
+            let used = predicates
+                .iter()
+                .filter_map(|p| column_usage(p, column))
+                .reduce(|a, b| a || b);
+            (column, used)
+        })
+        .collect();
+
+    if usage.iter().any(|(_, used)| *used == Some(true)) {
+        return;
+    }
+    let wrapped: Vec<&str> = usage
+        .iter()
+        .filter(|(_, used)| *used == Some(false))
+        .map(|(column, _)| *column)
+        .collect();
+    if let Some(&column) = wrapped.first() {
+        hints.push(PlanHint {
+            rule: PlanRule::PartitionPruningDefeated,
+            severity: HintSeverity::Warning,
+            node: id,
+            message: format!(
+                "The filter on {column} can't prune partitions of {}: the column is wrapped in a function or cast",
+                table.name
+            ),
+            suggestion: format!(
+                "Compare {column} itself against a range, e.g. {column} >= '2026-01-01' AND {column} < '2026-02-01'"
+            ),
+        });
+    } else {
+        hints.push(PlanHint {
+            rule: PlanRule::UnfilteredPartitionScan,
+            severity: HintSeverity::Warning,
+            node: id,
+            message: format!(
+                "{} is partitioned by {} but the scan has no filter on it, so every partition is read",
+                table.name,
+                columns.join(", ")
+            ),
+            suggestion: format!("Add a WHERE condition on {} to read only the partitions you need", columns[0]),
+        });
+    }
+}
+
+/// Report a misestimate where it starts, not on every node above it.
+fn check_misestimate(node: &PlanNode, id: usize, hints: &mut Vec<PlanHint>) {
+    let Some(ratio) = node.misestimate() else {
+        return;
+    };
+    if node.children.iter().any(|c| c.misestimate().is_some()) {
+        return;
+    }
+    let direction = if ratio >= 1.0 {
+        format!("{ratio:.0}\u{00D7} more")
+    } else {
+        format!("{:.0}\u{00D7} fewer", 1.0 / ratio)
+    };
+    let relation = node
+        .relation
+        .as_deref()
+        .or_else(|| {
+            node.preorder()
+                .into_iter()
+                .find_map(|n| n.relation.as_deref())
+        })
+        .map(table_name);
+    let suggestion = match relation {
+        Some(table) => {
+            format!("Refresh the statistics with ANALYZE {table}, then check the plan again")
+        }
+        None => "Refresh the statistics of the tables below this node".to_string(),
+    };
+    hints.push(PlanHint {
+        rule: PlanRule::RowMisestimate,
+        severity: HintSeverity::Warning,
+        node: id,
+        message: format!(
+            "{} expected {} rows but produced {}, {direction} than estimated",
+            node.operator,
+            node.estimated_rows,
+            node.actual_rows.unwrap_or(0)
+        ),
+        suggestion,
+    });
+}
+
+fn check_nested_loop(node: &PlanNode, id: usize, hints: &mut Vec<PlanHint>) {
+    let op = &node.operator;
+    if !(op.contains("Nested Loop") || op.contains("NestedLoopJoin") || op.contains("CrossJoin")) {
+        return;
+    }
+    let [outer, inner, ..] = node.children.as_slice() else {
+        return;
+    };
+    let (Some(outer_rows), Some(inner_rows)) = (rows_per_loop(outer), rows_per_loop(inner)) else {
+        return;
+    };
+    if outer_rows < LARGE_INPUT_ROWS || inner_rows < LARGE_INPUT_ROWS {
+        return;
+    }
+    hints.push(PlanHint {
+        rule: PlanRule::LargeNestedLoop,
+        severity: HintSeverity::Warning,
+        node: id,
+        message: format!(
+            "{op} compares {outer_rows:.0} \u{00D7} {inner_rows:.0} row pairs"
+        ),
+        suggestion: "Join on an equality condition so a hash join can be used, or filter both inputs before joining".to_string(),
+    });
+}
+
+fn check_sort(node: &PlanNode, id: usize, tables: &[TableInfo], hints: &mut Vec<PlanHint>) {
+    if !matches!(
+        node.operator.as_str(),
+        "Sort" | "SortExec" | "Incremental Sort"
+    ) {
+        return;
+    }
+    let Some(sort_key) = node
+        .extra
+        .iter()
+        .find(|(k, _)| k == "Sort Key" || k == "expr")
+        .map(|(_, v)| v.trim_matches(|c| c == '[' || c == ']'))
+    else {
+        return;
+    };
+    let keys = sort_keys(sort_key);
+    // Only a sort over a single table can follow that table's order
+    let mut found: Vec<&TableInfo> = Vec::new();
+    for relation in node
+        .preorder()
+        .into_iter()
+        .filter_map(|n| n.relation.as_deref())
+    {
+        if let Some(table) = find_table(relation, tables) {
+            if !found.iter().any(|t| std::ptr::eq(*t, table)) {
+                found.push(table);
+            }
+        }
+    }
+    let [table] = found.as_slice() else {
+        return;
+    };
+    let Some(order) = table.sort_columns.as_ref().filter(|c| !c.is_empty()) else {
+        return;
+    };
+    let follows = keys.len() <= order.len()
+        && keys
+            .iter()
+            .zip(order)
+            .all(|(key, col)| key.as_deref().is_some_and(|k| k.eq_ignore_ascii_case(col)));
+    if follows {
+        return;
+    }
+    // Unknown input size counts as large
+    let input = node.children.first().unwrap_or(node);
+    if rows_per_loop(input).is_some_and(|rows| rows < LARGE_INPUT_ROWS) {
+        return;
+    }
+    hints.push(PlanHint {
+        rule: PlanRule::SortMismatch,
+        severity: HintSeverity::Info,
+        node: id,
+        message: format!(
+            "Sorting on {sort_key} doesn't follow the sort order of {} ({})",
+            table.name,
+            order.join(", ")
+        ),
+        suggestion: format!(
+            "Order by a prefix of ({}) to use the stored order, add a LIMIT to keep only the top rows, or make this the table's sort order if the query is common",
+            order.join(", ")
+        ),
+    });
+}
+
+/// Rows a node produces each time it runs: actual when analyzed, else the
+/// estimate. `None` when the plan has no figure for it.
+fn rows_per_loop(node: &PlanNode) -> Option<f64> {
+    match (node.actual_rows, node.actual_loops) {
+        (_, Some(0)) => None,
+        (Some(rows), _) => Some(rows as f64),
+        (None, _) => (node.estimated_rows > 0).then_some(node.estimated_rows as f64),
+    }
+}
+
+/// Conditions of a node that restrict which rows it reads or keeps.
+fn predicates_of(node: &PlanNode) -> Vec<String> {
+    const KEYS: [&str; 8] = [
+        "filter",
+        "index cond",
+        "recheck cond",
+        "predicate",
+        "pruning_predicate",
+        "filters",
+        "partial_filters",
+        "full_filters",
+    ];
+    let filter_node = node.operator.contains("Filter");
+    node.extra
+        .iter()
+        .filter(|(key, _)| {
+            KEYS.iter().any(|k| key.eq_ignore_ascii_case(k)) || (filter_node && key == "expr")
+        })
+        .map(|(_, value)| value.clone())
+        .collect()
+}
+
+/// Table name of a plan relation such as `cur_data c` or `public.cur_data`.
+fn table_name(relation: &str) -> &str {
+    relation.split_whitespace().next().unwrap_or(relation)
+}
+
+fn find_table<'a>(relation: &str, tables: &'a [TableInfo]) -> Option<&'a TableInfo> {
+    let name = table_name(relation);
+    let (schema, name) = match name.rsplit_once('.') {
+        Some((schema, name)) => (Some(schema), name),
+        None => (None, name),
+    };
+    tables.iter().find(|t| {
+        t.name.eq_ignore_ascii_case(name)
+            && schema.is_none_or(|s| s.eq_ignore_ascii_case(&t.schema_name))
+    })
+}
+
+/// Column names of a sort key list like `c.cost DESC, service_name` or
+/// `[cost@1 DESC]`; `None` for expressions.
+fn sort_keys(value: &str) -> Vec<Option<String>> {
+    let value = value.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut keys = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                keys.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    keys.push(&value[start..]);
+    keys.into_iter()
+        .map(|key| {
+            let term = key.split_whitespace().next()?;
+            if term.contains('(') {
+                return None;
+            }
+            let term = term.split('@').next().unwrap_or(term);
+            let column = term.rsplit('.').next().unwrap_or(term);
+            Some(column.trim_matches('"').to_string())
+        })
+        .collect()
+}
+
+/// How `predicate` uses `column`: `None` if it doesn't mention it,
+/// `Some(true)` if it compares the bare column at least once, `Some(false)`
+/// if the column only appears inside function calls or casts.
+fn column_usage(predicate: &str, column: &str) -> Option<bool> {
+    const NOT_FUNCTIONS: [&str; 8] = ["and", "or", "not", "in", "any", "all", "when", "exists"];
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let text = predicate.to_ascii_lowercase();
+    let column = column.to_ascii_lowercase();
+    let mut usage = None;
+    for (start, _) in text.match_indices(&column) {
+        let end = start + column.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        if before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+            continue;
+        }
+        // Step back over a qualifier such as `c.`, then look for `fn(`
+        let head = text[..start].trim_end_matches(|c: char| is_ident(c) || c == '.' || c == '"');
+        let head = head.trim_end();
+        let in_call = head.strip_suffix('(').is_some_and(|h| {
+            let word: String = h
+                .trim_end()
+                .chars()
+                .rev()
+                .take_while(|&c| is_ident(c))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .collect();
+            !word.is_empty() && !NOT_FUNCTIONS.contains(&word.as_str())
+        });
+        // Skip a DataFusion column index such as `@2`, then look for `::`
+        let tail =
+            text[end..].trim_start_matches(|c: char| c == '@' || c.is_ascii_digit() || c == '"');
+        let cast = tail
+            .trim_start_matches(|c: char| c == ')' || c.is_whitespace())
+            .starts_with("::");
+        let bare = !in_call && !cast;
+        usage = Some(usage.unwrap_or(false) || bare);
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::PartitionInfo;
+
+    fn table(name: &str, partitions: &[&str], sort_columns: &[&str]) -> TableInfo {
+        TableInfo {
+            schema_name: "public".into(),
+            name: name.into(),
+            engine: "OLAP".into(),
+            row_count: None,
+            size_bytes: None,
+            columns: Vec::new(),
+            partitions: partitions
+                .iter()
+                .map(|c| PartitionInfo {
+                    column: c.to_string(),
+                    transform: "month".into(),
+                })
+                .collect(),
+            compression: None,
+            sort_columns: (!sort_columns.is_empty())
+                .then(|| sort_columns.iter().map(|c| c.to_string()).collect()),
+            primary_key: Vec::new(),
+        }
+    }
+
+    fn tables() -> Vec<TableInfo> {
+        vec![
+            table(
+                "cur_data",
+                &["billing_period"],
+                &["account_id", "service_name"],
+            ),
+            table("accounts", &[], &[]),
+        ]
+    }
+
+    fn rules(text: &str) -> Vec<(PlanRule, usize)> {
+        let plan = PlanNode::parse(text).unwrap();
+        advise(&plan, &tables())
+            .into_iter()
+            .map(|h| (h.rule, h.node))
+            .collect()
+    }
+
+    #[test]
+    fn flags_partitioned_scan_without_partition_filter() {
+        let unfiltered = "\
+Aggregate  (cost=500.00..500.01 rows=1 width=8)
+  ->  Seq Scan on cur_data c  (cost=0.00..400.00 rows=100000 width=8)
+        Filter: (region = 'us-east-1'::text)";
+        assert_eq!(rules(unfiltered), [(PlanRule::UnfilteredPartitionScan, 1)]);
+
+        let filtered = "\
+Seq Scan on cur_data  (cost=0.00..40.00 rows=100 width=8)
+  Filter: ((billing_period >= '2026-01-01'::date) AND (region = 'us-east-1'::text))";
+        assert!(rules(filtered).is_empty());
+
+        // DataFusion logical plan with the filter above the scan
+        let datafusion = "\
+Filter: cur_data.billing_period >= Date32(\"2026-01-01\")
+  TableScan: cur_data projection=[cost, billing_period]";
+        let plan = PlanNode::parse_datafusion(datafusion).unwrap();
+        assert!(advise(&plan, &tables()).is_empty());
+    }
+
+    #[test]
+    fn flags_partition_column_inside_function() {
+        let text = "\
+Seq Scan on cur_data  (cost=0.00..400.00 rows=100 width=8)
+  Filter: (date_trunc('month'::text, (c.billing_period)::timestamp) = '2026-01-01'::timestamp)";
+        assert_eq!(rules(text), [(PlanRule::PartitionPruningDefeated, 0)]);
+
+        assert_eq!(
+            column_usage("(billing_period)::text = '2026'", "billing_period"),
+            Some(false)
+        );
+        assert_eq!(
+            column_usage("CAST(billing_period@2 AS Utf8) = x", "billing_period"),
+            Some(false)
+        );
+        assert_eq!(
+            column_usage("a = 1 AND (billing_period >= x)", "billing_period"),
+            Some(true)
+        );
+        assert_eq!(
+            column_usage("billing_period_old = x", "billing_period"),
+            None
+        );
+    }
+
+    #[test]
+    fn flags_misestimate_where_it_starts() {
+        let text = "\
+Hash Join  (cost=10.00..500.00 rows=100 width=64) (actual time=0.500..42.000 rows=9000 loops=1)
+  Hash Cond: (c.account_id = a.account_id)
+  ->  Index Scan using cur_data_idx on cur_data c  (cost=0.00..400.00 rows=100 width=48) (actual time=0.010..30.000 rows=10000 loops=1)
+        Index Cond: (billing_period = '2026-01-01'::date)
+  ->  Hash  (cost=5.00..5.00 rows=50 width=16) (actual time=0.200..0.200 rows=50 loops=1)
+        ->  Seq Scan on accounts a  (cost=0.00..5.00 rows=50 width=16) (actual time=0.005..0.050 rows=50 loops=1)";
+        let plan = PlanNode::parse(text).unwrap();
+        let hints = advise(&plan, &tables());
+        assert_eq!(hints.len(), 1);
+        assert_eq!(
+            (hints[0].rule, hints[0].node),
+            (PlanRule::RowMisestimate, 1)
+        );
+        assert!(hints[0].suggestion.contains("ANALYZE cur_data"));
+    }
+
+    #[test]
+    fn flags_nested_loop_over_large_inputs() {
+        let large = "\
+Nested Loop  (cost=0.00..9000000.00 rows=1000 width=16)
+  Join Filter: (c.cost > a.budget)
+  ->  Seq Scan on accounts a  (cost=0.00..500.00 rows=20000 width=8)
+  ->  Materialize  (cost=0.00..800.00 rows=50000 width=8)
+        ->  Seq Scan on other c  (cost=0.00..600.00 rows=50000 width=8)";
+        assert_eq!(rules(large), [(PlanRule::LargeNestedLoop, 0)]);
+
+        // An index lookup per outer row is what nested loops are for
+        let indexed = "\
+Nested Loop  (cost=0.29..9000.00 rows=20000 width=16)
+  ->  Seq Scan on accounts a  (cost=0.00..500.00 rows=20000 width=8)
+  ->  Index Scan using other_pkey on other c  (cost=0.29..0.40 rows=1 width=8)
+        Index Cond: (id = a.id)";
+        assert!(rules(indexed).is_empty());
+    }
+
+    #[test]
+    fn flags_sorts_against_table_order() {
+        let mismatch = "\
+Sort  (cost=900.00..950.00 rows=50000 width=16)
+  Sort Key: c.cost DESC
+  ->  Seq Scan on cur_data c  (cost=0.00..400.00 rows=50000 width=16)
+        Filter: (billing_period = '2026-01-01'::date)";
+        let plan = PlanNode::parse(mismatch).unwrap();
+        let hints = advise(&plan, &tables());
+        assert_eq!((hints[0].rule, hints[0].node), (PlanRule::SortMismatch, 0));
+        assert_eq!(hints[0].severity, HintSeverity::Info);
+        assert!(hints[0].message.contains("(account_id, service_name)"));
+
+        let prefix = mismatch.replace("c.cost DESC", "c.account_id");
+        assert!(rules(&prefix).is_empty());
+        let small = mismatch.replace("rows=50000", "rows=500");
+        assert!(rules(&small).is_empty());
+        assert_eq!(
+            sort_keys("[cost@1 DESC, lower(region@2) ASC]"),
+            [Some("cost".to_string()), None]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Structural comparison of two [`PlanNode`] trees, e.g. EXPLAIN before
+//! and after changing a table's sort columns.
+//!
+//! Children are aligned by operator and relation, keeping their order;
+//! nodes left over between aligned ones are paired as replaced operators,
+//! and the rest count as added or removed.
+
+use crate::explain::PlanNode;
+
+/// How a node differs between the two plans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanChange {
+    Unchanged,
+    /// Same operator, different estimates or details.
+    Modified,
+    /// A different operator in the same place.
+    Replaced,
+    Added,
+    Removed,
+}
+
+/// The figures of one side of a compared node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanSide {
+    pub operator: String,
+    pub relation: Option<String>,
+    pub cost_total: f64,
+    pub estimated_rows: u64,
+    pub actual_rows: Option<u64>,
+    /// Total time over all loops, for EXPLAIN ANALYZE plans.
+    pub time_ms: Option<f64>,
+    pub extra: Vec<(String, String)>,
+}
+
+impl PlanSide {
+    fn of(node: &PlanNode) -> Self {
+        Self {
+            operator: node.operator.clone(),
+            relation: node.relation.clone(),
+            cost_total: node.cost_total,
+            estimated_rows: node.estimated_rows,
+            actual_rows: node.actual_rows,
+            time_ms: node.total_time_ms(),
+            extra: node.extra.clone(),
+        }
+    }
+}
+
+/// A node of the compared plan, with its figures before and after.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanDiffNode {
+    pub change: PlanChange,
+    pub before: Option<PlanSide>,
+    pub after: Option<PlanSide>,
+    pub children: Vec<PlanDiffNode>,
+}
+
+/// Number of nodes per kind of change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanDiffCounts {
+    pub unchanged: usize,
+    pub modified: usize,
+    pub replaced: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl PlanDiffCounts {
+    pub fn is_identical(&self) -> bool {
+        self.modified + self.replaced + self.added + self.removed == 0
+    }
+}
+
+/// Compare two plans. The roots are always paired.
+pub fn diff_plans(before: &PlanNode, after: &PlanNode) -> PlanDiffNode {
+    let change = if key(before) != key(after) {
+        PlanChange::Replaced
+    } else if before.cost_total != after.cost_total
+        || before.estimated_rows != after.estimated_rows
+        || before.extra != after.extra
+    {
+        PlanChange::Modified
+    } else {
+        PlanChange::Unchanged
+    };
+    let children = align(&before.children, &after.children)
+        .into_iter()
+        .map(|pair| match pair {
+            (Some(b), Some(a)) => diff_plans(b, a),
+            (Some(b), None) => one_sided(b, PlanChange::Removed),
+            (None, Some(a)) => one_sided(a, PlanChange::Added),
+            (None, None) => unreachable!("aligned pairs have at least one side"),
+        })
+        .collect();
+    PlanDiffNode {
+        change,
+        before: Some(PlanSide::of(before)),
+        after: Some(PlanSide::of(after)),
+        children,
+    }
+}
+
+impl PlanDiffNode {
+    /// The side shown for the node: after, or before for removed nodes.
+    pub fn side(&self) -> &PlanSide {
+        self.after
+            .as_ref()
+            .or(self.before.as_ref())
+            .expect("a compared node has at least one side")
+    }
+
+    /// Change in total cost, `after - before`.
+    pub fn cost_delta(&self) -> Option<f64> {
+        Some(self.after.as_ref()?.cost_total - self.before.as_ref()?.cost_total)
+    }
+
+    /// Change in estimated rows, `after - before`.
+    pub fn rows_delta(&self) -> Option<i64> {
+        Some(
+            self.after.as_ref()?.estimated_rows as i64
+                - self.before.as_ref()?.estimated_rows as i64,
+        )
+    }
+
+    /// Change in total time, `after - before`, when both were analyzed.
+    pub fn time_delta(&self) -> Option<f64> {
+        Some(self.after.as_ref()?.time_ms? - self.before.as_ref()?.time_ms?)
+    }
+
+    /// Count nodes in this subtree by change.
+    pub fn counts(&self) -> PlanDiffCounts {
+        let mut counts = PlanDiffCounts::default();
+        self.count_into(&mut counts);
+        counts
+    }
+
+    fn count_into(&self, counts: &mut PlanDiffCounts) {
+        match self.change {
+            PlanChange::Unchanged => counts.unchanged += 1,
+            PlanChange::Modified => counts.modified += 1,
+            PlanChange::Replaced => counts.replaced += 1,
+            PlanChange::Added => counts.added += 1,
+            PlanChange::Removed => counts.removed += 1,
+        }
+        for child in &self.children {
+            child.count_into(counts);
+        }
+    }
+}
+
+fn key(node: &PlanNode) -> (&str, Option<&str>) {
+    (&node.operator, node.relation.as_deref())
+}
+
+fn one_sided(node: &PlanNode, change: PlanChange) -> PlanDiffNode {
+    let side = Some(PlanSide::of(node));
+    let (before, after) = if change == PlanChange::Removed {
+        (side, None)
+    } else {
+        (None, side)
+    };
+    PlanDiffNode {
+        change,
+        before,
+        after,
+        children: node.children.iter().map(|c| one_sided(c, change)).collect(),
+    }
+}
+
+/// Align two child lists on their longest common subsequence of keys.
+/// Between matched children, leftovers are paired in order.
+fn align<'a>(
+    before: &'a [PlanNode],
+    after: &'a [PlanNode],
+) -> Vec<(Option<&'a PlanNode>, Option<&'a PlanNode>)> {
+    let (n, m) = (before.len(), after.len());
+    // lcs[i][j]: common length of before[i..] and after[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if key(&before[i]) == key(&after[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut pending_b, mut pending_a) = (Vec::new(), Vec::new());
+    let flush = |out: &mut Vec<_>, pb: &mut Vec<&'a PlanNode>, pa: &mut Vec<&'a PlanNode>| {
+        let len = pb.len().max(pa.len());
+        for k in 0..len {
+            out.push((pb.get(k).copied(), pa.get(k).copied()));
+        }
+        pb.clear();
+        pa.clear();
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && key(&before[i]) == key(&after[j]) {
+            flush(&mut out, &mut pending_b, &mut pending_a);
+            out.push((Some(&before[i]), Some(&after[j])));
+            i += 1;
+            j += 1;
+        } else if j >= m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            pending_b.push(&before[i]);
+            i += 1;
+        } else {
+            pending_a.push(&after[j]);
+            j += 1;
+        }
+    }
+    flush(&mut out, &mut pending_b, &mut pending_a);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(operator: &str, cost: f64, children: Vec<PlanNode>) -> PlanNode {
+        PlanNode {
+            operator: operator.into(),
+            relation: None,
+            cost_startup: 0.0,
+            cost_total: cost,
+            estimated_rows: 100,
+            actual_rows: None,
+            actual_time_ms: None,
+            actual_loops: None,
+            width: 0,
+            children,
+            extra: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_nodes_and_reports_changes() {
+        let before = node(
+            "Sort",
+            900.0,
+            vec![node(
+                "Hash Join",
+                500.0,
+                vec![node("Seq Scan", 400.0, vec![]), node("Hash", 50.0, vec![])],
+            )],
+        );
+        let after = node(
+            "Limit",
+            120.0,
+            vec![node(
+                "Hash Join",
+                100.0,
+                vec![
+                    node("Index Scan", 20.0, vec![]),
+                    node("Hash", 50.0, vec![]),
+                    node("Materialize", 5.0, vec![node("Seq Scan", 4.0, vec![])]),
+                ],
+            )],
+        );
+        let diff = diff_plans(&before, &after);
+
+        assert_eq!(diff.change, PlanChange::Replaced);
+        let join = &diff.children[0];
+        assert_eq!(join.change, PlanChange::Modified);
+        assert_eq!(join.cost_delta(), Some(-400.0));
+        let changes: Vec<PlanChange> = join.children.iter().map(|c| c.change).collect();
+        assert_eq!(
+            changes,
+            [
+                PlanChange::Replaced,
+                PlanChange::Unchanged,
+                PlanChange::Added
+            ]
+        );
+        assert_eq!(join.children[0].side().operator, "Index Scan");
+        assert_eq!(join.children[2].children[0].change, PlanChange::Added);
+
+        let counts = diff.counts();
+        assert_eq!(
+            (
+                counts.replaced,
+                counts.modified,
+                counts.unchanged,
+                counts.added,
+                counts.removed
+            ),
+            (2, 1, 1, 2, 0)
+        );
+        assert!(!counts.is_identical());
+        assert!(diff_plans(&before, &before).counts().is_identical());
+    }
+
+    #[test]
+    fn unmatched_children_are_removed() {
+        let before = node(
+            "Append",
+            10.0,
+            vec![node("A", 1.0, vec![]), node("B", 1.0, vec![])],
+        );
+        let after = node("Append", 10.0, vec![node("B", 1.0, vec![])]);
+        let diff = diff_plans(&before, &after);
+        assert_eq!(diff.change, PlanChange::Unchanged);
+        assert_eq!(diff.children[0].change, PlanChange::Removed);
+        assert_eq!(diff.children[0].side().operator, "A");
+        assert_eq!(diff.children[1].change, PlanChange::Unchanged);
+    }
+}
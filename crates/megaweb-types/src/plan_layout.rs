@@ -0,0 +1,222 @@
+//! Geometry for drawing a [`PlanNode`] tree: a node-link diagram and a
+//! flame (icicle) view. Nodes are identified by their preorder index, as
+//! returned by [`PlanNode::preorder`]. Positions are in abstract units; the
+//! caller scales them to pixels.
+
+use std::collections::HashSet;
+
+use crate::explain::PlanNode;
+
+/// A node placed in the node-link diagram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedNode {
+    pub id: usize,
+    pub parent: Option<usize>,
+    /// Column, in node widths; parents are centered over their children.
+    pub x: f64,
+    /// Row, equal to the node's depth.
+    pub depth: usize,
+    /// Descendants hidden because the node is collapsed.
+    pub hidden: usize,
+}
+
+/// Place the nodes of `root` as a top-down tree. Subtrees of `collapsed`
+/// nodes are left out.
+pub fn tree_layout(root: &PlanNode, collapsed: &HashSet<usize>) -> Vec<PlacedNode> {
+    fn place(
+        node: &PlanNode,
+        id: usize,
+        parent: Option<usize>,
+        depth: usize,
+        collapsed: &HashSet<usize>,
+        next_leaf: &mut f64,
+        out: &mut Vec<PlacedNode>,
+    ) -> (f64, usize) {
+        let slot = out.len();
+        out.push(PlacedNode {
+            id,
+            parent,
+            x: 0.0,
+            depth,
+            hidden: 0,
+        });
+        let size = node.size();
+        if collapsed.contains(&id) || node.children.is_empty() {
+            out[slot].x = *next_leaf;
+            out[slot].hidden = size - 1;
+            *next_leaf += 1.0;
+            return (out[slot].x, size);
+        }
+        let mut child_id = id + 1;
+        let (mut first, mut last) = (None, 0.0);
+        for child in &node.children {
+            let (x, child_size) = place(
+                child,
+                child_id,
+                Some(id),
+                depth + 1,
+                collapsed,
+                next_leaf,
+                out,
+            );
+            first.get_or_insert(x);
+            last = x;
+            child_id += child_size;
+        }
+        out[slot].x = (first.unwrap_or(last) + last) / 2.0;
+        (out[slot].x, size)
+    }
+
+    let mut out = Vec::new();
+    place(root, 0, None, 0, collapsed, &mut 0.0, &mut out);
+    out
+}
+
+/// Rows flowing out of a node into its parent: actual rows over all loops
+/// when analyzed, else the estimate.
+pub fn output_rows(node: &PlanNode) -> f64 {
+    match node.actual_rows {
+        Some(rows) => rows as f64 * node.actual_loops.unwrap_or(1) as f64,
+        None => node.estimated_rows as f64,
+    }
+}
+
+/// A box of the flame view; `start` and `width` are fractions of the full
+/// width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlameBox {
+    pub id: usize,
+    pub depth: usize,
+    pub start: f64,
+    pub width: f64,
+}
+
+/// Lay out the subtree of node `focus` as an icicle: each node spans the
+/// share of its parent's width that its total time (when `analyzed`) or
+/// total cost takes up. Children without any weight split the width evenly.
+pub fn flame_layout(root: &PlanNode, analyzed: bool, focus: usize) -> Vec<FlameBox> {
+    fn weight(node: &PlanNode, analyzed: bool) -> f64 {
+        let w = if analyzed {
+            node.total_time_ms().unwrap_or(0.0)
+        } else {
+            node.cost_total
+        };
+        w.max(0.0)
+    }
+
+    fn place(
+        node: &PlanNode,
+        id: usize,
+        depth: usize,
+        start: f64,
+        width: f64,
+        analyzed: bool,
+        out: &mut Vec<FlameBox>,
+    ) {
+        out.push(FlameBox {
+            id,
+            depth,
+            start,
+            width,
+        });
+        let weights: Vec<f64> = node.children.iter().map(|c| weight(c, analyzed)).collect();
+        let sum: f64 = weights.iter().sum();
+        let own = weight(node, analyzed);
+        // Children never take more than their parent's box
+        let scale = if sum <= 0.0 {
+            0.0
+        } else {
+            width / sum.max(own)
+        };
+        let even = width / node.children.len().max(1) as f64;
+        let mut offset = start;
+        let mut child_id = id + 1;
+        for (child, w) in node.children.iter().zip(weights) {
+            let child_width = if sum <= 0.0 { even } else { w * scale };
+            place(
+                child,
+                child_id,
+                depth + 1,
+                offset,
+                child_width,
+                analyzed,
+                out,
+            );
+            offset += child_width;
+            child_id += child.size();
+        }
+    }
+
+    let nodes = root.preorder();
+    let Some(node) = nodes.get(focus) else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    place(node, focus, 0, 0.0, 1.0, analyzed, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(operator: &str, cost: f64, children: Vec<PlanNode>) -> PlanNode {
+        PlanNode {
+            operator: operator.into(),
+            relation: None,
+            cost_startup: 0.0,
+            cost_total: cost,
+            estimated_rows: 10,
+            actual_rows: None,
+            actual_time_ms: None,
+            actual_loops: None,
+            width: 0,
+            children,
+            extra: Vec::new(),
+        }
+    }
+
+    fn plan() -> PlanNode {
+        // 0 Join(100) -> 1 Scan(60), 2 Hash(20) -> 3 Scan(20)
+        node(
+            "Join",
+            100.0,
+            vec![
+                node("Scan", 60.0, vec![]),
+                node("Hash", 20.0, vec![node("Scan", 20.0, vec![])]),
+            ],
+        )
+    }
+
+    #[test]
+    fn tree_layout_centers_parents_and_collapses() {
+        let placed = tree_layout(&plan(), &HashSet::new());
+        let xs: Vec<(usize, f64)> = placed.iter().map(|p| (p.id, p.x)).collect();
+        assert_eq!(xs, [(0, 0.5), (1, 0.0), (2, 1.0), (3, 1.0)]);
+        assert_eq!(placed[3].parent, Some(2));
+        assert_eq!(placed[3].depth, 2);
+
+        let placed = tree_layout(&plan(), &HashSet::from([2]));
+        assert_eq!(placed.len(), 3);
+        assert_eq!(placed[2].hidden, 1);
+    }
+
+    #[test]
+    fn flame_layout_sizes_by_cost_and_zooms() {
+        let boxes = flame_layout(&plan(), false, 0);
+        let spans: Vec<(usize, f64, f64)> =
+            boxes.iter().map(|b| (b.id, b.start, b.width)).collect();
+        assert_eq!(
+            spans,
+            [(0, 0.0, 1.0), (1, 0.0, 0.6), (2, 0.6, 0.2), (3, 0.6, 0.2)]
+        );
+
+        let zoomed = flame_layout(&plan(), false, 2);
+        assert_eq!(zoomed.len(), 2);
+        assert_eq!(
+            (zoomed[1].id, zoomed[1].depth, zoomed[1].width),
+            (3, 1, 1.0)
+        );
+        assert!(flame_layout(&plan(), false, 9).is_empty());
+    }
+}
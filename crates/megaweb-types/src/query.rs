@@ -1,4 +1,11 @@
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::chart::ResultChartSpec;
+use crate::result_view::{cell_number, cell_text};
 
 /// Request to execute a SQL query against MegaDB.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,16 +25,788 @@ pub struct QueryResult {
     pub execution_time_ms: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Execution statistics, when MegaDB reports them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<QueryStats>,
+}
+
+/// Server-side execution statistics of a query. Each figure is optional
+/// since not every engine reports all of them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryStats {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planning_time_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_time_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_scanned: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows_scanned: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitions_total: Option<u64>,
+    /// Partitions skipped without being read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitions_pruned: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_hits: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_misses: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_memory_bytes: Option<u64>,
+}
+
+impl QueryStats {
+    /// Whether no statistic was reported.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Partitions actually read, if both counts are known.
+    pub fn partitions_scanned(&self) -> Option<u64> {
+        Some(
+            self.partitions_total?
+                .saturating_sub(self.partitions_pruned?),
+        )
+    }
+
+    /// Whether partition pruning skipped anything. `None` when the counts
+    /// weren't reported or the table has no partitions.
+    pub fn pruning_applied(&self) -> Option<bool> {
+        self.partitions_total.filter(|&t| t > 0)?;
+        Some(self.partitions_pruned? > 0)
+    }
+
+    /// Share of cache lookups that hit, between 0 and 1.
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let hits = self.cache_hits?;
+        let lookups = hits + self.cache_misses.unwrap_or(0);
+        (lookups > 0).then(|| hits as f64 / lookups as f64)
+    }
 }
 
 /// Column metadata in a query result.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct QueryColumn {
     pub name: String,
     pub data_type: String,
     pub nullable: bool,
 }
 
+/// Broad category of a column's SQL type, used for sorting and filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Numeric,
+    Boolean,
+    /// Dates, times and timestamps; ISO text sorts chronologically.
+    Temporal,
+    Text,
+}
+
+impl ColumnKind {
+    /// Classify a SQL type name such as `BIGINT`, `DECIMAL(18,6)` or
+    /// `TIMESTAMP WITH TIME ZONE`.
+    pub fn from_data_type(data_type: &str) -> Self {
+        SqlType::parse(data_type).kind()
+    }
+}
+
+/// SQL type of a result column, parsed from MegaDB's type name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlType {
+    Boolean,
+    Integer {
+        bits: u8,
+        unsigned: bool,
+    },
+    Float {
+        bits: u8,
+    },
+    /// Both are `None` for a bare `DECIMAL`.
+    Decimal {
+        precision: Option<u8>,
+        scale: Option<u8>,
+    },
+    Date,
+    Time,
+    Timestamp {
+        tz: bool,
+    },
+    Interval,
+    Uuid,
+    Json,
+    Binary,
+    Text,
+    Array(Box<SqlType>),
+}
+
+impl SqlType {
+    /// Parse a type name such as `BIGINT`, `DECIMAL(18,6)`,
+    /// `TIMESTAMP WITH TIME ZONE`, `INTEGER[]` or `LIST<VARCHAR>`.
+    /// Unknown names are text.
+    pub fn parse(data_type: &str) -> Self {
+        let upper = data_type.trim().to_uppercase();
+        if let Some(inner) = upper.strip_suffix("[]") {
+            return SqlType::Array(Box::new(SqlType::parse(inner)));
+        }
+        for prefix in ["ARRAY<", "LIST<"] {
+            if let Some(inner) = upper.strip_prefix(prefix).and_then(|r| r.strip_suffix('>')) {
+                return SqlType::Array(Box::new(SqlType::parse(inner)));
+            }
+        }
+        let int = |bits, unsigned| SqlType::Integer { bits, unsigned };
+        let base = upper.split(['(', ' ']).next().unwrap_or_default();
+        match base {
+            "BOOLEAN" | "BOOL" => SqlType::Boolean,
+            "TINYINT" | "INT1" => int(8, false),
+            "SMALLINT" | "INT2" => int(16, false),
+            "INT" | "INTEGER" | "INT4" => int(32, false),
+            "BIGINT" | "INT8" => int(64, false),
+            "HUGEINT" | "INT128" => int(128, false),
+            "UTINYINT" => int(8, true),
+            "USMALLINT" => int(16, true),
+            "UINT" | "UINTEGER" => int(32, true),
+            "UBIGINT" => int(64, true),
+            "REAL" | "FLOAT4" => SqlType::Float { bits: 32 },
+            "FLOAT" | "FLOAT8" | "DOUBLE" => SqlType::Float { bits: 64 },
+            "DECIMAL" | "NUMERIC" => {
+                let (precision, scale) = type_args(&upper);
+                SqlType::Decimal {
+                    precision,
+                    scale: scale.or(precision.map(|_| 0)),
+                }
+            }
+            "DATE" => SqlType::Date,
+            "TIME" | "TIMETZ" => SqlType::Time,
+            "TIMESTAMPTZ" => SqlType::Timestamp { tz: true },
+            "TIMESTAMP" | "DATETIME" => SqlType::Timestamp {
+                tz: upper.contains("WITH TIME ZONE"),
+            },
+            "INTERVAL" => SqlType::Interval,
+            "UUID" => SqlType::Uuid,
+            "JSON" | "JSONB" => SqlType::Json,
+            "BYTEA" | "BLOB" | "BINARY" | "VARBINARY" => SqlType::Binary,
+            _ => SqlType::Text,
+        }
+    }
+
+    pub fn kind(&self) -> ColumnKind {
+        match self {
+            SqlType::Boolean => ColumnKind::Boolean,
+            SqlType::Integer { .. } | SqlType::Float { .. } | SqlType::Decimal { .. } => {
+                ColumnKind::Numeric
+            }
+            SqlType::Date | SqlType::Time | SqlType::Timestamp { .. } => ColumnKind::Temporal,
+            _ => ColumnKind::Text,
+        }
+    }
+
+    /// Numbers are right-aligned in the grid.
+    pub fn is_numeric(&self) -> bool {
+        self.kind() == ColumnKind::Numeric
+    }
+}
+
+/// The numeric arguments of `NAME(a, b)`.
+fn type_args(data_type: &str) -> (Option<u8>, Option<u8>) {
+    let Some((args, _)) = data_type
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+    else {
+        return (None, None);
+    };
+    let mut parts = args.split(',').map(|p| p.trim().parse::<u8>().ok());
+    (parts.next().flatten(), parts.next().flatten())
+}
+
+/// A cell decoded according to its column's [`SqlType`]. Values that don't
+/// parse as their column's type are kept as [`TypedValue::Text`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedValue {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    /// Exact decimal `unscaled * 10^-scale`.
+    Decimal {
+        unscaled: i128,
+        scale: u8,
+    },
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<FixedOffset>),
+    Interval(Interval),
+    Uuid(uuid::Uuid),
+    Json(Value),
+    Array(Vec<TypedValue>),
+    Binary(Vec<u8>),
+    Text(String),
+}
+
+impl TypedValue {
+    /// Decode a JSON cell as `ty`. Decimals are rescaled to the column's
+    /// scale; timestamps without an offset in a `TIMESTAMPTZ` column are
+    /// taken as UTC.
+    pub fn decode(value: &Value, ty: &SqlType) -> Self {
+        if value.is_null() {
+            return TypedValue::Null;
+        }
+        Self::try_decode(value, ty).unwrap_or_else(|| TypedValue::Text(cell_text(value)))
+    }
+
+    /// Decode a non-null JSON cell as `ty`, or `None` if it doesn't fit.
+    pub fn try_decode(value: &Value, ty: &SqlType) -> Option<Self> {
+        let text = || value.as_str().map(str::trim);
+        match ty {
+            SqlType::Boolean => match value {
+                Value::Bool(b) => Some(TypedValue::Bool(*b)),
+                Value::Number(n) => n.as_i64().map(|n| TypedValue::Bool(n != 0)),
+                Value::String(s) => match s.trim().to_lowercase().as_str() {
+                    "t" | "true" | "1" => Some(TypedValue::Bool(true)),
+                    "f" | "false" | "0" => Some(TypedValue::Bool(false)),
+                    _ => None,
+                },
+                _ => None,
+            },
+            SqlType::Integer { .. } => match value {
+                Value::Number(n) => n
+                    .as_i64()
+                    .map(i128::from)
+                    .or_else(|| n.as_u64().map(i128::from)),
+                Value::String(s) => s.trim().parse().ok(),
+                _ => None,
+            }
+            .map(TypedValue::Int),
+            SqlType::Float { .. } => cell_number(value).map(TypedValue::Float),
+            SqlType::Decimal { scale, .. } => {
+                let number = match value {
+                    Value::Number(n) => n.to_string(),
+                    Value::String(s) => s.trim().to_string(),
+                    _ => return None,
+                };
+                match parse_decimal(&number) {
+                    Some((unscaled, from)) => {
+                        let scale = scale.unwrap_or(from);
+                        let unscaled = rescale(unscaled, from, scale)?;
+                        Some(TypedValue::Decimal { unscaled, scale })
+                    }
+                    // Exponent notation
+                    None => cell_number(value).map(TypedValue::Float),
+                }
+            }
+            SqlType::Date => NaiveDate::parse_from_str(text()?, "%Y-%m-%d")
+                .ok()
+                .map(TypedValue::Date),
+            SqlType::Time => {
+                let text = text()?;
+                ["%H:%M:%S%.f", "%H:%M"]
+                    .iter()
+                    .find_map(|f| NaiveTime::parse_from_str(text, f).ok())
+                    .map(TypedValue::Time)
+            }
+            SqlType::Timestamp { tz: false } => parse_timestamp(text()?).map(TypedValue::Timestamp),
+            SqlType::Timestamp { tz: true } => {
+                let text = text()?;
+                parse_timestamp_tz(text)
+                    .or_else(|| parse_timestamp(text).map(|ts| ts.and_utc().fixed_offset()))
+                    .map(TypedValue::TimestampTz)
+            }
+            SqlType::Interval => Interval::parse(text()?).map(TypedValue::Interval),
+            SqlType::Uuid => uuid::Uuid::parse_str(text()?).ok().map(TypedValue::Uuid),
+            SqlType::Json => match value {
+                Value::String(s) => serde_json::from_str(s).ok().map(TypedValue::Json),
+                v => Some(TypedValue::Json(v.clone())),
+            },
+            SqlType::Binary => decode_hex(text()?.strip_prefix("\\x")?).map(TypedValue::Binary),
+            SqlType::Array(inner) => {
+                let items = match value {
+                    Value::Array(items) => items.clone(),
+                    Value::String(s) => parse_array_literal(s)?,
+                    _ => return None,
+                };
+                Some(TypedValue::Array(
+                    items.iter().map(|v| Self::decode(v, inner)).collect(),
+                ))
+            }
+            SqlType::Text => None,
+        }
+    }
+
+    /// Display text for the grid.
+    pub fn render(&self, format: &ValueFormat) -> String {
+        match self {
+            TypedValue::Null => "NULL".to_string(),
+            TypedValue::Bool(b) => b.to_string(),
+            TypedValue::Int(n) => format.locale.format(&n.to_string()),
+            TypedValue::Float(n) => {
+                let abs = n.abs();
+                if !n.is_finite() || abs >= 1e15 || (abs < 1e-4 && abs > 0.0) {
+                    format!("{n:e}")
+                } else {
+                    format.locale.format(&n.to_string())
+                }
+            }
+            TypedValue::Decimal { unscaled, scale } => {
+                format.locale.format(&decimal_text(*unscaled, *scale))
+            }
+            TypedValue::Date(d) => d.format("%Y-%m-%d").to_string(),
+            TypedValue::Time(t) => t.format("%H:%M:%S%.f").to_string(),
+            TypedValue::Timestamp(ts) => ts.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+            TypedValue::TimestampTz(ts) => {
+                let ts = format.time_zone.map_or(*ts, |tz| ts.with_timezone(&tz));
+                ts.format("%Y-%m-%d %H:%M:%S%.f%:z").to_string()
+            }
+            TypedValue::Interval(i) => i.to_string(),
+            TypedValue::Uuid(u) => u.to_string(),
+            TypedValue::Json(v) => v.to_string(),
+            TypedValue::Array(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.render(format)).collect();
+                format!("[{}]", items.join(", "))
+            }
+            TypedValue::Binary(bytes) => {
+                const PREVIEW: usize = 32;
+                let hex: String = bytes
+                    .iter()
+                    .take(PREVIEW)
+                    .map(|b| format!("{b:02x}"))
+                    .collect();
+                if bytes.len() > PREVIEW {
+                    format!("\\x{hex}\u{2026} ({} bytes)", bytes.len())
+                } else {
+                    format!("\\x{hex}")
+                }
+            }
+            TypedValue::Text(s) => s.clone(),
+        }
+    }
+
+    /// JSON form for the nested-value explorer: JSON stays as is, arrays
+    /// become JSON arrays and other values their plain text.
+    pub fn to_json(&self) -> Value {
+        match self {
+            TypedValue::Null => Value::Null,
+            TypedValue::Bool(b) => Value::Bool(*b),
+            TypedValue::Int(n) => {
+                i64::try_from(*n).map_or_else(|_| n.to_string().into(), Value::from)
+            }
+            TypedValue::Float(n) => Value::from(*n),
+            TypedValue::Json(v) => v.clone(),
+            TypedValue::Array(items) => Value::Array(items.iter().map(Self::to_json).collect()),
+            TypedValue::Text(s) => Value::String(s.clone()),
+            other => Value::String(other.render(&ValueFormat::default())),
+        }
+    }
+
+    /// The value scaled by `10^scale`, for exact decimal storage.
+    pub fn to_unscaled(&self, scale: u8) -> Option<i128> {
+        match self {
+            TypedValue::Int(n) => rescale(*n, 0, scale),
+            TypedValue::Decimal {
+                unscaled,
+                scale: from,
+            } => rescale(*unscaled, *from, scale),
+            TypedValue::Float(n) => parse_decimal(&n.to_string())
+                .and_then(|(unscaled, from)| rescale(unscaled, from, scale)),
+            _ => None,
+        }
+    }
+}
+
+/// `-123.45` as `(-12345, 2)`. Exponent notation is not accepted.
+fn parse_decimal(text: &str) -> Option<(i128, u8)> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    if int.is_empty() && frac.is_empty()
+        || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let scale = u8::try_from(frac.len()).ok()?;
+    let value: i128 = format!("0{int}{frac}").parse().ok()?;
+    Some((if negative { -value } else { value }, scale))
+}
+
+/// Change the scale of an unscaled decimal, rounding half away from zero.
+fn rescale(unscaled: i128, from: u8, to: u8) -> Option<i128> {
+    if to >= from {
+        unscaled.checked_mul(10i128.checked_pow(u32::from(to - from))?)
+    } else {
+        let div = 10i128.checked_pow(u32::from(from - to))?;
+        let (q, r) = (unscaled / div, unscaled % div);
+        Some(if r.abs() * 2 >= div {
+            q + unscaled.signum()
+        } else {
+            q
+        })
+    }
+}
+
+fn decimal_text(unscaled: i128, scale: u8) -> String {
+    let sign = if unscaled < 0 { "-" } else { "" };
+    let digits = unscaled.unsigned_abs().to_string();
+    let scale = usize::from(scale);
+    if scale == 0 {
+        return format!("{sign}{digits}");
+    }
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    format!("{sign}{int}.{frac}")
+}
+
+fn parse_timestamp(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(&text.replacen(' ', "T", 1), "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+fn parse_timestamp_tz(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.replacen(' ', "T", 1);
+    DateTime::parse_from_rfc3339(&text)
+        .or_else(|_| DateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S%.f%#z"))
+        .ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Elements of a JSON array (`[1,2]`) or PostgreSQL array literal
+/// (`{a,"b c",NULL}`). Nested arrays are kept as their literal text.
+fn parse_array_literal(text: &str) -> Option<Vec<Value>> {
+    let text = text.trim();
+    if text.starts_with('[') {
+        return serde_json::from_str(text).ok();
+    }
+    let inner = text.strip_prefix('{')?.strip_suffix('}')?;
+    let mut items = Vec::new();
+    if inner.trim().is_empty() {
+        return Some(items);
+    }
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut item = String::new();
+        let mut quoted = false;
+        if chars.next_if_eq(&'"').is_some() {
+            quoted = true;
+            loop {
+                match chars.next()? {
+                    '\\' => item.push(chars.next()?),
+                    '"' => break,
+                    c => item.push(c),
+                }
+            }
+            while chars.next_if(|&c| c != ',').is_some() {}
+        } else {
+            let mut depth = 0;
+            while let Some(&c) = chars.peek() {
+                match c {
+                    ',' if depth == 0 => break,
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                item.push(c);
+                chars.next();
+            }
+        }
+        let item_text = item.trim();
+        items.push(if !quoted && item_text.eq_ignore_ascii_case("NULL") {
+            Value::Null
+        } else if quoted {
+            Value::String(item)
+        } else {
+            Value::String(item_text.to_string())
+        });
+        if chars.next().is_none() {
+            break;
+        }
+    }
+    Some(items)
+}
+
+/// A SQL interval: months and days are kept apart from the clock time
+/// because their length varies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub micros: i64,
+}
+
+impl Interval {
+    /// Parse PostgreSQL output (`1 year 2 mons 3 days 04:05:06`) or ISO 8601
+    /// (`P1Y2M3DT4H5M6S`).
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(iso) = text.strip_prefix('P') {
+            return Self::parse_iso(iso);
+        }
+        let (mut months, mut days, mut micros) = (0i64, 0i64, 0i64);
+        let mut tokens = text.split_whitespace();
+        let mut any = false;
+        while let Some(token) = tokens.next() {
+            any = true;
+            if token.contains(':') {
+                micros += parse_clock(token)?;
+                continue;
+            }
+            let n: i64 = token.parse().ok()?;
+            let unit = tokens.next()?.to_lowercase();
+            match unit.trim_end_matches('s') {
+                "year" => months += n * 12,
+                "mon" | "month" => months += n,
+                "week" => days += n * 7,
+                "day" => days += n,
+                "hour" => micros += n * 3_600_000_000,
+                "min" | "minute" => micros += n * 60_000_000,
+                "sec" | "second" => micros += n * 1_000_000,
+                _ => return None,
+            }
+        }
+        any.then_some(())?;
+        Some(Self {
+            months: i32::try_from(months).ok()?,
+            days: i32::try_from(days).ok()?,
+            micros,
+        })
+    }
+
+    fn parse_iso(text: &str) -> Option<Self> {
+        let (date, time) = text.split_once('T').unwrap_or((text, ""));
+        let mut out = Self::default();
+        let mut number = String::new();
+        for c in date.chars() {
+            if c.is_ascii_digit() || c == '-' {
+                number.push(c);
+                continue;
+            }
+            let n: i32 = std::mem::take(&mut number).parse().ok()?;
+            match c {
+                'Y' => out.months += n * 12,
+                'M' => out.months += n,
+                'W' => out.days += n * 7,
+                'D' => out.days += n,
+                _ => return None,
+            }
+        }
+        for c in time.chars() {
+            if c.is_ascii_digit() || c == '-' || c == '.' {
+                number.push(c);
+                continue;
+            }
+            let n: f64 = std::mem::take(&mut number).parse().ok()?;
+            let unit = match c {
+                'H' => 3_600_000_000.0,
+                'M' => 60_000_000.0,
+                'S' => 1_000_000.0,
+                _ => return None,
+            };
+            out.micros += (n * unit).round() as i64;
+        }
+        number.is_empty().then_some(out)
+    }
+}
+
+/// `[-]HH:MM:SS[.ffffff]` as microseconds.
+fn parse_clock(text: &str) -> Option<i64> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let mut parts = text.split(':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next().unwrap_or("0").parse().ok()?;
+    let micros = (hours * 3600 + minutes * 60) * 1_000_000 + (seconds * 1e6).round() as i64;
+    Some(if negative { -micros } else { micros })
+}
+
+impl fmt::Display for Interval {
+    /// PostgreSQL style: `1 year 2 mons 3 days 04:05:06`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = |n: i32, one: &str, many: &str| {
+            format!("{n} {}", if n.abs() == 1 { one } else { many })
+        };
+        let mut parts = Vec::new();
+        let (years, months) = (self.months / 12, self.months % 12);
+        if years != 0 {
+            parts.push(unit(years, "year", "years"));
+        }
+        if months != 0 {
+            parts.push(unit(months, "mon", "mons"));
+        }
+        if self.days != 0 {
+            parts.push(unit(self.days, "day", "days"));
+        }
+        if self.micros != 0 || parts.is_empty() {
+            let sign = if self.micros < 0 { "-" } else { "" };
+            let abs = self.micros.unsigned_abs();
+            let (secs, frac) = (abs / 1_000_000, abs % 1_000_000);
+            let mut clock = format!(
+                "{sign}{:02}:{:02}:{:02}",
+                secs / 3600,
+                secs / 60 % 60,
+                secs % 60
+            );
+            if frac != 0 {
+                clock.push_str(format!(".{frac:06}").trim_end_matches('0'));
+            }
+            parts.push(clock);
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Digit grouping and decimal separator used to display numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberLocale {
+    /// As SQL writes it, without grouping.
+    #[default]
+    Plain,
+    English,
+    European,
+    French,
+    Swiss,
+}
+
+impl NumberLocale {
+    pub const ALL: [NumberLocale; 5] = [
+        NumberLocale::Plain,
+        NumberLocale::English,
+        NumberLocale::European,
+        NumberLocale::French,
+        NumberLocale::Swiss,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NumberLocale::Plain => "1234567.89",
+            NumberLocale::English => "1,234,567.89",
+            NumberLocale::European => "1.234.567,89",
+            NumberLocale::French => "1 234 567,89",
+            NumberLocale::Swiss => "1'234'567.89",
+        }
+    }
+
+    /// (group separator, decimal separator)
+    fn separators(self) -> (Option<char>, char) {
+        match self {
+            NumberLocale::Plain => (None, '.'),
+            NumberLocale::English => (Some(','), '.'),
+            NumberLocale::European => (Some('.'), ','),
+            NumberLocale::French => (Some('\u{202F}'), ','),
+            NumberLocale::Swiss => (Some('\''), '.'),
+        }
+    }
+
+    /// Closest number style for a BCP 47 language tag such as `de-DE`.
+    pub fn from_language_tag(tag: &str) -> Self {
+        let tag = tag.to_lowercase();
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        if tag.ends_with("-ch") && language != "fr" {
+            return NumberLocale::Swiss;
+        }
+        match language {
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" => {
+                NumberLocale::European
+            }
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" => {
+                NumberLocale::French
+            }
+            _ => NumberLocale::English,
+        }
+    }
+
+    /// Localize a plain number such as `-1234567.5`.
+    pub fn format(self, number: &str) -> String {
+        let (group, decimal) = self.separators();
+        let (sign, digits) = match number.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", number),
+        };
+        let (int, frac) = match digits.split_once('.') {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (digits, None),
+        };
+        let mut out = String::from(sign);
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                if let Some(g) = group {
+                    out.push(g);
+                }
+            }
+            out.push(c);
+        }
+        if let Some(frac) = frac {
+            out.push(decimal);
+            out.push_str(frac);
+        }
+        out
+    }
+}
+
+/// Which offset `TIMESTAMPTZ` values are displayed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeZoneDisplay {
+    /// The browser's time zone.
+    #[default]
+    Local,
+    Utc,
+    /// The offset the server sent.
+    Original,
+}
+
+impl TimeZoneDisplay {
+    pub const ALL: [TimeZoneDisplay; 3] = [
+        TimeZoneDisplay::Local,
+        TimeZoneDisplay::Utc,
+        TimeZoneDisplay::Original,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeZoneDisplay::Local => "Local time",
+            TimeZoneDisplay::Utc => "UTC",
+            TimeZoneDisplay::Original => "As returned",
+        }
+    }
+}
+
+/// Display preferences for [`TypedValue::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ValueFormat {
+    pub locale: NumberLocale,
+    /// Offset `TIMESTAMPTZ` values are shown in; `None` keeps the offset
+    /// they arrived with.
+    pub time_zone: Option<FixedOffset>,
+}
+
+/// Compact summary of a query result, kept where the full rows are not.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResultSummary {
+    pub row_count: u64,
+    pub column_count: usize,
+    pub execution_time_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl QueryColumn {
+    pub fn kind(&self) -> ColumnKind {
+        ColumnKind::from_data_type(&self.data_type)
+    }
+}
+
 /// A saved query with name and metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SavedQuery {
@@ -37,6 +816,9 @@ pub struct SavedQuery {
     pub database: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Chart configuration saved alongside the query, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chart: Option<ResultChartSpec>,
 }
 
 /// Entry in the query history.
@@ -49,6 +831,134 @@ pub struct QueryHistoryEntry {
     pub row_count: u64,
     pub executed_at: chrono::DateTime<chrono::Utc>,
     pub success: bool,
+    /// Name of the connection the query ran on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Pinned entries are kept at the top of the history list.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Whether a snapshot of the result was stored alongside this entry.
+    #[serde(default)]
+    pub has_snapshot: bool,
+}
+
+/// Results with more rows than this aren't kept as history snapshots.
+pub const MAX_SNAPSHOT_ROWS: usize = 5_000;
+
+/// Nor are results whose JSON is larger than this.
+pub const MAX_SNAPSHOT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Result snapshots kept; older ones are evicted, pinned entries' last.
+pub const MAX_HISTORY_SNAPSHOTS: usize = 100;
+
+/// History entries loaded at a time. Every entry stays in storage; older
+/// pages are loaded on demand.
+pub const HISTORY_PAGE: usize = 200;
+
+/// What to drop so that the loaded history stays within a number of
+/// entries and [`MAX_HISTORY_SNAPSHOTS`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryOverflow {
+    /// Entries to unload; they stay in storage.
+    pub unloaded: Vec<uuid::Uuid>,
+    /// Entries to keep without their snapshot.
+    pub evicted: Vec<uuid::Uuid>,
+}
+
+impl HistoryOverflow {
+    /// The overflow of loaded `history`, oldest first, beyond `loaded`
+    /// entries. Unpinned entries lose their snapshot when unloaded, so
+    /// only loaded and pinned entries have one.
+    pub fn of(history: &[QueryHistoryEntry], loaded: usize) -> Self {
+        let excess = history.len().saturating_sub(loaded);
+        let (unloaded, kept) = history.split_at(excess);
+
+        let mut evicted: Vec<uuid::Uuid> = unloaded
+            .iter()
+            .filter(|e| e.has_snapshot && !e.pinned)
+            .map(|e| e.id)
+            .collect();
+        let mut snapshots: Vec<&QueryHistoryEntry> =
+            kept.iter().filter(|e| e.has_snapshot).collect();
+        // Stable, so each group stays oldest first
+        snapshots.sort_by_key(|e| e.pinned);
+        let excess = snapshots.len().saturating_sub(MAX_HISTORY_SNAPSHOTS);
+        evicted.extend(snapshots.iter().take(excess).map(|e| e.id));
+        Self {
+            unloaded: unloaded.iter().map(|e| e.id).collect(),
+            evicted,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unloaded.is_empty() && self.evicted.is_empty()
+    }
+}
+
+impl QueryHistoryEntry {
+    /// Key in the history store: the execution time in milliseconds,
+    /// zero-padded so keys sort by time, then the id.
+    pub fn storage_key(&self) -> String {
+        format!(
+            "{:013}-{}",
+            self.executed_at.timestamp_millis().max(0),
+            self.id
+        )
+    }
+}
+
+/// Criteria for searching query history. Empty fields match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryFilter {
+    /// Whitespace-separated terms that must all appear in the SQL (case-insensitive).
+    pub text: String,
+    pub database: Option<String>,
+    pub connection: Option<String>,
+    /// `Some(true)` for successful queries only, `Some(false)` for failures only.
+    pub success: Option<bool>,
+    pub min_duration_ms: Option<u64>,
+    pub max_duration_ms: Option<u64>,
+    /// Inclusive date range on `executed_at` (UTC).
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub pinned_only: bool,
+}
+
+impl HistoryFilter {
+    /// Check whether a history entry satisfies every criterion.
+    pub fn matches(&self, entry: &QueryHistoryEntry) -> bool {
+        if self.pinned_only && !entry.pinned {
+            return false;
+        }
+        if self.success.is_some_and(|s| s != entry.success) {
+            return false;
+        }
+        if self.database.as_ref().is_some_and(|d| *d != entry.database) {
+            return false;
+        }
+        if self.connection.is_some() && self.connection != entry.connection {
+            return false;
+        }
+        if self
+            .min_duration_ms
+            .is_some_and(|min| entry.execution_time_ms < min)
+            || self
+                .max_duration_ms
+                .is_some_and(|max| entry.execution_time_ms > max)
+        {
+            return false;
+        }
+        let date = entry.executed_at.date_naive();
+        if self.from.is_some_and(|from| date < from) || self.to.is_some_and(|to| date > to) {
+            return false;
+        }
+        let sql = entry.sql.to_lowercase();
+        self.text
+            .split_whitespace()
+            .all(|term| sql.contains(&term.to_lowercase()))
+    }
 }
 
 impl QueryResult {
@@ -60,6 +970,7 @@ impl QueryResult {
             row_count: 0,
             execution_time_ms: 0,
             error: None,
+            stats: None,
         }
     }
 
@@ -67,4 +978,283 @@ impl QueryResult {
     pub fn is_ok(&self) -> bool {
         self.error.is_none()
     }
+
+    /// Check if fewer rows were returned than the query produced,
+    /// e.g. because of the row limit.
+    pub fn is_truncated(&self) -> bool {
+        (self.rows.len() as u64) < self.row_count
+    }
+
+    /// Whether this result is small enough to keep as a history snapshot;
+    /// see [`MAX_SNAPSHOT_ROWS`] and [`MAX_SNAPSHOT_BYTES`].
+    pub fn fits_snapshot(&self) -> bool {
+        self.is_ok()
+            && self.rows.len() <= MAX_SNAPSHOT_ROWS
+            && serde_json::to_vec(self).is_ok_and(|json| json.len() <= MAX_SNAPSHOT_BYTES)
+    }
+
+    /// Summarize this result without its rows.
+    pub fn summary(&self) -> QueryResultSummary {
+        QueryResultSummary {
+            row_count: self.row_count,
+            column_count: self.columns.len(),
+            execution_time_ms: self.execution_time_ms,
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// A result with the given `(name, type)` columns and rows, for tests.
+#[cfg(test)]
+pub(crate) fn result(columns: &[(&str, &str)], rows: Vec<Vec<Value>>) -> QueryResult {
+    QueryResult {
+        columns: columns
+            .iter()
+            .map(|(name, data_type)| QueryColumn {
+                name: (*name).into(),
+                data_type: (*data_type).into(),
+                nullable: true,
+            })
+            .collect(),
+        row_count: rows.len() as u64,
+        rows,
+        execution_time_ms: 1,
+        error: None,
+        stats: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(sql: &str, success: bool, ms: u64) -> QueryHistoryEntry {
+        QueryHistoryEntry {
+            id: uuid::Uuid::new_v4(),
+            sql: sql.to_string(),
+            database: "megadb".to_string(),
+            execution_time_ms: ms,
+            row_count: 0,
+            executed_at: "2026-02-10T12:00:00Z".parse().unwrap(),
+            success,
+            connection: Some("Local MegaDB".to_string()),
+            error: None,
+            pinned: false,
+            has_snapshot: false,
+        }
+    }
+
+    fn decimal(scale: u8) -> SqlType {
+        SqlType::Decimal {
+            precision: Some(18),
+            scale: Some(scale),
+        }
+    }
+
+    #[test]
+    fn parses_type_names() {
+        assert_eq!(SqlType::parse("DECIMAL(18, 2)"), decimal(2));
+        assert_eq!(
+            SqlType::parse("timestamp with time zone"),
+            SqlType::Timestamp { tz: true }
+        );
+        assert_eq!(
+            SqlType::parse("TIMESTAMP WITHOUT TIME ZONE"),
+            SqlType::Timestamp { tz: false }
+        );
+        assert_eq!(
+            SqlType::parse("BIGINT[]"),
+            SqlType::Array(Box::new(SqlType::Integer {
+                bits: 64,
+                unsigned: false
+            }))
+        );
+        assert_eq!(
+            SqlType::parse("DOUBLE PRECISION").kind(),
+            ColumnKind::Numeric
+        );
+        assert_eq!(SqlType::parse("INTERVAL").kind(), ColumnKind::Text);
+    }
+
+    #[test]
+    fn renders_numbers_with_scale_and_locale() {
+        let plain = ValueFormat::default();
+        let english = ValueFormat {
+            locale: NumberLocale::English,
+            ..plain
+        };
+        let value = TypedValue::decode(&Value::from("1234.5"), &decimal(2));
+        assert_eq!(value.render(&plain), "1234.50");
+        assert_eq!(value.render(&english), "1,234.50");
+        let rounded = TypedValue::decode(&Value::from(-0.005), &decimal(2));
+        assert_eq!(rounded.render(&plain), "-0.01");
+        let int = SqlType::parse("BIGINT");
+        let european = ValueFormat {
+            locale: NumberLocale::European,
+            ..plain
+        };
+        assert_eq!(
+            TypedValue::decode(&Value::from(-1234567), &int).render(&european),
+            "-1.234.567"
+        );
+        assert_eq!(
+            NumberLocale::from_language_tag("de-CH"),
+            NumberLocale::Swiss
+        );
+        assert_eq!(
+            NumberLocale::from_language_tag("fr-CH"),
+            NumberLocale::French
+        );
+    }
+
+    #[test]
+    fn converts_timestamps_to_display_zone() {
+        let ty = SqlType::Timestamp { tz: true };
+        let value = TypedValue::decode(&Value::from("2026-02-10 12:30:00+00"), &ty);
+        let seoul = ValueFormat {
+            time_zone: FixedOffset::east_opt(9 * 3600),
+            ..Default::default()
+        };
+        assert_eq!(value.render(&seoul), "2026-02-10 21:30:00+09:00");
+        let naive = TypedValue::decode(&Value::from("2026-02-10T12:30:00.25"), &ty);
+        assert_eq!(
+            naive.render(&ValueFormat::default()),
+            "2026-02-10 12:30:00.250+00:00"
+        );
+    }
+
+    #[test]
+    fn decodes_intervals_arrays_and_binary() {
+        let interval = Interval::parse("1 year 2 mons -3 days 04:05:06.5").unwrap();
+        assert_eq!(interval.to_string(), "1 year 2 mons -3 days 04:05:06.5");
+        assert_eq!(
+            Interval::parse("P1DT2H").unwrap().to_string(),
+            "1 day 02:00:00"
+        );
+
+        let ty = SqlType::parse("TEXT[]");
+        let value = TypedValue::decode(&Value::from(r#"{a,"b, c",NULL}"#), &ty);
+        assert_eq!(value.render(&ValueFormat::default()), "[a, b, c, NULL]");
+
+        let bytes = TypedValue::decode(&Value::from("\\x0aff"), &SqlType::Binary);
+        assert_eq!(bytes, TypedValue::Binary(vec![0x0a, 0xff]));
+        let bad = TypedValue::decode(&Value::from("infinity"), &SqlType::Date);
+        assert_eq!(bad, TypedValue::Text("infinity".into()));
+    }
+
+    #[test]
+    fn history_filter_combines_criteria() {
+        let e = entry("SELECT service_name, SUM(cost) FROM cur_data", true, 250);
+
+        assert!(HistoryFilter::default().matches(&e));
+        assert!(HistoryFilter {
+            text: "sum CUR_DATA".into(),
+            success: Some(true),
+            min_duration_ms: Some(100),
+            from: "2026-02-10".parse().ok(),
+            to: "2026-02-10".parse().ok(),
+            ..Default::default()
+        }
+        .matches(&e));
+
+        assert!(!HistoryFilter {
+            text: "cmdb".into(),
+            ..Default::default()
+        }
+        .matches(&e));
+        assert!(!HistoryFilter {
+            success: Some(false),
+            ..Default::default()
+        }
+        .matches(&e));
+        assert!(!HistoryFilter {
+            max_duration_ms: Some(100),
+            ..Default::default()
+        }
+        .matches(&e));
+        assert!(!HistoryFilter {
+            from: "2026-02-11".parse().ok(),
+            ..Default::default()
+        }
+        .matches(&e));
+        assert!(!HistoryFilter {
+            pinned_only: true,
+            ..Default::default()
+        }
+        .matches(&e));
+    }
+
+    #[test]
+    fn history_overflow_unloads_old_entries_and_evicts_old_snapshots() {
+        let mut history: Vec<QueryHistoryEntry> = (0..HISTORY_PAGE + 3)
+            .map(|i| {
+                let mut e = entry("SELECT 1", true, 1);
+                e.pinned = i == 0;
+                e.has_snapshot = i < MAX_HISTORY_SNAPSHOTS + 6;
+                e
+            })
+            .collect();
+        let overflow = HistoryOverflow::of(&history, HISTORY_PAGE);
+        assert_eq!(
+            overflow.unloaded,
+            [history[0].id, history[1].id, history[2].id]
+        );
+        // Unloaded entries lose their snapshot unless pinned, then the
+        // oldest loaded snapshots go
+        assert_eq!(
+            overflow.evicted,
+            [
+                history[1].id,
+                history[2].id,
+                history[3].id,
+                history[4].id,
+                history[5].id
+            ]
+        );
+
+        history.truncate(10);
+        assert!(HistoryOverflow::of(&history, HISTORY_PAGE).is_empty());
+    }
+
+    #[test]
+    fn history_keys_sort_by_time() {
+        let mut older = entry("SELECT 1", true, 1);
+        let mut newer = entry("SELECT 2", true, 1);
+        older.executed_at = "2026-01-01T00:00:00Z".parse().unwrap();
+        newer.executed_at = "2026-01-01T00:00:00.010Z".parse().unwrap();
+        assert!(older.storage_key() < newer.storage_key());
+        assert!(newer.storage_key().ends_with(&newer.id.to_string()));
+        assert_eq!(older.storage_key().find('-'), Some(13));
+    }
+
+    #[test]
+    fn only_small_successful_results_fit_a_snapshot() {
+        let mut result = QueryResult::empty();
+        assert!(result.fits_snapshot());
+        result.rows = vec![vec![Value::from(1)]; MAX_SNAPSHOT_ROWS + 1];
+        assert!(!result.fits_snapshot());
+        result.rows = vec![vec![Value::from("x".repeat(MAX_SNAPSHOT_BYTES))]];
+        assert!(!result.fits_snapshot());
+        result.rows.clear();
+        result.error = Some("boom".into());
+        assert!(!result.fits_snapshot());
+    }
+
+    #[test]
+    fn stats_are_optional_and_derive_ratios() {
+        let result: QueryResult =
+            serde_json::from_str(r#"{"columns":[],"rows":[],"row_count":0,"execution_time_ms":3}"#)
+                .unwrap();
+        assert!(result.stats.is_none());
+
+        let stats: QueryStats = serde_json::from_str(
+            r#"{"partitions_total":24,"partitions_pruned":23,"cache_hits":3,"cache_misses":1}"#,
+        )
+        .unwrap();
+        assert_eq!(stats.partitions_scanned(), Some(1));
+        assert_eq!(stats.pruning_applied(), Some(true));
+        assert_eq!(stats.cache_hit_ratio(), Some(0.75));
+        assert!(!stats.is_empty());
+        assert_eq!(QueryStats::default().pruning_applied(), None);
+    }
 }
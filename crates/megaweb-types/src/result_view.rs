@@ -0,0 +1,456 @@
+//! Client-side views over a loaded [`QueryResult`]: type-aware sorting,
+//! per-column filters, column profiles and grid selection. All of it works
+//! on row indices so the underlying result is never copied.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use serde_json::Value;
+
+use crate::query::{ColumnKind, QueryResult};
+
+/// Number of most frequent values kept in a [`ColumnProfile`].
+const TOP_VALUES: usize = 5;
+/// Number of equal-width bins in a numeric histogram.
+const HISTOGRAM_BINS: usize = 10;
+
+/// Render a cell value as display text. `NULL` for nulls, strings unquoted.
+pub fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Numeric value of a cell. Decimals often arrive as strings, so those are
+/// parsed too.
+pub fn cell_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Compare two non-null cells of a column of the given kind. Values that
+/// don't fit the kind fall back to text comparison.
+pub fn compare_values(kind: ColumnKind, a: &Value, b: &Value) -> Ordering {
+    match kind {
+        ColumnKind::Numeric => match (cell_number(a), cell_number(b)) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            _ => cell_text(a).cmp(&cell_text(b)),
+        },
+        ColumnKind::Boolean => match (a.as_bool(), b.as_bool()) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            _ => cell_text(a).cmp(&cell_text(b)),
+        },
+        ColumnKind::Temporal | ColumnKind::Text => cell_text(a).cmp(&cell_text(b)),
+    }
+}
+
+/// Sort row indices by one column. Nulls always sort last; the sort is
+/// stable so ties keep their current order.
+pub fn sort_rows(result: &QueryResult, rows: &mut [usize], col: usize, descending: bool) {
+    let kind = result
+        .columns
+        .get(col)
+        .map(|c| c.kind())
+        .unwrap_or(ColumnKind::Text);
+    let cell = |row: usize| result.rows[row].get(col).unwrap_or(&Value::Null);
+    rows.sort_by(|&a, &b| match (cell(a), cell(b)) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (x, y) => {
+            let ord = compare_values(kind, x, y);
+            if descending {
+                ord.reverse()
+            } else {
+                ord
+            }
+        }
+    });
+}
+
+/// A parsed per-column filter.
+///
+/// - `null` / `!null` match (non-)null cells
+/// - on numeric columns, `>10`, `>=10`, `<10`, `<=10`, `=10` and `!=10`
+///   compare numerically
+/// - anything else is a case-insensitive substring match on the display text
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellFilter {
+    IsNull,
+    NotNull,
+    Compare(Ordering, bool, f64),
+    Contains(String),
+}
+
+impl CellFilter {
+    /// Parse filter input. Returns `None` for blank input (no filter).
+    pub fn parse(input: &str, kind: ColumnKind) -> Option<Self> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        match input.to_lowercase().as_str() {
+            "null" => return Some(CellFilter::IsNull),
+            "!null" => return Some(CellFilter::NotNull),
+            _ => {}
+        }
+        if kind == ColumnKind::Numeric {
+            // (operator, target ordering, negate)
+            const OPS: [(&str, Ordering, bool); 6] = [
+                (">=", Ordering::Less, true),
+                ("<=", Ordering::Greater, true),
+                ("!=", Ordering::Equal, true),
+                (">", Ordering::Greater, false),
+                ("<", Ordering::Less, false),
+                ("=", Ordering::Equal, false),
+            ];
+            for (op, ord, negate) in OPS {
+                if let Some(n) = input
+                    .strip_prefix(op)
+                    .and_then(|rest| rest.trim().parse::<f64>().ok())
+                {
+                    return Some(CellFilter::Compare(ord, negate, n));
+                }
+            }
+        }
+        Some(CellFilter::Contains(input.to_lowercase()))
+    }
+
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            CellFilter::IsNull => value.is_null(),
+            CellFilter::NotNull => !value.is_null(),
+            CellFilter::Compare(ord, negate, n) => match cell_number(value) {
+                Some(v) => (v.total_cmp(n) == *ord) != *negate,
+                None => false,
+            },
+            CellFilter::Contains(needle) => {
+                !value.is_null() && cell_text(value).to_lowercase().contains(needle)
+            }
+        }
+    }
+}
+
+/// Indices of rows matching every `(column, filter)` pair, in row order.
+pub fn filter_rows(result: &QueryResult, filters: &[(usize, CellFilter)]) -> Vec<usize> {
+    result
+        .rows
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| {
+            filters
+                .iter()
+                .all(|(col, f)| f.matches(row.get(*col).unwrap_or(&Value::Null)))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Copy of `result` restricted to the given rows and columns, in that order.
+pub fn subset(result: &QueryResult, rows: &[usize], cols: &[usize]) -> QueryResult {
+    QueryResult {
+        columns: cols.iter().map(|&c| result.columns[c].clone()).collect(),
+        rows: rows
+            .iter()
+            .map(|&r| {
+                cols.iter()
+                    .map(|&c| result.rows[r].get(c).cloned().unwrap_or(Value::Null))
+                    .collect()
+            })
+            .collect(),
+        row_count: rows.len() as u64,
+        execution_time_ms: result.execution_time_ms,
+        error: None,
+        stats: None,
+    }
+}
+
+/// What a [`GridSelection`] spans besides its anchor-to-focus rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionMode {
+    Cells,
+    /// Whole rows: every column of the selected rows.
+    Rows,
+    /// Whole columns: every row of the selected columns.
+    Columns,
+}
+
+/// Rectangular grid selection in view coordinates: row positions in the
+/// filtered, sorted row list and column positions in display order. The
+/// anchor stays put while the focus moves with the keyboard or mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridSelection {
+    pub mode: SelectionMode,
+    /// (row, column)
+    pub anchor: (usize, usize),
+    /// (row, column)
+    pub focus: (usize, usize),
+}
+
+impl GridSelection {
+    pub fn cell(row: usize, col: usize) -> Self {
+        Self {
+            mode: SelectionMode::Cells,
+            anchor: (row, col),
+            focus: (row, col),
+        }
+    }
+
+    pub fn all(rows: usize, cols: usize) -> Self {
+        Self {
+            mode: SelectionMode::Cells,
+            anchor: (0, 0),
+            focus: (rows.saturating_sub(1), cols.saturating_sub(1)),
+        }
+    }
+
+    /// Selected row positions, given the number of rows in view.
+    pub fn rows(&self, total: usize) -> RangeInclusive<usize> {
+        match self.mode {
+            SelectionMode::Columns => 0..=total.saturating_sub(1),
+            _ => span(self.anchor.0, self.focus.0),
+        }
+    }
+
+    /// Selected column positions, given the number of columns.
+    pub fn cols(&self, total: usize) -> RangeInclusive<usize> {
+        match self.mode {
+            SelectionMode::Rows => 0..=total.saturating_sub(1),
+            _ => span(self.anchor.1, self.focus.1),
+        }
+    }
+
+    pub fn contains(&self, row: usize, col: usize, rows: usize, cols: usize) -> bool {
+        self.rows(rows).contains(&row) && self.cols(cols).contains(&col)
+    }
+
+    /// Move the focus by `(d_row, d_col)`, clamped to the grid. With
+    /// `extend` the anchor stays and the selection grows; otherwise the
+    /// selection collapses to the new focus cell.
+    pub fn moved(
+        &self,
+        d_row: isize,
+        d_col: isize,
+        extend: bool,
+        rows: usize,
+        cols: usize,
+    ) -> Self {
+        let clamp =
+            |v: usize, d: isize, n: usize| v.saturating_add_signed(d).min(n.saturating_sub(1));
+        let focus = (
+            clamp(self.focus.0, d_row, rows),
+            clamp(self.focus.1, d_col, cols),
+        );
+        if extend {
+            Self { focus, ..*self }
+        } else {
+            Self::cell(focus.0, focus.1)
+        }
+    }
+
+    /// Number of selected cells.
+    pub fn cell_count(&self, rows: usize, cols: usize) -> usize {
+        self.rows(rows).count() * self.cols(cols).count()
+    }
+}
+
+fn span(a: usize, b: usize) -> RangeInclusive<usize> {
+    a.min(b)..=a.max(b)
+}
+
+/// One bucket of a numeric histogram, covering `start..end` (the last
+/// bucket also includes `end`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+/// Summary statistics for one column over a set of rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnProfile {
+    pub kind: ColumnKind,
+    pub row_count: usize,
+    pub null_count: usize,
+    pub distinct_count: usize,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    /// Most frequent non-null values with their counts, most frequent first.
+    pub top_values: Vec<(Value, usize)>,
+    /// Present for numeric columns with at least one numeric value.
+    pub histogram: Option<Vec<HistogramBin>>,
+}
+
+impl ColumnProfile {
+    /// Profile column `col` over the given row indices.
+    pub fn compute(result: &QueryResult, col: usize, rows: &[usize]) -> Self {
+        let kind = result
+            .columns
+            .get(col)
+            .map(|c| c.kind())
+            .unwrap_or(ColumnKind::Text);
+        let values: Vec<&Value> = rows
+            .iter()
+            .map(|&r| result.rows[r].get(col).unwrap_or(&Value::Null))
+            .filter(|v| !v.is_null())
+            .collect();
+
+        // Count by serialized value; keep the first occurrence for display.
+        let mut counts: HashMap<String, (&Value, usize)> = HashMap::new();
+        for v in &values {
+            counts.entry(v.to_string()).or_insert((v, 0)).1 += 1;
+        }
+        let distinct_count = counts.len();
+        let mut top: Vec<(&Value, usize)> = counts.into_values().collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| compare_values(kind, a.0, b.0)));
+        let top_values = top
+            .into_iter()
+            .take(TOP_VALUES)
+            .map(|(v, n)| (v.clone(), n))
+            .collect();
+
+        let min = values
+            .iter()
+            .copied()
+            .min_by(|a, b| compare_values(kind, a, b))
+            .cloned();
+        let max = values
+            .iter()
+            .copied()
+            .max_by(|a, b| compare_values(kind, a, b))
+            .cloned();
+
+        let histogram = (kind == ColumnKind::Numeric)
+            .then(|| {
+                let numbers: Vec<f64> = values.iter().filter_map(|v| cell_number(v)).collect();
+                histogram(&numbers)
+            })
+            .flatten();
+
+        ColumnProfile {
+            kind,
+            row_count: rows.len(),
+            null_count: rows.len() - values.len(),
+            distinct_count,
+            min,
+            max,
+            top_values,
+            histogram,
+        }
+    }
+}
+
+fn histogram(numbers: &[f64]) -> Option<Vec<HistogramBin>> {
+    let lo = numbers.iter().copied().reduce(f64::min)?;
+    let hi = numbers.iter().copied().reduce(f64::max)?;
+    if lo == hi {
+        return Some(vec![HistogramBin {
+            start: lo,
+            end: hi,
+            count: numbers.len(),
+        }]);
+    }
+    let width = (hi - lo) / HISTOGRAM_BINS as f64;
+    let mut bins: Vec<HistogramBin> = (0..HISTOGRAM_BINS)
+        .map(|i| HistogramBin {
+            start: lo + width * i as f64,
+            end: lo + width * (i + 1) as f64,
+            count: 0,
+        })
+        .collect();
+    for n in numbers {
+        let i = (((n - lo) / width) as usize).min(HISTOGRAM_BINS - 1);
+        bins[i].count += 1;
+    }
+    Some(bins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result() -> QueryResult {
+        crate::query::result(
+            &[("service", "VARCHAR"), ("cost", "DECIMAL(18,2)")],
+            vec![
+                vec![json!("EC2"), json!("100.50")],
+                vec![json!("S3"), json!("9.25")],
+                vec![json!("Lambda"), Value::Null],
+                vec![json!("ec2-spot"), json!("20")],
+            ],
+        )
+    }
+
+    #[test]
+    fn sorts_numerically_with_nulls_last() {
+        let r = result();
+        let mut rows: Vec<usize> = (0..4).collect();
+        sort_rows(&r, &mut rows, 1, false);
+        assert_eq!(rows, vec![1, 3, 0, 2]);
+        sort_rows(&r, &mut rows, 1, true);
+        assert_eq!(rows, vec![0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn filters_by_text_and_numeric_comparison() {
+        let r = result();
+        let ec2 = CellFilter::parse("ec2", ColumnKind::Text).unwrap();
+        assert_eq!(filter_rows(&r, &[(0, ec2.clone())]), vec![0, 3]);
+
+        let cheap = CellFilter::parse("< 50", ColumnKind::Numeric).unwrap();
+        assert_eq!(filter_rows(&r, &[(1, cheap)]), vec![1, 3]);
+        let at_least = CellFilter::parse(">=20", ColumnKind::Numeric).unwrap();
+        assert_eq!(filter_rows(&r, &[(0, ec2), (1, at_least)]), vec![0, 3]);
+
+        let nulls = CellFilter::parse("NULL", ColumnKind::Numeric).unwrap();
+        assert_eq!(filter_rows(&r, &[(1, nulls)]), vec![2]);
+        assert_eq!(CellFilter::parse("  ", ColumnKind::Text), None);
+    }
+
+    #[test]
+    fn profiles_numeric_column() {
+        let r = result();
+        let p = ColumnProfile::compute(&r, 1, &[0, 1, 2, 3]);
+        assert_eq!(p.row_count, 4);
+        assert_eq!(p.null_count, 1);
+        assert_eq!(p.distinct_count, 3);
+        assert_eq!(p.min, Some(json!("9.25")));
+        assert_eq!(p.max, Some(json!("100.50")));
+        let bins = p.histogram.unwrap();
+        assert_eq!(bins.len(), HISTOGRAM_BINS);
+        assert_eq!(bins.iter().map(|b| b.count).sum::<usize>(), 3);
+        assert_eq!(bins.last().unwrap().count, 1);
+    }
+
+    #[test]
+    fn selection_extends_and_clamps() {
+        let sel = GridSelection::cell(2, 1);
+        let grown = sel.moved(5, -3, true, 4, 3);
+        assert_eq!(grown.anchor, (2, 1));
+        assert_eq!(grown.focus, (3, 0));
+        assert_eq!(grown.rows(4), 2..=3);
+        assert_eq!(grown.cols(3), 0..=1);
+        assert_eq!(grown.cell_count(4, 3), 4);
+        assert_eq!(grown.moved(-1, 0, false, 4, 3), GridSelection::cell(2, 0));
+
+        let whole_rows = GridSelection {
+            mode: SelectionMode::Rows,
+            ..grown
+        };
+        assert!(whole_rows.contains(3, 2, 4, 3));
+        assert_eq!(
+            subset(&result(), &[3, 1], &[1]).rows,
+            vec![vec![json!("20")], vec![json!("9.25")]]
+        );
+    }
+}
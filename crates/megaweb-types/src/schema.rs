@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Top-level database info.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DatabaseInfo {
     pub name: String,
     pub default_engine: String,
@@ -9,14 +9,14 @@ pub struct DatabaseInfo {
 }
 
 /// Schema within a database.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SchemaInfo {
     pub name: String,
     pub tables: Vec<TableInfo>,
 }
 
 /// Table metadata.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TableInfo {
     pub schema_name: String,
     pub name: String,
@@ -32,10 +32,13 @@ pub struct TableInfo {
     /// OLAP-specific: sort columns
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort_columns: Option<Vec<String>>,
+    /// OLTP-specific: primary key columns, in key order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub primary_key: Vec<String>,
 }
 
 /// Column metadata.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ColumnInfo {
     pub name: String,
     pub data_type: String,
@@ -45,9 +48,53 @@ pub struct ColumnInfo {
 }
 
 /// Partition specification.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PartitionInfo {
     pub column: String,
     /// Transform: "identity", "bucket(N)", "year", "month", "day", "hour", "truncate(N)"
     pub transform: String,
 }
+
+impl TableInfo {
+    /// Rename a column, keeping sort, partition and key references.
+    pub fn rename_column(&mut self, index: usize, name: String) {
+        let old = std::mem::replace(&mut self.columns[index].name, name.clone());
+        let references = self
+            .sort_columns
+            .iter_mut()
+            .flatten()
+            .chain(self.primary_key.iter_mut())
+            .chain(self.partitions.iter_mut().map(|p| &mut p.column));
+        for reference in references {
+            if *reference == old {
+                *reference = name.clone();
+            }
+        }
+    }
+
+    /// Add or remove a column from the sort columns; added ones go last.
+    pub fn toggle_sort_column(&mut self, index: usize, on: bool) {
+        let name = self.columns[index].name.clone();
+        let sort = self.sort_columns.get_or_insert_with(Vec::new);
+        sort.retain(|c| *c != name);
+        if on {
+            sort.push(name);
+        }
+        if sort.is_empty() {
+            self.sort_columns = None;
+        }
+    }
+
+    /// Remove a column along with its sort, partition and key references.
+    pub fn remove_column(&mut self, index: usize) {
+        let name = self.columns.remove(index).name;
+        if let Some(sort) = &mut self.sort_columns {
+            sort.retain(|c| *c != name);
+            if sort.is_empty() {
+                self.sort_columns = None;
+            }
+        }
+        self.partitions.retain(|p| p.column != name);
+        self.primary_key.retain(|c| *c != name);
+    }
+}
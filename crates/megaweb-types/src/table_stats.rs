@@ -0,0 +1,350 @@
+//! Data preview and quick statistics of a table for the schema browser.
+//!
+//! Both are computed by the server from requests whose limits it clamps,
+//! so that browsing a billion-row table never scans all of it: the
+//! preview pages through the first rows only, and column statistics are
+//! taken over a sample of [`STATS_SAMPLE_ROWS`] rows.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::catalog::{cell, number_value, text_value};
+use crate::ddl::quote_literal;
+use crate::export::{quote_ident, quote_table};
+use crate::query::{QueryResult, SqlType};
+use crate::schema::ColumnInfo;
+
+/// Rows per preview page.
+pub const PREVIEW_PAGE_ROWS: u64 = 100;
+
+/// The preview doesn't page past this many rows; deep offsets still read
+/// every row before them.
+pub const MAX_PREVIEW_ROWS: u64 = 10_000;
+
+/// Rows column statistics are computed over.
+pub const STATS_SAMPLE_ROWS: u64 = 100_000;
+
+/// Partitions listed at most.
+pub const MAX_PARTITIONS: u64 = 1_000;
+
+/// A page of a table's rows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TablePreviewRequest {
+    pub database: String,
+    /// `schema.table`.
+    pub table: String,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+impl TablePreviewRequest {
+    /// The page actually read: at most [`PREVIEW_PAGE_ROWS`] rows, within
+    /// the first [`MAX_PREVIEW_ROWS`].
+    pub fn clamped(&self) -> (u64, u64) {
+        let offset = self.offset.min(MAX_PREVIEW_ROWS.saturating_sub(1));
+        let limit = self
+            .limit
+            .clamp(1, PREVIEW_PAGE_ROWS)
+            .min(MAX_PREVIEW_ROWS - offset);
+        (offset, limit)
+    }
+
+    /// The page's query. One row more than the page is read to tell
+    /// whether there is a next one.
+    pub fn sql(&self) -> String {
+        let (offset, limit) = self.clamped();
+        format!(
+            "SELECT * FROM {} LIMIT {} OFFSET {offset}",
+            quote_table(&self.table),
+            limit + 1
+        )
+    }
+
+    /// The page from the result of [`Self::sql`].
+    pub fn preview(&self, mut result: QueryResult) -> TablePreview {
+        let (offset, limit) = self.clamped();
+        let more_rows = result.rows.len() as u64 > limit;
+        result.rows.truncate(limit as usize);
+        result.row_count = result.rows.len() as u64;
+        TablePreview {
+            offset,
+            has_more: more_rows && offset + limit < MAX_PREVIEW_ROWS,
+            result,
+        }
+    }
+}
+
+/// A page of rows.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TablePreview {
+    pub offset: u64,
+    pub has_more: bool,
+    pub result: QueryResult,
+}
+
+/// Statistics of a table's columns and partitions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableStatsRequest {
+    pub database: String,
+    /// `schema.table`.
+    pub table: String,
+    pub columns: Vec<ColumnInfo>,
+}
+
+impl TableStatsRequest {
+    /// Null count, distinct estimate, min and max of each column over the
+    /// first [`STATS_SAMPLE_ROWS`] rows. Min and max are left out for
+    /// types they don't apply to.
+    pub fn column_stats_sql(&self) -> String {
+        let mut selects = vec!["COUNT(*) AS sampled_rows".to_string()];
+        for (i, column) in self.columns.iter().enumerate() {
+            let name = quote_ident(&column.name);
+            selects.push(format!("COUNT({name}) AS non_null_{i}"));
+            selects.push(format!("APPROX_DISTINCT({name}) AS distinct_{i}"));
+            if has_min_max(&column.data_type) {
+                selects.push(format!("MIN({name}) AS min_{i}"));
+                selects.push(format!("MAX({name}) AS max_{i}"));
+            }
+        }
+        format!(
+            "SELECT {} FROM (SELECT * FROM {} LIMIT {STATS_SAMPLE_ROWS}) AS sample",
+            selects.join(", "),
+            quote_table(&self.table)
+        )
+    }
+
+    /// The table's partitions; see [`partitions_sql`].
+    pub fn partitions_sql(&self) -> String {
+        partitions_sql(&self.table)
+    }
+
+    /// Read the result of [`Self::column_stats_sql`].
+    pub fn column_stats(&self, result: &QueryResult) -> Result<(u64, Vec<ColumnStats>), String> {
+        let row = result
+            .rows
+            .first()
+            .ok_or("The statistics query returned no rows")?;
+        let cell = |name: &str| cell(result, row, name);
+        let sampled = cell("sampled_rows").and_then(number_value).unwrap_or(0);
+        let columns = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let non_null = cell(&format!("non_null_{i}")).and_then(number_value);
+                ColumnStats {
+                    name: column.name.clone(),
+                    null_fraction: non_null
+                        .filter(|_| sampled > 0)
+                        .map(|n| (sampled.saturating_sub(n)) as f64 / sampled as f64),
+                    distinct_estimate: cell(&format!("distinct_{i}")).and_then(number_value),
+                    min: cell(&format!("min_{i}")).cloned(),
+                    max: cell(&format!("max_{i}")).cloned(),
+                }
+            })
+            .collect();
+        Ok((sampled, columns))
+    }
+}
+
+fn has_min_max(data_type: &str) -> bool {
+    !matches!(
+        SqlType::parse(data_type),
+        SqlType::Json | SqlType::Binary | SqlType::Array(_)
+    )
+}
+
+/// Statistics of one column over the sample.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnStats {
+    pub name: String,
+    /// Share of NULLs, 0 to 1.
+    pub null_fraction: Option<f64>,
+    /// Approximate number of distinct values.
+    pub distinct_estimate: Option<u64>,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+}
+
+/// One stored partition of a table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartitionStats {
+    /// The partition's value or range, e.g. `billing_period_month=2024-06`.
+    pub partition: String,
+    pub row_count: Option<u64>,
+    pub file_count: Option<u64>,
+    pub size_bytes: Option<u64>,
+    pub last_modified: Option<String>,
+}
+
+/// The partitions of `table` (`schema.table`) from MegaDB's
+/// `information_schema.partitions`, at most [`MAX_PARTITIONS`].
+pub fn partitions_sql(table: &str) -> String {
+    let (schema, name) = table.split_once('.').unwrap_or(("public", table));
+    format!(
+        "SELECT * FROM information_schema.partitions \
+         WHERE table_schema = {} AND table_name = {} LIMIT {MAX_PARTITIONS}",
+        quote_literal(schema),
+        quote_literal(name)
+    )
+}
+
+/// Read the result of [`partitions_sql`], by column name since MegaDB
+/// versions differ in what they report.
+pub fn partitions_from_result(result: &QueryResult) -> Vec<PartitionStats> {
+    result
+        .rows
+        .iter()
+        .filter_map(|row| {
+            let cell = |names: &[&str]| names.iter().find_map(|n| cell(result, row, n));
+            Some(PartitionStats {
+                partition: cell(&["partition", "partition_value", "partition_name"])
+                    .and_then(text_value)?,
+                row_count: cell(&["row_count", "rows"]).and_then(number_value),
+                file_count: cell(&["file_count", "files"]).and_then(number_value),
+                size_bytes: cell(&["size_bytes", "bytes"]).and_then(number_value),
+                last_modified: cell(&["last_modified", "updated_at"]).and_then(text_value),
+            })
+        })
+        .collect()
+}
+
+/// Column and partition statistics of a table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableStats {
+    /// Rows the column statistics were computed over, at most
+    /// [`STATS_SAMPLE_ROWS`].
+    pub sampled_rows: u64,
+    pub columns: Vec<ColumnStats>,
+    pub partitions: Vec<PartitionStats>,
+    /// Why partitions couldn't be listed, e.g. a MegaDB without
+    /// `information_schema.partitions`.
+    pub partitions_error: Option<String>,
+}
+
+impl TableStats {
+    /// Whether the sample limit was reached, so the table may have more
+    /// rows than the statistics cover.
+    pub fn is_sampled(&self) -> bool {
+        self.sampled_rows >= STATS_SAMPLE_ROWS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::result;
+
+    #[test]
+    fn pages_within_limits() {
+        let page = |offset, limit| TablePreviewRequest {
+            database: "megadb".into(),
+            table: "public.cur_data".into(),
+            offset,
+            limit,
+        };
+        assert_eq!(
+            page(200, 100).sql(),
+            "SELECT * FROM public.cur_data LIMIT 101 OFFSET 200"
+        );
+        assert_eq!(page(0, 1_000_000).clamped(), (0, PREVIEW_PAGE_ROWS));
+        assert_eq!(page(9_950, 100).clamped(), (9_950, 50));
+        assert_eq!(page(u64::MAX, 100).clamped(), (MAX_PREVIEW_ROWS - 1, 1));
+
+        let rows = (0..3).map(|i| vec![Value::from(i)]).collect();
+        let preview = page(0, 2).preview(result(&[("id", "BIGINT")], rows));
+        assert!(preview.has_more);
+        assert_eq!(preview.result.rows.len(), 2);
+        assert_eq!(preview.result.row_count, 2);
+    }
+
+    #[test]
+    fn computes_column_stats_over_a_sample() {
+        let request = TableStatsRequest {
+            database: "megadb".into(),
+            table: "public.events".into(),
+            columns: vec![
+                ColumnInfo {
+                    name: "user id".into(),
+                    data_type: "BIGINT".into(),
+                    nullable: true,
+                    comment: None,
+                },
+                ColumnInfo {
+                    name: "payload".into(),
+                    data_type: "JSON".into(),
+                    nullable: true,
+                    comment: None,
+                },
+            ],
+        };
+        assert_eq!(
+            request.column_stats_sql(),
+            "SELECT COUNT(*) AS sampled_rows, COUNT(\"user id\") AS non_null_0, \
+             APPROX_DISTINCT(\"user id\") AS distinct_0, MIN(\"user id\") AS min_0, \
+             MAX(\"user id\") AS max_0, COUNT(payload) AS non_null_1, \
+             APPROX_DISTINCT(payload) AS distinct_1 \
+             FROM (SELECT * FROM public.events LIMIT 100000) AS sample"
+        );
+
+        let stats = result(
+            &[
+                ("sampled_rows", "BIGINT"),
+                ("non_null_0", "BIGINT"),
+                ("distinct_0", "BIGINT"),
+                ("min_0", "BIGINT"),
+                ("max_0", "BIGINT"),
+                ("non_null_1", "BIGINT"),
+                ("distinct_1", "BIGINT"),
+            ],
+            vec![vec![
+                Value::from(200),
+                Value::from(150),
+                Value::from("42"),
+                Value::from(1),
+                Value::from(99),
+                Value::from(200),
+                Value::Null,
+            ]],
+        );
+        let (sampled, columns) = request.column_stats(&stats).unwrap();
+        assert_eq!(sampled, 200);
+        assert_eq!(columns[0].null_fraction, Some(0.25));
+        assert_eq!(columns[0].distinct_estimate, Some(42));
+        assert_eq!(columns[0].max, Some(Value::from(99)));
+        assert_eq!(columns[1].null_fraction, Some(0.0));
+        assert_eq!(columns[1].distinct_estimate, None);
+        assert_eq!(columns[1].min, None);
+    }
+
+    #[test]
+    fn reads_partitions_by_column_name() {
+        let partitions = partitions_from_result(&result(
+            &[
+                ("table_name", "BIGINT"),
+                ("partition_value", "BIGINT"),
+                ("rows", "BIGINT"),
+                ("size_bytes", "BIGINT"),
+            ],
+            vec![
+                vec![
+                    "cur_data".into(),
+                    "2024-06".into(),
+                    1200.into(),
+                    "4096".into(),
+                ],
+                vec!["cur_data".into(), Value::Null, 1.into(), 1.into()],
+            ],
+        ));
+        assert_eq!(
+            partitions,
+            [PartitionStats {
+                partition: "2024-06".into(),
+                row_count: Some(1200),
+                file_count: None,
+                size_bytes: Some(4096),
+                last_modified: None,
+            }]
+        );
+    }
+}
@@ -14,8 +14,10 @@ const editors = new Map();
  * @param {string} initialContent - Initial SQL text
  * @param {Function} onExecute - Called with SQL string on Ctrl+Enter
  * @param {Function} onChange - Called with SQL string on every change
+ * @param {number} [initialCursor] - Initial cursor offset
+ * @param {Function} [onCursor] - Called with the cursor offset when the selection moves
  */
-function createEditor(containerId, initialContent, onExecute, onChange) {
+function createEditor(containerId, initialContent, onExecute, onChange, initialCursor, onCursor) {
     const container = document.getElementById(containerId);
     if (!container) return;
 
@@ -33,8 +35,12 @@ function createEditor(containerId, initialContent, onExecute, onChange) {
         },
     ]);
 
+    const doc = initialContent || "";
+    const cursor = Math.min(Math.max(initialCursor || 0, 0), doc.length);
+
     const state = EditorState.create({
-        doc: initialContent || "",
+        doc,
+        selection: { anchor: cursor },
         extensions: [
             basicSetup,
             sql({ dialect: PostgreSQL }),
@@ -44,6 +50,9 @@ function createEditor(containerId, initialContent, onExecute, onChange) {
                 if (update.docChanged) {
                     onChange(update.state.doc.toString());
                 }
+                if (update.selectionSet && onCursor) {
+                    onCursor(update.state.selection.main.head);
+                }
             }),
             EditorView.theme({
                 "&": {